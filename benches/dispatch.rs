@@ -0,0 +1,112 @@
+use ps4hid::latency::Histogram;
+use ps4hid::metrics::Metrics;
+use ps4hid::transport::MockTransport;
+use ps4hid::{ConnectionInfo, Controller, Snapshot, Stick};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Feeds a second of 1 kHz reports through parse, diff and dispatch, then times
+// another ten seconds' worth and counts heap allocations made while doing so.
+// Run with `cargo bench --bench dispatch`.
+
+const WARMUP: usize = 1000;
+const REPORTS: usize = 10_000;
+
+struct Counting;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Sticks sweeping and X toggling, so every report changes something.
+fn frame(i: usize) -> Snapshot {
+    let sweep = (i % 256) as u8;
+    Snapshot {
+        x: i / 50 % 2 == 1,
+        left_stick: Stick::new(sweep, 255 - sweep),
+        right_stick: Stick::new(255 - sweep, sweep),
+        r2_value: sweep,
+        ..Snapshot::default()
+    }
+}
+
+fn main() {
+    let (transport, mut pad) = MockTransport::new();
+    let mut controller = Controller::with_transport(transport, ConnectionInfo::default());
+    controller.set_metrics(Arc::new(Metrics::new()));
+    controller.set_history_capacity(1000);
+    let presses = Arc::new(AtomicU64::new(0));
+    let counted = presses.clone();
+    let _x = controller.controls.x.subscribe(move |_, pressed| {
+        if pressed {
+            counted.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+    let _stick = controller.controls.left_stick.subscribe(|_, _| {});
+
+    for i in 0..WARMUP + REPORTS {
+        pad.send_snapshot(&frame(i), 1000).unwrap();
+    }
+
+    let mut events = 0;
+    for _ in 0..WARMUP {
+        controller.update().unwrap();
+        events += controller.events().count();
+    }
+
+    let mut times = Histogram::new(Duration::from_nanos(100), 1000);
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let started = Instant::now();
+    for _ in 0..REPORTS {
+        let at = Instant::now();
+        controller.update().unwrap();
+        events += controller.events().count();
+        times.record(at.elapsed());
+    }
+    let elapsed = started.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+    println!(
+        "{} reports in {:?} ({:.1}% of a 1 kHz budget)",
+        REPORTS,
+        elapsed,
+        elapsed.as_secs_f64() / (REPORTS as f64 / 1000.0) * 100.0
+    );
+    for q in [0.5, 0.99] {
+        println!(
+            "p{:<3} {:?}",
+            q * 100.0,
+            times.quantile(q).unwrap_or_default()
+        );
+    }
+    println!("max  {:?}", times.max().unwrap_or_default());
+    println!(
+        "{} presses, {} device events",
+        presses.load(Ordering::Relaxed),
+        events
+    );
+    println!(
+        "{} allocations ({:.3} per report)",
+        allocations,
+        allocations as f64 / REPORTS as f64
+    );
+}
@@ -0,0 +1,249 @@
+use ps4hid::choreography::Choreography;
+use ps4hid::metrics::Metrics;
+use ps4hid::profile_switch::ProfileSwitcher;
+use ps4hid::transport::{encode_input, MockTransport};
+use ps4hid::{ButtonId, ConnectionInfo, Controller, DPad, Profile, Snapshot, Stick};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::env;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+// Runs the controller stack against the mock transport for millions of reports,
+// with the pad randomly disconnecting and sending garbage, and checks that
+// nothing panics, that every session gives back what it allocated, and that
+// memory stays bounded. Run with `cargo bench --bench soak`; set SOAK_REPORTS
+// to change the length and SOAK_SEED to replay a particular run.
+
+const DEFAULT_REPORTS: u64 = 2_000_000;
+/// Roughly one disconnect per this many reports.
+const DISCONNECT_ODDS: u64 = 20_000;
+/// Roughly one malformed packet per this many reports.
+const MALFORMED_ODDS: u64 = 200;
+/// Live heap that may legitimately stay behind after a session, e.g. lazily
+/// initialized statics.
+const LEAK_SLACK: usize = 64 * 1024;
+const PEAK_LIMIT: usize = 16 * 1024 * 1024;
+
+struct Tracking;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+fn grew(by: usize) {
+    let live = LIVE.fetch_add(by, Ordering::Relaxed) + by;
+    PEAK.fetch_max(live, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for Tracking {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        grew(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        grew(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Tracking = Tracking;
+
+/// xorshift64, so a failing run can be repeated from its seed.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn one_in(&mut self, odds: u64) -> bool {
+        self.next().is_multiple_of(odds)
+    }
+}
+
+/// Mostly plausible input: drifting sticks and triggers, and now and then a
+/// press, including the profile chord.
+fn frame(rng: &mut Rng, i: u64) -> Snapshot {
+    let bits = rng.next();
+    let sweep = (i % 256) as u8;
+    let mut snapshot = Snapshot {
+        left_stick: Stick::new(sweep, bits as u8),
+        right_stick: Stick::new((bits >> 8) as u8, 255 - sweep),
+        l2_value: (bits >> 16) as u8,
+        r2_value: (bits >> 24) as u8,
+        dpad: match bits >> 32 & 0x3f {
+            0 => DPad::East,
+            1 => DPad::West,
+            2 => DPad::North,
+            _ => DPad::Released,
+        },
+        ..Snapshot::default()
+    };
+    for (n, id) in ButtonId::ALL.into_iter().enumerate() {
+        snapshot.set_pressed(id, bits >> (40 + n) & 0x7 == 0);
+    }
+    snapshot
+}
+
+/// Truncated, oversized, mislabeled or random reports.
+fn malformed(rng: &mut Rng, good: &[u8]) -> Vec<u8> {
+    match rng.next() % 5 {
+        0 => Vec::new(),
+        1 => good[..(rng.next() as usize % good.len())].to_vec(),
+        2 => {
+            let mut report = good.to_vec();
+            report[0] = rng.next() as u8;
+            report
+        }
+        3 => {
+            let mut report = good.to_vec();
+            report.resize(good.len() + 1 + rng.next() as usize % 64, 0xff);
+            report
+        }
+        _ => (0..rng.next() % 80).map(|_| rng.next() as u8).collect(),
+    }
+}
+
+#[derive(Default)]
+struct Totals {
+    reports: u64,
+    malformed: u64,
+    rejected: u64,
+    sessions: u64,
+    events: u64,
+    switches: u64,
+}
+
+/// One connection, from open until the pad disconnects or `budget` reports
+/// have been sent.
+fn session(rng: &mut Rng, budget: u64, totals: &mut Totals, metrics: &Arc<Metrics>) {
+    let (transport, mut pad) = MockTransport::new();
+    let mut controller = Controller::with_transport(transport, ConnectionInfo::default());
+    controller.set_metrics(metrics.clone());
+    controller.set_history_capacity(1000);
+    controller.set_choreography(Some(Choreography::default()));
+    let presses = Arc::new(AtomicU64::new(0));
+    let counted = presses.clone();
+    let _x = controller.controls.x.subscribe(move |_, pressed| {
+        if pressed {
+            counted.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+    let _stick = controller.controls.left_stick.subscribe(|_, _| {});
+    let mut switcher = ProfileSwitcher::new(vec![Profile::default(), Profile::default()]);
+    totals.sessions += 1;
+
+    for _ in 0..budget {
+        let i = totals.reports;
+        totals.reports += 1;
+        if rng.one_in(DISCONNECT_ODDS) {
+            drop(pad);
+            let result = controller.update();
+            assert!(
+                result.is_err_and(|e| e.is_device_error()),
+                "a disconnect wasn't reported as a device error"
+            );
+            return;
+        }
+        let snapshot = frame(rng, i);
+        if rng.one_in(MALFORMED_ODDS) {
+            let good = encode_input(&snapshot, (i & 0x3f) as u8, i as u16);
+            pad.send_report(malformed(rng, &good)).unwrap();
+            totals.malformed += 1;
+        } else {
+            pad.send_snapshot(&snapshot, 1000).unwrap();
+        }
+        match controller.update() {
+            Ok(()) => {}
+            Err(e) if e.is_device_error() => panic!("device error while connected: {}", e),
+            Err(_) => totals.rejected += 1,
+        }
+        totals.events += controller.events().count() as u64;
+        if switcher.update(&mut controller).unwrap().is_some() {
+            totals.switches += 1;
+        }
+        // Output a real pad would have consumed.
+        for _ in pad.written() {}
+    }
+}
+
+fn main() {
+    let reports = env::var("SOAK_REPORTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_REPORTS);
+    let seed = env::var("SOAK_SEED")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0x05ee_dd54_u64)
+        .max(1);
+    let mut rng = Rng(seed);
+    let metrics = Arc::new(Metrics::new());
+    let mut totals = Totals::default();
+
+    // The first session initializes whatever is lazily set up once per process.
+    session(&mut rng, 1000, &mut totals, &metrics);
+    let baseline = LIVE.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+
+    let started = Instant::now();
+    let mut panics = 0;
+    let mut worst_leak = 0;
+    while totals.reports < reports {
+        let budget = reports - totals.reports;
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+            session(&mut rng, budget, &mut totals, &metrics)
+        }));
+        if outcome.is_err() {
+            panics += 1;
+        }
+        let leaked = LIVE.load(Ordering::Relaxed).saturating_sub(baseline);
+        worst_leak = worst_leak.max(leaked);
+        assert!(
+            leaked <= LEAK_SLACK,
+            "session {} left {} bytes behind (seed {})",
+            totals.sessions,
+            leaked,
+            seed
+        );
+    }
+    let elapsed = started.elapsed();
+    let peak = PEAK.load(Ordering::Relaxed);
+
+    println!(
+        "{} reports over {} sessions in {:?} (seed {})",
+        totals.reports, totals.sessions, elapsed, seed
+    );
+    println!(
+        "{} malformed sent, {} rejected, {} device events, {} profile switches",
+        totals.malformed, totals.rejected, totals.events, totals.switches
+    );
+    println!(
+        "peak {} KiB over baseline, worst leftover {} bytes, {} allocations",
+        peak.saturating_sub(baseline) / 1024,
+        worst_leak,
+        ALLOCATIONS.load(Ordering::Relaxed)
+    );
+    assert_eq!(panics, 0, "{} sessions panicked (seed {})", panics, seed);
+    assert!(
+        peak - baseline <= PEAK_LIMIT,
+        "peak heap {} bytes over baseline (seed {})",
+        peak - baseline,
+        seed
+    );
+}
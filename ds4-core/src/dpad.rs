@@ -0,0 +1,78 @@
+use crate::ParseError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DPad {
+    #[default]
+    Released,
+    NorthWest,
+    West,
+    SouthWest,
+    South,
+    SouthEast,
+    East,
+    NorthEast,
+    North,
+}
+
+impl DPad {
+    pub fn from_byte(b: u8) -> Result<Self, ParseError> {
+        match b & 0x0f {
+            0x08 => Ok(DPad::Released),
+            0x07 => Ok(DPad::NorthWest),
+            0x06 => Ok(DPad::West),
+            0x05 => Ok(DPad::SouthWest),
+            0x04 => Ok(DPad::South),
+            0x03 => Ok(DPad::SouthEast),
+            0x02 => Ok(DPad::East),
+            0x01 => Ok(DPad::NorthEast),
+            0x00 => Ok(DPad::North),
+            _ => Err(ParseError::InvalidDPad(b & 0x0f)),
+        }
+    }
+
+    /// The direction of `(x, y)` by sign, with Y up.
+    pub fn from_xy(x: i8, y: i8) -> Self {
+        match (x.signum(), y.signum()) {
+            (0, 1) => DPad::North,
+            (1, 1) => DPad::NorthEast,
+            (1, 0) => DPad::East,
+            (1, -1) => DPad::SouthEast,
+            (0, -1) => DPad::South,
+            (-1, -1) => DPad::SouthWest,
+            (-1, 0) => DPad::West,
+            (-1, 1) => DPad::NorthWest,
+            _ => DPad::Released,
+        }
+    }
+
+    /// `(x, y)` as unit steps with Y up, the inverse of `from_xy`.
+    pub fn to_xy(&self) -> (i8, i8) {
+        match self {
+            DPad::Released => (0, 0),
+            DPad::North => (0, 1),
+            DPad::NorthEast => (1, 1),
+            DPad::East => (1, 0),
+            DPad::SouthEast => (1, -1),
+            DPad::South => (0, -1),
+            DPad::SouthWest => (-1, -1),
+            DPad::West => (-1, 0),
+            DPad::NorthWest => (-1, 1),
+        }
+    }
+
+    /// The low nibble of the report byte, the inverse of `from_byte`.
+    pub fn to_byte(&self) -> u8 {
+        match self {
+            DPad::Released => 0x08,
+            DPad::NorthWest => 0x07,
+            DPad::West => 0x06,
+            DPad::SouthWest => 0x05,
+            DPad::South => 0x04,
+            DPad::SouthEast => 0x03,
+            DPad::East => 0x02,
+            DPad::NorthEast => 0x01,
+            DPad::North => 0x00,
+        }
+    }
+}
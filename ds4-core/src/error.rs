@@ -0,0 +1,27 @@
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    ShortReport {
+        len: usize,
+        expected: usize,
+    },
+    InvalidDPad(u8),
+    /// A wire message from a format version this build doesn't know.
+    UnsupportedVersion(u8),
+    /// A wire message with bits set that the format reserves.
+    ReservedBits,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::ShortReport { len, expected } => {
+                write!(f, "short report: got {} bytes, expected {}", len, expected)
+            }
+            ParseError::InvalidDPad(b) => write!(f, "invalid dpad value: 0b{:04b}", b),
+            ParseError::UnsupportedVersion(v) => write!(f, "unsupported wire format version {}", v),
+            ParseError::ReservedBits => write!(f, "reserved bits set in wire message"),
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::snapshot::{ButtonId, Snapshot};
+use crate::DPad;
+
+bitflags::bitflags! {
+    /// Every digital input packed into one word, for cheap per-frame storage.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct ButtonFlags: u32 {
+        const TRIANGLE = 1 << 0;
+        const CIRCLE = 1 << 1;
+        const X = 1 << 2;
+        const SQUARE = 1 << 3;
+        const R3 = 1 << 4;
+        const L3 = 1 << 5;
+        const OPTIONS = 1 << 6;
+        const SHARE = 1 << 7;
+        const R2 = 1 << 8;
+        const L2 = 1 << 9;
+        const R1 = 1 << 10;
+        const L1 = 1 << 11;
+        const TPAD = 1 << 12;
+        const PS = 1 << 13;
+        const DPAD_UP = 1 << 16;
+        const DPAD_DOWN = 1 << 17;
+        const DPAD_LEFT = 1 << 18;
+        const DPAD_RIGHT = 1 << 19;
+    }
+}
+
+impl ButtonFlags {
+    /// Every button bit, bit `n` for `ButtonId::ALL[n]`; the dpad sits above these.
+    pub const BUTTONS: ButtonFlags = ButtonFlags::from_bits_retain((1 << ButtonId::ALL.len()) - 1);
+
+    pub fn pressed_any(&self, buttons: ButtonFlags) -> bool {
+        self.intersects(buttons)
+    }
+
+    pub fn pressed_all(&self, buttons: ButtonFlags) -> bool {
+        self.contains(buttons)
+    }
+
+    /// `(pressed, released)` going from `previous` to `self`.
+    pub fn diff(&self, previous: ButtonFlags) -> (ButtonFlags, ButtonFlags) {
+        (*self - previous, previous - *self)
+    }
+
+    /// The buttons set, in `ButtonId::ALL` order. Walks set bits only, so a mostly
+    /// idle pad costs almost nothing; the dpad bits are skipped.
+    pub fn ids(&self) -> ButtonIds {
+        ButtonIds((*self & Self::BUTTONS).bits())
+    }
+
+    /// Every button that differs from `previous`, with its new state, found by XOR
+    /// rather than comparing each button.
+    pub fn changes(&self, previous: ButtonFlags) -> ButtonChanges {
+        ButtonChanges {
+            changed: ButtonIds(((*self ^ previous) & Self::BUTTONS).bits()),
+            current: *self,
+        }
+    }
+
+    pub fn dpad(&self) -> DPad {
+        let y = self.contains(Self::DPAD_UP) as i8 - self.contains(Self::DPAD_DOWN) as i8;
+        let x = self.contains(Self::DPAD_RIGHT) as i8 - self.contains(Self::DPAD_LEFT) as i8;
+        DPad::from_xy(x, y)
+    }
+
+    pub fn from_dpad(dpad: DPad) -> ButtonFlags {
+        match dpad {
+            DPad::Released => ButtonFlags::empty(),
+            DPad::North => Self::DPAD_UP,
+            DPad::NorthEast => Self::DPAD_UP | Self::DPAD_RIGHT,
+            DPad::East => Self::DPAD_RIGHT,
+            DPad::SouthEast => Self::DPAD_DOWN | Self::DPAD_RIGHT,
+            DPad::South => Self::DPAD_DOWN,
+            DPad::SouthWest => Self::DPAD_DOWN | Self::DPAD_LEFT,
+            DPad::West => Self::DPAD_LEFT,
+            DPad::NorthWest => Self::DPAD_UP | Self::DPAD_LEFT,
+        }
+    }
+}
+
+/// Iterator over the buttons in a `ButtonFlags`, from [`ButtonFlags::ids`].
+#[derive(Debug, Clone)]
+pub struct ButtonIds(u32);
+
+impl Iterator for ButtonIds {
+    type Item = ButtonId;
+
+    fn next(&mut self) -> Option<ButtonId> {
+        if self.0 == 0 {
+            return None;
+        }
+        let n = self.0.trailing_zeros();
+        // Clears the lowest set bit.
+        self.0 &= self.0 - 1;
+        ButtonId::ALL.get(n as usize).copied()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.0.count_ones() as usize;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for ButtonIds {}
+
+/// Iterator over `(button, pressed)` for each changed button, from
+/// [`ButtonFlags::changes`].
+#[derive(Debug, Clone)]
+pub struct ButtonChanges {
+    changed: ButtonIds,
+    current: ButtonFlags,
+}
+
+impl Iterator for ButtonChanges {
+    type Item = (ButtonId, bool);
+
+    fn next(&mut self) -> Option<(ButtonId, bool)> {
+        let id = self.changed.next()?;
+        Some((id, self.current.contains(id.into())))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.changed.size_hint()
+    }
+}
+
+impl ExactSizeIterator for ButtonChanges {}
+
+impl From<ButtonId> for ButtonFlags {
+    fn from(id: ButtonId) -> Self {
+        // Declaration order matches `ButtonId::ALL` and the bit layout.
+        ButtonFlags::from_bits_retain(1 << id as u32)
+    }
+}
+
+impl Snapshot {
+    /// Packs every digital input into one word, without branching per button.
+    pub fn buttons(&self) -> ButtonFlags {
+        let bits = [
+            self.triangle,
+            self.circle,
+            self.x,
+            self.square,
+            self.r3,
+            self.l3,
+            self.options,
+            self.share,
+            self.r2,
+            self.l2,
+            self.r1,
+            self.l1,
+            self.tpad,
+            self.ps,
+        ]
+        .into_iter()
+        .enumerate()
+        .fold(0, |bits, (n, pressed)| bits | (pressed as u32) << n);
+        ButtonFlags::from_bits_retain(bits) | ButtonFlags::from_dpad(self.dpad)
+    }
+
+    /// Sets every digital input, including the dpad, from `flags`.
+    pub fn set_buttons(&mut self, flags: ButtonFlags) {
+        for id in ButtonId::ALL {
+            self.set_pressed(id, flags.contains(id.into()));
+        }
+        self.dpad = flags.dpad();
+    }
+}
@@ -0,0 +1,31 @@
+/// Offset of the gyro and accelerometer block in the USB-aligned report.
+pub const IMU_OFFSET: usize = 13;
+const IMU_LEN: usize = 12;
+
+/// Raw motion sensor readings, uncalibrated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Imu {
+    /// Angular velocity about x (pitch), y (yaw) and z (roll).
+    pub gyro: [i16; 3],
+    /// Acceleration along x, y and z; roughly 8192 per g.
+    pub accel: [i16; 3],
+    /// Raw sensor temperature from the byte ahead of the block; higher is warmer,
+    /// but the scale is undocumented. Gyro bias drifts with it.
+    pub temperature: u8,
+}
+
+impl Imu {
+    /// Decodes the block from a USB-aligned report, or all zeroes if it's too short.
+    pub fn parse(report: &[u8]) -> Self {
+        let Some(b) = report.get(IMU_OFFSET - 1..IMU_OFFSET + IMU_LEN) else {
+            return Imu::default();
+        };
+        let axis = |i: usize| i16::from_le_bytes([b[1 + i * 2], b[2 + i * 2]]);
+        Imu {
+            gyro: [axis(0), axis(1), axis(2)],
+            accel: [axis(3), axis(4), axis(5)],
+            temperature: b[0],
+        }
+    }
+}
@@ -0,0 +1,25 @@
+#![no_std]
+#![deny(clippy::unwrap_used, clippy::expect_used, clippy::panic)]
+
+#[cfg(feature = "std")]
+extern crate std;
+
+mod dpad;
+mod error;
+mod flags;
+mod imu;
+pub mod report;
+mod snapshot;
+mod stick;
+pub mod touch;
+pub mod wire;
+
+pub use dpad::DPad;
+pub use error::ParseError;
+pub use flags::{ButtonChanges, ButtonFlags, ButtonIds};
+pub use imu::{Imu, IMU_OFFSET};
+pub use snapshot::{AxisId, ButtonId, Snapshot};
+pub use stick::{Stick, YAxis};
+
+/// Length of one device clock tick in seconds (16/3 µs).
+pub const TICK_SECS: f64 = 16.0 / 3.0 / 1_000_000.0;
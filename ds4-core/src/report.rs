@@ -0,0 +1,96 @@
+use crate::touch::{TouchPackets, TOUCH_OFFSET};
+use crate::{ButtonFlags, DPad, Imu, ParseError, Stick};
+
+/// Offset of the battery and peripheral status byte in the USB-aligned report.
+pub const STATUS_OFFSET: usize = 30;
+
+/// Decodes reports of exactly `LEN` bytes whose control data starts `OFFSET` bytes
+/// later than in the USB report. With `TOUCH` false the touch section is skipped.
+///
+/// The layout is fixed at compile time and nothing allocates, for hosts that know
+/// which report they'll get; `Controller` detects the layout at runtime instead.
+pub struct ReportParser<const LEN: usize, const OFFSET: usize, const TOUCH: bool>;
+
+/// The 64-byte USB report, also used by the wireless adapter.
+pub type UsbParser = ReportParser<64, 0, true>;
+/// The full 78-byte Bluetooth report 0x11.
+pub type BluetoothParser = ReportParser<78, 2, true>;
+/// Sticks, buttons and triggers only, e.g. the short Bluetooth report.
+pub type BasicParser = ReportParser<10, 0, false>;
+
+/// Everything one report carries, by value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InputState {
+    pub buttons: ButtonFlags,
+    pub left_stick: Stick,
+    pub right_stick: Stick,
+    pub l2: u8,
+    pub r2: u8,
+    /// 6-bit report counter.
+    pub counter: u8,
+    /// Device clock in 16/3 µs ticks; 0 if the layout doesn't carry it.
+    pub timestamp: u16,
+    /// Battery and peripheral status byte; 0 if the layout doesn't carry it.
+    pub status: u8,
+    /// All zeroes if the layout doesn't carry it.
+    pub imu: Imu,
+    /// Empty unless `TOUCH` is set.
+    pub touch: TouchPackets,
+}
+
+impl<const LEN: usize, const OFFSET: usize, const TOUCH: bool> ReportParser<LEN, OFFSET, TOUCH> {
+    pub const LEN: usize = LEN;
+
+    pub fn parse(report: &[u8; LEN]) -> Result<InputState, ParseError> {
+        let data = &report[OFFSET.min(LEN)..];
+        let &[_, lx, ly, rx, ry, b5, b6, b7, l2, r2, ..] = data else {
+            return Err(ParseError::ShortReport {
+                len: LEN,
+                expected: OFFSET + 10,
+            });
+        };
+        let dpad = DPad::from_byte(b5)?;
+
+        let mut buttons = ButtonFlags::from_dpad(dpad);
+        for (byte, bit, flag) in [
+            (b5, 0x80, ButtonFlags::TRIANGLE),
+            (b5, 0x40, ButtonFlags::CIRCLE),
+            (b5, 0x20, ButtonFlags::X),
+            (b5, 0x10, ButtonFlags::SQUARE),
+            (b6, 0x80, ButtonFlags::R3),
+            (b6, 0x40, ButtonFlags::L3),
+            (b6, 0x20, ButtonFlags::OPTIONS),
+            (b6, 0x10, ButtonFlags::SHARE),
+            (b6, 0x08, ButtonFlags::R2),
+            (b6, 0x04, ButtonFlags::L2),
+            (b6, 0x02, ButtonFlags::R1),
+            (b6, 0x01, ButtonFlags::L1),
+            (b7, 0x02, ButtonFlags::TPAD),
+            (b7, 0x01, ButtonFlags::PS),
+        ] {
+            buttons.set(flag, byte & bit != 0);
+        }
+
+        let mut state = InputState {
+            buttons,
+            left_stick: Stick::new(lx, ly),
+            right_stick: Stick::new(rx, ry),
+            l2,
+            r2,
+            counter: b7 >> 2,
+            ..InputState::default()
+        };
+        if let Some(&[lo, hi]) = data.get(10..12) {
+            state.timestamp = u16::from_le_bytes([lo, hi]);
+        }
+        state.imu = Imu::parse(data);
+        if let Some(&status) = data.get(STATUS_OFFSET) {
+            state.status = status;
+        }
+        if TOUCH && data.len() > TOUCH_OFFSET {
+            state.touch = TouchPackets::parse(data);
+        }
+        Ok(state)
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{DPad, Stick};
+use core::fmt;
+use core::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ButtonId {
+    Triangle,
+    Circle,
+    X,
+    Square,
+    R3,
+    L3,
+    Options,
+    Share,
+    R2,
+    L2,
+    R1,
+    L1,
+    Tpad,
+    Ps,
+}
+
+impl ButtonId {
+    pub const ALL: [ButtonId; 14] = [
+        ButtonId::Triangle,
+        ButtonId::Circle,
+        ButtonId::X,
+        ButtonId::Square,
+        ButtonId::R3,
+        ButtonId::L3,
+        ButtonId::Options,
+        ButtonId::Share,
+        ButtonId::R2,
+        ButtonId::L2,
+        ButtonId::R1,
+        ButtonId::L1,
+        ButtonId::Tpad,
+        ButtonId::Ps,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ButtonId::Triangle => "triangle",
+            ButtonId::Circle => "circle",
+            ButtonId::X => "x",
+            ButtonId::Square => "square",
+            ButtonId::R3 => "r3",
+            ButtonId::L3 => "l3",
+            ButtonId::Options => "options",
+            ButtonId::Share => "share",
+            ButtonId::R2 => "r2",
+            ButtonId::L2 => "l2",
+            ButtonId::R1 => "r1",
+            ButtonId::L1 => "l1",
+            ButtonId::Tpad => "tpad",
+            ButtonId::Ps => "ps",
+        }
+    }
+}
+
+impl fmt::Display for ButtonId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ButtonId {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        ButtonId::ALL
+            .into_iter()
+            .find(|id| id.name().eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AxisId {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    L2,
+    R2,
+}
+
+/// Plain copy of the state of every control at one point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot {
+    pub triangle: bool,
+    pub circle: bool,
+    pub x: bool,
+    pub square: bool,
+    pub dpad: DPad,
+    pub r3: bool,
+    pub l3: bool,
+    pub options: bool,
+    pub share: bool,
+    pub r2: bool,
+    pub l2: bool,
+    pub r1: bool,
+    pub l1: bool,
+    pub tpad: bool,
+    pub ps: bool,
+    pub left_stick: Stick,
+    pub right_stick: Stick,
+    pub l2_value: u8,
+    pub r2_value: u8,
+}
+
+impl Snapshot {
+    pub fn pressed(&self, id: ButtonId) -> bool {
+        match id {
+            ButtonId::Triangle => self.triangle,
+            ButtonId::Circle => self.circle,
+            ButtonId::X => self.x,
+            ButtonId::Square => self.square,
+            ButtonId::R3 => self.r3,
+            ButtonId::L3 => self.l3,
+            ButtonId::Options => self.options,
+            ButtonId::Share => self.share,
+            ButtonId::R2 => self.r2,
+            ButtonId::L2 => self.l2,
+            ButtonId::R1 => self.r1,
+            ButtonId::L1 => self.l1,
+            ButtonId::Tpad => self.tpad,
+            ButtonId::Ps => self.ps,
+        }
+    }
+
+    pub fn set_pressed(&mut self, id: ButtonId, pressed: bool) {
+        let field = match id {
+            ButtonId::Triangle => &mut self.triangle,
+            ButtonId::Circle => &mut self.circle,
+            ButtonId::X => &mut self.x,
+            ButtonId::Square => &mut self.square,
+            ButtonId::R3 => &mut self.r3,
+            ButtonId::L3 => &mut self.l3,
+            ButtonId::Options => &mut self.options,
+            ButtonId::Share => &mut self.share,
+            ButtonId::R2 => &mut self.r2,
+            ButtonId::L2 => &mut self.l2,
+            ButtonId::R1 => &mut self.r1,
+            ButtonId::L1 => &mut self.l1,
+            ButtonId::Tpad => &mut self.tpad,
+            ButtonId::Ps => &mut self.ps,
+        };
+        *field = pressed;
+    }
+
+    /// Sticks in `-1.0..=1.0` (raw convention, Y down), triggers in `0.0..=1.0`.
+    pub fn axis(&self, id: AxisId) -> f32 {
+        match id {
+            AxisId::LeftX => self.left_stick.x_f32(),
+            AxisId::LeftY => self.left_stick.y_f32(),
+            AxisId::RightX => self.right_stick.x_f32(),
+            AxisId::RightY => self.right_stick.y_f32(),
+            AxisId::L2 => self.l2_value as f32 / 255.0,
+            AxisId::R2 => self.r2_value as f32 / 255.0,
+        }
+    }
+}
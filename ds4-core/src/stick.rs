@@ -0,0 +1,91 @@
+/// Which way positive Y points in a centered stick value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum YAxis {
+    /// Up is positive, as in maths and most game engines.
+    #[default]
+    Up,
+    /// Down is positive, as in the raw report and screen coordinates.
+    Down,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stick {
+    pub x: u8,
+    pub y: u8,
+}
+
+impl Default for Stick {
+    fn default() -> Self {
+        Stick { x: 0x80, y: 0x80 }
+    }
+}
+
+impl Stick {
+    pub fn new(x: u8, y: u8) -> Self {
+        Stick { x, y }
+    }
+
+    /// Horizontal deflection in `-1.0..=1.0`, right positive.
+    pub fn x_f32(&self) -> f32 {
+        axis_to_f32(self.x)
+    }
+
+    /// Vertical deflection in `-1.0..=1.0`, down positive (the raw report convention).
+    pub fn y_f32(&self) -> f32 {
+        axis_to_f32(self.y)
+    }
+
+    /// `(x, y)` in `-1.0..=1.0` with Y pointing the requested way.
+    pub fn centered(&self, y_axis: YAxis) -> (f32, f32) {
+        (self.x_f32(), flip(y_axis, self.y_f32()))
+    }
+
+    /// `(x, y)` in `-32767..=32767` with Y pointing the requested way, the range
+    /// used by evdev and XInput.
+    pub fn centered_i16(&self, y_axis: YAxis) -> (i16, i16) {
+        let (x, y) = self.centered(y_axis);
+        ((x * 32767.0) as i16, (y * 32767.0) as i16)
+    }
+
+    /// Builds a stick from centered `-1.0..=1.0` values, the inverse of [`Stick::centered`].
+    pub fn from_centered(x: f32, y: f32, y_axis: YAxis) -> Self {
+        Stick {
+            x: f32_to_axis(x),
+            y: f32_to_axis(flip(y_axis, y)),
+        }
+    }
+
+    /// Magnitude and angle in radians, counter-clockwise from right with Y up.
+    /// The raw gate is square-ish, so diagonals can reach a magnitude above 1.
+    #[cfg(feature = "std")]
+    pub fn polar(&self) -> (f32, f32) {
+        let (x, y) = self.centered(YAxis::Up);
+        (x.hypot(y), y.atan2(x))
+    }
+
+    /// `(x, y)` with Y up, scaled back onto the unit circle if outside it.
+    #[cfg(feature = "std")]
+    pub fn clamped(&self) -> (f32, f32) {
+        let (r, theta) = self.polar();
+        let r = r.min(1.0);
+        (r * theta.cos(), r * theta.sin())
+    }
+}
+
+fn axis_to_f32(v: u8) -> f32 {
+    ((v as f32 - 128.0) / 127.0).clamp(-1.0, 1.0)
+}
+
+fn f32_to_axis(v: f32) -> u8 {
+    // Always positive, so truncating after adding 0.5 rounds to nearest.
+    (v.clamp(-1.0, 1.0) * 127.0 + 128.5) as u8
+}
+
+fn flip(y_axis: YAxis, y: f32) -> f32 {
+    match y_axis {
+        YAxis::Up => -y,
+        YAxis::Down => y,
+    }
+}
@@ -0,0 +1,175 @@
+use crate::TICK_SECS;
+
+pub const TOUCH_OFFSET: usize = 33;
+const PACKET_LEN: usize = 9;
+pub const MAX_TOUCH_PACKETS: usize = 4;
+
+pub const TOUCHPAD_WIDTH: u16 = 1920;
+pub const TOUCHPAD_HEIGHT: u16 = 943;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Touch {
+    pub active: bool,
+    /// Incremented by the pad for each new finger contact (7 bits).
+    pub id: u8,
+    /// `0..TOUCHPAD_WIDTH`.
+    pub x: u16,
+    /// `0..TOUCHPAD_HEIGHT`.
+    pub y: u16,
+}
+
+impl Touch {
+    fn parse(b: &[u8]) -> Self {
+        Touch {
+            active: b[0] & 0x80 == 0,
+            id: b[0] & 0x7f,
+            x: b[1] as u16 | ((b[2] as u16 & 0x0f) << 8),
+            y: (b[2] as u16 >> 4) | ((b[3] as u16) << 4),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TouchPacket {
+    /// Hardware sequence counter, wrapping at 256.
+    pub counter: u8,
+    pub fingers: [Touch; 2],
+}
+
+/// All touch packets carried by one input report, oldest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TouchPackets {
+    len: usize,
+    packets: [TouchPacket; MAX_TOUCH_PACKETS],
+}
+
+impl TouchPackets {
+    /// Parses the touch section of a USB-aligned input report.
+    pub fn parse(data: &[u8]) -> Self {
+        let mut out = TouchPackets::default();
+        let Some(&count) = data.get(TOUCH_OFFSET) else {
+            return out;
+        };
+        for i in 0..(count as usize).min(MAX_TOUCH_PACKETS) {
+            let start = TOUCH_OFFSET + 1 + i * PACKET_LEN;
+            let Some(b) = data.get(start..start + PACKET_LEN) else {
+                break;
+            };
+            out.packets[out.len] = TouchPacket {
+                counter: b[0],
+                fingers: [Touch::parse(&b[1..5]), Touch::parse(&b[5..9])],
+            };
+            out.len += 1;
+        }
+        if let Some(first) = out.packets.first().map(|p| p.counter) {
+            out.packets[..out.len].sort_unstable_by_key(|p| p.counter.wrapping_sub(first) as i8);
+        }
+        out
+    }
+
+    pub fn as_slice(&self) -> &[TouchPacket] {
+        &self.packets[..self.len]
+    }
+
+    pub fn latest(&self) -> Option<&TouchPacket> {
+        self.as_slice().last()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// One finger with its motion measured against the controller's own clock.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TouchMotion {
+    pub touch: Touch,
+    /// Touchpad units per second; zero until the same contact has been seen twice.
+    pub velocity: (f32, f32),
+    /// Touchpad units per second squared; zero until the contact has been seen three times.
+    pub acceleration: (f32, f32),
+}
+
+/// Derives per-finger velocity and acceleration from consecutive touch packets.
+///
+/// Packets already seen in an earlier report are skipped by their counter, and the
+/// device time elapsed since the last report is split evenly across the new ones.
+#[derive(Debug, Clone, Default)]
+pub struct TouchTracker {
+    last_counter: Option<u8>,
+    last_timestamp: Option<u16>,
+    fingers: [TouchMotion; 2],
+    samples: [u8; 2],
+}
+
+impl TouchTracker {
+    pub fn update(&mut self, packets: &TouchPackets, timestamp: u16) {
+        let elapsed = self
+            .last_timestamp
+            .map_or(0, |last| timestamp.wrapping_sub(last));
+        self.last_timestamp = Some(timestamp);
+
+        let last_counter = self.last_counter;
+        let is_new = |p: &&TouchPacket| {
+            last_counter.is_none_or(|last| p.counter.wrapping_sub(last) as i8 > 0)
+        };
+        let count = packets.as_slice().iter().filter(is_new).count();
+        if count == 0 {
+            return;
+        }
+        let dt = ticks_to_secs(elapsed) / count as f32;
+        for packet in packets.as_slice().iter().filter(is_new) {
+            for i in 0..2 {
+                self.advance(i, packet.fingers[i], dt);
+            }
+            self.last_counter = Some(packet.counter);
+        }
+    }
+
+    pub fn fingers(&self) -> &[TouchMotion; 2] {
+        &self.fingers
+    }
+
+    fn advance(&mut self, i: usize, touch: Touch, dt: f32) {
+        let prev = self.fingers[i];
+        let same_contact = touch.active && prev.touch.active && touch.id == prev.touch.id;
+        if !same_contact || dt <= 0.0 {
+            self.fingers[i] = TouchMotion {
+                touch,
+                ..TouchMotion::default()
+            };
+            self.samples[i] = touch.active as u8;
+            return;
+        }
+        let velocity = (
+            (touch.x as f32 - prev.touch.x as f32) / dt,
+            (touch.y as f32 - prev.touch.y as f32) / dt,
+        );
+        let acceleration = if self.samples[i] >= 2 {
+            (
+                (velocity.0 - prev.velocity.0) / dt,
+                (velocity.1 - prev.velocity.1) / dt,
+            )
+        } else {
+            (0.0, 0.0)
+        };
+        self.fingers[i] = TouchMotion {
+            touch,
+            velocity,
+            acceleration,
+        };
+        self.samples[i] = self.samples[i].saturating_add(1);
+    }
+}
+
+fn ticks_to_secs(ticks: u16) -> f32 {
+    (ticks as f64 * TICK_SECS) as f32
+}
@@ -0,0 +1,80 @@
+use crate::{ButtonFlags, ButtonId, DPad, ParseError, Snapshot, Stick};
+
+/// Current version of the wire format, carried in the first byte.
+pub const WIRE_VERSION: u8 = 1;
+pub const WIRE_LEN: usize = 10;
+
+/// Encodes the full control state in `WIRE_LEN` bytes, version 1:
+///
+/// | byte | contents |
+/// |------|----------|
+/// | 0    | `WIRE_VERSION` |
+/// | 1-2  | buttons, little-endian, bit `n` for `ButtonId::ALL[n]`; bits 14-15 are zero |
+/// | 3    | dpad in the low nibble, as in input reports; high nibble zero |
+/// | 4-7  | left x, left y, right x, right y, raw |
+/// | 8-9  | L2, R2, raw |
+///
+/// `decode(&encode(s)) == Ok(s)`, and `decode` rejects anything `encode` can't
+/// produce, so every accepted message has exactly one meaning.
+pub fn encode(snapshot: &Snapshot) -> [u8; WIRE_LEN] {
+    let [b0, b1] = buttons(snapshot).to_le_bytes();
+    [
+        WIRE_VERSION,
+        b0,
+        b1,
+        snapshot.dpad.to_byte(),
+        snapshot.left_stick.x,
+        snapshot.left_stick.y,
+        snapshot.right_stick.x,
+        snapshot.right_stick.y,
+        snapshot.l2_value,
+        snapshot.r2_value,
+    ]
+}
+
+/// Decodes a message from `encode`. Trailing bytes are ignored so later versions
+/// can append fields.
+pub fn decode(bytes: &[u8]) -> Result<Snapshot, ParseError> {
+    let b: &[u8; WIRE_LEN] = bytes
+        .get(..WIRE_LEN)
+        .and_then(|b| b.try_into().ok())
+        .ok_or(ParseError::ShortReport {
+            len: bytes.len(),
+            expected: WIRE_LEN,
+        })?;
+    if b[0] != WIRE_VERSION {
+        return Err(ParseError::UnsupportedVersion(b[0]));
+    }
+    let pressed = u16::from_le_bytes([b[1], b[2]]);
+    if pressed >> ButtonId::ALL.len() != 0 || b[3] & 0xf0 != 0 {
+        return Err(ParseError::ReservedBits);
+    }
+    let mut snapshot = Snapshot {
+        dpad: DPad::from_byte(b[3])?,
+        left_stick: Stick::new(b[4], b[5]),
+        right_stick: Stick::new(b[6], b[7]),
+        l2_value: b[8],
+        r2_value: b[9],
+        ..Snapshot::default()
+    };
+    for (i, id) in ButtonId::ALL.into_iter().enumerate() {
+        snapshot.set_pressed(id, pressed & (1 << i) != 0);
+    }
+    Ok(snapshot)
+}
+
+fn buttons(snapshot: &Snapshot) -> u16 {
+    (snapshot.buttons() & ButtonFlags::BUTTONS).bits() as u16
+}
+
+impl Snapshot {
+    /// See [`encode`].
+    pub fn to_wire(&self) -> [u8; WIRE_LEN] {
+        encode(self)
+    }
+
+    /// See [`decode`].
+    pub fn from_wire(bytes: &[u8]) -> Result<Snapshot, ParseError> {
+        decode(bytes)
+    }
+}
@@ -0,0 +1,109 @@
+use ds4_core::wire::{decode, encode, WIRE_LEN, WIRE_VERSION};
+use ds4_core::{ButtonId, DPad, ParseError, Snapshot, Stick};
+use proptest::prelude::*;
+
+fn snapshot() -> impl Strategy<Value = Snapshot> {
+    (0u16..1 << ButtonId::ALL.len(), 0u8..=8, any::<[u8; 6]>()).prop_map(|(pressed, dpad, a)| {
+        let mut snapshot = Snapshot {
+            dpad: DPad::from_byte(dpad).unwrap_or_default(),
+            left_stick: Stick::new(a[0], a[1]),
+            right_stick: Stick::new(a[2], a[3]),
+            l2_value: a[4],
+            r2_value: a[5],
+            ..Snapshot::default()
+        };
+        for (i, id) in ButtonId::ALL.into_iter().enumerate() {
+            snapshot.set_pressed(id, pressed & (1 << i) != 0);
+        }
+        snapshot
+    })
+}
+
+fn message() -> [u8; WIRE_LEN] {
+    encode(&Snapshot::default())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(20_000))]
+
+    #[test]
+    fn encode_then_decode_round_trips(s in snapshot()) {
+        prop_assert_eq!(decode(&encode(&s)), Ok(s));
+    }
+
+    /// Anything accepted re-encodes to the same bytes, so one message can't
+    /// have two spellings.
+    #[test]
+    fn decode_is_canonical(bytes in proptest::collection::vec(any::<u8>(), 0..32)) {
+        if let Ok(s) = decode(&bytes) {
+            prop_assert_eq!(&encode(&s)[..], &bytes[..WIRE_LEN]);
+        }
+    }
+
+    /// Mostly well-formed messages with a few bits flipped, which random bytes
+    /// rarely reach past the version check.
+    #[test]
+    fn decode_survives_bit_flips(s in snapshot(), flips in proptest::collection::vec((0..WIRE_LEN, 0u8..8), 1..4)) {
+        let mut bytes = encode(&s);
+        for (at, bit) in flips {
+            bytes[at] ^= 1 << bit;
+        }
+        if let Ok(decoded) = decode(&bytes) {
+            prop_assert_eq!(encode(&decoded), bytes);
+        }
+    }
+}
+
+#[test]
+fn rejects_short_input() {
+    let bytes = message();
+    for len in 0..WIRE_LEN {
+        assert_eq!(
+            decode(&bytes[..len]),
+            Err(ParseError::ShortReport {
+                len,
+                expected: WIRE_LEN
+            })
+        );
+    }
+}
+
+#[test]
+fn ignores_trailing_bytes() {
+    let mut bytes = message().to_vec();
+    bytes.extend_from_slice(&[0xff; 4]);
+    assert_eq!(decode(&bytes), Ok(Snapshot::default()));
+}
+
+#[test]
+fn rejects_unknown_versions() {
+    for version in (0..=u8::MAX).filter(|&v| v != WIRE_VERSION) {
+        let mut bytes = message();
+        bytes[0] = version;
+        assert_eq!(decode(&bytes), Err(ParseError::UnsupportedVersion(version)));
+    }
+}
+
+#[test]
+fn rejects_reserved_bits() {
+    for bit in ButtonId::ALL.len()..16 {
+        let mut bytes = message();
+        let pressed = u16::from_le_bytes([bytes[1], bytes[2]]) | 1 << bit;
+        bytes[1..3].copy_from_slice(&pressed.to_le_bytes());
+        assert_eq!(decode(&bytes), Err(ParseError::ReservedBits));
+    }
+    for bit in 4..8 {
+        let mut bytes = message();
+        bytes[3] |= 1 << bit;
+        assert_eq!(decode(&bytes), Err(ParseError::ReservedBits));
+    }
+}
+
+#[test]
+fn rejects_invalid_dpad() {
+    for value in 9..16 {
+        let mut bytes = message();
+        bytes[3] = value;
+        assert_eq!(decode(&bytes), Err(ParseError::InvalidDPad(value)));
+    }
+}
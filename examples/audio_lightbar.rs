@@ -0,0 +1,22 @@
+use hidapi::HidApi;
+use ps4hid::audio::PcmSource;
+use ps4hid::lightbar::{AudioVisualizer, Lightbar, VisualizerMode};
+use ps4hid::Controller;
+use std::io;
+use std::time::Instant;
+
+// Usage: parec --format=s16le --channels=1 --rate=48000 | cargo run --example audio_lightbar
+fn main() {
+    let api = HidApi::new().unwrap();
+    let mut controller = Controller::open(&api).expect("Couldn't open controller");
+
+    let source = PcmSource::new(io::stdin().lock(), 48000, 1);
+    let visualizer = AudioVisualizer::new(Box::new(source), VisualizerMode::Spectrum);
+    let mut lightbar = Lightbar::new(Box::new(visualizer));
+
+    loop {
+        lightbar
+            .update(&mut controller, Instant::now())
+            .expect("failed to update lightbar");
+    }
+}
@@ -0,0 +1,38 @@
+use hidapi::HidApi;
+use ps4hid::audio::{AudioRumble, PcmSource, SampleSource, WavSource};
+use ps4hid::{Controller, RateLimiter};
+use std::env;
+use std::io;
+use std::time::Duration;
+
+// Usage: cargo run --example audio_rumble <file.wav>
+//    or: parec --format=s16le --channels=1 --rate=48000 | cargo run --example audio_rumble -
+fn main() {
+    let arg = env::args()
+        .nth(1)
+        .expect("usage: audio_rumble <file.wav | ->");
+    let mut source: Box<dyn SampleSource> = if arg == "-" {
+        Box::new(PcmSource::new(io::stdin().lock(), 48000, 1))
+    } else {
+        Box::new(WavSource::open(&arg).expect("Couldn't open WAV file"))
+    };
+
+    let api = HidApi::new().unwrap();
+    let mut controller = Controller::open(&api).expect("Couldn't open controller");
+
+    const UPDATES_PER_SEC: u32 = 60;
+    let chunk = (source.sample_rate() / UPDATES_PER_SEC) as usize;
+    let mut rumble = AudioRumble::new(source.sample_rate());
+    let mut rl = RateLimiter::new(Duration::from_secs(1) / UPDATES_PER_SEC);
+
+    while let Some((strong, weak)) = rumble.pull(source.as_mut(), chunk).expect("read failed") {
+        // Reading from stdin is paced by the audio server; only throttle files.
+        if arg != "-" {
+            rl.wait();
+        }
+        controller
+            .set_rumble(strong, weak)
+            .expect("failed to set rumble");
+    }
+    controller.set_rumble(0, 0).expect("failed to set rumble");
+}
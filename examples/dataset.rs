@@ -0,0 +1,44 @@
+use hidapi::HidApi;
+use ps4hid::dataset::DatasetWriter;
+use ps4hid::Controller;
+use std::time::{Duration, Instant};
+
+// Captures motion and input for the given number of seconds (default 60) into
+// capture.parquet, or capture.arrow with `--arrow`.
+//
+//     cargo run --example dataset --features parquet -- 3600
+fn main() {
+    let arrow = std::env::args().any(|a| a == "--arrow");
+    let seconds = std::env::args()
+        .skip(1)
+        .find(|a| a != "--arrow")
+        .map_or(60, |s| s.parse().expect("expected a number of seconds"));
+
+    let api = HidApi::new().unwrap();
+    let mut controller = Controller::open(&api).expect("Couldn't open controller");
+    let (path, writer) = if arrow {
+        ("capture.arrow", DatasetWriter::arrow_ipc("capture.arrow"))
+    } else {
+        ("capture.parquet", DatasetWriter::parquet("capture.parquet"))
+    };
+    let mut writer = writer.expect("failed to create capture file");
+
+    let start = Instant::now();
+    let mut last = controller.sampled_at();
+    while start.elapsed() < Duration::from_secs(seconds) {
+        controller.update().expect("failed to update controller");
+        if controller.sampled_at() != last {
+            last = controller.sampled_at();
+            writer
+                .record(
+                    &controller.controls,
+                    controller.device_clock().device_time(),
+                )
+                .expect("failed to write capture");
+        }
+    }
+
+    let rows = writer.len();
+    writer.finish().expect("failed to finish capture");
+    println!("wrote {} rows to {}", rows, path);
+}
@@ -0,0 +1,43 @@
+/* Prints the sticks and buttons of the first DS4 through the C API, and
+ * rumbles while Cross is held.
+ *
+ *     cargo build --release --features ffi
+ *     cc -Iinclude examples/ffi_poll.c -Ltarget/release -lps4hid -o ffi_poll
+ */
+#include "ps4hid.h"
+#include <stdio.h>
+
+int main(void) {
+    if (ds4_abi_version() != DS4_ABI_VERSION) {
+        fprintf(stderr, "libps4hid doesn't match ps4hid.h\n");
+        return 1;
+    }
+    Ds4Controller *pad = ds4_open();
+    if (!pad) {
+        fprintf(stderr, "%s\n", ds4_last_error());
+        return 1;
+    }
+    ds4_set_lightbar(pad, 0, 0, 64);
+    Ds4State state;
+    int rc;
+    while ((rc = ds4_poll(pad, &state)) == DS4_OK || rc == DS4_ERR_MALFORMED_REPORT) {
+        if (rc != DS4_OK) {
+            continue;
+        }
+        printf("\rL %3u,%3u  R %3u,%3u  L2 %3u  R2 %3u  buttons %06x",
+               state.left_x, state.left_y, state.right_x, state.right_y,
+               state.l2, state.r2, state.buttons);
+        fflush(stdout);
+        uint8_t speed = (state.buttons & DS4_BUTTON_CROSS) ? 255 : 0;
+        ds4_set_rumble(pad, speed, speed);
+        if (state.buttons & DS4_BUTTON_PS) {
+            break;
+        }
+    }
+    printf("\n");
+    if (rc != DS4_OK) {
+        fprintf(stderr, "%s\n", ds4_last_error());
+    }
+    ds4_close(pad);
+    return rc == DS4_OK ? 0 : 1;
+}
@@ -0,0 +1,36 @@
+// Logs input events from the browser Gamepad API to the console.
+//
+// Build: cargo build --example gamepad_web --target wasm32-unknown-unknown \
+//            --no-default-features --features gamepad
+// then load the output with wasm-bindgen's `--target web` glue.
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    use ps4hid::gamepad::GamepadSource;
+    use ps4hid::source::InputSource;
+    use wasm_bindgen::closure::Closure;
+    use wasm_bindgen::JsCast;
+
+    let mut source = GamepadSource::new();
+    let poll = Closure::<dyn FnMut()>::new(move || loop {
+        match source.next_event() {
+            Ok(Some(event)) => web_sys::console::log_1(&format!("{:?}", event).into()),
+            Ok(None) => break,
+            Err(e) => {
+                web_sys::console::error_1(&e.to_string().into());
+                break;
+            }
+        }
+    });
+    let window = web_sys::window().expect("no window");
+    window
+        .set_interval_with_callback_and_timeout_and_arguments_0(poll.as_ref().unchecked_ref(), 16)
+        .expect("failed to start polling");
+    // The interval runs for the page's lifetime.
+    poll.forget();
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    eprintln!("this example runs in the browser; build it for wasm32-unknown-unknown");
+}
@@ -0,0 +1,37 @@
+use hidapi::HidApi;
+use ps4hid::heatmap::Heatmaps;
+use ps4hid::Controller;
+use std::time::{Duration, Instant};
+
+// Records touchpad and stick heatmaps for the given number of seconds (default
+// 60), then writes CSV and PNG files into heatmaps/.
+//
+//     cargo run --example heatmap -- 300
+fn main() {
+    let seconds = std::env::args()
+        .nth(1)
+        .map_or(60, |s| s.parse().expect("expected a number of seconds"));
+
+    let api = HidApi::new().unwrap();
+    let mut controller = Controller::open(&api).expect("Couldn't open controller");
+    let mut heatmaps = Heatmaps::new();
+
+    let start = Instant::now();
+    let mut last = controller.sampled_at();
+    while start.elapsed() < Duration::from_secs(seconds) {
+        controller.update().expect("failed to update controller");
+        // Only count new reports so the maps measure time, not loop iterations.
+        if controller.sampled_at() != last {
+            last = controller.sampled_at();
+            heatmaps.record(&controller.controls);
+        }
+    }
+
+    heatmaps
+        .write("heatmaps", 8)
+        .expect("failed to write heatmaps");
+    println!(
+        "wrote heatmaps/ from {} reports",
+        heatmaps.left_stick.total()
+    );
+}
@@ -0,0 +1,33 @@
+// Drives the usual handlers from the terminal keyboard instead of a controller:
+// WASD moves the left stick, arrows the dpad, IJKL are the face buttons.
+//
+// Usage: cargo run --example keyboard
+
+#[cfg(target_os = "linux")]
+fn main() {
+    use ps4hid::keyboard::KeyboardSource;
+
+    let mut keyboard = KeyboardSource::new().expect("stdin is not a terminal");
+    keyboard.controls.x.set_handler(|_, pressed| {
+        if pressed {
+            println!("X PRESSED");
+        }
+    });
+    keyboard
+        .controls
+        .dpad
+        .set_handler(|old, new| println!("dpad: {:?} => {:?}", old, new));
+    let sticks = keyboard.controls.left_stick.stream();
+
+    loop {
+        keyboard.update().expect("failed to read keyboard");
+        for stick in sticks.try_iter() {
+            println!("left stick: ({:+.2}, {:+.2})", stick.x_f32(), stick.y_f32());
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn main() {
+    eprintln!("the keyboard source is only available on Linux");
+}
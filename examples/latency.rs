@@ -0,0 +1,21 @@
+use hidapi::HidApi;
+use ps4hid::latency::LatencyTester;
+use ps4hid::Controller;
+
+// Press X repeatedly; each press toggles the lightbar and records the
+// output-write to next-input-report round trip.
+fn main() {
+    let api = HidApi::new().unwrap();
+    let mut controller = Controller::open(&api).expect("Couldn't open controller");
+    let mut tester = LatencyTester::new(|controls| controls.x.state());
+
+    loop {
+        controller.update().expect("failed to update controller");
+        let sample = tester
+            .update(&mut controller)
+            .expect("failed to write output report");
+        if sample.is_some() {
+            print!("{}", tester.histogram);
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use hidapi::HidApi;
+use ps4hid::macros::{MacroForwarder, MacroPlayer, MacroRecorder};
+use ps4hid::virtual_pad::UinputPad;
+use ps4hid::{ButtonId, Controller};
+use std::time::Instant;
+
+// Forwards the pad to a uinput device. Hold Share to record a macro, then
+// press Options to replay it three times at double speed.
+fn main() {
+    let api = HidApi::new().unwrap();
+    let mut controller = Controller::open(&api).expect("Couldn't open controller");
+    let pad = UinputPad::create("DS4 macro pad").expect("Couldn't create uinput device");
+
+    let mut recorder: Option<MacroRecorder> = None;
+    let mut forwarder =
+        MacroForwarder::new(pad, MacroPlayer::new(Default::default()), ButtonId::Options);
+
+    loop {
+        controller.update().expect("failed to update controller");
+        let now = Instant::now();
+        let live = controller.controls.snapshot();
+
+        if live.share {
+            recorder
+                .get_or_insert_with(MacroRecorder::new)
+                .record(now, live);
+        } else if let Some(done) = recorder.take() {
+            let mut player = MacroPlayer::new(done.finish());
+            player.loops = 3;
+            player.speed = 2.0;
+            forwarder.player = player;
+            println!("macro recorded");
+        }
+
+        forwarder
+            .update(now, &live)
+            .expect("failed to write virtual pad");
+    }
+}
@@ -0,0 +1,44 @@
+// Logs the sticks and pressed buttons of the first DS4 and shows the battery
+// level on the lightbar, the way an Electron overlay would from its main
+// process. Build the addon first with `npm run build`.
+const { Controller } = require('..');
+
+async function main() {
+  const pad = new Controller();
+  console.log('connected over', pad.connection);
+  let battery = null;
+  while (pad.isOpen) {
+    let state;
+    try {
+      state = await pad.update();
+    } catch (e) {
+      if (!pad.isOpen) {
+        console.log('\ndisconnected');
+        break;
+      }
+      continue;
+    }
+    for (const event of pad.events()) {
+      if (event.kind === 'OutputStalled') {
+        console.log(`\noutput stalled after ${event.failures} failures: ${event.error}`);
+      } else {
+        console.log('\n' + event.kind);
+      }
+    }
+    if (state.batteryPercent != null && state.batteryPercent !== battery) {
+      battery = state.batteryPercent;
+      const level = Math.round((battery / 100) * 255);
+      pad.setLightbar(255 - level, level, 0);
+    }
+    process.stdout.write(
+      `\rL ${state.leftX},${state.leftY}  R ${state.rightX},${state.rightY}  ` +
+        state.buttons.join(' ').padEnd(40),
+    );
+    if (state.buttons.includes('ps')) {
+      pad.close();
+    }
+  }
+  console.log();
+}
+
+main();
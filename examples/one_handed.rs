@@ -0,0 +1,21 @@
+use hidapi::HidApi;
+use ps4hid::one_handed::OneHanded;
+use ps4hid::virtual_pad::{UinputPad, VirtualPad};
+use ps4hid::Controller;
+
+// Forwards the pad to a uinput device laid out for the left hand alone: click
+// the touchpad quadrants for the face buttons, turn the pad to aim, L1 to fire.
+fn main() {
+    let api = HidApi::new().unwrap();
+    let mut controller = Controller::open(&api).expect("Couldn't open controller");
+    // Keep the pad's saved calibration; take only the remap.
+    controller.profile_mut().remap = OneHanded::profile().remap;
+    let mut pad = UinputPad::create("DS4 one-handed").expect("Couldn't create uinput device");
+    let layout = OneHanded::new();
+
+    loop {
+        controller.update().expect("failed to update controller");
+        pad.emit(&layout.snapshot(&controller))
+            .expect("failed to write virtual pad");
+    }
+}
@@ -0,0 +1,23 @@
+# Prints the sticks and pressed buttons of the first DS4, rumbles while X is
+# held, and stops on PS. Build the module first with `maturin develop`.
+import ps4hid
+
+with ps4hid.Controller() as pad:
+    print("connected over", pad.connection)
+    pad.set_lightbar(0, 0, 64)
+    while True:
+        try:
+            state = pad.update()
+        except ps4hid.DisconnectedError:
+            print("\ndisconnected")
+            break
+        except ps4hid.Ds4Error:
+            continue
+        for event in pad.events():
+            print("\n", event)
+        print(f"\rL {state.left_stick}  R {state.right_stick}  {' '.join(state.buttons):40}", end="")
+        speed = 255 if state.pressed("x") else 0
+        pad.set_rumble(speed, speed)
+        if state.pressed("ps"):
+            print()
+            break
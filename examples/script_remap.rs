@@ -0,0 +1,27 @@
+use hidapi::HidApi;
+use ps4hid::scripting::Script;
+use ps4hid::virtual_pad::{UinputPad, VirtualPad};
+use ps4hid::Controller;
+use std::env;
+
+// Usage: cargo run --features scripting --example script_remap <bindings.rhai>
+// Runs the script's `frame(pad)` on every report and forwards the result to a
+// uinput virtual pad.
+fn main() {
+    let path = env::args()
+        .nth(1)
+        .expect("usage: script_remap <script.rhai>");
+    let mut script = Script::load(&path).expect("Couldn't load script");
+
+    let api = HidApi::new().unwrap();
+    let mut controller = Controller::open(&api).expect("Couldn't open controller");
+    let mut pad = UinputPad::create("DS4 scripted").expect("Couldn't create uinput device");
+
+    loop {
+        controller.update().expect("failed to update controller");
+        match script.frame_controller(&controller) {
+            Ok(out) => pad.emit(&out).expect("failed to write virtual pad"),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+}
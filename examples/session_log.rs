@@ -0,0 +1,31 @@
+use hidapi::HidApi;
+use ps4hid::logger::{LogFormat, LoggingSource, SessionLogger};
+use ps4hid::source::InputSource;
+use ps4hid::Controller;
+use std::time::Duration;
+
+// Logs every input event, plus a snapshot each second, to logs/session.jsonl (or
+// .csv with `--csv`), rotating at 10 MB and keeping five old files. Stop with
+// Ctrl-C.
+//
+//     cargo run --example session_log -- --csv
+fn main() {
+    let csv = std::env::args().any(|a| a == "--csv");
+    let (path, format) = if csv {
+        ("logs/session.csv", LogFormat::Csv)
+    } else {
+        ("logs/session.jsonl", LogFormat::JsonLines)
+    };
+
+    let api = HidApi::new().unwrap();
+    let controller = Controller::open(&api).expect("Couldn't open controller");
+    let logger = SessionLogger::create(path, format)
+        .expect("failed to create log file")
+        .rotate(10_000_000, 5);
+    let mut source = LoggingSource::new(controller, logger).snapshot_every(Duration::from_secs(1));
+    println!("logging to {}", path);
+
+    loop {
+        source.next_event().expect("failed to read controller");
+    }
+}
@@ -0,0 +1,41 @@
+use ps4hid::shm::{self, SharedStateReader};
+use std::io::{self, Write};
+use std::time::Duration;
+
+// Follows the input state `ds4d --shm` publishes, redrawing a one-line HUD
+// whenever a new frame arrives. Reads never block the daemon.
+//
+//     cargo run --example shm_hud --features shm
+fn main() {
+    let path = shm::path("ds4d");
+    let reader = SharedStateReader::open(&path)
+        .unwrap_or_else(|e| panic!("couldn't open {}: {}", path.display(), e));
+    let mut last = None;
+    loop {
+        let state = reader
+            .read()
+            .expect("writer stopped part way through a write");
+        if last != Some(state.frame) {
+            last = Some(state.frame);
+            let s = &state.snapshot;
+            if state.connected {
+                print!(
+                    "\r#{:<8} L({:>3},{:>3}) R({:>3},{:>3}) L2 {:>3} R2 {:>3} {:?} battery {:?}   ",
+                    state.frame,
+                    s.left_stick.x,
+                    s.left_stick.y,
+                    s.right_stick.x,
+                    s.right_stick.y,
+                    s.l2_value,
+                    s.r2_value,
+                    s.dpad,
+                    state.battery_percent
+                );
+            } else {
+                print!("\r{:<80}", "no controller");
+            }
+            let _ = io::stdout().flush();
+        }
+        std::thread::sleep(Duration::from_millis(16));
+    }
+}
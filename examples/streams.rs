@@ -0,0 +1,28 @@
+use hidapi::HidApi;
+use ps4hid::Controller;
+use std::thread;
+
+// Each control stream is consumed on its own thread while the main thread
+// keeps polling the controller.
+fn main() {
+    let api = HidApi::new().unwrap();
+    let mut controller = Controller::open(&api).expect("Couldn't open controller");
+
+    let left_stick = controller.controls.left_stick.stream();
+    let cross = controller.controls.x.stream();
+
+    thread::spawn(move || {
+        for stick in left_stick {
+            println!("left stick: ({:+.2}, {:+.2})", stick.x_f32(), stick.y_f32());
+        }
+    });
+    thread::spawn(move || {
+        for _ in cross.iter().filter(|&pressed| pressed) {
+            println!("X pressed");
+        }
+    });
+
+    loop {
+        controller.update().expect("failed to update controller");
+    }
+}
@@ -0,0 +1,19 @@
+use hidapi::HidApi;
+use ps4hid::tilt::TiltStick;
+use ps4hid::virtual_pad::{UinputPad, VirtualPad};
+use ps4hid::Controller;
+
+// Forwards the pad to a uinput device whose right stick follows tilt: click R3
+// to turn it on, holding the pad level, then tip it to look around.
+fn main() {
+    let api = HidApi::new().unwrap();
+    let mut controller = Controller::open(&api).expect("Couldn't open controller");
+    let mut pad = UinputPad::create("DS4 tilt").expect("Couldn't create uinput device");
+    let mut tilt = TiltStick::new().range(25.0).curve(1.5);
+
+    loop {
+        controller.update().expect("failed to update controller");
+        pad.emit(&tilt.snapshot(&controller))
+            .expect("failed to write virtual pad");
+    }
+}
@@ -0,0 +1,46 @@
+use hidapi::HidApi;
+use ps4hid::trace::{Channel, Trace};
+use ps4hid::Controller;
+use std::time::{Duration, Instant};
+
+// Records the given channels (default `lx ly gz`) for ten seconds, printing
+// sparklines as it goes, then writes trace.svg.
+//
+//     cargo run --example trace -- rx ry
+fn main() {
+    let mut channels: Vec<Channel> = std::env::args()
+        .skip(1)
+        .map(|name| name.parse().expect("unknown channel"))
+        .collect();
+    if channels.is_empty() {
+        channels = ["lx", "ly", "gz"]
+            .iter()
+            .filter_map(|n| n.parse().ok())
+            .collect();
+    }
+
+    let api = HidApi::new().unwrap();
+    let mut controller = Controller::open(&api).expect("Couldn't open controller");
+    let mut trace = Trace::new(channels);
+
+    let start = Instant::now();
+    let mut printed = start;
+    while start.elapsed() < Duration::from_secs(10) {
+        controller.update().expect("failed to update controller");
+        trace.record(
+            &controller.controls,
+            controller.device_clock().device_time(),
+        );
+        if printed.elapsed() > Duration::from_millis(250) {
+            printed = Instant::now();
+            print!("\x1b[2J\x1b[H{}", trace.sparklines(72));
+        }
+    }
+
+    std::fs::write(
+        "trace.svg",
+        trace.to_svg(1200, 200 * trace.channels().len() as u32),
+    )
+    .expect("failed to write trace.svg");
+    println!("wrote {} samples to trace.svg", trace.len());
+}
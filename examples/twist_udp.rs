@@ -0,0 +1,42 @@
+use hidapi::HidApi;
+use ps4hid::twist::{Twist, TwistConfig};
+use ps4hid::{Controller, RateLimiter};
+use std::env;
+use std::net::UdpSocket;
+use std::time::Duration;
+
+// Usage: cargo run --example twist_udp [target addr, default 127.0.0.1:9870]
+// Hold L1 (the deadman button) to send non-zero velocities.
+fn main() {
+    let target = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:9870".to_string());
+
+    let api = HidApi::new().unwrap();
+    let mut controller = Controller::open(&api).expect("Couldn't open controller");
+    let socket = UdpSocket::bind("0.0.0.0:0").expect("Couldn't bind socket");
+
+    let config = TwistConfig::default();
+    let mut rl = RateLimiter::new(Duration::from_millis(1000 / 50));
+
+    let send = |t: Twist| {
+        let msg = format!(
+            "{{\"linear\":{{\"x\":{},\"y\":{},\"z\":{}}},\"angular\":{{\"x\":{},\"y\":{},\"z\":{}}}}}",
+            t.linear.x, t.linear.y, t.linear.z, t.angular.x, t.angular.y, t.angular.z
+        );
+        if let Err(e) = socket.send_to(msg.as_bytes(), &target) {
+            eprintln!("send failed: {}", e);
+        }
+    };
+
+    loop {
+        rl.wait();
+        if let Err(e) = controller.update() {
+            // Stop the robot rather than leave it on its last command.
+            send(Twist::default());
+            eprintln!("controller lost: {}", e);
+            std::process::exit(1);
+        }
+        send(config.twist(&controller.controls));
+    }
+}
@@ -0,0 +1,82 @@
+// Streams a controller to a browser page over a WebRTC data channel, and prints
+// the state of the gamepad attached to the browser's device.
+//
+// Usage: cargo run --example webrtc_stream --features webrtc [port, default 8080] [stun server]
+// then open the printed address, e.g. on a phone on the same network. Pass a
+// STUN server such as stun.l.google.com:19302 when the browser is behind
+// another NAT.
+use hidapi::HidApi;
+use ps4hid::webrtc::{self, PeerConfig, SignalingServer};
+use ps4hid::{Controller, Snapshot};
+use std::env;
+use std::net::ToSocketAddrs;
+use std::time::{Duration, Instant};
+
+/// The data channel is unreliable, so resend unchanged state this often in case
+/// the last change was lost.
+const RESEND: Duration = Duration::from_millis(100);
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let port: u16 = args.next().map_or(8080, |p| p.parse().expect("bad port"));
+    let host = webrtc::local_ip().expect("no network interface");
+    let mut config = PeerConfig::new(host);
+    if let Some(stun) = args.next() {
+        let server = stun
+            .to_socket_addrs()
+            .expect("couldn't resolve the STUN server")
+            .find(|a| a.is_ipv4() == host.is_ipv4())
+            .expect("no STUN server address for this network");
+        config = config.stun(server);
+    }
+    let server = SignalingServer::bind(("0.0.0.0", port), config).expect("couldn't listen");
+    println!("open {} in a browser", server.url(host));
+
+    let api = HidApi::new().unwrap();
+    let mut controller = Controller::open(&api).ok();
+    if controller.is_none() {
+        println!("no controller; only showing the browser's gamepad");
+    }
+
+    loop {
+        let mut peer = server.accept().expect("signaling failed");
+        println!("browser connecting");
+        let mut sent: Option<(Snapshot, Instant)> = None;
+        while !peer.is_closed() {
+            // The controller's reports pace the loop when there is one.
+            let wait = match controller.as_mut() {
+                Some(controller) => {
+                    controller.update().expect("failed to update controller");
+                    let snapshot = controller.controls.snapshot();
+                    let due =
+                        sent.is_none_or(|(last, at)| last != snapshot || at.elapsed() >= RESEND);
+                    if due && peer.send_snapshot(&snapshot).unwrap_or(false) {
+                        sent = Some((snapshot, Instant::now()));
+                    }
+                    Duration::ZERO
+                }
+                None => Duration::from_millis(16),
+            };
+            match peer.poll_snapshot(wait) {
+                Ok(Some(remote)) => {
+                    let held: Vec<_> = remote.buttons().ids().map(|id| id.name()).collect();
+                    println!(
+                        "browser: {:?} dpad {:?} left {:+.2} {:+.2} right {:+.2} {:+.2}",
+                        held,
+                        remote.dpad,
+                        remote.left_stick.x_f32(),
+                        remote.left_stick.y_f32(),
+                        remote.right_stick.x_f32(),
+                        remote.right_stick.y_f32()
+                    );
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    eprintln!("connection failed: {}", e);
+                    break;
+                }
+            }
+        }
+        println!("browser disconnected");
+    }
+}
@@ -0,0 +1,196 @@
+/* The ps4hid C API, built into the cdylib with `--features ffi`. The ds4_open
+ * family also needs the default `hid` feature. */
+
+#ifndef PS4HID_H
+#define PS4HID_H
+
+/* Generated by cbindgen from src/ffi.rs; don't edit by hand. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+// Bumped whenever a struct layout or function signature changes.
+#define DS4_ABI_VERSION 1
+
+// Room for an output report in either layout. Spelled out so it reaches the
+// header as a number.
+#define DS4_OUTPUT_REPORT_MAX_LEN 78
+
+#define DS4_OK 0
+
+#define DS4_ERR_NOT_FOUND -1
+
+#define DS4_ERR_PERMISSION_DENIED -2
+
+#define DS4_ERR_DEVICE_BUSY -3
+
+// The pad went away or was closed; close the handle and open it again.
+#define DS4_ERR_DISCONNECTED -4
+
+#define DS4_ERR_MALFORMED_REPORT -5
+
+#define DS4_ERR_INVALID_ARGUMENT -6
+
+#define DS4_ERR_BUFFER_TOO_SMALL -7
+
+// A bug in the library; the message says where.
+#define DS4_ERR_PANIC -8
+
+#define DS4_ERR_OTHER -9
+
+#define DS4_BUTTON_TRIANGLE (1 << 0)
+
+#define DS4_BUTTON_CIRCLE (1 << 1)
+
+#define DS4_BUTTON_CROSS (1 << 2)
+
+#define DS4_BUTTON_SQUARE (1 << 3)
+
+#define DS4_BUTTON_R3 (1 << 4)
+
+#define DS4_BUTTON_L3 (1 << 5)
+
+#define DS4_BUTTON_OPTIONS (1 << 6)
+
+#define DS4_BUTTON_SHARE (1 << 7)
+
+#define DS4_BUTTON_R2 (1 << 8)
+
+#define DS4_BUTTON_L2 (1 << 9)
+
+#define DS4_BUTTON_R1 (1 << 10)
+
+#define DS4_BUTTON_L1 (1 << 11)
+
+#define DS4_BUTTON_TOUCHPAD (1 << 12)
+
+#define DS4_BUTTON_PS (1 << 13)
+
+#define DS4_DPAD_UP (1 << 16)
+
+#define DS4_DPAD_DOWN (1 << 17)
+
+#define DS4_DPAD_LEFT (1 << 18)
+
+#define DS4_DPAD_RIGHT (1 << 19)
+
+// An open controller, owned by the caller until `ds4_close`.
+typedef struct Ds4Controller Ds4Controller;
+
+// One finger on the touchpad.
+typedef struct Ds4Touch {
+  bool active;
+  // Incremented by the pad for each new contact (7 bits).
+  uint8_t id;
+  uint16_t x;
+  uint16_t y;
+} Ds4Touch;
+
+// Every control's state at one report.
+typedef struct Ds4State {
+  // `DS4_BUTTON_*` and `DS4_DPAD_*` bits.
+  uint32_t buttons;
+  // Raw stick axes, 0 left or up to 255 right or down.
+  uint8_t left_x;
+  uint8_t left_y;
+  uint8_t right_x;
+  uint8_t right_y;
+  uint8_t l2;
+  uint8_t r2;
+  // Raw IMU readings.
+  int16_t gyro[3];
+  int16_t accel[3];
+  struct Ds4Touch touches[2];
+  // 0-100, or 255 if unknown. Always unknown from `ds4_parse_report`.
+  uint8_t battery_percent;
+  bool charging;
+  // The pad's 6-bit report counter.
+  uint8_t counter;
+} Ds4State;
+
+
+
+
+
+
+
+
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+uint32_t ds4_abi_version(void);
+
+// Describes the last error returned on this thread. The string stays valid
+// until the next failing call on the same thread.
+const char *ds4_last_error(void);
+
+// Decodes a raw input report as read from the device, report id first: the
+// 64-byte USB report, the 78-byte Bluetooth report 0x11, or the short
+// Bluetooth report. Returns `DS4_OK` or an error code.
+//
+// # Safety
+//
+// `data` must point to `len` readable bytes and `out` to a writable `Ds4State`.
+int32_t ds4_parse_report(const uint8_t *data, size_t len, struct Ds4State *out);
+
+// Encodes an output report setting rumble and lightbar together, for the
+// Bluetooth layout (with its checksum) if `bluetooth` is set and USB
+// otherwise, ready to write to the device. Returns its length or an error code.
+//
+// # Safety
+//
+// `buf` must point to `cap` writable bytes; `DS4_OUTPUT_REPORT_MAX_LEN` is
+// always enough.
+ptrdiff_t ds4_encode_output(bool bluetooth,
+                            uint8_t strong,
+                            uint8_t weak,
+                            uint8_t r,
+                            uint8_t g,
+                            uint8_t b,
+                            uint8_t *buf,
+                            size_t cap);
+
+// Opens the first connected DS4 with its saved profile applied, or returns
+// null with the reason in `ds4_last_error`.
+struct Ds4Controller *ds4_open(void);
+
+// Sends any held-back output and frees the handle. Null is ignored.
+//
+// # Safety
+//
+// `handle` must come from `ds4_open` and not be used again.
+void ds4_close(struct Ds4Controller *handle);
+
+// Waits for the next input report and fills `out` with the new state.
+// Returns `DS4_OK`, or an error code; after `DS4_ERR_DISCONNECTED` only
+// `ds4_close` is useful.
+//
+// # Safety
+//
+// `handle` must come from `ds4_open` and `out` point to a writable
+// `Ds4State`. A handle may move between threads but not be used from two
+// at once.
+int32_t ds4_poll(struct Ds4Controller *handle, struct Ds4State *out);
+
+// Sets the strong (left) and weak (right) motor speeds.
+//
+// # Safety
+//
+// `handle` must come from `ds4_open`.
+int32_t ds4_set_rumble(struct Ds4Controller *handle, uint8_t strong, uint8_t weak);
+
+// Sets the lightbar color.
+//
+// # Safety
+//
+// `handle` must come from `ds4_open`.
+int32_t ds4_set_lightbar(struct Ds4Controller *handle, uint8_t r, uint8_t g, uint8_t b);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PS4HID_H */
@@ -0,0 +1,296 @@
+use crate::{Error, Result};
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::Path;
+
+/// A pull-based mono audio source with samples in `-1.0..=1.0`.
+pub trait SampleSource {
+    fn sample_rate(&self) -> u32;
+
+    /// Fills `buf` and returns the number of samples written; 0 means end of stream.
+    fn read(&mut self, buf: &mut [f32]) -> Result<usize>;
+}
+
+/// Interleaved signed 16-bit little-endian PCM, e.g. from `parec --format=s16le`.
+pub struct PcmSource<R> {
+    reader: R,
+    sample_rate: u32,
+    channels: u16,
+    frame: Vec<u8>,
+}
+
+impl<R: Read> PcmSource<R> {
+    pub fn new(reader: R, sample_rate: u32, channels: u16) -> Self {
+        let channels = channels.max(1);
+        PcmSource {
+            reader,
+            sample_rate,
+            channels,
+            frame: vec![0; 2 * channels as usize],
+        }
+    }
+}
+
+impl<R: Read> SampleSource for PcmSource<R> {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn read(&mut self, buf: &mut [f32]) -> Result<usize> {
+        for (n, out) in buf.iter_mut().enumerate() {
+            match self.reader.read_exact(&mut self.frame) {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(n),
+                Err(e) => return Err(e.into()),
+            }
+            let sum: f32 = self
+                .frame
+                .chunks_exact(2)
+                .map(|c| i16::from_le_bytes([c[0], c[1]]) as f32 / 32768.0)
+                .sum();
+            *out = sum / self.channels as f32;
+        }
+        Ok(buf.len())
+    }
+}
+
+/// The longest `fmt ` chunk there is: WAVE_FORMAT_EXTENSIBLE's 40 bytes.
+const MAX_FMT_LEN: usize = 40;
+
+/// 16-bit PCM WAV files.
+pub struct WavSource<R> {
+    pcm: PcmSource<R>,
+}
+
+impl WavSource<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        WavSource::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> WavSource<R> {
+    /// Reads the header up to the start of the `data` chunk.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut riff = [0u8; 12];
+        reader.read_exact(&mut riff)?;
+        if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+            return Err(Error::InvalidFormat("not a WAV file"));
+        }
+
+        let mut format = None;
+        loop {
+            let mut header = [0u8; 8];
+            reader.read_exact(&mut header)?;
+            let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+            match &header[0..4] {
+                b"fmt " => {
+                    // The length comes from the file, so don't trust it with an
+                    // allocation.
+                    if !(16..=MAX_FMT_LEN).contains(&len) {
+                        return Err(Error::InvalidFormat("WAV fmt chunk"));
+                    }
+                    let mut buf = [0u8; MAX_FMT_LEN];
+                    let fmt = &mut buf[..len + len % 2];
+                    reader.read_exact(fmt)?;
+                    let tag = u16::from_le_bytes([fmt[0], fmt[1]]);
+                    let channels = u16::from_le_bytes([fmt[2], fmt[3]]);
+                    let rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+                    let bits = u16::from_le_bytes([fmt[14], fmt[15]]);
+                    if tag != 1 || bits != 16 {
+                        return Err(Error::InvalidFormat("only 16-bit PCM WAV is supported"));
+                    }
+                    format = Some((rate, channels));
+                }
+                b"data" => {
+                    let (rate, channels) =
+                        format.ok_or(Error::InvalidFormat("WAV data before fmt"))?;
+                    return Ok(WavSource {
+                        pcm: PcmSource::new(reader, rate, channels),
+                    });
+                }
+                _ => {
+                    let skipped = std::io::copy(
+                        &mut (&mut reader).take((len + len % 2) as u64),
+                        &mut std::io::sink(),
+                    )?;
+                    if skipped < len as u64 {
+                        return Err(Error::InvalidFormat("truncated WAV chunk"));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read> SampleSource for WavSource<R> {
+    fn sample_rate(&self) -> u32 {
+        self.pcm.sample_rate()
+    }
+
+    fn read(&mut self, buf: &mut [f32]) -> Result<usize> {
+        self.pcm.read(buf)
+    }
+}
+
+/// One-pole low-pass filter.
+#[derive(Debug, Clone, Copy)]
+pub struct LowPass {
+    alpha: f32,
+    state: f32,
+}
+
+impl LowPass {
+    pub fn new(cutoff_hz: f32, sample_rate: u32) -> Self {
+        let dt = 1.0 / sample_rate.max(1) as f32;
+        let rc = 1.0 / (2.0 * std::f32::consts::PI * cutoff_hz.max(1.0));
+        LowPass {
+            alpha: dt / (rc + dt),
+            state: 0.0,
+        }
+    }
+
+    pub fn process(&mut self, x: f32) -> f32 {
+        self.state += self.alpha * (x - self.state);
+        self.state
+    }
+}
+
+/// Peak envelope follower with separate attack and release times.
+#[derive(Debug, Clone, Copy)]
+pub struct Envelope {
+    attack: f32,
+    release: f32,
+    level: f32,
+}
+
+impl Envelope {
+    pub fn new(attack_ms: f32, release_ms: f32, sample_rate: u32) -> Self {
+        let coeff =
+            |ms: f32| 1.0 - (-1.0 / (ms.max(0.01) * 0.001 * sample_rate.max(1) as f32)).exp();
+        Envelope {
+            attack: coeff(attack_ms),
+            release: coeff(release_ms),
+            level: 0.0,
+        }
+    }
+
+    pub fn process(&mut self, x: f32) -> f32 {
+        let x = x.abs();
+        let coeff = if x > self.level {
+            self.attack
+        } else {
+            self.release
+        };
+        self.level += coeff * (x - self.level);
+        self.level
+    }
+
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+}
+
+/// Splits audio into a low band (below ~150 Hz, driving the strong motor) and a
+/// high band (above ~1.5 kHz, driving the weak motor) and follows their envelopes.
+pub struct AudioRumble {
+    low: LowPass,
+    high_split: LowPass,
+    low_env: Envelope,
+    high_env: Envelope,
+    pub gain: f32,
+    buf: Vec<f32>,
+}
+
+impl AudioRumble {
+    pub fn new(sample_rate: u32) -> Self {
+        AudioRumble {
+            low: LowPass::new(150.0, sample_rate),
+            high_split: LowPass::new(1500.0, sample_rate),
+            low_env: Envelope::new(5.0, 120.0, sample_rate),
+            high_env: Envelope::new(2.0, 60.0, sample_rate),
+            gain: 2.0,
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn process(&mut self, samples: &[f32]) -> (u8, u8) {
+        for &x in samples {
+            self.low_env.process(self.low.process(x));
+            self.high_env.process(x - self.high_split.process(x));
+        }
+        (
+            to_motor(self.low_env.level() * self.gain),
+            to_motor(self.high_env.level() * self.gain),
+        )
+    }
+
+    /// Pulls `len` samples from `source` and returns `(strong, weak)` motor speeds,
+    /// or `None` at end of stream.
+    pub fn pull(&mut self, source: &mut dyn SampleSource, len: usize) -> Result<Option<(u8, u8)>> {
+        self.buf.resize(len, 0.0);
+        let mut buf = std::mem::take(&mut self.buf);
+        let n = source.read(&mut buf)?;
+        let motors = (n > 0).then(|| self.process(&buf[..n]));
+        self.buf = buf;
+        Ok(motors)
+    }
+}
+
+fn to_motor(level: f32) -> u8 {
+    (level.clamp(0.0, 1.0) * 255.0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav(fmt_len: u32, fmt: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut file = b"RIFF\0\0\0\0WAVEfmt ".to_vec();
+        file.extend(fmt_len.to_le_bytes());
+        file.extend(fmt);
+        file.extend(b"data");
+        file.extend((data.len() as u32).to_le_bytes());
+        file.extend(data);
+        file
+    }
+
+    fn pcm_fmt(rate: u32, channels: u16) -> Vec<u8> {
+        let mut fmt = vec![1, 0];
+        fmt.extend(channels.to_le_bytes());
+        fmt.extend(rate.to_le_bytes());
+        fmt.extend((rate * 2 * channels as u32).to_le_bytes());
+        fmt.extend((2 * channels).to_le_bytes());
+        fmt.extend(16u16.to_le_bytes());
+        fmt
+    }
+
+    #[test]
+    fn reads_16_bit_pcm() {
+        let file = wav(16, &pcm_fmt(8000, 1), &[0x00, 0x40, 0x00, 0xc0]);
+        let source = WavSource::new(file.as_slice());
+        assert!(source.is_ok());
+        let Ok(mut source) = source else { return };
+        assert_eq!(source.sample_rate(), 8000);
+        let mut buf = [0.0; 4];
+        assert!(source.read(&mut buf).is_ok_and(|n| n == 2));
+        assert_eq!(buf[..2], [0.5, -0.5]);
+    }
+
+    #[test]
+    fn huge_fmt_chunk_is_rejected() {
+        let file = wav(u32::MAX, &pcm_fmt(8000, 1), &[]);
+        assert!(matches!(
+            WavSource::new(file.as_slice()),
+            Err(Error::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn short_fmt_chunk_is_rejected() {
+        let file = wav(8, &pcm_fmt(8000, 1)[..8], &[]);
+        assert!(matches!(
+            WavSource::new(file.as_slice()),
+            Err(Error::InvalidFormat(_))
+        ));
+    }
+}
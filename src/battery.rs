@@ -0,0 +1,135 @@
+pub use ds4_core::report::STATUS_OFFSET;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+const MAX_SAMPLES: usize = 360;
+const SMOOTHING: Duration = Duration::from_secs(60);
+/// History span at which an estimate is considered fully trustworthy.
+const CONFIDENT_SPAN: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    /// Smoothed charge in `0.0..=100.0`.
+    pub percent: f32,
+    /// Change in percent per hour; negative while discharging.
+    pub rate_per_hour: Option<f32>,
+    pub time_remaining: Option<Duration>,
+    /// `0.0..=1.0`, growing with the length and linearity of the history.
+    pub confidence: f32,
+}
+
+/// Tracks the coarse battery nibble over the session.
+#[derive(Debug, Clone, Default)]
+pub struct Battery {
+    raw: Option<u8>,
+    cable: bool,
+    smoothed: Option<(Instant, f32)>,
+    samples: VecDeque<(Instant, f32)>,
+}
+
+impl Battery {
+    pub fn new() -> Self {
+        Battery::default()
+    }
+
+    /// Feeds the status byte of an input report.
+    pub fn update(&mut self, at: Instant, status: u8) {
+        let cable = status & 0x10 > 0;
+        let raw = status & 0x0f;
+        if cable != self.cable {
+            self.samples.clear();
+            self.smoothed = None;
+        }
+        self.cable = cable;
+        self.raw = Some(raw);
+
+        let percent = (raw as f32 * 10.0).min(100.0);
+        let smoothed = match self.smoothed {
+            Some((last, value)) => {
+                let dt = at.saturating_duration_since(last).as_secs_f32();
+                let alpha = 1.0 - (-dt / SMOOTHING.as_secs_f32()).exp();
+                value + alpha * (percent - value)
+            }
+            None => percent,
+        };
+        self.smoothed = Some((at, smoothed));
+
+        let due = self
+            .samples
+            .back()
+            .is_none_or(|(last, _)| at.saturating_duration_since(*last) >= SAMPLE_INTERVAL);
+        if due {
+            if self.samples.len() == MAX_SAMPLES {
+                self.samples.pop_front();
+            }
+            self.samples.push_back((at, smoothed));
+        }
+    }
+
+    /// The raw level nibble: 0-10 on battery, up to 11 (full) while charging.
+    pub fn raw_level(&self) -> Option<u8> {
+        self.raw
+    }
+
+    pub fn cable_connected(&self) -> bool {
+        self.cable
+    }
+
+    pub fn percent(&self) -> Option<f32> {
+        self.smoothed.map(|(_, p)| p)
+    }
+
+    pub fn estimate(&self) -> Option<Estimate> {
+        let percent = self.percent()?;
+        let (rate, fit) = match self.trend() {
+            Some((rate, fit)) => (Some(rate * 3600.0), fit),
+            None => (None, 0.0),
+        };
+        let span = match (self.samples.front(), self.samples.back()) {
+            (Some((first, _)), Some((last, _))) => last.saturating_duration_since(*first),
+            _ => Duration::ZERO,
+        };
+        let time_remaining = rate
+            .filter(|r| *r < 0.0 && !self.cable)
+            .and_then(|r| Duration::try_from_secs_f32(percent / -r * 3600.0).ok());
+        Some(Estimate {
+            percent,
+            rate_per_hour: rate,
+            time_remaining,
+            confidence: (span.as_secs_f32() / CONFIDENT_SPAN.as_secs_f32()).min(1.0) * fit,
+        })
+    }
+
+    /// Least-squares slope in percent per second, plus the fit's r².
+    fn trend(&self) -> Option<(f32, f32)> {
+        let (t0, _) = *self.samples.front()?;
+        if self.samples.len() < 3 {
+            return None;
+        }
+        let n = self.samples.len() as f32;
+        let points: Vec<(f32, f32)> = self
+            .samples
+            .iter()
+            .map(|(at, p)| (at.saturating_duration_since(t0).as_secs_f32(), *p))
+            .collect();
+        let mean_t = points.iter().map(|(t, _)| t).sum::<f32>() / n;
+        let mean_p = points.iter().map(|(_, p)| p).sum::<f32>() / n;
+        let (mut stt, mut stp, mut spp) = (0.0, 0.0, 0.0);
+        for (t, p) in &points {
+            stt += (t - mean_t) * (t - mean_t);
+            stp += (t - mean_t) * (p - mean_p);
+            spp += (p - mean_p) * (p - mean_p);
+        }
+        if stt == 0.0 {
+            return None;
+        }
+        let slope = stp / stt;
+        let r2 = if spp == 0.0 {
+            1.0
+        } else {
+            (stp * stp) / (stt * spp)
+        };
+        Some((slope, r2))
+    }
+}
@@ -0,0 +1,227 @@
+use hidapi::HidApi;
+use ps4hid::calibration::{Calibration, CircularitySweep, Deadzones, TouchBounds, TriggerRange};
+use ps4hid::gyro::GyroAutoZero;
+use ps4hid::{Controller, Stick};
+use std::io::{self, BufRead, Write};
+use std::process;
+use std::time::{Duration, Instant};
+
+/// Long enough to average out jitter in the resting positions.
+const REST_TIME: Duration = Duration::from_secs(1);
+/// Give up on a step after this long and keep what it measured.
+const STEP_TIMEOUT: Duration = Duration::from_secs(15);
+const SWEEP_COVERAGE: f32 = 0.95;
+const SHORT_REACH: f32 = 0.85;
+/// A trigger counts as fully pulled and released within this of its ends.
+const TRIGGER_SLACK: u8 = 8;
+
+fn fail(e: impl std::fmt::Display) -> ! {
+    eprintln!("ds4-calibrate: {}", e);
+    process::exit(1);
+}
+
+fn update(controller: &mut Controller) {
+    controller.update().unwrap_or_else(|e| fail(e));
+}
+
+fn prompt(text: &str) {
+    print!("\n{} Press Enter when ready.", text);
+    let _ = io::stdout().flush();
+    let mut line = String::new();
+    let _ = io::stdin().lock().read_line(&mut line);
+}
+
+fn progress(text: &str) {
+    print!("\r{:<60}", text);
+    let _ = io::stdout().flush();
+}
+
+/// Averages both sticks while they're left alone.
+fn rest_centers(controller: &mut Controller) -> (Stick, Stick) {
+    prompt("Let go of both sticks.");
+    let (mut sums, mut n) = ([0u32; 4], 0u32);
+    let start = Instant::now();
+    while start.elapsed() < REST_TIME || n == 0 {
+        update(controller);
+        let l = controller.controls.left_stick.state();
+        let r = controller.controls.right_stick.state();
+        for (sum, v) in sums.iter_mut().zip([l.x, l.y, r.x, r.y]) {
+            *sum += v as u32;
+        }
+        n += 1;
+    }
+    let [lx, ly, rx, ry] = sums.map(|s| (s / n) as u8);
+    (Stick::new(lx, ly), Stick::new(rx, ry))
+}
+
+/// Runs until the stick has been around the whole gate, then reports how far
+/// out the gate reaches; a worn or off-center stick shows up as a short reach.
+fn sweep(controller: &mut Controller, name: &str, right: bool, center: Stick) {
+    prompt(&format!(
+        "Roll the {} stick slowly around the edge of its gate a few times.",
+        name
+    ));
+    let calibration = Calibration::default();
+    let mut sweep = CircularitySweep::new();
+    let start = Instant::now();
+    while sweep.coverage() < SWEEP_COVERAGE && start.elapsed() < STEP_TIMEOUT {
+        update(controller);
+        let controls = &controller.controls;
+        let raw = if right {
+            controls.right_stick.state()
+        } else {
+            controls.left_stick.state()
+        };
+        sweep.record(calibration.stick(raw, center));
+        progress(&format!(
+            "{:.0}% of the gate covered",
+            sweep.coverage() * 100.0
+        ));
+    }
+    println!();
+    let radii = sweep.finish();
+    let reach = radii.radii().iter().copied().fold(f32::INFINITY, f32::min);
+    println!(
+        "{} stick reaches {:.0}% of full travel at its narrowest",
+        name,
+        reach * 100.0
+    );
+    if reach < SHORT_REACH {
+        println!("the {} stick may be worn or sticking", name);
+    }
+}
+
+/// Tracks the trigger's travel until it has been pulled all the way and let go.
+fn trigger(controller: &mut Controller, name: &str, right: bool) -> TriggerRange {
+    prompt(&format!("Pull {} all the way in, then let it go.", name));
+    let mut range = TriggerRange { min: 255, max: 0 };
+    let mut pulled = false;
+    let start = Instant::now();
+    while start.elapsed() < STEP_TIMEOUT {
+        update(controller);
+        let v = if right {
+            controller.controls.r2.value()
+        } else {
+            controller.controls.l2.value()
+        };
+        range.min = range.min.min(v);
+        range.max = range.max.max(v);
+        pulled |= v >= 255 - TRIGGER_SLACK;
+        progress(&format!("{} travel {}..={}", name, range.min, range.max));
+        if pulled && v <= range.min.saturating_add(TRIGGER_SLACK) {
+            break;
+        }
+    }
+    println!();
+    if range.max <= range.min {
+        println!("{} didn't move; keeping the full range", name);
+        return TriggerRange::default();
+    }
+    range
+}
+
+/// Waits for the resting-pad detector to settle on a bias.
+fn gyro_rest(controller: &mut Controller) -> [i16; 3] {
+    prompt("Put the controller down on a flat surface and don't touch it.");
+    let mut zero = GyroAutoZero::new();
+    let start = Instant::now();
+    while start.elapsed() < STEP_TIMEOUT {
+        update(controller);
+        if let Some(bias) = zero.observe(controller.controls.imu) {
+            return bias.map(|b| b.round() as i16);
+        }
+        progress("waiting for the controller to be still");
+    }
+    println!();
+    println!("the controller never held still; keeping no gyro bias");
+    [0; 3]
+}
+
+/// Grows the bounds as a finger traces the edge, until the touchpad is clicked.
+fn touchpad(controller: &mut Controller) -> TouchBounds {
+    prompt("Run a finger into every corner of the touchpad, then click it.");
+    let mut bounds: Option<TouchBounds> = None;
+    let start = Instant::now();
+    while start.elapsed() < STEP_TIMEOUT {
+        update(controller);
+        for touch in controller.controls.touches.state() {
+            if !touch.active {
+                continue;
+            }
+            let b = bounds.get_or_insert(TouchBounds {
+                min_x: touch.x,
+                min_y: touch.y,
+                max_x: touch.x,
+                max_y: touch.y,
+            });
+            b.min_x = b.min_x.min(touch.x);
+            b.min_y = b.min_y.min(touch.y);
+            b.max_x = b.max_x.max(touch.x);
+            b.max_y = b.max_y.max(touch.y);
+        }
+        if let Some(b) = bounds {
+            progress(&format!(
+                "x {}..={}, y {}..={}",
+                b.min_x, b.max_x, b.min_y, b.max_y
+            ));
+        }
+        if controller.controls.tpad.state() && bounds.is_some() {
+            break;
+        }
+    }
+    println!();
+    match bounds {
+        Some(b) if b.max_x > b.min_x && b.max_y > b.min_y => b,
+        _ => {
+            println!("no corners traced; keeping the full touchpad");
+            TouchBounds::default()
+        }
+    }
+}
+
+fn main() {
+    let api = HidApi::new().unwrap_or_else(|e| fail(e));
+    let mut controller = Controller::open(&api).unwrap_or_else(|e| fail(e));
+    println!(
+        "Calibrating the controller over {}.",
+        controller.connection()
+    );
+
+    // Measure raw values: the saved calibration and deadzones would otherwise
+    // be applied to every report read here.
+    let deadzones = controller.profile().deadzones;
+    controller.profile_mut().calibration = Calibration::default();
+    controller.profile_mut().deadzones = Deadzones {
+        left: 0.0,
+        right: 0.0,
+    };
+    controller.set_gyro_auto_zero(None);
+
+    let (left_center, right_center) = rest_centers(&mut controller);
+    println!(
+        "centers: left {} {}, right {} {}",
+        left_center.x, left_center.y, right_center.x, right_center.y
+    );
+    sweep(&mut controller, "left", false, left_center);
+    sweep(&mut controller, "right", true, right_center);
+    let l2 = trigger(&mut controller, "L2", false);
+    let r2 = trigger(&mut controller, "R2", true);
+    let gyro_bias = gyro_rest(&mut controller);
+    let touchpad = touchpad(&mut controller);
+
+    let profile = controller.profile_mut();
+    profile.calibration = Calibration {
+        left_center,
+        right_center,
+        l2,
+        r2,
+        gyro_bias,
+        touchpad,
+    };
+    profile.deadzones = deadzones;
+    print!("\n{}", controller.profile());
+    match controller.save_profile() {
+        Ok(()) => println!("saved"),
+        Err(e) => fail(format!("couldn't save the profile: {}", e)),
+    }
+}
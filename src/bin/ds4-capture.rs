@@ -0,0 +1,112 @@
+use hidapi::HidApi;
+use ps4hid::fixture::{self, Scenario, Summary};
+use ps4hid::proxy::InspectingTransport;
+use ps4hid::{ConnectionInfo, Controller};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::Path;
+use std::process;
+use std::time::{Duration, Instant};
+
+/// Recording carries on this long after a scenario is complete, so the final
+/// release makes it into the capture.
+const TAIL: Duration = Duration::from_millis(500);
+
+fn usage() -> ! {
+    let names: Vec<_> = Scenario::ALL.iter().map(|s| s.name()).collect();
+    eprintln!(
+        "usage: ds4-capture record DIR [SCENARIO...]|codegen DIR\n\
+         scenarios: {}",
+        names.join(", ")
+    );
+    process::exit(2);
+}
+
+fn fail(e: impl std::fmt::Display) -> ! {
+    eprintln!("ds4-capture: {}", e);
+    process::exit(1);
+}
+
+fn prompt(text: &str) {
+    print!("\n{} Press Enter when ready.", text);
+    let _ = io::stdout().flush();
+    let mut line = String::new();
+    let _ = io::stdin().lock().read_line(&mut line);
+}
+
+/// Records one scenario to `<dir>/<name>.ds4cap`, through a fresh connection so
+/// each capture starts from the pad's first report.
+fn record(api: &HidApi, dir: &Path, scenario: Scenario) {
+    prompt(scenario.prompt());
+    let info = Controller::enumerate(api)
+        .next()
+        .unwrap_or_else(|| fail(ps4hid::Error::NotFound));
+    let device = info.open_device(api).unwrap_or_else(|e| fail(e));
+    let path = dir.join(format!("{}.ds4cap", scenario.name()));
+    let file = BufWriter::new(File::create(&path).unwrap_or_else(|e| fail(e)));
+    let transport = InspectingTransport::new(device, file).unwrap_or_else(|e| fail(e));
+    let mut controller =
+        Controller::with_transport(transport, ConnectionInfo::from_device_info(info));
+
+    let mut summary = Summary::new();
+    let start = Instant::now();
+    let mut completed: Option<Instant> = None;
+    while start.elapsed() < scenario.duration() && completed.is_none_or(|at| at.elapsed() < TAIL) {
+        let before = controller.sampled_at();
+        controller.update().unwrap_or_else(|e| fail(e));
+        if controller.sampled_at() == before {
+            continue;
+        }
+        summary.record(&controller.controls.snapshot());
+        if completed.is_none() && scenario.is_complete(&summary) {
+            completed = Some(Instant::now());
+        }
+    }
+    let status = match scenario {
+        Scenario::Rest => "done",
+        _ if completed.is_some() => "complete",
+        _ => "timed out; kept what was recorded",
+    };
+    println!(
+        "{}: {} reports, {}",
+        path.display(),
+        summary.reports,
+        status
+    );
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let command = args.next().unwrap_or_else(|| usage());
+    let dir = args.next().unwrap_or_else(|| usage());
+    let dir = Path::new(&dir);
+    match command.as_str() {
+        "record" => {
+            let scenarios: Vec<Scenario> = args
+                .map(|s| s.parse().unwrap_or_else(|_| usage()))
+                .collect();
+            let scenarios = if scenarios.is_empty() {
+                Scenario::ALL.to_vec()
+            } else {
+                scenarios
+            };
+            fs::create_dir_all(dir).unwrap_or_else(|e| fail(e));
+            let api = HidApi::new().unwrap_or_else(|e| fail(e));
+            for scenario in scenarios {
+                record(&api, dir, scenario);
+            }
+            println!(
+                "\nrun `ds4-capture codegen {}` to update the fixtures",
+                dir.display()
+            );
+        }
+        "codegen" => {
+            let source = fixture::codegen(dir).unwrap_or_else(|e| fail(e));
+            let out = dir.join("mod.rs");
+            fs::write(&out, source).unwrap_or_else(|e| fail(e));
+            println!("wrote {}", out.display());
+        }
+        _ => usage(),
+    }
+}
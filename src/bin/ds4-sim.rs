@@ -0,0 +1,103 @@
+use ps4hid::sim::{SimCommand, Simulator};
+use ps4hid::Error;
+use std::io::{self, BufRead};
+use std::{env, fs, thread};
+
+const REPORT_RATE: u32 = 250;
+
+fn usage() -> ! {
+    eprintln!("usage: ds4-sim [--uinput] [SCRIPT]");
+    eprintln!("Without a script, reads commands from stdin, e.g. `press x`, `stick left 0 1`.");
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut uinput = false;
+    let mut script = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--uinput" => uinput = true,
+            _ if arg.starts_with('-') || script.is_some() => usage(),
+            _ => script = Some(arg),
+        }
+    }
+
+    let (mut controller, sim) = Simulator::controller(REPORT_RATE);
+    let mut pad = uinput.then(create_virtual_pad);
+
+    // Commands block on `wait`, so they run beside the controller's read loop.
+    let driver = thread::spawn(move || {
+        let result = match script {
+            Some(path) => fs::read_to_string(&path)
+                .map_err(Error::from)
+                .and_then(|script| sim.run_script(&script)),
+            None => run_stdin(&sim),
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+        }
+    });
+
+    let mut last = controller.controls.snapshot();
+    loop {
+        match controller.update() {
+            Ok(()) => {}
+            Err(Error::Io(_)) => break,
+            Err(e) => {
+                eprintln!("ignoring report: {}", e);
+                continue;
+            }
+        }
+        let snapshot = controller.controls.snapshot();
+        if snapshot != last {
+            println!("{:?}", snapshot);
+            last = snapshot;
+            if let Some(pad) = pad.as_mut() {
+                emit(pad, &snapshot);
+            }
+        }
+    }
+    let _ = driver.join();
+}
+
+fn run_stdin(sim: &Simulator) -> ps4hid::Result<()> {
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.parse::<SimCommand>() {
+            Ok(command) => sim.apply(&command),
+            Err(e) => eprintln!("{}: {}", line, e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "uinput", target_os = "linux"))]
+type Pad = ps4hid::virtual_pad::UinputPad;
+#[cfg(not(all(feature = "uinput", target_os = "linux")))]
+type Pad = ();
+
+#[cfg(all(feature = "uinput", target_os = "linux"))]
+fn create_virtual_pad() -> Pad {
+    ps4hid::virtual_pad::UinputPad::create("ds4-sim").expect("Couldn't create uinput device")
+}
+
+#[cfg(not(all(feature = "uinput", target_os = "linux")))]
+fn create_virtual_pad() -> Pad {
+    eprintln!("--uinput needs Linux and the uinput feature");
+    std::process::exit(2);
+}
+
+#[cfg(all(feature = "uinput", target_os = "linux"))]
+fn emit(pad: &mut Pad, snapshot: &ps4hid::Snapshot) {
+    use ps4hid::virtual_pad::VirtualPad;
+    if let Err(e) = pad.emit(snapshot) {
+        eprintln!("uinput: {}", e);
+    }
+}
+
+#[cfg(not(all(feature = "uinput", target_os = "linux")))]
+fn emit(_: &mut Pad, _: &ps4hid::Snapshot) {}
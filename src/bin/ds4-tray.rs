@@ -0,0 +1,25 @@
+#[cfg(target_os = "linux")]
+fn main() {
+    use ps4hid::daemon::Daemon;
+    use ps4hid::tray::StatusTray;
+
+    let mut daemon = Daemon::new().expect("Couldn't initialize hidapi");
+    let tray = StatusTray::new(daemon.handle())
+        .spawn()
+        .expect("Couldn't register the tray icon; is a StatusNotifier host running?");
+    // Nudge the panel to re-read the status after every change.
+    daemon.on_event(Box::new(move |_| {
+        tray.update(|_| {});
+    }));
+
+    if let Err(e) = daemon.run() {
+        eprintln!("ds4-tray: {}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn main() {
+    eprintln!("ds4-tray needs a StatusNotifierItem desktop, which is Linux-only for now");
+    std::process::exit(2);
+}
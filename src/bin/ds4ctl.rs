@@ -0,0 +1,251 @@
+use hidapi::HidApi;
+use ps4hid::pcapng::{LinkType, PcapngWriter};
+use ps4hid::proxy::{CaptureReader, InspectingTransport};
+#[cfg(feature = "noise")]
+use ps4hid::remote::KeyStore;
+use ps4hid::remote::{self, RemoteServer};
+use ps4hid::{ConnectionInfo, Controller};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::process;
+use std::time::{Duration, Instant};
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: ds4ctl info|test|stats|capture FILE [SECONDS]|dump FILE|pcapng FILE OUT [usbmon]|serve [PORT] [pair]|pair ADDR CODE|discover [SECONDS]"
+    );
+    process::exit(2);
+}
+
+fn open() -> Controller {
+    let api = HidApi::new().expect("Couldn't initialize hidapi");
+    Controller::open(&api).unwrap_or_else(|e| fail(e))
+}
+
+/// Prints the version report and the capabilities detected from it.
+fn info() {
+    let controller = open();
+    println!("connection = {}", controller.connection());
+    match controller.firmware() {
+        Some(firmware) => {
+            println!("build_date = {}", firmware.build_date);
+            println!("hardware_version = {:#06x}", firmware.hardware_version);
+            println!("firmware_version = {:#06x}", firmware.firmware_version);
+        }
+        None => println!("firmware = unknown (possibly a clone)"),
+    }
+    let caps = controller.capabilities();
+    println!("has_lightbar = {}", caps.has_lightbar);
+    println!("imu_trustworthy = {}", caps.imu_trustworthy);
+    println!("bt_1khz = {}", caps.bt_1khz);
+}
+
+fn test() {
+    let mut controller = open();
+    println!(
+        "testing controller via {}; keep it still",
+        controller.connection()
+    );
+    let report = controller.self_test();
+    print!("{}", report);
+    if !report.passed() {
+        process::exit(1);
+    }
+}
+
+/// Prints the saved usage statistics; the session `open` just started isn't saved.
+fn stats() {
+    let controller = open();
+    let stats = controller.stats();
+    print!("{}", stats);
+    println!("total_presses = {}", stats.total_presses());
+}
+
+fn fail(e: impl std::fmt::Display) -> ! {
+    eprintln!("ds4ctl: {}", e);
+    process::exit(1);
+}
+
+/// Drives the first pad through a capturing transport, printing each report as
+/// it passes, until `seconds` have elapsed or forever.
+fn capture(path: &str, seconds: Option<u64>) {
+    let api = HidApi::new().expect("Couldn't initialize hidapi");
+    let info = Controller::enumerate(&api)
+        .next()
+        .unwrap_or_else(|| fail(ps4hid::Error::NotFound));
+    let device = info.open_device(&api).unwrap_or_else(|e| fail(e));
+    let file = File::create(path).unwrap_or_else(|e| fail(e));
+    let transport = InspectingTransport::new(device, file)
+        .unwrap_or_else(|e| fail(e))
+        .log_to(io::stdout());
+    let mut controller =
+        Controller::with_transport(transport, ConnectionInfo::from_device_info(info));
+    // Reads the version and pairing reports, so they land in the capture too.
+    controller.restore_profile().unwrap_or_else(|e| fail(e));
+    let deadline = seconds.map(|s| Instant::now() + Duration::from_secs(s));
+    while deadline.is_none_or(|d| Instant::now() < d) {
+        controller.update().unwrap_or_else(|e| fail(e));
+    }
+}
+
+/// Prints a capture with each report decoded.
+fn dump(path: &str) {
+    let file = File::open(path).unwrap_or_else(|e| fail(e));
+    let reader = CaptureReader::new(BufReader::new(file)).unwrap_or_else(|e| fail(e));
+    for record in reader {
+        println!("{}", record.unwrap_or_else(|e| fail(e)));
+    }
+}
+
+/// Converts a capture for Wireshark.
+fn pcapng(path: &str, out: &str, link: LinkType) {
+    let file = File::open(path).unwrap_or_else(|e| fail(e));
+    let reader = CaptureReader::new(BufReader::new(file)).unwrap_or_else(|e| fail(e));
+    let out = BufWriter::new(File::create(out).unwrap_or_else(|e| fail(e)));
+    let mut writer = PcapngWriter::new(out, link, reader.started()).unwrap_or_else(|e| fail(e));
+    let mut packets = 0;
+    for record in reader {
+        writer
+            .write(&record.unwrap_or_else(|e| fail(e)))
+            .unwrap_or_else(|e| fail(e));
+        packets += 1;
+    }
+    writer.flush().unwrap_or_else(|e| fail(e));
+    println!("wrote {} reports", packets);
+}
+
+/// Shares the pad on the network until killed, advertising it when built with
+/// mDNS support. Only paired clients are served once any have paired, and
+/// `pair` shows a code for pairing another.
+fn serve(port: u16, pair: bool) {
+    let mut controller = open();
+    let server = RemoteServer::bind(("0.0.0.0", port)).unwrap_or_else(|e| fail(e));
+    #[cfg(feature = "noise")]
+    let (mut server, clients) = {
+        let path = KeyStore::clients_path().unwrap_or_else(|| fail("no config directory"));
+        let keys = KeyStore::load(&path).unwrap_or_else(|e| fail(e));
+        let mut server = if pair || !keys.keys.is_empty() {
+            server.require_pairing(keys)
+        } else {
+            server
+        };
+        if pair {
+            let code = server.start_pairing().unwrap_or_else(|e| fail(e));
+            println!("pairing code {}", code);
+        }
+        (server, path)
+    };
+    #[cfg(not(feature = "noise"))]
+    let mut server = {
+        if pair {
+            fail("built without the noise feature")
+        }
+        server
+    };
+    #[cfg(feature = "mdns")]
+    let _advertisement = {
+        let name = remote::default_name();
+        let advertisement = server.advertise(&name).unwrap_or_else(|e| fail(e));
+        println!("advertising as \"{}\"", name);
+        advertisement
+    };
+    println!("serving on port {}", port);
+    loop {
+        controller.update().unwrap_or_else(|e| fail(e));
+        server
+            .publish(&controller.controls.snapshot())
+            .unwrap_or_else(|e| fail(e));
+        #[cfg(feature = "noise")]
+        if let Some(key) = server.take_paired() {
+            if let Some(keys) = server.keys() {
+                keys.save(&clients).unwrap_or_else(|e| fail(e));
+            }
+            println!("paired with {}", key.label);
+        }
+    }
+}
+
+/// Pairs with a server showing `code`, remembering it for later connections.
+#[cfg(feature = "noise")]
+fn pair(addr: &str, code: &str) {
+    let key = remote::RemoteController::pair(addr, code).unwrap_or_else(|e| fail(e));
+    let path = KeyStore::servers_path().unwrap_or_else(|| fail("no config directory"));
+    let mut keys = KeyStore::load(&path).unwrap_or_else(|e| fail(e));
+    println!("paired with {}", key.label);
+    keys.insert(key);
+    keys.save(&path).unwrap_or_else(|e| fail(e));
+}
+
+#[cfg(not(feature = "noise"))]
+fn pair(_addr: &str, _code: &str) {
+    fail("built without the noise feature")
+}
+
+#[cfg(feature = "mdns")]
+fn discover(seconds: u64) {
+    let found = remote::RemoteController::discover(Duration::from_secs(seconds))
+        .unwrap_or_else(|e| fail(e));
+    for endpoint in &found {
+        println!("{} ({})", endpoint, endpoint.host);
+    }
+    if found.is_empty() {
+        println!("no controllers found");
+    }
+}
+
+#[cfg(not(feature = "mdns"))]
+fn discover(_seconds: u64) {
+    fail("built without the mdns feature")
+}
+
+fn main() {
+    match env::args().nth(1).as_deref() {
+        Some("info") => info(),
+        Some("test") => test(),
+        Some("stats") => stats(),
+        Some("capture") => {
+            let path = env::args().nth(2).unwrap_or_else(|| usage());
+            let seconds = env::args()
+                .nth(3)
+                .map(|s| s.parse().unwrap_or_else(|_| usage()));
+            capture(&path, seconds)
+        }
+        Some("dump") => dump(&env::args().nth(2).unwrap_or_else(|| usage())),
+        Some("pcapng") => {
+            let (Some(path), Some(out)) = (env::args().nth(2), env::args().nth(3)) else {
+                usage()
+            };
+            let link = match env::args().nth(4).as_deref() {
+                None => LinkType::User0,
+                Some("usbmon") => LinkType::Usbmon,
+                Some(_) => usage(),
+            };
+            pcapng(&path, &out, link)
+        }
+        Some("serve") => {
+            let port = env::args().nth(2).map_or(remote::DEFAULT_PORT, |p| {
+                p.parse().unwrap_or_else(|_| usage())
+            });
+            let pair = match env::args().nth(3).as_deref() {
+                None => false,
+                Some("pair") => true,
+                Some(_) => usage(),
+            };
+            serve(port, pair)
+        }
+        Some("pair") => {
+            let (Some(addr), Some(code)) = (env::args().nth(2), env::args().nth(3)) else {
+                usage()
+            };
+            pair(&addr, &code)
+        }
+        Some("discover") => {
+            let seconds = env::args()
+                .nth(2)
+                .map_or(3, |s| s.parse().unwrap_or_else(|_| usage()));
+            discover(seconds)
+        }
+        _ => usage(),
+    }
+}
@@ -0,0 +1,149 @@
+use ps4hid::choreography::Choreography;
+use ps4hid::daemon::Daemon;
+use ps4hid::metrics::{self, Metrics};
+use ps4hid::notify::Notifier;
+use ps4hid::priority::{ThreadHints, ThreadPriority};
+use ps4hid::profile_switch::ProfileSwitcher;
+use std::env;
+use std::sync::Arc;
+
+struct Args {
+    metrics_addr: Option<String>,
+    notify: bool,
+    low_battery: Vec<f32>,
+    hints: ThreadHints,
+    profiles: bool,
+    announce: bool,
+    #[cfg(feature = "shm")]
+    shm: bool,
+    #[cfg(all(feature = "dbus", target_os = "linux"))]
+    dbus: bool,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: ds4d [--metrics ADDR] [--notify [--low-battery PERCENT]...] \
+         [--priority normal|high|realtime[=N]] [--cpu N]... [--profiles] [--announce] [--shm] [--dbus]"
+    );
+    std::process::exit(2);
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        metrics_addr: None,
+        notify: false,
+        low_battery: Vec::new(),
+        hints: ThreadHints::new(),
+        profiles: false,
+        announce: false,
+        #[cfg(feature = "shm")]
+        shm: false,
+        #[cfg(all(feature = "dbus", target_os = "linux"))]
+        dbus: false,
+    };
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--metrics" => args.metrics_addr = Some(iter.next().unwrap_or_else(|| usage())),
+            "--notify" => args.notify = true,
+            "--profiles" => args.profiles = true,
+            "--announce" => args.announce = true,
+            "--low-battery" => args.low_battery.push(
+                iter.next()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or_else(|| usage()),
+            ),
+            "--priority" => {
+                args.hints.priority = iter
+                    .next()
+                    .and_then(|v| parse_priority(&v))
+                    .unwrap_or_else(|| usage())
+            }
+            "--cpu" => args.hints.affinity.push(
+                iter.next()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or_else(|| usage()),
+            ),
+            #[cfg(feature = "shm")]
+            "--shm" => args.shm = true,
+            #[cfg(all(feature = "dbus", target_os = "linux"))]
+            "--dbus" => args.dbus = true,
+            _ => usage(),
+        }
+    }
+    args
+}
+
+/// `normal`, `high`, `realtime` (priority 10) or `realtime=N`.
+fn parse_priority(value: &str) -> Option<ThreadPriority> {
+    match value.split_once('=') {
+        Some(("realtime", n)) => n.parse().ok().map(ThreadPriority::Realtime),
+        None => match value {
+            "normal" => Some(ThreadPriority::Normal),
+            "high" => Some(ThreadPriority::High),
+            "realtime" => Some(ThreadPriority::Realtime(10)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn main() {
+    let args = parse_args();
+    let mut daemon = Daemon::new().expect("Couldn't initialize hidapi");
+    daemon.set_thread_hints(args.hints.clone());
+
+    if let Some(addr) = &args.metrics_addr {
+        let metrics = Arc::new(Metrics::new());
+        metrics::serve(addr.as_str(), metrics.clone()).expect("Couldn't bind metrics endpoint");
+        daemon.set_metrics(metrics);
+        println!("serving metrics on http://{}/metrics", addr);
+    }
+
+    if args.announce {
+        daemon.set_choreography(Choreography::default());
+    }
+
+    #[cfg(feature = "shm")]
+    if args.shm {
+        let path = ps4hid::shm::path("ds4d");
+        let writer = ps4hid::shm::SharedStateWriter::create(&path)
+            .expect("Couldn't create shared state region");
+        daemon.set_shared_state(writer);
+        println!("publishing input state to {}", path.display());
+    }
+
+    if args.profiles {
+        let profiles = ProfileSwitcher::load().expect("Couldn't load mapping profiles");
+        if profiles.is_empty() {
+            eprintln!("ds4d: no mapping profiles in {:?}", ProfileSwitcher::dir());
+            std::process::exit(1);
+        }
+        println!(
+            "loaded {} mapping profiles; hold PS and press left or right to switch",
+            profiles.len()
+        );
+        daemon.set_profile_switcher(ProfileSwitcher::new(profiles));
+    }
+
+    if args.notify {
+        let mut notifier = if args.low_battery.is_empty() {
+            Notifier::default()
+        } else {
+            Notifier::new(args.low_battery)
+        };
+        daemon.on_event(Box::new(move |event| notifier.handle(event)));
+    }
+
+    #[cfg(all(feature = "dbus", target_os = "linux"))]
+    let _dbus = args.dbus.then(|| {
+        let conn = ps4hid::dbus::serve(&mut daemon).expect("Couldn't register on D-Bus");
+        println!("registered {} on the session bus", ps4hid::dbus::BUS_NAME);
+        conn
+    });
+
+    if let Err(e) = daemon.run() {
+        eprintln!("ds4d: {}", e);
+        std::process::exit(1);
+    }
+}
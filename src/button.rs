@@ -0,0 +1,225 @@
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+
+pub struct Button<T> {
+    state: T,
+    previous: T,
+    handler: Option<ButtonHandler<T>>,
+    subscribers: Vec<Subscriber<T>>,
+    streams: Vec<Sender<T>>,
+    enabled: bool,
+    lock_mode: LockMode,
+    queued: VecDeque<T>,
+}
+
+pub type ButtonHandler<T> = fn(T, T);
+
+/// Changes a queued control keeps while disabled; older ones are dropped.
+const QUEUE_LIMIT: usize = 256;
+
+/// What a disabled control does with the changes it sees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LockMode {
+    /// Ignore them; once re-enabled the control picks up whatever state the next
+    /// report carries, so a button held through the lock reads as a fresh press.
+    #[default]
+    Drop,
+    /// Keep them and replay them, handlers and all, at the first update after the
+    /// control is re-enabled.
+    Queue,
+}
+
+struct Subscriber<T> {
+    active: Arc<AtomicBool>,
+    handler: Box<dyn FnMut(T, T) + Send>,
+}
+
+/// Keeps a handler added with `Button::subscribe` registered; dropping it
+/// unsubscribes the handler.
+#[must_use = "dropping a Subscription unsubscribes its handler immediately"]
+pub struct Subscription {
+    active: Arc<AtomicBool>,
+}
+
+impl Subscription {
+    /// Leaves the handler registered for the control's lifetime.
+    pub fn detach(self) {
+        std::mem::forget(self);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.active.store(false, Ordering::Relaxed);
+    }
+}
+
+/// What happens when a handler panics during an update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicPolicy {
+    /// Unwind through the caller, e.g. `Controller::update`.
+    #[default]
+    Propagate,
+    /// Catch the panic so the update and the read loop carry on.
+    Isolate,
+}
+
+impl<T: Default + Eq + Copy> Button<T> {
+    pub fn new(state: T) -> Self {
+        Button {
+            state,
+            previous: state,
+            handler: None,
+            subscribers: Vec::new(),
+            streams: Vec::new(),
+            enabled: true,
+            lock_mode: LockMode::Drop,
+            queued: VecDeque::new(),
+        }
+    }
+
+    pub fn state(&self) -> T {
+        self.state
+    }
+
+    /// The state before the most recent `update`, whether or not it changed.
+    pub fn previous(&self) -> T {
+        self.previous
+    }
+
+    pub fn changed(&self) -> bool {
+        self.previous != self.state
+    }
+
+    /// Whether an update to `new_state` would do anything: change the state, catch
+    /// `previous` up, or pass through a disabled or replaying control.
+    pub(crate) fn needs_update(&self, new_state: T) -> bool {
+        self.state != new_state || !self.is_settled()
+    }
+
+    fn is_settled(&self) -> bool {
+        self.enabled && self.queued.is_empty() && self.previous == self.state
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// A disabled control holds its state and fires nothing; see `LockMode` for
+    /// what happens to the changes it misses.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn set_lock_mode(&mut self, mode: LockMode) {
+        self.lock_mode = mode;
+        if mode == LockMode::Drop {
+            self.queued.clear();
+        }
+    }
+
+    /// Replaces the control's primary handler. Handlers added with `subscribe` are
+    /// unaffected.
+    pub fn set_handler(&mut self, handler: ButtonHandler<T>) {
+        self.handler = Some(handler);
+    }
+
+    /// Adds a handler alongside any others, called after the primary handler in
+    /// subscription order. It stays registered until the returned `Subscription`
+    /// is dropped.
+    pub fn subscribe(&mut self, handler: impl FnMut(T, T) + Send + 'static) -> Subscription {
+        let active = Arc::new(AtomicBool::new(true));
+        self.subscribers.push(Subscriber {
+            active: active.clone(),
+            handler: Box::new(handler),
+        });
+        Subscription { active }
+    }
+
+    /// Returns a channel receiving every new state of this control. Dropping the
+    /// receiver unsubscribes it.
+    pub fn stream(&mut self) -> Receiver<T> {
+        let (tx, rx) = channel();
+        self.streams.push(tx);
+        rx
+    }
+
+    pub fn update(&mut self, new_state: T) {
+        let _ = self.update_with(new_state, PanicPolicy::Propagate);
+    }
+
+    /// Like `update`, returning the first panic message if a handler panicked and
+    /// `policy` isolated it. Under `Isolate` every handler still runs, and streams
+    /// are fed either way.
+    pub fn update_with(&mut self, new_state: T, policy: PanicPolicy) -> Option<String> {
+        if !self.enabled {
+            self.previous = self.state;
+            let last = self.queued.back().copied().unwrap_or(self.state);
+            if self.lock_mode == LockMode::Queue && last != new_state {
+                if self.queued.len() == QUEUE_LIMIT {
+                    self.queued.pop_front();
+                }
+                self.queued.push_back(new_state);
+            }
+            return None;
+        }
+        let mut panicked = None;
+        while let Some(queued) = self.queued.pop_front() {
+            let message = self.apply(queued, policy);
+            panicked = panicked.or(message);
+        }
+        let message = self.apply(new_state, policy);
+        panicked.or(message)
+    }
+
+    fn apply(&mut self, new_state: T, policy: PanicPolicy) -> Option<String> {
+        self.previous = self.state;
+        if self.state == new_state {
+            return None;
+        }
+        let old_state = self.state;
+        self.state = new_state;
+        let mut panicked = None;
+        let mut call = |handler: &mut dyn FnMut(T, T)| match policy {
+            PanicPolicy::Propagate => handler(old_state, new_state),
+            PanicPolicy::Isolate => {
+                let result =
+                    panic::catch_unwind(AssertUnwindSafe(|| handler(old_state, new_state)));
+                if let Err(payload) = result {
+                    panicked.get_or_insert_with(|| panic_message(&*payload));
+                }
+            }
+        };
+        if let Some(mut handler) = self.handler {
+            call(&mut handler);
+        }
+        self.subscribers
+            .retain(|s| s.active.load(Ordering::Relaxed));
+        for subscriber in &mut self.subscribers {
+            call(&mut subscriber.handler);
+        }
+        self.streams.retain(|tx| tx.send(new_state).is_ok());
+        panicked
+    }
+}
+
+impl<T: Default + Eq + Copy> Default for Button<T> {
+    fn default() -> Self {
+        Button::new(T::default())
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "handler panicked".to_string())
+}
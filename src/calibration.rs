@@ -0,0 +1,201 @@
+use crate::touch::{TOUCHPAD_HEIGHT, TOUCHPAD_WIDTH};
+use crate::Stick;
+use std::f32::consts::TAU;
+
+const SWEEP_BINS: usize = 32;
+
+/// Raw trigger travel; values are rescaled so `min..=max` covers `0..=255`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TriggerRange {
+    pub min: u8,
+    pub max: u8,
+}
+
+impl Default for TriggerRange {
+    fn default() -> Self {
+        TriggerRange { min: 0, max: 255 }
+    }
+}
+
+impl TriggerRange {
+    pub fn apply(&self, v: u8) -> u8 {
+        if self.max <= self.min {
+            return v;
+        }
+        let t = (v.saturating_sub(self.min) as u32 * 255) / (self.max - self.min) as u32;
+        t.min(255) as u8
+    }
+}
+
+/// The part of the touchpad a finger can actually reach, in raw coordinates.
+/// Pads differ by a few dozen units at the edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TouchBounds {
+    pub min_x: u16,
+    pub min_y: u16,
+    pub max_x: u16,
+    pub max_y: u16,
+}
+
+impl Default for TouchBounds {
+    fn default() -> Self {
+        TouchBounds {
+            min_x: 0,
+            min_y: 0,
+            max_x: TOUCHPAD_WIDTH - 1,
+            max_y: TOUCHPAD_HEIGHT - 1,
+        }
+    }
+}
+
+impl TouchBounds {
+    /// `(x, y)` in `0.0..=1.0` across the reachable area.
+    pub fn normalize(&self, x: u16, y: u16) -> (f32, f32) {
+        let scale = |v: u16, min: u16, max: u16| {
+            if max <= min {
+                return 0.0;
+            }
+            (v.saturating_sub(min) as f32 / (max - min) as f32).min(1.0)
+        };
+        (
+            scale(x, self.min_x, self.max_x),
+            scale(y, self.min_y, self.max_y),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Calibration {
+    /// Raw value each stick reports at rest.
+    pub left_center: Stick,
+    pub right_center: Stick,
+    pub l2: TriggerRange,
+    pub r2: TriggerRange,
+    /// Raw gyro reading at rest, which `Controller::gyro` subtracts. Auto-zeroing
+    /// starts from it and refines it from there.
+    pub gyro_bias: [i16; 3],
+    pub touchpad: TouchBounds,
+}
+
+/// Radial deadzones as a fraction of full deflection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deadzones {
+    pub left: f32,
+    pub right: f32,
+}
+
+impl Default for Deadzones {
+    fn default() -> Self {
+        Deadzones {
+            left: 0.05,
+            right: 0.05,
+        }
+    }
+}
+
+fn recenter(v: u8, center: u8) -> u8 {
+    (v as i16 - center as i16 + 0x80).clamp(0, 255) as u8
+}
+
+fn apply_deadzone(stick: Stick, deadzone: f32) -> Stick {
+    if stick.x_f32().hypot(stick.y_f32()) < deadzone {
+        Stick::default()
+    } else {
+        stick
+    }
+}
+
+impl Calibration {
+    pub fn stick(&self, raw: Stick, center: Stick) -> Stick {
+        Stick::new(recenter(raw.x, center.x), recenter(raw.y, center.y))
+    }
+
+    /// Rewrites the stick and trigger bytes of a USB-aligned input report in place.
+    pub(crate) fn apply(&self, deadzones: &Deadzones, data: &mut [u8]) {
+        if data.len() < 10 {
+            return;
+        }
+        let left = self.stick(Stick::new(data[1], data[2]), self.left_center);
+        let left = apply_deadzone(left, deadzones.left);
+        let right = self.stick(Stick::new(data[3], data[4]), self.right_center);
+        let right = apply_deadzone(right, deadzones.right);
+        data[1] = left.x;
+        data[2] = left.y;
+        data[3] = right.x;
+        data[4] = right.y;
+        data[8] = self.l2.apply(data[8]);
+        data[9] = self.r2.apply(data[9]);
+    }
+}
+
+/// Records the outer edge of a stick's gate while the user rotates it at full
+/// deflection, producing a `Circularity` correction.
+#[derive(Debug, Clone)]
+pub struct CircularitySweep {
+    max_radius: [f32; SWEEP_BINS],
+}
+
+impl Default for CircularitySweep {
+    fn default() -> Self {
+        CircularitySweep {
+            max_radius: [0.0; SWEEP_BINS],
+        }
+    }
+}
+
+fn bin_position(theta: f32) -> f32 {
+    theta.rem_euclid(TAU) / TAU * SWEEP_BINS as f32
+}
+
+impl CircularitySweep {
+    pub fn new() -> Self {
+        CircularitySweep::default()
+    }
+
+    pub fn record(&mut self, stick: Stick) {
+        let (r, theta) = stick.polar();
+        let bin = (bin_position(theta) as usize).min(SWEEP_BINS - 1);
+        self.max_radius[bin] = self.max_radius[bin].max(r);
+    }
+
+    /// Fraction of angular bins that have seen a deflection of at least half travel.
+    pub fn coverage(&self) -> f32 {
+        self.max_radius.iter().filter(|r| **r >= 0.5).count() as f32 / SWEEP_BINS as f32
+    }
+
+    /// Bins the sweep missed fall back to a radius of 1.
+    pub fn finish(&self) -> Circularity {
+        let mut max_radius = self.max_radius;
+        for r in max_radius.iter_mut() {
+            if *r < 0.5 {
+                *r = 1.0;
+            }
+        }
+        Circularity { max_radius }
+    }
+}
+
+/// Rescales stick positions so the measured gate maps onto the unit circle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Circularity {
+    max_radius: [f32; SWEEP_BINS],
+}
+
+impl Circularity {
+    /// Measured edge of the gate in each angular bin, counter-clockwise from the
+    /// right, as a fraction of full deflection.
+    pub fn radii(&self) -> &[f32] {
+        &self.max_radius
+    }
+
+    /// Corrected `(x, y)` with Y up, within the unit circle.
+    pub fn correct(&self, stick: Stick) -> (f32, f32) {
+        let (r, theta) = stick.polar();
+        let pos = bin_position(theta) - 0.5;
+        let i = pos.floor().rem_euclid(SWEEP_BINS as f32) as usize;
+        let t = pos - pos.floor();
+        let edge = self.max_radius[i] * (1.0 - t) + self.max_radius[(i + 1) % SWEEP_BINS] * t;
+        let r = (r / edge.max(0.01)).min(1.0);
+        (r * theta.cos(), r * theta.sin())
+    }
+}
@@ -0,0 +1,281 @@
+use crate::lifecycle::{LifecycleState, Transition};
+use crate::lightbar::Rgb;
+use crate::output::{OutputReport, OutputReportBuilder};
+use crate::{Controller, Event, Result};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const PULSE: Duration = Duration::from_millis(120);
+const GAP: Duration = Duration::from_millis(120);
+const HOLD: Duration = Duration::from_millis(300);
+const PULSE_RUMBLE: (u8, u8) = (0, 160);
+
+/// One stretch of a pattern: the motors at `rumble` and the lightbar at `color`
+/// for `duration`. Lightbar flashing is stopped while a pattern plays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    /// Strong and weak motor speeds.
+    pub rumble: (u8, u8),
+    /// `None` leaves the lightbar on the color it had before the pattern.
+    pub color: Option<Rgb>,
+    pub duration: Duration,
+}
+
+impl Step {
+    /// Motors off and the lightbar unchanged for `duration`.
+    pub fn new(duration: Duration) -> Self {
+        Step {
+            rumble: (0, 0),
+            color: None,
+            duration,
+        }
+    }
+
+    pub fn rumble(mut self, strong: u8, weak: u8) -> Self {
+        self.rumble = (strong, weak);
+        self
+    }
+
+    pub fn color(mut self, color: Rgb) -> Self {
+        self.color = Some(color);
+        self
+    }
+}
+
+/// A short sequence of rumble and lightbar steps. The output it overrides is
+/// restored once it ends.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Pattern {
+    pub steps: Vec<Step>,
+}
+
+impl Pattern {
+    pub fn new() -> Self {
+        Pattern::default()
+    }
+
+    pub fn then(mut self, step: Step) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// `count` short pulses, each with the lightbar lit in `color` and dark in
+    /// between, then `color` held briefly: the pad that becomes player 2 buzzes
+    /// and blinks twice.
+    pub fn player(count: u8, color: Rgb) -> Self {
+        let mut pattern = Pattern::new();
+        for _ in 0..count.max(1) {
+            pattern = pattern
+                .then(
+                    Step::new(PULSE)
+                        .rumble(PULSE_RUMBLE.0, PULSE_RUMBLE.1)
+                        .color(color),
+                )
+                .then(Step::new(GAP).color(Rgb::new(0, 0, 0)));
+        }
+        pattern.then(Step::new(HOLD).color(color))
+    }
+
+    /// A single longer, dimming pulse in red.
+    pub fn farewell() -> Self {
+        Pattern::new()
+            .then(Step::new(HOLD).rumble(0, 120).color(Rgb::new(255, 0, 0)))
+            .then(Step::new(HOLD).color(Rgb::new(64, 0, 0)))
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.steps.iter().map(|s| s.duration).sum()
+    }
+}
+
+/// Which pattern is playing, as reported by `Event::ChoreographyStarted` and
+/// `Event::ChoreographyFinished`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cue {
+    Connect,
+    Disconnect,
+    /// Played on request with `Controller::play`, e.g. to point out one pad.
+    Identify,
+}
+
+/// Physical confirmation of lifecycle changes, for `Controller::set_choreography`.
+///
+/// `connect` plays when the pad starts streaming full reports, whether newly
+/// opened or woken from sleep. A pad that disappeared can't be written to, so
+/// `disconnect` only plays when it's let go deliberately, from
+/// `Controller::close` or `Controller::farewell`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Choreography {
+    pub connect: Option<Pattern>,
+    pub disconnect: Option<Pattern>,
+}
+
+impl Default for Choreography {
+    /// One white pulse on connect and `Pattern::farewell` on disconnect.
+    fn default() -> Self {
+        Choreography {
+            connect: Some(Pattern::player(1, Rgb::new(255, 255, 255))),
+            disconnect: Some(Pattern::farewell()),
+        }
+    }
+}
+
+impl Choreography {
+    pub fn new() -> Self {
+        Choreography::default()
+    }
+
+    /// Announces the pad as player `number` (from 1) in `color` when it connects.
+    pub fn player(number: u8, color: Rgb) -> Self {
+        Choreography {
+            connect: Some(Pattern::player(number, color)),
+            ..Choreography::default()
+        }
+    }
+
+    pub fn connect(mut self, pattern: Option<Pattern>) -> Self {
+        self.connect = pattern;
+        self
+    }
+
+    pub fn disconnect(mut self, pattern: Option<Pattern>) -> Self {
+        self.disconnect = pattern;
+        self
+    }
+}
+
+/// A pattern in progress on a controller, with the output it's overriding.
+pub(crate) struct Playback {
+    cue: Cue,
+    steps: Vec<Step>,
+    index: usize,
+    step_ends: Instant,
+    /// The output to go back to, kept up to date with anything sent meanwhile.
+    pub saved: OutputReport,
+}
+
+impl Controller {
+    /// Plays `choreography`'s patterns on lifecycle changes from now on; `None`
+    /// stops doing so.
+    pub fn set_choreography(&mut self, choreography: Option<Choreography>) {
+        self.choreography = choreography;
+    }
+
+    pub fn choreography(&self) -> Option<&Choreography> {
+        self.choreography.as_ref()
+    }
+
+    /// Starts `pattern` in place of the current lightbar and rumble, stepped by
+    /// `update`. Output sent while it plays takes effect once it ends.
+    pub fn play(&mut self, pattern: &Pattern) {
+        self.start_playback(Cue::Identify, pattern, Instant::now());
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    /// Plays the disconnect pattern to the end, blocking for its duration, and
+    /// puts the previous output back. Does nothing without one, or if the pad
+    /// isn't streaming.
+    pub fn farewell(&mut self) -> Result<()> {
+        let Some(pattern) = self
+            .choreography
+            .as_ref()
+            .and_then(|c| c.disconnect.clone())
+        else {
+            return Ok(());
+        };
+        if !self.state().is_streaming() {
+            return Ok(());
+        }
+        let saved = self
+            .finish_playback()
+            .unwrap_or_else(|| self.output_state());
+        self.push_event(Event::ChoreographyStarted(Cue::Disconnect));
+        for step in &pattern.steps {
+            self.write_now(&step_output(step, &saved))?;
+            thread::sleep(step.duration);
+        }
+        self.push_event(Event::ChoreographyFinished(Cue::Disconnect));
+        self.restore_output(saved)
+    }
+
+    /// Starts the connect pattern when full reports begin.
+    pub(crate) fn cue_transition(&mut self, transition: Transition) {
+        let connected = matches!(
+            transition.previous,
+            LifecycleState::Connecting | LifecycleState::Handshaking
+        ) && transition.current == LifecycleState::Streaming;
+        if let Some(pattern) = self
+            .choreography
+            .as_ref()
+            .and_then(|c| c.connect.clone())
+            .filter(|_| connected)
+        {
+            self.start_playback(Cue::Connect, &pattern, Instant::now());
+        }
+    }
+
+    fn start_playback(&mut self, cue: Cue, pattern: &Pattern, at: Instant) {
+        let Some(first) = pattern.steps.first() else {
+            return;
+        };
+        // A pattern cut short by another still restores what was there before either.
+        let saved = self
+            .finish_playback()
+            .unwrap_or_else(|| self.output_state());
+        self.queue_output(&step_output(first, &saved));
+        self.playback = Some(Playback {
+            cue,
+            steps: pattern.steps.clone(),
+            index: 0,
+            step_ends: at + first.duration,
+            saved,
+        });
+        self.push_event(Event::ChoreographyStarted(cue));
+    }
+
+    /// Moves a playing pattern on to the step due at `at`, restoring the saved
+    /// output once it's done.
+    pub(crate) fn advance_playback(&mut self, at: Instant) {
+        let Some(playback) = &mut self.playback else {
+            return;
+        };
+        if at < playback.step_ends {
+            return;
+        }
+        playback.index += 1;
+        match playback.steps.get(playback.index).copied() {
+            Some(step) => {
+                playback.step_ends += step.duration;
+                let output = step_output(&step, &playback.saved);
+                self.queue_output(&output);
+            }
+            None => {
+                let cue = playback.cue;
+                if let Some(saved) = self.finish_playback() {
+                    self.set_output_state(saved);
+                }
+                self.push_event(Event::ChoreographyFinished(cue));
+            }
+        }
+    }
+
+    /// Stops any pattern, returning the output it was covering.
+    pub(crate) fn finish_playback(&mut self) -> Option<OutputReport> {
+        self.playback.take().map(|p| p.saved)
+    }
+}
+
+/// What the pad should show during `step`, with `saved` as the output beneath.
+fn step_output(step: &Step, saved: &OutputReport) -> OutputReportBuilder {
+    let Rgb { r, g, b } = step
+        .color
+        .unwrap_or(Rgb::new(saved.red, saved.green, saved.blue));
+    let (strong, weak) = step.rumble;
+    OutputReportBuilder::new()
+        .rumble(strong, weak)
+        .lightbar(r, g, b)
+        .flash(0, 0)
+}
@@ -0,0 +1,124 @@
+pub use ds4_core::TICK_SECS;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// The 16-bit device clock wraps this often, about every 350ms.
+const WRAP_SECS: f64 = 65536.0 * TICK_SECS;
+const DEFAULT_WINDOW: usize = 512;
+
+/// Maps the controller's report timestamps onto host time.
+///
+/// Host arrival times carry USB/Bluetooth and scheduling latency on top of the true
+/// sample time, so the model fits the drift between the two clocks by least squares
+/// and takes the offset from the lower envelope of arrivals, where latency was least.
+pub struct DeviceClock {
+    epoch: Option<Instant>,
+    last: Option<(u16, Instant)>,
+    ticks: u64,
+    window: usize,
+    samples: VecDeque<(f64, f64)>,
+    rate: f64,
+    offset: f64,
+}
+
+impl DeviceClock {
+    pub fn new() -> Self {
+        DeviceClock::with_window(DEFAULT_WINDOW)
+    }
+
+    /// Fits over the last `window` reports; larger windows are steadier but slower
+    /// to follow temperature drift.
+    pub fn with_window(window: usize) -> Self {
+        DeviceClock {
+            epoch: None,
+            last: None,
+            ticks: 0,
+            window: window.max(2),
+            samples: VecDeque::with_capacity(window.max(2)),
+            rate: 1.0,
+            offset: 0.0,
+        }
+    }
+
+    /// Forgets the model, e.g. after the pad slept and its clock restarted.
+    pub fn reset(&mut self) {
+        *self = DeviceClock::with_window(self.window);
+    }
+
+    /// Records a report's timestamp and host arrival time, returning the host time
+    /// the report was actually sampled at.
+    pub fn observe(&mut self, ticks: u16, arrived: Instant) -> Instant {
+        let epoch = *self.epoch.get_or_insert(arrived);
+        if let Some((last_ticks, last_arrived)) = self.last {
+            let delta = ticks.wrapping_sub(last_ticks) as f64 * TICK_SECS;
+            // Gaps longer than one wrap hide whole wraps; recover them from host time.
+            let host_delta = arrived
+                .saturating_duration_since(last_arrived)
+                .as_secs_f64();
+            let wraps = ((host_delta - delta) / WRAP_SECS).round().max(0.0);
+            self.ticks += ticks.wrapping_sub(last_ticks) as u64 + wraps as u64 * 65536;
+        }
+        self.last = Some((ticks, arrived));
+
+        let device = self.ticks as f64 * TICK_SECS;
+        let host = arrived.saturating_duration_since(epoch).as_secs_f64();
+        if self.samples.len() == self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((device, host));
+        self.fit();
+        self.to_host(self.device_time())
+    }
+
+    /// Device time elapsed since the first observed report.
+    pub fn device_time(&self) -> Duration {
+        Duration::from_secs_f64(self.ticks as f64 * TICK_SECS)
+    }
+
+    /// The host instant matching a device time from [`DeviceClock::device_time`].
+    pub fn to_host(&self, device_time: Duration) -> Instant {
+        let Some(epoch) = self.epoch else {
+            return Instant::now();
+        };
+        let host = self.offset + self.rate * device_time.as_secs_f64();
+        if host >= 0.0 {
+            epoch + Duration::from_secs_f64(host)
+        } else {
+            epoch
+                .checked_sub(Duration::from_secs_f64(-host))
+                .unwrap_or(epoch)
+        }
+    }
+
+    /// How fast the device clock runs relative to the host, in parts per million.
+    pub fn drift_ppm(&self) -> f64 {
+        (1.0 / self.rate - 1.0) * 1_000_000.0
+    }
+
+    fn fit(&mut self) {
+        let n = self.samples.len() as f64;
+        if n >= 16.0 {
+            let (mean_d, mean_h) = self
+                .samples
+                .iter()
+                .fold((0.0, 0.0), |(d, h), &(sd, sh)| (d + sd / n, h + sh / n));
+            let (cov, var) = self.samples.iter().fold((0.0, 0.0), |(c, v), &(d, h)| {
+                (c + (d - mean_d) * (h - mean_h), v + (d - mean_d).powi(2))
+            });
+            if var > 0.0 {
+                self.rate = cov / var;
+            }
+        }
+        self.offset = self
+            .samples
+            .iter()
+            .map(|&(d, h)| h - self.rate * d)
+            .fold(f64::INFINITY, f64::min);
+    }
+}
+
+impl Default for DeviceClock {
+    fn default() -> Self {
+        DeviceClock::new()
+    }
+}
@@ -0,0 +1,262 @@
+use crate::{ButtonId, Snapshot};
+use crate::{DPad, Stick, YAxis};
+use smallvec::SmallVec;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComboInput {
+    Press(ButtonId),
+    /// Matches the dpad, or the left stick when the dpad is released.
+    Direction(DPad),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Combo {
+    pub name: String,
+    pub steps: Vec<ComboInput>,
+    /// Maximum time from the first step to the last.
+    pub window: Duration,
+}
+
+impl Combo {
+    pub fn new(name: &str, window: Duration, steps: &[ComboInput]) -> Self {
+        Combo {
+            name: name.to_string(),
+            steps: steps.to_vec(),
+            window,
+        }
+    }
+
+    /// Down, down-forward, forward + `button`, with forward being east.
+    pub fn quarter_circle_forward(name: &str, button: ButtonId, window: Duration) -> Self {
+        Combo::new(
+            name,
+            window,
+            &[
+                ComboInput::Direction(DPad::South),
+                ComboInput::Direction(DPad::SouthEast),
+                ComboInput::Direction(DPad::East),
+                ComboInput::Press(button),
+            ],
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComboEvent {
+    pub name: String,
+    pub at: Instant,
+}
+
+struct Progress {
+    step: usize,
+    started: Instant,
+}
+
+/// Recognizes combos from successive snapshots. Inputs that don't match the next
+/// step are ignored rather than resetting progress, so only the window expires a combo.
+pub struct ComboDetector {
+    combos: Vec<(Combo, Option<Progress>)>,
+    last: Snapshot,
+    stick_threshold: f32,
+}
+
+impl Default for ComboDetector {
+    fn default() -> Self {
+        ComboDetector::new()
+    }
+}
+
+impl ComboDetector {
+    pub fn new() -> Self {
+        ComboDetector {
+            combos: Vec::new(),
+            last: Snapshot::default(),
+            stick_threshold: 0.5,
+        }
+    }
+
+    pub fn add(&mut self, combo: Combo) {
+        self.combos.push((combo, None));
+    }
+
+    pub fn update(&mut self, at: Instant, snapshot: &Snapshot) -> Vec<ComboEvent> {
+        // At most a direction and every button, so this never spills to the heap.
+        let mut inputs = SmallVec::<[ComboInput; ButtonId::ALL.len() + 1]>::new();
+        let (old_dir, new_dir) = (self.direction(&self.last), self.direction(snapshot));
+        if new_dir != old_dir && new_dir != DPad::Released {
+            inputs.push(ComboInput::Direction(new_dir));
+        }
+        let (pressed, _) = snapshot.buttons().diff(self.last.buttons());
+        inputs.extend(pressed.ids().map(ComboInput::Press));
+        self.last = *snapshot;
+
+        let mut events = Vec::new();
+        for input in inputs {
+            for (combo, progress) in self.combos.iter_mut() {
+                if progress
+                    .as_ref()
+                    .is_some_and(|p| at.saturating_duration_since(p.started) > combo.window)
+                {
+                    *progress = None;
+                }
+
+                let step = progress.as_ref().map_or(0, |p| p.step);
+                if combo.steps.get(step) == Some(&input) {
+                    let started = progress.as_ref().map_or(at, |p| p.started);
+                    *progress = Some(Progress {
+                        step: step + 1,
+                        started,
+                    });
+                } else if combo.steps.first() == Some(&input) {
+                    *progress = Some(Progress {
+                        step: 1,
+                        started: at,
+                    });
+                }
+
+                if progress
+                    .as_ref()
+                    .is_some_and(|p| p.step == combo.steps.len())
+                {
+                    *progress = None;
+                    events.push(ComboEvent {
+                        name: combo.name.clone(),
+                        at,
+                    });
+                }
+            }
+        }
+        events
+    }
+
+    fn direction(&self, snapshot: &Snapshot) -> DPad {
+        if snapshot.dpad != DPad::Released {
+            snapshot.dpad
+        } else {
+            stick_direction(snapshot.left_stick, self.stick_threshold)
+        }
+    }
+}
+
+fn stick_direction(stick: Stick, threshold: f32) -> DPad {
+    let (x, y) = stick.centered(YAxis::Up);
+    if x.hypot(y) < threshold {
+        return DPad::Released;
+    }
+    let octant = ((y.atan2(x).to_degrees() + 360.0 + 22.5) % 360.0 / 45.0) as u8;
+    match octant {
+        0 => DPad::East,
+        1 => DPad::NorthEast,
+        2 => DPad::North,
+        3 => DPad::NorthWest,
+        4 => DPad::West,
+        5 => DPad::SouthWest,
+        6 => DPad::South,
+        _ => DPad::SouthEast,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_millis(500);
+
+    fn dpad(dpad: DPad) -> Snapshot {
+        Snapshot {
+            dpad,
+            ..Snapshot::default()
+        }
+    }
+
+    fn stick(x: f32, y: f32) -> Snapshot {
+        Snapshot {
+            left_stick: Stick::from_centered(x, y, YAxis::Up),
+            ..Snapshot::default()
+        }
+    }
+
+    fn pressed(base: Snapshot, id: ButtonId) -> Snapshot {
+        let mut snapshot = base;
+        snapshot.set_pressed(id, true);
+        snapshot
+    }
+
+    /// Feeds `frames` 16 ms apart, returning the names of the combos detected.
+    fn run(detector: &mut ComboDetector, start: Instant, frames: &[Snapshot]) -> Vec<String> {
+        let mut names = Vec::new();
+        for (i, frame) in frames.iter().enumerate() {
+            let at = start + Duration::from_millis(16 * i as u64);
+            names.extend(detector.update(at, frame).into_iter().map(|e| e.name));
+        }
+        names
+    }
+
+    fn hadouken() -> ComboDetector {
+        let mut detector = ComboDetector::new();
+        detector.add(Combo::quarter_circle_forward(
+            "hadouken",
+            ButtonId::Square,
+            WINDOW,
+        ));
+        detector
+    }
+
+    #[test]
+    fn quarter_circle_on_the_dpad() {
+        let frames = [
+            dpad(DPad::South),
+            dpad(DPad::SouthEast),
+            dpad(DPad::East),
+            pressed(dpad(DPad::East), ButtonId::Square),
+        ];
+        assert_eq!(run(&mut hadouken(), Instant::now(), &frames), ["hadouken"]);
+    }
+
+    #[test]
+    fn quarter_circle_on_the_stick() {
+        let frames = [
+            stick(0.0, -1.0),
+            stick(0.7, -0.7),
+            stick(1.0, 0.0),
+            pressed(stick(1.0, 0.0), ButtonId::Square),
+        ];
+        assert_eq!(run(&mut hadouken(), Instant::now(), &frames), ["hadouken"]);
+    }
+
+    #[test]
+    fn unrelated_inputs_do_not_break_the_combo() {
+        let frames = [
+            dpad(DPad::South),
+            pressed(dpad(DPad::South), ButtonId::X),
+            dpad(DPad::SouthEast),
+            dpad(DPad::East),
+            pressed(dpad(DPad::East), ButtonId::Square),
+        ];
+        assert_eq!(run(&mut hadouken(), Instant::now(), &frames), ["hadouken"]);
+    }
+
+    #[test]
+    fn a_slow_combo_expires() {
+        let mut detector = hadouken();
+        let start = Instant::now();
+        assert!(detector.update(start, &dpad(DPad::South)).is_empty());
+        assert!(detector.update(start, &dpad(DPad::SouthEast)).is_empty());
+        assert!(detector.update(start, &dpad(DPad::East)).is_empty());
+        let late = start + WINDOW + Duration::from_millis(1);
+        let frame = pressed(dpad(DPad::East), ButtonId::Square);
+        assert!(detector.update(late, &frame).is_empty());
+    }
+
+    #[test]
+    fn out_of_order_steps_do_not_match() {
+        let frames = [
+            dpad(DPad::East),
+            dpad(DPad::SouthEast),
+            dpad(DPad::South),
+            pressed(dpad(DPad::South), ButtonId::Square),
+        ];
+        assert!(run(&mut hadouken(), Instant::now(), &frames).is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+#[cfg(feature = "hid")]
+use crate::controller::PRODUCT_ID_DONGLE;
+use crate::controller::{PRODUCT_ID, VENDOR_ID};
+#[cfg(feature = "hid")]
+use hidapi::DeviceInfo;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Connection {
+    Usb,
+    Bluetooth,
+    /// The Sony wireless adapter, which presents a Bluetooth pad as a USB device.
+    Dongle,
+}
+
+impl Connection {
+    /// The default minimum time between output writes. Bluetooth pads, including
+    /// ones behind the adapter, stall their input stream when written to much
+    /// faster than this, so they get a longer interval than USB.
+    pub fn output_interval(self) -> Duration {
+        match self {
+            Connection::Usb => Duration::from_millis(4),
+            Connection::Bluetooth | Connection::Dongle => Duration::from_millis(16),
+        }
+    }
+}
+
+impl fmt::Display for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Connection::Usb => "USB",
+            Connection::Bluetooth => "Bluetooth",
+            Connection::Dongle => "Dongle",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReportLayout {
+    /// 64-byte report 0x01.
+    Usb,
+    /// 78-byte report 0x11, sent once the host has requested the calibration feature report.
+    Bluetooth,
+    /// Reduced report 0x01 sent over Bluetooth before the host handshake: no touch or IMU data.
+    BluetoothShort,
+}
+
+impl ReportLayout {
+    pub(crate) fn detect(connection: Connection, report_id: u8) -> Option<Self> {
+        match (connection, report_id) {
+            (_, 0x11) => Some(ReportLayout::Bluetooth),
+            (Connection::Bluetooth, 0x01) => Some(ReportLayout::BluetoothShort),
+            (_, 0x01) => Some(ReportLayout::Usb),
+            _ => None,
+        }
+    }
+
+    /// Number of bytes to skip so the control data lines up with the USB layout.
+    pub fn offset(&self) -> usize {
+        match self {
+            ReportLayout::Usb | ReportLayout::BluetoothShort => 0,
+            ReportLayout::Bluetooth => 2,
+        }
+    }
+
+    pub fn input_len(&self) -> usize {
+        match self {
+            ReportLayout::Usb => 64,
+            ReportLayout::Bluetooth => 78,
+            ReportLayout::BluetoothShort => 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    pub connection: Connection,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub path: Option<String>,
+    pub serial: Option<String>,
+    /// `None` when the platform doesn't report one, which is typical for Bluetooth.
+    pub interface: Option<i32>,
+}
+
+impl ConnectionInfo {
+    #[cfg(feature = "hid")]
+    pub fn from_device_info(info: &DeviceInfo) -> Self {
+        let interface = info.interface_number();
+        let connection = if info.product_id() == PRODUCT_ID_DONGLE {
+            Connection::Dongle
+        } else if interface < 0 {
+            Connection::Bluetooth
+        } else {
+            Connection::Usb
+        };
+        ConnectionInfo {
+            connection,
+            vendor_id: info.vendor_id(),
+            product_id: info.product_id(),
+            path: info.path().to_str().ok().map(str::to_string),
+            serial: info
+                .serial_number()
+                .filter(|s| !s.is_empty())
+                .map(str::to_string),
+            interface: (interface >= 0).then_some(interface),
+        }
+    }
+}
+
+impl Default for ConnectionInfo {
+    fn default() -> Self {
+        ConnectionInfo {
+            connection: Connection::Usb,
+            vendor_id: VENDOR_ID,
+            product_id: PRODUCT_ID,
+            path: None,
+            serial: None,
+            interface: None,
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use crate::source::{InputEvent, InputSource};
+use crate::{ButtonId, DPad, Result};
+
+type EventHandler = Box<dyn FnMut(&InputEvent) -> bool + Send>;
+type GuideHandler = Box<dyn FnMut(bool) + Send>;
+
+/// A named set of input bindings, such as "gameplay", "menu" or "text entry".
+///
+/// Handlers return whether they consumed the event. A context that doesn't
+/// consume an event still stops it from reaching the contexts below unless it
+/// passes unconsumed events through.
+pub struct InputContext {
+    pub name: String,
+    /// Hand unconsumed events to the context below, e.g. for an overlay that
+    /// only cares about a few buttons.
+    pub pass_through: bool,
+    handlers: Vec<EventHandler>,
+}
+
+impl InputContext {
+    pub fn new(name: impl Into<String>) -> Self {
+        InputContext {
+            name: name.into(),
+            pass_through: false,
+            handlers: Vec::new(),
+        }
+    }
+
+    pub fn pass_through(mut self, pass_through: bool) -> Self {
+        self.pass_through = pass_through;
+        self
+    }
+
+    /// Sees every event; return `true` to consume it.
+    pub fn on_event(mut self, handler: impl FnMut(&InputEvent) -> bool + Send + 'static) -> Self {
+        self.handlers.push(Box::new(handler));
+        self
+    }
+
+    /// Called with the new state whenever `id` changes; consumes those events.
+    pub fn on_button(self, id: ButtonId, mut handler: impl FnMut(bool) + Send + 'static) -> Self {
+        self.on_event(move |event| match *event {
+            InputEvent::Button {
+                id: changed,
+                pressed,
+            } if changed == id => {
+                handler(pressed);
+                true
+            }
+            _ => false,
+        })
+    }
+
+    /// Called whenever the dpad changes; consumes those events.
+    pub fn on_dpad(self, mut handler: impl FnMut(DPad) + Send + 'static) -> Self {
+        self.on_event(move |event| match *event {
+            InputEvent::DPad(dpad) => {
+                handler(dpad);
+                true
+            }
+            _ => false,
+        })
+    }
+
+    /// Runs handlers in registration order until one consumes the event.
+    fn handle(&mut self, event: &InputEvent) -> bool {
+        self.handlers.iter_mut().any(|handler| handler(event))
+    }
+}
+
+/// The app-level handler for the guide (PS) button, like a console's: it sees
+/// the button whatever context is active, e.g. to open an overlay.
+pub struct GuideHook {
+    pub button: ButtonId,
+    /// Keep guide events from reaching contexts, as consoles do.
+    pub suppress: bool,
+    handler: GuideHandler,
+}
+
+impl GuideHook {
+    /// Calls `handler` with the PS button's new state whenever it changes, and
+    /// suppresses it.
+    pub fn new(handler: impl FnMut(bool) + Send + 'static) -> Self {
+        GuideHook {
+            button: ButtonId::Ps,
+            suppress: true,
+            handler: Box::new(handler),
+        }
+    }
+
+    pub fn button(mut self, id: ButtonId) -> Self {
+        self.button = id;
+        self
+    }
+
+    pub fn suppress(mut self, suppress: bool) -> Self {
+        self.suppress = suppress;
+        self
+    }
+
+    /// Runs the handler if `event` is the guide button, returning whether the
+    /// event should be kept from everything else. Use it directly to filter a
+    /// source's events without a `ContextStack`.
+    pub fn handle(&mut self, event: &InputEvent) -> bool {
+        match *event {
+            InputEvent::Button { id, pressed } if id == self.button => {
+                (self.handler)(pressed);
+                self.suppress
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Stack of input contexts where events go to the top context first, after the
+/// guide hook if there is one.
+#[derive(Default)]
+pub struct ContextStack {
+    contexts: Vec<InputContext>,
+    guide: Option<GuideHook>,
+}
+
+impl ContextStack {
+    pub fn new() -> Self {
+        ContextStack::default()
+    }
+
+    pub fn push(&mut self, context: InputContext) {
+        self.contexts.push(context);
+    }
+
+    pub fn pop(&mut self) -> Option<InputContext> {
+        self.contexts.pop()
+    }
+
+    /// Pops contexts down to and including the topmost one called `name`.
+    pub fn pop_to(&mut self, name: &str) -> Option<InputContext> {
+        let i = self.contexts.iter().rposition(|c| c.name == name)?;
+        self.contexts.drain(i..).next()
+    }
+
+    /// Sets the hook that sees the guide button before any context, even with
+    /// the stack empty.
+    pub fn set_guide_hook(&mut self, hook: GuideHook) {
+        self.guide = Some(hook);
+    }
+
+    pub fn clear_guide_hook(&mut self) -> Option<GuideHook> {
+        self.guide.take()
+    }
+
+    pub fn top(&self) -> Option<&InputContext> {
+        self.contexts.last()
+    }
+
+    pub fn len(&self) -> usize {
+        self.contexts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contexts.is_empty()
+    }
+
+    /// Delivers `event` from the top down, returning whether a context, or the
+    /// guide hook suppressing it, consumed it.
+    pub fn dispatch(&mut self, event: &InputEvent) -> bool {
+        if self.guide.as_mut().is_some_and(|guide| guide.handle(event)) {
+            return true;
+        }
+        for context in self.contexts.iter_mut().rev() {
+            if context.handle(event) {
+                return true;
+            }
+            if !context.pass_through {
+                break;
+            }
+        }
+        false
+    }
+
+    /// Dispatches the next event from `source`, waiting as `next_event` does,
+    /// and those already pending behind it. It stops there, so it returns even
+    /// while the input keeps changing.
+    pub fn pump(&mut self, source: &mut dyn InputSource) -> Result<()> {
+        let Some(event) = source.next_event()? else {
+            return Ok(());
+        };
+        self.dispatch(&event);
+        for _ in 0..source.pending() {
+            match source.next_event()? {
+                Some(event) => self.dispatch(&event),
+                None => break,
+            };
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Snapshot;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// A source whose input never stops changing, with one more event always
+    /// pending.
+    struct Endless;
+
+    impl InputSource for Endless {
+        fn next_event(&mut self) -> Result<Option<InputEvent>> {
+            Ok(Some(InputEvent::DPad(DPad::North)))
+        }
+
+        fn pending(&self) -> usize {
+            1
+        }
+
+        fn state(&self) -> Snapshot {
+            Snapshot::default()
+        }
+    }
+
+    #[test]
+    fn pump_returns_while_input_keeps_coming() {
+        let seen = Arc::new(AtomicUsize::new(0));
+        let counter = seen.clone();
+        let mut stack = ContextStack::new();
+        stack.push(InputContext::new("all").on_event(move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            true
+        }));
+        assert!(stack.pump(&mut Endless).is_ok());
+        assert_eq!(seen.load(Ordering::Relaxed), 2);
+    }
+}
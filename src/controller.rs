@@ -0,0 +1,1123 @@
+use crate::battery::{Battery, STATUS_OFFSET};
+use crate::choreography::{Choreography, Playback};
+use crate::clock::DeviceClock;
+use crate::connection::{Connection, ConnectionInfo, ReportLayout};
+use crate::firmware::{Capabilities, Firmware, FIRMWARE_REPORT_ID, FIRMWARE_REPORT_LEN};
+use crate::gyro::GyroAutoZero;
+use crate::lifecycle::{Lifecycle, LifecycleState};
+use crate::lightbar::Rgb;
+use crate::metrics::Metrics;
+use crate::output::{
+    OutputReport, OutputReportBuilder, OutputScheduler, OutputStats, FLAG_FLASH, FLAG_LIGHTBAR,
+    OUTPUT_REPORT_MAX_LEN, STALL_AFTER_FAILURES,
+};
+use crate::pairing::{Pairing, PAIRING_REPORT_ID, PAIRING_REPORT_LEN};
+use crate::quirks::{self, Quirk};
+use crate::source::{EventQueue, InputEvent};
+use crate::transport::Transport;
+use crate::wake::{WakeFilter, WakeGuard};
+use crate::{Controls, Error, Event, History, PeripheralState, Profile, Result, UsageStats};
+#[cfg(feature = "hid")]
+use hidapi::{DeviceInfo, HidApi, HidDevice};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub const VENDOR_ID: u16 = 1356;
+pub const PRODUCT_ID: u16 = 2508;
+pub const PRODUCT_ID_V1: u16 = 1476;
+pub const PRODUCT_ID_DONGLE: u16 = 2976;
+
+const MAX_REPORT_LEN: usize = 78;
+/// Reading the Bluetooth calibration report switches the pad to full 0x11 reports.
+const BT_CALIBRATION_REPORT_ID: u8 = 0x05;
+const BT_CALIBRATION_REPORT_LEN: usize = 41;
+const HANDSHAKE_RETRY: Duration = Duration::from_millis(500);
+/// Device events queued before any allocation, far more than one report yields.
+const EVENT_CAPACITY: usize = 32;
+/// A report read this long after it was sampled sat in a queue rather than in
+/// transit; about three Bluetooth report intervals at the default rate.
+const BACKLOG_LAG: Duration = Duration::from_millis(24);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Identity<'a> {
+    /// Serial number, or Bluetooth address when the device reports no serial.
+    pub key: Option<&'a str>,
+    pub name: Option<&'a str>,
+    pub color: Option<Rgb>,
+}
+
+pub struct Controller {
+    device: Box<dyn Transport + Send>,
+    pub controls: Controls,
+    info: ConnectionInfo,
+    layout: Option<ReportLayout>,
+    last_report: Option<Instant>,
+    report_interval: Option<Duration>,
+    battery: Battery,
+    peripherals: Option<PeripheralState>,
+    identity_key: Option<String>,
+    profile: Profile,
+    stats: UsageStats,
+    events: VecDeque<Event>,
+    history: Option<History>,
+    pairing: Option<Pairing>,
+    firmware: Option<Firmware>,
+    /// The version report read failed, so it's retried after the next wake.
+    firmware_pending: bool,
+    capabilities: Capabilities,
+    quirk: Option<Quirk>,
+    pairing_watch: Option<(Duration, Instant)>,
+    last_handshake: Option<Instant>,
+    output: OutputScheduler,
+    metrics: Option<Arc<Metrics>>,
+    last_counter: Option<u8>,
+    /// Whether the last report arrived stale, so a backlog is reported once.
+    backlogged: bool,
+    clock: DeviceClock,
+    sampled_at: Option<Instant>,
+    gyro_zero: Option<GyroAutoZero>,
+    wake: WakeFilter,
+    lifecycle: Lifecycle,
+    pub(crate) input_events: EventQueue,
+    pub(crate) choreography: Option<Choreography>,
+    pub(crate) playback: Option<Playback>,
+}
+
+impl Controller {
+    #[cfg(feature = "hid")]
+    pub fn new(device: HidDevice, info: ConnectionInfo) -> Controller {
+        Controller::with_transport(device, info)
+    }
+
+    /// A controller reading from any transport, such as a `MockTransport`.
+    pub fn with_transport(
+        transport: impl Transport + Send + 'static,
+        info: ConnectionInfo,
+    ) -> Controller {
+        let quirk = quirks::lookup(info.vendor_id, info.product_id, None);
+        let mut controller = Controller {
+            device: Box::new(transport),
+            controls: Controls::new(),
+            layout: None,
+            last_report: None,
+            report_interval: None,
+            battery: Battery::new(),
+            peripherals: None,
+            identity_key: info.serial.clone(),
+            output: OutputScheduler::new(info.connection.output_interval()),
+            info,
+            profile: Profile::default(),
+            stats: UsageStats::new(),
+            events: VecDeque::with_capacity(EVENT_CAPACITY),
+            history: None,
+            pairing: None,
+            firmware: None,
+            firmware_pending: false,
+            capabilities: Capabilities::FULL,
+            quirk,
+            pairing_watch: None,
+            last_handshake: None,
+            metrics: None,
+            last_counter: None,
+            backlogged: false,
+            clock: DeviceClock::new(),
+            sampled_at: None,
+            gyro_zero: Some(GyroAutoZero::new()),
+            wake: WakeFilter::new(WakeGuard::Off),
+            lifecycle: Lifecycle::new(LifecycleState::Handshaking),
+            input_events: EventQueue::new(),
+            choreography: None,
+            playback: None,
+        };
+        if let Some(capabilities) = controller.quirk.as_ref().and_then(|q| q.capabilities) {
+            controller.set_capabilities(capabilities);
+        }
+        controller
+    }
+
+    /// Opens the first connected DS4 (either hardware revision, or the wireless adapter),
+    /// loads its saved profile, if any, and applies the profile's lightbar color.
+    #[cfg(feature = "hid")]
+    pub fn open(api: &HidApi) -> Result<Controller> {
+        let info = Controller::enumerate(api).next().ok_or(Error::NotFound)?;
+        Controller::open_device(api, info)
+    }
+
+    /// Opens every connected DS4, in enumeration order.
+    #[cfg(feature = "hid")]
+    pub fn open_all(api: &HidApi) -> Result<Vec<Controller>> {
+        let mut seen = Vec::new();
+        let mut controllers = Vec::new();
+        for info in Controller::enumerate(api) {
+            if seen.contains(&info.path()) {
+                continue;
+            }
+            seen.push(info.path());
+            controllers.push(Controller::open_device(api, info)?);
+        }
+        Ok(controllers)
+    }
+
+    #[cfg(feature = "hid")]
+    pub fn enumerate(api: &HidApi) -> impl Iterator<Item = &DeviceInfo> {
+        api.device_list().filter(|d| {
+            (d.vendor_id() == VENDOR_ID
+                && [PRODUCT_ID, PRODUCT_ID_V1, PRODUCT_ID_DONGLE].contains(&d.product_id()))
+                || quirks::is_known(d.vendor_id(), d.product_id())
+        })
+    }
+
+    #[cfg(feature = "hid")]
+    fn open_device(api: &HidApi, info: &DeviceInfo) -> Result<Controller> {
+        let device = info
+            .open_device(api)
+            .map_err(|e| Error::from_open(e, info.path()))?;
+        let mut controller = Controller::new(device, ConnectionInfo::from_device_info(info));
+        controller.restore_profile()?;
+        Ok(controller)
+    }
+
+    /// Identifies the pad and what it supports, loads its saved profile, if any,
+    /// and applies the profile's lightbar color.
+    pub fn restore_profile(&mut self) -> Result<()> {
+        let feature_reports = !self.quirk.as_ref().is_some_and(|q| q.no_feature_reports);
+        if feature_reports {
+            // A failed read leaves every capability in place; `update` tries
+            // again once the pad is streaming after a wake.
+            let _ = self.read_firmware();
+        }
+        if self.identity_key.is_none() && feature_reports {
+            self.identity_key = self.read_pairing().ok().map(|p| p.device.to_string());
+        }
+        // A broken file mustn't keep the pad from opening: defaults stand in,
+        // and `Event::ConfigIgnored` says why.
+        if let Some(key) = self.identity_key.clone() {
+            match Profile::load(&key) {
+                Ok(Some(profile)) => {
+                    self.profile = profile;
+                    if let Some(zero) = self.gyro_zero.as_mut() {
+                        zero.set_bias(self.profile.calibration.gyro_bias.map(f32::from));
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => self.config_ignored(Profile::path(&key), e),
+            }
+            match UsageStats::load(&key) {
+                Ok(Some(stats)) => self.stats = stats,
+                Ok(None) => {}
+                Err(e) => self.config_ignored(UsageStats::path(&key), e),
+            }
+        }
+        self.stats.begin_session();
+        if let Some(Rgb { r, g, b }) = self.profile.color {
+            self.set_lightbar(r, g, b)?;
+        }
+        Ok(())
+    }
+
+    fn config_ignored(&mut self, path: Option<PathBuf>, error: Error) {
+        if let Some(path) = path {
+            self.push_event(Event::ConfigIgnored {
+                path,
+                error: error.to_string(),
+            });
+        }
+    }
+
+    pub fn update(&mut self) -> Result<()> {
+        if self.lifecycle.state() == LifecycleState::Closed {
+            return Err(Error::Closed);
+        }
+        let result = self.read_report().and_then(|()| {
+            self.advance_playback(Instant::now());
+            self.flush_output()
+        });
+        if result.as_ref().is_err_and(Error::is_device_error) {
+            self.set_state(LifecycleState::Disconnected);
+        }
+        if let Some(metrics) = &self.metrics {
+            match &result {
+                Ok(()) => {
+                    metrics.reports.fetch_add(1, Ordering::Relaxed);
+                    if let Some(percent) = self.battery.percent() {
+                        metrics.set_battery_percent(percent);
+                    }
+                    if let Some(temperature) = self.imu_temperature() {
+                        metrics.set_imu_temperature(temperature);
+                    }
+                }
+                Err(e) if e.is_device_error() => {}
+                Err(_) => {
+                    metrics.parse_errors.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+        result
+    }
+
+    fn read_report(&mut self) -> Result<()> {
+        let mut report = [0u8; MAX_REPORT_LEN];
+        let len = self.device.read(&mut report)?;
+        // Non-blocking transports return nothing when no report is pending.
+        if len == 0 {
+            return Ok(());
+        }
+        let report = &mut report[..len];
+
+        let now = Instant::now();
+        let mut interval = Duration::ZERO;
+        if let Some(last) = self.last_report.replace(now) {
+            interval = now - last;
+            if let Some(metrics) = &self.metrics {
+                metrics.record_interval(interval);
+            }
+            self.report_interval = Some(match self.report_interval {
+                Some(avg) => (avg * 7 + interval) / 8,
+                None => interval,
+            });
+        }
+
+        let report_id = *report.first().ok_or(Error::ShortReport {
+            len: 0,
+            expected: 1,
+        })?;
+        let layout = ReportLayout::detect(self.info.connection, report_id)
+            .ok_or(Error::MalformedReport("unknown input report id"))?;
+        if layout == ReportLayout::Bluetooth && self.info.connection == Connection::Usb {
+            self.info.connection = Connection::Bluetooth;
+        }
+        let previous_layout = self.layout.replace(layout);
+
+        // Short reports arrive after a wake until the host asks for features again.
+        // Their contents aren't trustworthy, so drop them and redo the handshake.
+        if layout == ReportLayout::BluetoothShort {
+            if self
+                .last_handshake
+                .is_none_or(|at| now.saturating_duration_since(at) >= HANDSHAKE_RETRY)
+            {
+                self.last_handshake = Some(now);
+                self.handshake()?;
+            }
+            self.set_state(LifecycleState::Handshaking);
+            return Ok(());
+        }
+        if previous_layout == Some(ReportLayout::BluetoothShort) {
+            // Short reports also precede the first full one after connecting,
+            // which resumes nothing.
+            if self.sampled_at.is_some() {
+                self.events.push_back(Event::Resumed);
+            }
+            self.clock.reset();
+            if let Some(zero) = self.gyro_zero.as_mut() {
+                zero.reset();
+            }
+            self.wake.arm();
+            if self.firmware_pending {
+                let _ = self.read_firmware();
+            }
+        }
+
+        let offset = layout.offset() + self.quirk.as_ref().map_or(0, |q| q.input_offset);
+        let data = &mut report[offset.min(len)..];
+        let mut skipped = 0;
+        let counts = !self.quirk.as_ref().is_some_and(|q| q.static_counter);
+        if let Some(&b) = data.get(7).filter(|_| counts) {
+            let counter = b >> 2;
+            if let Some(last) = self.last_counter {
+                skipped = counter.wrapping_sub(last).wrapping_sub(1) & 0x3f;
+            }
+            if let Some(metrics) = &self.metrics {
+                metrics
+                    .dropped_reports
+                    .fetch_add(skipped as u64, Ordering::Relaxed);
+            }
+            self.last_counter = Some(counter);
+        }
+        self.wake.apply(data, now);
+        self.profile
+            .calibration
+            .apply(&self.profile.deadzones, data);
+        let result = self.controls.update(data);
+        self.events
+            .extend(self.controls.drain_panics().map(Event::HandlerPanicked));
+        result?;
+        if let Some(metrics) = &self.metrics {
+            metrics.record_dispatch(now.elapsed());
+        }
+        if self.lifecycle.state() == LifecycleState::Handshaking {
+            self.set_state(LifecycleState::Streaming);
+        }
+        let sampled_at = self.clock.observe(self.controls.timestamp, now);
+        self.sampled_at = Some(sampled_at);
+        let behind = now.saturating_duration_since(sampled_at);
+        let stale = behind >= BACKLOG_LAG;
+        if skipped > 0 || (stale && !self.backlogged) {
+            self.events.push_back(Event::InputBacklog {
+                skipped: skipped as u32,
+                behind,
+            });
+        }
+        self.backlogged = stale;
+        if let Some(bias) = self
+            .gyro_zero
+            .as_mut()
+            .and_then(|zero| zero.observe(self.controls.imu))
+        {
+            self.events.push_back(Event::GyroRecalibrated { bias });
+        }
+        if let Some(&status) = data.get(STATUS_OFFSET) {
+            self.battery.update(now, status);
+            let current = PeripheralState::from_status(status);
+            if let Some(previous) = self.peripherals.replace(current) {
+                if previous != current {
+                    self.events
+                        .push_back(Event::PeripheralsChanged { previous, current });
+                }
+            }
+        }
+        let snapshot = self.controls.snapshot();
+        self.stats.record(&snapshot, interval);
+        if let Some(history) = self.history.as_mut() {
+            history.push(sampled_at, snapshot);
+        }
+
+        if let Some((interval, last)) = self.pairing_watch {
+            if last.elapsed() >= interval {
+                self.pairing_watch = Some((interval, Instant::now()));
+                self.read_pairing()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// When the latest report was sampled, on the host clock but derived from the
+    /// device's own timestamps, so free of transport and scheduling jitter.
+    pub fn sampled_at(&self) -> Option<Instant> {
+        self.sampled_at
+    }
+
+    pub fn device_clock(&self) -> &DeviceClock {
+        &self.clock
+    }
+
+    /// Angular velocity in raw gyro units with the auto-zeroed bias removed, or
+    /// the calibrated one when auto-zeroing is off.
+    pub fn gyro(&self) -> [f32; 3] {
+        let gyro = self.controls.imu.gyro;
+        match &self.gyro_zero {
+            Some(zero) => zero.correct(gyro),
+            None => {
+                let bias = self.profile.calibration.gyro_bias;
+                [0, 1, 2].map(|i| gyro[i] as f32 - bias[i] as f32)
+            }
+        }
+    }
+
+    /// Raw IMU temperature from the latest report; see `Imu::temperature`.
+    pub fn imu_temperature(&self) -> Option<u8> {
+        self.sampled_at.map(|_| self.controls.imu.temperature)
+    }
+
+    pub fn gyro_auto_zero(&self) -> Option<&GyroAutoZero> {
+        self.gyro_zero.as_ref()
+    }
+
+    /// Replaces the bias estimator, or turns auto-zeroing off with `None`.
+    pub fn set_gyro_auto_zero(&mut self, zero: Option<GyroAutoZero>) {
+        self.gyro_zero = zero;
+    }
+
+    pub fn state(&self) -> LifecycleState {
+        self.lifecycle.state()
+    }
+
+    pub fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    fn set_state(&mut self, state: LifecycleState) {
+        if let Some(transition) = self.lifecycle.transition(state) {
+            self.events.push_back(Event::StateChanged(transition));
+            self.cue_transition(transition);
+        }
+    }
+
+    pub(crate) fn push_event(&mut self, event: Event) {
+        self.events.push_back(event);
+    }
+
+    /// Plays the disconnect choreography, if any, sends any held-back output and
+    /// moves to `LifecycleState::Closed`; `update` fails with `Error::Closed` from
+    /// then on.
+    pub fn close(&mut self) -> Result<()> {
+        let farewell = self.farewell();
+        if let Some(saved) = self.finish_playback() {
+            self.set_output_state(saved);
+        }
+        let result = if farewell.is_err() {
+            farewell
+        } else if self.output.is_dirty() {
+            let report = self.output.take(Instant::now());
+            self.write_output(report)
+        } else {
+            Ok(())
+        };
+        self.set_state(LifecycleState::Closed);
+        result
+    }
+
+    /// Requests the calibration feature report, which makes a Bluetooth pad start
+    /// sending full input reports.
+    pub fn handshake(&mut self) -> Result<()> {
+        let mut report = [0u8; BT_CALIBRATION_REPORT_LEN];
+        report[0] = BT_CALIBRATION_REPORT_ID;
+        self.device.get_feature_report(&mut report)?;
+        Ok(())
+    }
+
+    pub fn wake_guard(&self) -> WakeGuard {
+        self.wake.guard
+    }
+
+    /// Keeps the press that woke the pad (or everything for a while) from reaching
+    /// the controls after opening and after each `Event::Resumed`. Takes effect
+    /// from the next (re)connection.
+    pub fn set_wake_guard(&mut self, guard: WakeGuard) {
+        self.wake.guard = guard;
+    }
+
+    /// Feeds report, error and battery statistics into `metrics` from now on.
+    pub fn set_metrics(&mut self, metrics: Arc<Metrics>) {
+        metrics.connected.store(true, Ordering::Relaxed);
+        self.metrics = Some(metrics);
+    }
+
+    /// Queues an `Event::Marker` labelled `label`, e.g. `"trial_3_start"`, so loggers
+    /// and recordings reading the event stream can be aligned with external
+    /// experiment events afterwards. It is delivered with the other events, after
+    /// the reports already read.
+    pub fn mark(&mut self, label: impl Into<String>) {
+        self.events.push_back(Event::Marker {
+            label: label.into(),
+            at: Instant::now(),
+            device_time: self.clock.device_time(),
+        });
+    }
+
+    pub fn events(&mut self) -> impl Iterator<Item = Event> + '_ {
+        self.events.drain(..)
+    }
+
+    /// Turns the latest snapshot and pending device events into `InputSource` events.
+    pub(crate) fn queue_input_events(&mut self) {
+        self.input_events.push_snapshot(self.controls.snapshot());
+        for event in self.events.drain(..) {
+            self.input_events.push(InputEvent::Device(event));
+        }
+    }
+
+    /// Keeps the last `capacity` snapshots for time-based queries; 0 disables it.
+    pub fn set_history_capacity(&mut self, capacity: usize) {
+        self.history = (capacity > 0).then(|| History::new(capacity));
+    }
+
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// Calibration and deadzone changes apply from the next `update`.
+    pub fn profile_mut(&mut self) -> &mut Profile {
+        &mut self.profile
+    }
+
+    /// Saves the profile under this controller's identity key so `open` restores it.
+    pub fn save_profile(&self) -> Result<()> {
+        let key = self.identity_key.as_deref().ok_or(Error::InvalidFormat(
+            "controller has no serial number or address",
+        ))?;
+        self.profile.save(key)
+    }
+
+    /// Press counts, stick travel and play time, loaded by `open` and updated by
+    /// every report.
+    pub fn stats(&self) -> &UsageStats {
+        &self.stats
+    }
+
+    pub fn stats_mut(&mut self) -> &mut UsageStats {
+        &mut self.stats
+    }
+
+    /// Saves the usage statistics under this controller's identity key.
+    pub fn save_stats(&self) -> Result<()> {
+        let key = self.identity_key.as_deref().ok_or(Error::InvalidFormat(
+            "controller has no serial number or address",
+        ))?;
+        self.stats.save(key)
+    }
+
+    pub fn identity(&self) -> Identity<'_> {
+        Identity {
+            key: self.identity_key.as_deref(),
+            name: self.profile.name.as_deref(),
+            color: self.profile.color,
+        }
+    }
+
+    /// Names this controller and gives it a lightbar color, applied now and on every
+    /// future `open`.
+    pub fn set_identity(&mut self, name: &str, color: Option<Rgb>) -> Result<()> {
+        crate::profile::check_name(name)?;
+        self.profile.name = Some(name.to_string());
+        self.profile.color = color;
+        if let Some(Rgb { r, g, b }) = color {
+            self.set_lightbar(r, g, b)?;
+        }
+        self.save_profile()
+    }
+
+    pub fn battery(&self) -> &Battery {
+        &self.battery
+    }
+
+    /// `None` until a report carrying the status byte has been read.
+    pub fn peripherals(&self) -> Option<PeripheralState> {
+        self.peripherals
+    }
+
+    pub fn connection(&self) -> Connection {
+        self.info.connection
+    }
+
+    pub fn connection_info(&self) -> &ConnectionInfo {
+        &self.info
+    }
+
+    /// The layout of the most recent input report.
+    pub fn report_layout(&self) -> Option<ReportLayout> {
+        self.layout
+    }
+
+    /// Smoothed input report rate in Hz, as observed by `update`.
+    pub fn report_rate(&self) -> Option<f32> {
+        self.report_interval
+            .filter(|i| !i.is_zero())
+            .map(|i| 1.0 / i.as_secs_f32())
+    }
+
+    /// Asks a Bluetooth pad to send input reports at roughly `hz` (e.g. 1000, 500 or
+    /// 250), trading latency for battery life. Over USB the rate is fixed and this
+    /// only takes effect if the pad is later used over Bluetooth. Pads without
+    /// `Capabilities::bt_1khz` are held to 500 Hz.
+    pub fn set_report_rate(&mut self, hz: u32) -> Result<()> {
+        let fastest = if self.capabilities.bt_1khz { 1 } else { 2 };
+        self.output.state.bt_interval = (1000 / hz.max(1)).clamp(fastest, 16) as u8;
+        if self.info.connection != Connection::Bluetooth {
+            return Ok(());
+        }
+        self.output.mark_dirty();
+        self.flush_output()
+    }
+
+    /// The rate last passed to `set_report_rate`, rounded to what the pad supports.
+    pub fn requested_report_rate(&self) -> Option<u32> {
+        let interval = self.output.state.bt_interval;
+        (interval > 0).then(|| 1000 / interval as u32)
+    }
+
+    fn output_layout(&self) -> ReportLayout {
+        match self.info.connection {
+            Connection::Bluetooth => ReportLayout::Bluetooth,
+            Connection::Usb | Connection::Dongle => ReportLayout::Usb,
+        }
+    }
+
+    pub fn set_lightbar(&mut self, r: u8, g: u8, b: u8) -> Result<()> {
+        self.send(OutputReportBuilder::new().lightbar(r, g, b))
+    }
+
+    pub fn set_rumble(&mut self, strong: u8, weak: u8) -> Result<()> {
+        self.send(OutputReportBuilder::new().rumble(strong, weak))
+    }
+
+    /// Merges `output` into the pad's output state and writes it, unless a write went
+    /// out within the output interval, in which case `update` sends it once due.
+    ///
+    /// Write failures don't surface here: the write is retried from `update` with
+    /// backoff, and `Event::OutputStalled` reports failures that persist.
+    ///
+    /// While a choreography pattern plays, `output` is kept for when it ends.
+    pub fn send(&mut self, output: OutputReportBuilder) -> Result<()> {
+        if let Some(playback) = &mut self.playback {
+            output.apply(&mut playback.saved);
+            return Ok(());
+        }
+        let coalesced = self.output.apply(&self.supported(output));
+        if let Some(metrics) = self.metrics.as_ref().filter(|_| coalesced) {
+            metrics.coalesced_outputs.fetch_add(1, Ordering::Relaxed);
+        }
+        self.flush_output()
+    }
+
+    /// Minimum time between output writes; changes made sooner are coalesced.
+    /// Defaults to `Connection::output_interval` for the pad's connection.
+    pub fn set_output_interval(&mut self, interval: Duration) {
+        self.output.min_interval = interval;
+    }
+
+    pub fn output_interval(&self) -> Duration {
+        self.output.min_interval
+    }
+
+    /// What the output scheduler has done since the controller was opened.
+    pub fn output_stats(&self) -> OutputStats {
+        self.output.stats
+    }
+
+    /// Writes pending output if the output interval and any retry backoff allow.
+    pub fn flush_output(&mut self) -> Result<()> {
+        let now = Instant::now();
+        let Some(report) = self.output.poll(now) else {
+            return Ok(());
+        };
+        match self.write_output(report) {
+            Ok(()) => {
+                if self.output.succeeded() {
+                    self.events.push_back(Event::OutputRecovered);
+                    if self.lifecycle.state() == LifecycleState::Degraded {
+                        self.set_state(LifecycleState::Streaming);
+                    }
+                }
+            }
+            Err(e) => {
+                let failures = self.output.failed(now);
+                if failures == STALL_AFTER_FAILURES {
+                    self.events.push_back(Event::OutputStalled {
+                        failures,
+                        error: e.to_string(),
+                    });
+                    self.set_state(LifecycleState::Degraded);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes `output` straight away, bypassing the output interval and retries.
+    pub(crate) fn write_now(&mut self, output: &OutputReportBuilder) -> Result<()> {
+        self.output.apply(&self.supported(*output));
+        let report = self.output.take(Instant::now());
+        self.write_output(report)
+    }
+
+    /// The 6-bit counter from the latest report.
+    #[cfg(all(feature = "ffi", feature = "hid"))]
+    pub(crate) fn last_counter(&self) -> Option<u8> {
+        self.last_counter
+    }
+
+    pub(crate) fn output_state(&self) -> OutputReport {
+        self.output.state
+    }
+
+    /// Merges `output` into the pending state for `update` to send, skipping any
+    /// choreography in progress.
+    pub(crate) fn queue_output(&mut self, output: &OutputReportBuilder) {
+        self.output.apply(&self.supported(*output));
+    }
+
+    /// Replaces the pending state for `update` to send.
+    pub(crate) fn set_output_state(&mut self, state: OutputReport) {
+        if self.output.state != state {
+            self.output.state = state;
+            self.output.mark_dirty();
+        }
+    }
+
+    pub(crate) fn restore_output(&mut self, state: OutputReport) -> Result<()> {
+        self.output.state = state;
+        let report = self.output.take(Instant::now());
+        self.write_output(report)
+    }
+
+    /// `output` without anything the pad can't do.
+    fn supported(&self, output: OutputReportBuilder) -> OutputReportBuilder {
+        if self.capabilities.has_lightbar {
+            output
+        } else {
+            output.without(FLAG_LIGHTBAR | FLAG_FLASH)
+        }
+    }
+
+    fn write_output(&mut self, report: OutputReport) -> Result<()> {
+        let mut buf = [0u8; OUTPUT_REPORT_MAX_LEN];
+        self.device
+            .write(report.encode(self.output_layout(), &mut buf))?;
+        self.output.stats.writes += 1;
+        if let Some(metrics) = &self.metrics {
+            metrics.output_writes.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// The version info from the most recent `read_firmware`; `None` if it hasn't
+    /// been read or didn't parse.
+    pub fn firmware(&self) -> Option<&Firmware> {
+        self.firmware.as_ref()
+    }
+
+    /// The workarounds in effect for this pad; see `quirks::register`.
+    pub fn quirk(&self) -> Option<&Quirk> {
+        self.quirk.as_ref()
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// Overrides detected capabilities, e.g. for a clone known to have a working
+    /// lightbar.
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        if !capabilities.imu_trustworthy {
+            self.gyro_zero = None;
+        }
+        self.capabilities = capabilities;
+    }
+
+    /// Reads the version feature report and updates `capabilities` from it.
+    /// Capabilities are only reduced by a known quirk or an answer that doesn't
+    /// parse; if the read fails they stay as they were.
+    pub fn read_firmware(&mut self) -> Result<Option<Firmware>> {
+        let mut report = [0u8; FIRMWARE_REPORT_LEN];
+        report[0] = FIRMWARE_REPORT_ID;
+        let result = self.device.get_feature_report(&mut report);
+        let firmware = match &result {
+            Ok(len) => Firmware::from_report(&report[..*len]),
+            Err(_) => None,
+        };
+        self.firmware_pending = result.is_err();
+        self.quirk = quirks::lookup(self.info.vendor_id, self.info.product_id, firmware.as_ref());
+        let capabilities = match self.quirk.as_ref().and_then(|q| q.capabilities) {
+            Some(capabilities) => Some(capabilities),
+            None if result.is_ok() => Some(Capabilities::detect(
+                self.info.product_id,
+                firmware.as_ref(),
+            )),
+            None => None,
+        };
+        if let Some(capabilities) = capabilities {
+            self.set_capabilities(capabilities);
+        }
+        self.firmware = firmware.clone();
+        result.map(|_| firmware)
+    }
+
+    /// The pairing info from the most recent `read_pairing`.
+    pub fn pairing(&self) -> Option<Pairing> {
+        self.pairing
+    }
+
+    /// Reads the pairing feature report, emitting `Event::PairingChanged` if it differs
+    /// from the last read.
+    pub fn read_pairing(&mut self) -> Result<Pairing> {
+        let mut report = [0u8; PAIRING_REPORT_LEN];
+        report[0] = PAIRING_REPORT_ID;
+        let len = self.device.get_feature_report(&mut report)?;
+        let current = Pairing::from_report(&report[..len])
+            .ok_or(Error::MalformedReport("pairing feature report"))?;
+
+        if self.pairing != Some(current) {
+            self.events.push_back(Event::PairingChanged {
+                previous: self.pairing,
+                current,
+            });
+            self.pairing = Some(current);
+        }
+
+        Ok(current)
+    }
+
+    /// Re-reads the pairing report from `update` every `interval`; `None` disables polling.
+    pub fn watch_pairing(&mut self, interval: Option<Duration>) {
+        self.pairing_watch = interval.map(|interval| (interval, Instant::now() - interval));
+    }
+
+    pub fn is_paired_to_this_host(&self) -> bool {
+        self.pairing
+            .map(|p| p.is_paired_to_this_host())
+            .unwrap_or(false)
+    }
+}
+
+impl Drop for Controller {
+    /// Sends output still held back by the output interval, such as a final
+    /// "stop rumble".
+    fn drop(&mut self) {
+        if self.output.is_dirty() && self.lifecycle.state() != LifecycleState::Closed {
+            let report = self.output.take(Instant::now());
+            let _ = self.write_output(report);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{encode_input, MockHandle, MockTransport};
+    use crate::{DPad, Snapshot};
+
+    /// A device that fails every call, like one unplugged mid-read.
+    struct Failing;
+
+    impl Transport for Failing {
+        fn read(&mut self, _buf: &mut [u8]) -> Result<usize> {
+            Err(Error::Io(std::io::ErrorKind::BrokenPipe.into()))
+        }
+
+        fn write(&mut self, _data: &[u8]) -> Result<usize> {
+            Err(Error::Io(std::io::ErrorKind::BrokenPipe.into()))
+        }
+
+        fn get_feature_report(&mut self, _buf: &mut [u8]) -> Result<usize> {
+            Err(Error::Io(std::io::ErrorKind::BrokenPipe.into()))
+        }
+    }
+
+    /// A controller with `reports` queued, and the pad's handle, which keeps it
+    /// connected so only the reports themselves can fail.
+    fn controller_fed(reports: Vec<Vec<u8>>) -> (Controller, MockHandle) {
+        let (transport, handle) = MockTransport::new();
+        for report in reports {
+            assert!(handle.send_report(report).is_ok());
+        }
+        let controller = Controller::with_transport(transport, ConnectionInfo::default());
+        (controller, handle)
+    }
+
+    fn good_report() -> Vec<u8> {
+        encode_input(&Snapshot::default(), 0, 0)
+    }
+
+    #[test]
+    fn truncated_reports_are_errors() {
+        let good = good_report();
+        for len in 1..10 {
+            let (mut controller, _pad) = controller_fed(vec![good[..len].to_vec()]);
+            assert!(controller.update().is_err(), "{}-byte report accepted", len);
+        }
+    }
+
+    #[test]
+    fn unknown_report_ids_are_errors() {
+        let mut report = good_report();
+        report[0] = 0x42;
+        let (mut controller, _pad) = controller_fed(vec![report]);
+        assert!(matches!(
+            controller.update(),
+            Err(Error::MalformedReport(_))
+        ));
+    }
+
+    #[test]
+    fn garbage_never_panics() {
+        // xorshift, so a failure is reproducible.
+        let mut state = 0x2545_f491_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let reports = (0..2000)
+            .map(|_| {
+                let len = (next() % 80) as usize;
+                let mut report: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+                if let Some(id) = report.first_mut() {
+                    // Mostly plausible ids, so parsing gets past the first byte.
+                    *id = [0x01, 0x11, next() as u8][(next() % 3) as usize];
+                }
+                report
+            })
+            .collect();
+        let (mut controller, _pad) = controller_fed(reports);
+        for _ in 0..2000 {
+            let _ = controller.update();
+        }
+    }
+
+    #[test]
+    fn bad_dpad_values_are_errors() {
+        for value in 9..16u8 {
+            let mut report = good_report();
+            report[5] = (report[5] & 0xf0) | value;
+            let (mut controller, _pad) = controller_fed(vec![report]);
+            assert!(matches!(controller.update(), Err(Error::InvalidDPad(_))));
+        }
+    }
+
+    #[test]
+    fn bad_report_leaves_state_alone() {
+        let pressed = Snapshot {
+            dpad: DPad::North,
+            ..Snapshot::default()
+        };
+        let mut bad = encode_input(&Snapshot::default(), 1, 0);
+        bad[5] = 0x0f;
+        let (mut controller, _pad) = controller_fed(vec![encode_input(&pressed, 0, 0), bad]);
+        assert!(controller.update().is_ok());
+        assert!(controller.update().is_err());
+        assert_eq!(controller.controls.snapshot().dpad, DPad::North);
+    }
+
+    #[test]
+    fn device_errors_are_reported_and_close_the_session() {
+        let mut controller = Controller::with_transport(Failing, ConnectionInfo::default());
+        let result = controller.update();
+        assert!(result.is_err_and(|e| e.is_device_error()));
+        assert_eq!(controller.state(), LifecycleState::Disconnected);
+        assert!(controller.update().is_err());
+    }
+
+    #[test]
+    fn dropped_pad_is_a_device_error() {
+        let (transport, handle) = MockTransport::new();
+        let mut controller = Controller::with_transport(transport, ConnectionInfo::default());
+        drop(handle);
+        assert!(controller.update().is_err_and(|e| e.is_device_error()));
+    }
+
+    /// A mock whose first `refusals` version report reads fail.
+    struct SlowFirmware {
+        inner: MockTransport,
+        refusals: usize,
+    }
+
+    impl Transport for SlowFirmware {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            self.inner.read(buf)
+        }
+
+        fn write(&mut self, data: &[u8]) -> Result<usize> {
+            self.inner.write(data)
+        }
+
+        fn get_feature_report(&mut self, buf: &mut [u8]) -> Result<usize> {
+            if buf.first() == Some(&FIRMWARE_REPORT_ID) && self.refusals > 0 {
+                self.refusals -= 1;
+                return Err(Error::Io(std::io::ErrorKind::TimedOut.into()));
+            }
+            self.inner.get_feature_report(buf)
+        }
+    }
+
+    fn short_bt_report() -> Vec<u8> {
+        let mut report = vec![0u8; 10];
+        report[0] = 0x01;
+        report
+    }
+
+    fn full_bt_report() -> Vec<u8> {
+        let mut report = vec![0x11, 0xc0];
+        report.extend(good_report());
+        report.resize(78, 0);
+        report
+    }
+
+    fn bluetooth_controller_fed(reports: Vec<Vec<u8>>) -> (Controller, MockHandle) {
+        let (transport, handle) = MockTransport::new();
+        for report in reports {
+            assert!(handle.send_report(report).is_ok());
+        }
+        let info = ConnectionInfo {
+            connection: Connection::Bluetooth,
+            ..ConnectionInfo::default()
+        };
+        (Controller::with_transport(transport, info), handle)
+    }
+
+    fn resumed_events(controller: &mut Controller, reports: usize) -> usize {
+        for _ in 0..reports {
+            assert!(controller.update().is_ok());
+        }
+        controller
+            .events()
+            .filter(|e| matches!(e, Event::Resumed))
+            .count()
+    }
+
+    #[test]
+    fn first_full_report_after_connecting_is_not_a_resume() {
+        let reports = vec![short_bt_report(), short_bt_report(), full_bt_report()];
+        let (mut controller, _pad) = bluetooth_controller_fed(reports);
+        assert_eq!(resumed_events(&mut controller, 3), 0);
+    }
+
+    #[test]
+    fn full_reports_after_a_wake_are_a_resume() {
+        let reports = vec![
+            short_bt_report(),
+            full_bt_report(),
+            short_bt_report(),
+            full_bt_report(),
+        ];
+        let (mut controller, _pad) = bluetooth_controller_fed(reports);
+        assert_eq!(resumed_events(&mut controller, 4), 1);
+    }
+
+    fn genuine_firmware() -> Vec<u8> {
+        let mut report = vec![0u8; FIRMWARE_REPORT_LEN];
+        report[0] = FIRMWARE_REPORT_ID;
+        report[1..12].copy_from_slice(b"Sep 21 2018");
+        report[17..25].copy_from_slice(b"04:50:51");
+        report
+    }
+
+    #[test]
+    fn failed_firmware_read_keeps_capabilities() {
+        let (inner, _pad) = MockTransport::new();
+        let transport = SlowFirmware { inner, refusals: 1 };
+        let mut controller = Controller::with_transport(transport, ConnectionInfo::default());
+        assert!(controller.read_firmware().is_err());
+        assert_eq!(controller.capabilities(), Capabilities::FULL);
+    }
+
+    #[test]
+    fn garbage_firmware_answer_drops_the_lightbar() {
+        let (transport, pad) = MockTransport::new();
+        let mut controller = Controller::with_transport(transport, ConnectionInfo::default());
+        assert!(pad
+            .set_feature_report(vec![FIRMWARE_REPORT_ID, 0xff, 0x13])
+            .is_ok());
+        assert!(controller.read_firmware().is_ok_and(|f| f.is_none()));
+        assert!(!controller.capabilities().has_lightbar);
+    }
+
+    #[test]
+    fn failed_firmware_read_is_retried_after_a_wake() {
+        let (inner, pad) = MockTransport::new();
+        let transport = SlowFirmware { inner, refusals: 1 };
+        let info = ConnectionInfo {
+            connection: Connection::Bluetooth,
+            ..ConnectionInfo::default()
+        };
+        let mut controller = Controller::with_transport(transport, info);
+        assert!(controller.read_firmware().is_err());
+        assert!(pad.set_feature_report(genuine_firmware()).is_ok());
+
+        assert!(pad.send_report(short_bt_report()).is_ok());
+        assert!(pad.send_report(full_bt_report()).is_ok());
+        assert!(controller.update().is_ok());
+        assert!(controller.firmware().is_none());
+        assert!(controller.update().is_ok());
+        assert!(controller.firmware().is_some());
+        assert!(controller.capabilities().has_lightbar);
+    }
+}
@@ -0,0 +1,253 @@
+use crate::touch::{Touch, TouchPackets, TouchTracker};
+use crate::{
+    Button, ButtonFlags, DPad, Error, Imu, PanicPolicy, Result, Stick, Trigger, TriggerState,
+};
+
+pub const INPUT_REPORT_MIN_LEN: usize = 10;
+
+/// Where each button's bit sits in the input report: `(byte, mask, flag)`.
+const REPORT_BITS: [(usize, u8, ButtonFlags); 14] = [
+    (5, 0x80, ButtonFlags::TRIANGLE),
+    (5, 0x40, ButtonFlags::CIRCLE),
+    (5, 0x20, ButtonFlags::X),
+    (5, 0x10, ButtonFlags::SQUARE),
+    (6, 0x80, ButtonFlags::R3),
+    (6, 0x40, ButtonFlags::L3),
+    (6, 0x20, ButtonFlags::OPTIONS),
+    (6, 0x10, ButtonFlags::SHARE),
+    (6, 0x08, ButtonFlags::R2),
+    (6, 0x04, ButtonFlags::L2),
+    (6, 0x02, ButtonFlags::R1),
+    (6, 0x01, ButtonFlags::L1),
+    (7, 0x02, ButtonFlags::TPAD),
+    (7, 0x01, ButtonFlags::PS),
+];
+
+/// The triggers' digital bits, which are dispatched with their travel.
+const TRIGGER_BITS: ButtonFlags = ButtonFlags::L2.union(ButtonFlags::R2);
+
+#[derive(Default)]
+pub struct Controls {
+    pub triangle: Button<bool>,
+    pub circle: Button<bool>,
+    pub x: Button<bool>,
+    pub square: Button<bool>,
+    pub dpad: Button<DPad>,
+    pub r3: Button<bool>,
+    pub l3: Button<bool>,
+    pub options: Button<bool>,
+    pub share: Button<bool>,
+    pub r2: Trigger,
+    pub l2: Trigger,
+    pub r1: Button<bool>,
+    pub l1: Button<bool>,
+    pub tpad: Button<bool>,
+    pub ps: Button<bool>,
+    pub left_stick: Button<Stick>,
+    pub right_stick: Button<Stick>,
+    /// Both fingers as of the newest touch packet.
+    pub touches: Button<[Touch; 2]>,
+    /// Every touch packet in the latest report, including the pad's history packets.
+    pub touch_packets: TouchPackets,
+    /// Per-finger velocity and acceleration in device time.
+    pub touch_motion: TouchTracker,
+    /// Device clock at the latest report, in wrapping 16/3 µs ticks.
+    pub timestamp: u16,
+    /// Raw gyro and accelerometer readings from the latest report.
+    pub imu: Imu,
+    /// Whether a panicking handler unwinds out of `update`.
+    pub panic_policy: PanicPolicy,
+    panics: Vec<HandlerPanic>,
+    /// The buttons held in the latest report, to diff the next one against.
+    buttons: ButtonFlags,
+    /// Buttons the next report must update even if it leaves them alone: ones
+    /// whose `previous` lags their state, and ones disabled or replaying a queue.
+    unsettled: ButtonFlags,
+}
+
+/// A handler panic caught under `PanicPolicy::Isolate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandlerPanic {
+    /// The field name of the control, e.g. `"triangle"`.
+    pub control: &'static str,
+    pub message: String,
+}
+
+impl Controls {
+    pub fn new() -> Self {
+        Controls::default()
+    }
+
+    /// Leaves every control untouched if the report is invalid.
+    pub fn update(&mut self, report: &[u8]) -> Result<()> {
+        if report.len() < INPUT_REPORT_MIN_LEN {
+            return Err(Error::ShortReport {
+                len: report.len(),
+                expected: INPUT_REPORT_MIN_LEN,
+            });
+        }
+        let dpad = DPad::from_byte(report[5])?;
+        let buttons = REPORT_BITS
+            .iter()
+            .filter(|&&(byte, mask, _)| report[byte] & mask > 0)
+            .fold(ButtonFlags::empty(), |flags, &(_, _, flag)| flags | flag);
+
+        // Only controls the report changes, or that still need settling, are
+        // touched; a pad held still costs a few comparisons.
+        macro_rules! update {
+            ($control:ident, $state:expr) => {{
+                let state = $state;
+                if self.$control.needs_update(state) {
+                    if let Some(message) = self.$control.update_with(state, self.panic_policy) {
+                        self.panics.push(HandlerPanic {
+                            control: stringify!($control),
+                            message,
+                        });
+                    }
+                }
+            }};
+        }
+
+        update!(left_stick, Stick::new(report[1], report[2]));
+        update!(right_stick, Stick::new(report[3], report[4]));
+        update!(dpad, dpad);
+        update!(
+            l2,
+            TriggerState {
+                value: report[8],
+                digital: buttons.contains(ButtonFlags::L2),
+            }
+        );
+        update!(
+            r2,
+            TriggerState {
+                value: report[9],
+                digital: buttons.contains(ButtonFlags::R2),
+            }
+        );
+
+        let changes = buttons.changes(self.buttons);
+        let changed = (buttons ^ self.buttons) - TRIGGER_BITS;
+        let settling = (self.unsettled - changed)
+            .ids()
+            .map(|id| (id, buttons.contains(id.into())));
+        let mut unsettled = ButtonFlags::empty();
+        for (id, pressed) in changes.chain(settling) {
+            let policy = self.panic_policy;
+            let Some(button) = self.button_mut(id) else {
+                continue;
+            };
+            let message = button.update_with(pressed, policy);
+            if button.needs_update(pressed) {
+                unsettled |= id.into();
+            }
+            if let Some(message) = message {
+                self.panics.push(HandlerPanic {
+                    control: id.name(),
+                    message,
+                });
+            }
+        }
+        self.buttons = buttons;
+        self.unsettled = unsettled;
+
+        if let Some(&[lo, hi]) = report.get(10..12) {
+            self.timestamp = u16::from_le_bytes([lo, hi]);
+        }
+
+        self.imu = Imu::parse(report);
+        self.touch_packets = TouchPackets::parse(report);
+        self.touch_motion
+            .update(&self.touch_packets, self.timestamp);
+        if let Some(latest) = self.touch_packets.latest() {
+            update!(touches, latest.fingers);
+        }
+
+        Ok(())
+    }
+
+    /// Handler panics isolated since the last call.
+    pub fn take_panics(&mut self) -> Vec<HandlerPanic> {
+        std::mem::take(&mut self.panics)
+    }
+
+    /// Like `take_panics`, keeping the buffer for next time.
+    pub(crate) fn drain_panics(&mut self) -> std::vec::Drain<'_, HandlerPanic> {
+        self.panics.drain(..)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LockMode;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn report(x: bool) -> [u8; INPUT_REPORT_MIN_LEN] {
+        let mut report = [0x01, 0x80, 0x80, 0x80, 0x80, 0x08, 0, 0, 0, 0];
+        if x {
+            report[5] |= 0x20;
+        }
+        report
+    }
+
+    #[test]
+    fn handlers_fire_once_per_change() {
+        let mut controls = Controls::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = calls.clone();
+        controls
+            .x
+            .subscribe(move |_, _| {
+                counted.fetch_add(1, Ordering::Relaxed);
+            })
+            .detach();
+        for x in [false, true, true, true, false, false] {
+            assert!(controls.update(&report(x)).is_ok());
+        }
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn unchanged_report_settles_previous() {
+        let mut controls = Controls::new();
+        assert!(controls.update(&report(true)).is_ok());
+        assert!(controls.x.changed());
+        assert!(controls.update(&report(true)).is_ok());
+        assert!(!controls.x.changed());
+        assert!(controls.x.state());
+    }
+
+    #[test]
+    fn button_held_through_a_lock_reads_as_a_fresh_press() {
+        let mut controls = Controls::new();
+        controls.x.set_enabled(false);
+        assert!(controls.update(&report(true)).is_ok());
+        assert!(!controls.x.state());
+        controls.x.set_enabled(true);
+        assert!(controls.update(&report(true)).is_ok());
+        assert!(controls.x.state() && controls.x.changed());
+    }
+
+    #[test]
+    fn queued_changes_replay_without_a_new_change() {
+        let mut controls = Controls::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = calls.clone();
+        controls
+            .x
+            .subscribe(move |_, _| {
+                counted.fetch_add(1, Ordering::Relaxed);
+            })
+            .detach();
+        controls.x.set_lock_mode(LockMode::Queue);
+        controls.x.set_enabled(false);
+        assert!(controls.update(&report(true)).is_ok());
+        assert!(controls.update(&report(false)).is_ok());
+        controls.x.set_enabled(true);
+        assert!(controls.update(&report(false)).is_ok());
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        assert!(!controls.x.state());
+    }
+}
@@ -0,0 +1,347 @@
+use crate::choreography::Choreography;
+use crate::lifecycle::{Lifecycle, LifecycleState, Transition};
+use crate::lightbar::Rgb;
+use crate::metrics::Metrics;
+use crate::priority::ThreadHints;
+use crate::profile_switch::ProfileSwitcher;
+use crate::reconnect::{FixedInterval, ReconnectPolicy};
+#[cfg(feature = "shm")]
+use crate::shm::SharedStateWriter;
+use crate::{Connection, Controller, Error, Event, Result};
+use hidapi::HidApi;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Status {
+    pub connected: bool,
+    pub state: LifecycleState,
+    pub connection: Option<Connection>,
+    pub name: Option<String>,
+    pub battery_percent: Option<f32>,
+    pub charging: bool,
+    /// The active mapping profile's name, when switching is set up.
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    SetLightbar(Rgb),
+    SetRumble {
+        strong: u8,
+        weak: u8,
+    },
+    /// Disconnects a Bluetooth pad, which makes it switch itself off. Ignored over USB.
+    PowerOff,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DaemonEvent {
+    Connected {
+        connection: Connection,
+        name: Option<String>,
+    },
+    Disconnected,
+    /// Sent whenever the smoothed battery percentage changes by a whole percent.
+    Battery {
+        percent: f32,
+        charging: bool,
+    },
+    StateChanged(Transition),
+    /// The profile chord switched to another mapping profile.
+    ProfileChanged {
+        index: usize,
+        name: Option<String>,
+    },
+}
+
+pub type DaemonListener = Box<dyn FnMut(&DaemonEvent) + Send>;
+
+/// Shared view of a running daemon, for front ends such as D-Bus or a tray icon.
+#[derive(Clone)]
+pub struct DaemonHandle {
+    status: Arc<Mutex<Status>>,
+    commands: Sender<Command>,
+}
+
+impl DaemonHandle {
+    pub fn status(&self) -> Status {
+        self.status.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    /// Queues a command for the connected controller; dropped if none is connected.
+    pub fn send(&self, command: Command) {
+        let _ = self.commands.send(command);
+    }
+}
+
+/// Keeps a controller open, reopening it whenever it disappears, and applies
+/// commands sent through its handles.
+pub struct Daemon {
+    api: HidApi,
+    status: Arc<Mutex<Status>>,
+    commands: Receiver<Command>,
+    handle: DaemonHandle,
+    metrics: Option<Arc<Metrics>>,
+    listeners: Vec<DaemonListener>,
+    lifecycle: Lifecycle,
+    reconnect: Box<dyn ReconnectPolicy>,
+    thread_hints: ThreadHints,
+    profiles: Option<ProfileSwitcher>,
+    choreography: Option<Choreography>,
+    #[cfg(feature = "shm")]
+    shared: Option<SharedStateWriter>,
+}
+
+impl Daemon {
+    pub fn new() -> Result<Self> {
+        let status = Arc::new(Mutex::new(Status::default()));
+        let (tx, rx) = channel();
+        Ok(Daemon {
+            api: HidApi::new()?,
+            handle: DaemonHandle {
+                status: status.clone(),
+                commands: tx,
+            },
+            status,
+            commands: rx,
+            metrics: None,
+            listeners: Vec::new(),
+            lifecycle: Lifecycle::default(),
+            reconnect: Box::new(FixedInterval(RECONNECT_INTERVAL)),
+            thread_hints: ThreadHints::default(),
+            profiles: None,
+            choreography: None,
+            #[cfg(feature = "shm")]
+            shared: None,
+        })
+    }
+
+    pub fn handle(&self) -> DaemonHandle {
+        self.handle.clone()
+    }
+
+    pub fn set_metrics(&mut self, metrics: Arc<Metrics>) {
+        self.metrics = Some(metrics);
+    }
+
+    /// How long to wait between attempts to find and open a controller. The default
+    /// retries every second forever.
+    pub fn set_reconnect_policy(&mut self, policy: impl ReconnectPolicy + 'static) {
+        self.reconnect = Box::new(policy);
+    }
+
+    /// Priority and affinity requested for the thread that calls `run`, which is
+    /// the one reading the controller.
+    pub fn set_thread_hints(&mut self, hints: ThreadHints) {
+        self.thread_hints = hints;
+    }
+
+    /// Lets the pad switch between mapping profiles with `switcher`'s chord.
+    /// The active profile carries over when a controller reconnects.
+    pub fn set_profile_switcher(&mut self, switcher: ProfileSwitcher) {
+        self.profiles = Some(switcher);
+    }
+
+    /// Rumbles and flashes each controller as it connects, and before `PowerOff`
+    /// switches it off.
+    pub fn set_choreography(&mut self, choreography: Choreography) {
+        self.choreography = Some(choreography);
+    }
+
+    /// Publishes each report's input to `writer` for other processes to read.
+    #[cfg(feature = "shm")]
+    pub fn set_shared_state(&mut self, writer: SharedStateWriter) {
+        self.shared = Some(writer);
+    }
+
+    pub fn on_event(&mut self, listener: DaemonListener) {
+        self.listeners.push(listener);
+    }
+
+    fn emit(&mut self, event: DaemonEvent) {
+        for listener in self.listeners.iter_mut() {
+            listener(&event);
+        }
+    }
+
+    fn set_status(&self, f: impl FnOnce(&mut Status)) {
+        if let Ok(mut status) = self.status.lock() {
+            f(&mut status);
+        }
+    }
+
+    fn set_state(&mut self, state: LifecycleState) {
+        if let Some(transition) = self.lifecycle.transition(state) {
+            self.set_status(|s| s.state = state);
+            self.emit(DaemonEvent::StateChanged(transition));
+        }
+    }
+
+    /// Serves controllers until the reconnect policy gives up, which the default
+    /// never does.
+    pub fn run(mut self) -> Result<()> {
+        if let Err(e) = self.thread_hints.apply() {
+            eprintln!("couldn't apply thread priority or affinity: {}", e);
+        }
+        let mut opened_before = false;
+        let mut attempt = 0;
+        loop {
+            if attempt > 0 {
+                let delay = self.reconnect.next_delay(attempt).ok_or(Error::NotFound)?;
+                thread::sleep(delay);
+            }
+            attempt += 1;
+            let _ = self.api.refresh_devices();
+            if Controller::enumerate(&self.api).next().is_none() {
+                continue;
+            }
+            self.set_state(LifecycleState::Connecting);
+            let mut controller = match Controller::open(&self.api) {
+                Ok(c) => c,
+                Err(_) => {
+                    self.set_state(LifecycleState::Disconnected);
+                    continue;
+                }
+            };
+            for event in controller.events() {
+                if let Event::ConfigIgnored { path, error } = event {
+                    eprintln!("ignoring {}: {}", path.display(), error);
+                }
+            }
+            attempt = 0;
+            self.reconnect.reset();
+            self.set_state(controller.state());
+            if let Some(metrics) = &self.metrics {
+                if opened_before {
+                    metrics.reconnects.fetch_add(1, Ordering::Relaxed);
+                }
+                controller.set_metrics(metrics.clone());
+            }
+            opened_before = true;
+            controller.set_choreography(self.choreography.clone());
+            let name = controller.identity().name.map(str::to_string);
+            let connection = controller.connection();
+            let mut profile = None;
+            if let Some(switcher) = &self.profiles {
+                if let Err(e) = switcher.apply(&mut controller) {
+                    eprintln!("couldn't apply mapping profile: {}", e);
+                }
+                profile = switcher.active_profile().and_then(|p| p.name.clone());
+            }
+            self.set_status(|s| {
+                s.connected = true;
+                s.connection = Some(connection);
+                s.name = name.clone();
+                s.profile = profile;
+            });
+            self.emit(DaemonEvent::Connected { connection, name });
+            // Commands queued while disconnected are stale.
+            while self.commands.try_recv().is_ok() {}
+
+            if let Err(e) = self.serve(&mut controller) {
+                eprintln!("controller disconnected: {}", e);
+            }
+            if let Err(e) = controller.save_stats() {
+                eprintln!("couldn't save usage statistics: {}", e);
+            }
+
+            if let Some(metrics) = &self.metrics {
+                metrics.connected.store(false, Ordering::Relaxed);
+            }
+            #[cfg(feature = "shm")]
+            if let Some(shared) = &mut self.shared {
+                shared.disconnected();
+            }
+            self.set_status(|s| *s = Status::default());
+            self.set_state(LifecycleState::Disconnected);
+            self.emit(DaemonEvent::Disconnected);
+        }
+    }
+
+    /// Runs until the controller fails with a device error.
+    fn serve(&mut self, controller: &mut Controller) -> Result<()> {
+        let mut last_battery = None;
+        loop {
+            match controller.update() {
+                Ok(()) => {}
+                Err(e) if e.is_device_error() => return Err(e),
+                Err(e) => eprintln!("ignoring report: {}", e),
+            }
+            self.set_state(controller.state());
+            #[cfg(feature = "shm")]
+            if let Some(shared) = &mut self.shared {
+                shared.publish_controller(controller);
+            }
+
+            let switched = match &mut self.profiles {
+                Some(switcher) => switcher.update(controller)?.map(|index| {
+                    (
+                        index,
+                        switcher.active_profile().and_then(|p| p.name.clone()),
+                    )
+                }),
+                None => None,
+            };
+            if let Some((index, name)) = switched {
+                self.set_status(|s| s.profile = name.clone());
+                self.emit(DaemonEvent::ProfileChanged { index, name });
+            }
+
+            while let Ok(command) = self.commands.try_recv() {
+                match command {
+                    Command::SetLightbar(Rgb { r, g, b }) => controller.set_lightbar(r, g, b)?,
+                    Command::SetRumble { strong, weak } => controller.set_rumble(strong, weak)?,
+                    Command::PowerOff => {
+                        if let Err(e) = controller.farewell() {
+                            eprintln!("couldn't play disconnect choreography: {}", e);
+                        }
+                        if let Err(e) = power_off(controller) {
+                            eprintln!("couldn't power off controller: {}", e);
+                        }
+                    }
+                }
+            }
+
+            let battery = controller.battery();
+            let (percent, charging) = (battery.percent(), battery.cable_connected());
+            self.set_status(|s| {
+                s.battery_percent = percent;
+                s.charging = charging;
+            });
+            if let Some(percent) = percent {
+                let rounded = (percent as i32, charging);
+                if last_battery != Some(rounded) {
+                    last_battery = Some(rounded);
+                    self.emit(DaemonEvent::Battery { percent, charging });
+                }
+            }
+        }
+    }
+}
+
+/// Asks BlueZ to drop the pad's link; a DS4 powers down once disconnected.
+fn power_off(controller: &mut Controller) -> Result<()> {
+    if controller.connection() != Connection::Bluetooth {
+        return Ok(());
+    }
+    let address = match controller.pairing() {
+        Some(pairing) => pairing.device,
+        None => controller.read_pairing()?.device,
+    };
+    let status = std::process::Command::new("bluetoothctl")
+        .args(["disconnect", &address.to_string()])
+        .stdout(std::process::Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(Error::Io(std::io::Error::other(
+            "bluetoothctl disconnect failed",
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,261 @@
+use crate::touch::Touch;
+use crate::{ButtonId, Controls, Error, Imu, Result, Snapshot};
+use arrow_array::{
+    ArrayRef, BooleanArray, DurationMicrosecondArray, Int16Array, RecordBatch, StringArray,
+    UInt16Array, UInt8Array,
+};
+use arrow_ipc::writer::FileWriter;
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Rows buffered before a record batch is written; about four seconds at 250 Hz.
+const BATCH_ROWS: usize = 1024;
+
+/// One report's worth of columns.
+#[derive(Debug, Clone)]
+struct Row {
+    time: Duration,
+    imu: Imu,
+    snapshot: Snapshot,
+    touches: [Touch; 2],
+    marker: Option<String>,
+}
+
+enum Sink {
+    Parquet(ArrowWriter<File>),
+    Ipc(FileWriter<BufWriter<File>>),
+}
+
+/// Writes motion and input data, one row per report, to an Arrow IPC or Parquet
+/// file for analysis in pandas, polars, DuckDB and the like.
+///
+/// Rows are written in batches as they accumulate, so long captures don't sit in
+/// memory; call `finish` to write the last batch and the file footer. The schema
+/// is `time` (device time, microseconds), `gyro_{x,y,z}` and `accel_{x,y,z}`
+/// (raw i16), `imu_temperature`, the raw stick and trigger bytes, one boolean per
+/// button, `dpad` as a name, `touch{0,1}_{active,x,y}` and a nullable `marker`.
+pub struct DatasetWriter {
+    schema: SchemaRef,
+    sink: Option<Sink>,
+    rows: Vec<Row>,
+    written: u64,
+    marker: Option<String>,
+}
+
+impl DatasetWriter {
+    /// A Snappy-compressed Parquet file.
+    pub fn parquet(path: impl AsRef<Path>) -> Result<Self> {
+        let schema = schema();
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let writer = ArrowWriter::try_new(File::create(path)?, schema.clone(), Some(props))
+            .map_err(export_error)?;
+        Ok(DatasetWriter::with_sink(schema, Sink::Parquet(writer)))
+    }
+
+    /// An Arrow IPC (Feather v2) file.
+    pub fn arrow_ipc(path: impl AsRef<Path>) -> Result<Self> {
+        let schema = schema();
+        let writer =
+            FileWriter::try_new_buffered(File::create(path)?, &schema).map_err(export_error)?;
+        Ok(DatasetWriter::with_sink(schema, Sink::Ipc(writer)))
+    }
+
+    fn with_sink(schema: SchemaRef, sink: Sink) -> Self {
+        DatasetWriter {
+            schema,
+            sink: Some(sink),
+            rows: Vec::with_capacity(BATCH_ROWS),
+            written: 0,
+            marker: None,
+        }
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// Rows recorded so far, including ones not yet written.
+    pub fn len(&self) -> u64 {
+        self.written + self.rows.len() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds a row for the current state at `at`, e.g.
+    /// `Controller::device_clock().device_time()`.
+    pub fn record(&mut self, controls: &Controls, at: Duration) -> Result<()> {
+        self.rows.push(Row {
+            time: at,
+            imu: controls.imu,
+            snapshot: controls.snapshot(),
+            touches: controls.touches.state(),
+            marker: self.marker.take(),
+        });
+        if self.rows.len() >= BATCH_ROWS {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Labels the next recorded row's `marker` column, e.g. with `"trial_3_start"`.
+    /// Several marks before the same row are joined with `;`.
+    pub fn mark(&mut self, label: &str) {
+        match &mut self.marker {
+            Some(marker) => {
+                marker.push(';');
+                marker.push_str(label);
+            }
+            None => self.marker = Some(label.to_string()),
+        }
+    }
+
+    /// Writes buffered rows as a record batch.
+    pub fn flush(&mut self) -> Result<()> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+        let batch =
+            RecordBatch::try_new(self.schema.clone(), columns(&self.rows)).map_err(export_error)?;
+        match self.sink.as_mut().ok_or(Error::Closed)? {
+            Sink::Parquet(writer) => writer.write(&batch).map_err(export_error)?,
+            Sink::Ipc(writer) => writer.write(&batch).map_err(export_error)?,
+        }
+        self.written += self.rows.len() as u64;
+        self.rows.clear();
+        Ok(())
+    }
+
+    /// Writes the remaining rows and the file footer. Dropping the writer without
+    /// calling this leaves a truncated file.
+    pub fn finish(mut self) -> Result<()> {
+        self.flush()?;
+        match self.sink.take().ok_or(Error::Closed)? {
+            Sink::Parquet(writer) => {
+                writer.close().map_err(export_error)?;
+            }
+            Sink::Ipc(mut writer) => writer.finish().map_err(export_error)?,
+        }
+        Ok(())
+    }
+}
+
+fn export_error(e: impl std::fmt::Display) -> Error {
+    Error::Export(e.to_string())
+}
+
+fn schema() -> SchemaRef {
+    let mut fields = vec![Field::new(
+        "time",
+        DataType::Duration(TimeUnit::Microsecond),
+        false,
+    )];
+    for name in [
+        "gyro_x", "gyro_y", "gyro_z", "accel_x", "accel_y", "accel_z",
+    ] {
+        fields.push(Field::new(name, DataType::Int16, false));
+    }
+    fields.push(Field::new("imu_temperature", DataType::UInt8, false));
+    for name in ["left_x", "left_y", "right_x", "right_y", "l2", "r2"] {
+        fields.push(Field::new(name, DataType::UInt8, false));
+    }
+    for id in ButtonId::ALL {
+        fields.push(Field::new(
+            format!("{}_pressed", id),
+            DataType::Boolean,
+            false,
+        ));
+    }
+    fields.push(Field::new("dpad", DataType::Utf8, false));
+    for finger in 0..2 {
+        fields.push(Field::new(
+            format!("touch{}_active", finger),
+            DataType::Boolean,
+            false,
+        ));
+        fields.push(Field::new(
+            format!("touch{}_x", finger),
+            DataType::UInt16,
+            false,
+        ));
+        fields.push(Field::new(
+            format!("touch{}_y", finger),
+            DataType::UInt16,
+            false,
+        ));
+    }
+    fields.push(Field::new("marker", DataType::Utf8, true));
+    let metadata = HashMap::from([
+        ("source".to_string(), "ps4hid".to_string()),
+        (
+            "units".to_string(),
+            "gyro and accel are raw sensor counts; sticks and triggers are raw 0-255 bytes"
+                .to_string(),
+        ),
+    ]);
+    Arc::new(Schema::new_with_metadata(fields, metadata))
+}
+
+/// Columns in the same order as `schema`.
+fn columns(rows: &[Row]) -> Vec<ArrayRef> {
+    let i16s = |f: &dyn Fn(&Row) -> i16| {
+        Arc::new(Int16Array::from_iter_values(rows.iter().map(f))) as ArrayRef
+    };
+    let u8s = |f: &dyn Fn(&Row) -> u8| {
+        Arc::new(UInt8Array::from_iter_values(rows.iter().map(f))) as ArrayRef
+    };
+    let u16s = |f: &dyn Fn(&Row) -> u16| {
+        Arc::new(UInt16Array::from_iter_values(rows.iter().map(f))) as ArrayRef
+    };
+    let bools = |f: &dyn Fn(&Row) -> bool| {
+        Arc::new(rows.iter().map(|r| Some(f(r))).collect::<BooleanArray>()) as ArrayRef
+    };
+
+    let mut columns: Vec<ArrayRef> = vec![Arc::new(DurationMicrosecondArray::from_iter_values(
+        rows.iter().map(|r| r.time.as_micros() as i64),
+    ))];
+    columns.extend([
+        i16s(&|r| r.imu.gyro[0]),
+        i16s(&|r| r.imu.gyro[1]),
+        i16s(&|r| r.imu.gyro[2]),
+        i16s(&|r| r.imu.accel[0]),
+        i16s(&|r| r.imu.accel[1]),
+        i16s(&|r| r.imu.accel[2]),
+        u8s(&|r| r.imu.temperature),
+        u8s(&|r| r.snapshot.left_stick.x),
+        u8s(&|r| r.snapshot.left_stick.y),
+        u8s(&|r| r.snapshot.right_stick.x),
+        u8s(&|r| r.snapshot.right_stick.y),
+        u8s(&|r| r.snapshot.l2_value),
+        u8s(&|r| r.snapshot.r2_value),
+    ]);
+    for id in ButtonId::ALL {
+        columns.push(bools(&|r| r.snapshot.pressed(id)));
+    }
+    columns.push(Arc::new(StringArray::from_iter_values(
+        rows.iter()
+            .map(|r| format!("{:?}", r.snapshot.dpad).to_lowercase()),
+    )));
+    for finger in 0..2 {
+        columns.push(bools(&|r| r.touches[finger].active));
+        columns.push(u16s(&|r| r.touches[finger].x));
+        columns.push(u16s(&|r| r.touches[finger].y));
+    }
+    columns.push(Arc::new(
+        rows.iter()
+            .map(|r| r.marker.as_deref())
+            .collect::<StringArray>(),
+    ));
+    columns
+}
@@ -0,0 +1,103 @@
+use crate::daemon::{Command, Daemon, DaemonEvent, DaemonHandle};
+use crate::lightbar::Rgb;
+use async_io::block_on;
+use zbus::blocking::{connection, Connection};
+use zbus::interface;
+
+pub const BUS_NAME: &str = "org.ps4hid.Daemon";
+pub const OBJECT_PATH: &str = "/org/ps4hid/Controller";
+
+struct ControllerInterface {
+    handle: DaemonHandle,
+}
+
+/// Mirrors the UPower device conventions where they apply: `Percentage` is in
+/// `0.0..=100.0` and `IsPresent` reports whether a controller is connected.
+#[interface(name = "org.ps4hid.Controller1")]
+impl ControllerInterface {
+    #[zbus(property)]
+    fn is_present(&self) -> bool {
+        self.handle.status().connected
+    }
+
+    /// "USB", "Bluetooth", "Dongle", or empty when disconnected.
+    #[zbus(property)]
+    fn connection(&self) -> String {
+        self.handle
+            .status()
+            .connection
+            .map(|c| c.to_string())
+            .unwrap_or_default()
+    }
+
+    #[zbus(property)]
+    fn name(&self) -> String {
+        self.handle.status().name.unwrap_or_default()
+    }
+
+    #[zbus(property)]
+    fn percentage(&self) -> f64 {
+        self.handle.status().battery_percent.unwrap_or(0.0) as f64
+    }
+
+    #[zbus(property)]
+    fn charging(&self) -> bool {
+        self.handle.status().charging
+    }
+
+    fn set_lightbar(&self, r: u8, g: u8, b: u8) {
+        self.handle.send(Command::SetLightbar(Rgb::new(r, g, b)));
+    }
+
+    fn set_rumble(&self, strong: u8, weak: u8) {
+        self.handle.send(Command::SetRumble { strong, weak });
+    }
+
+    fn power_off(&self) {
+        self.handle.send(Command::PowerOff);
+    }
+}
+
+/// Registers the daemon on the session bus and sends `PropertiesChanged` as its
+/// status changes. Keep the returned connection alive for as long as the service
+/// should stay registered.
+pub fn serve(daemon: &mut Daemon) -> zbus::Result<Connection> {
+    let handle = daemon.handle();
+    let connection = connection::Builder::session()?
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, ControllerInterface { handle })?
+        .build()?;
+    let bus = connection.clone();
+    daemon.on_event(Box::new(move |event| {
+        if let Err(e) = properties_changed(&bus, event) {
+            eprintln!("couldn't signal D-Bus property change: {}", e);
+        }
+    }));
+    Ok(connection)
+}
+
+/// Announces the properties `event` may have changed; the values are read from
+/// the handle, which the daemon updates before emitting.
+fn properties_changed(connection: &Connection, event: &DaemonEvent) -> zbus::Result<()> {
+    let iface = connection
+        .object_server()
+        .interface::<_, ControllerInterface>(OBJECT_PATH)?;
+    let emitter = iface.signal_emitter();
+    let controller = iface.get();
+    block_on(async {
+        match event {
+            DaemonEvent::Connected { .. } | DaemonEvent::Disconnected => {
+                controller.is_present_changed(emitter).await?;
+                controller.connection_changed(emitter).await?;
+                controller.name_changed(emitter).await?;
+                controller.percentage_changed(emitter).await?;
+                controller.charging_changed(emitter).await
+            }
+            DaemonEvent::Battery { .. } => {
+                controller.percentage_changed(emitter).await?;
+                controller.charging_changed(emitter).await
+            }
+            DaemonEvent::StateChanged(_) | DaemonEvent::ProfileChanged { .. } => Ok(()),
+        }
+    })
+}
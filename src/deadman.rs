@@ -0,0 +1,171 @@
+use crate::Controls;
+use std::time::{Duration, Instant};
+
+pub type DeadmanSwitch = Box<dyn Fn(&Controls) -> bool + Send>;
+
+/// How long the switch stays engaged without a report, by default.
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadmanEvent {
+    Engaged,
+    Disengaged,
+}
+
+/// Passes values through only while the switch is held and reports keep
+/// arriving; starts disengaged.
+pub struct Deadman {
+    switch: DeadmanSwitch,
+    engaged: bool,
+    timeout: Duration,
+    last_report: Option<Instant>,
+}
+
+impl Deadman {
+    pub fn new(switch: impl Fn(&Controls) -> bool + Send + 'static) -> Self {
+        Deadman {
+            switch: Box::new(switch),
+            engaged: false,
+            timeout: DEFAULT_TIMEOUT,
+            last_report: None,
+        }
+    }
+
+    /// How long after the last report the switch counts as released, e.g. when
+    /// the link drops. Defaults to 100 ms.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Call once per report, before gating any outputs derived from it.
+    pub fn update(&mut self, controls: &Controls) -> Option<DeadmanEvent> {
+        self.update_at(controls, Instant::now())
+    }
+
+    /// Like `update`, for a report read at `at`.
+    pub fn update_at(&mut self, controls: &Controls, at: Instant) -> Option<DeadmanEvent> {
+        self.last_report = Some(at);
+        let engaged = (self.switch)(controls);
+        self.set(engaged)
+    }
+
+    /// Disengages if no report has arrived within the timeout. Call this between
+    /// reads, since a dropped link means `update` is never called again.
+    pub fn check(&mut self, now: Instant) -> Option<DeadmanEvent> {
+        if self.engaged && !self.fresh(now) {
+            self.set(false)
+        } else {
+            None
+        }
+    }
+
+    /// Disengages at once; call when reading fails or the pad goes away.
+    pub fn release(&mut self) -> Option<DeadmanEvent> {
+        self.set(false)
+    }
+
+    fn fresh(&self, now: Instant) -> bool {
+        self.last_report
+            .is_some_and(|at| now.saturating_duration_since(at) <= self.timeout)
+    }
+
+    fn set(&mut self, engaged: bool) -> Option<DeadmanEvent> {
+        if engaged == self.engaged {
+            return None;
+        }
+        self.engaged = engaged;
+        Some(if engaged {
+            DeadmanEvent::Engaged
+        } else {
+            DeadmanEvent::Disengaged
+        })
+    }
+
+    /// Whether the switch is held and the latest report is within the timeout.
+    pub fn is_engaged(&self) -> bool {
+        self.engaged && self.fresh(Instant::now())
+    }
+
+    /// Returns `value` while engaged, otherwise its neutral (default) value.
+    pub fn gate<T: Default>(&self, value: T) -> T {
+        if self.is_engaged() {
+            value
+        } else {
+            T::default()
+        }
+    }
+
+    pub fn controls<'a>(&self, controls: &'a Controls) -> Option<&'a Controls> {
+        self.is_engaged().then_some(controls)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn held() -> Controls {
+        let mut controls = Controls::new();
+        controls.l1.update(true);
+        controls
+    }
+
+    fn deadman() -> Deadman {
+        Deadman::new(|controls: &Controls| controls.l1.state())
+    }
+
+    #[test]
+    fn follows_the_switch() {
+        let mut deadman = deadman();
+        let now = Instant::now();
+        assert_eq!(deadman.update_at(&held(), now), Some(DeadmanEvent::Engaged));
+        assert_eq!(deadman.update_at(&held(), now), None);
+        assert_eq!(
+            deadman.update_at(&Controls::new(), now),
+            Some(DeadmanEvent::Disengaged)
+        );
+    }
+
+    #[test]
+    fn disengages_when_reports_stop() {
+        let mut deadman = deadman().timeout(Duration::from_millis(50));
+        let start = Instant::now();
+        assert_eq!(
+            deadman.update_at(&held(), start),
+            Some(DeadmanEvent::Engaged)
+        );
+        assert_eq!(deadman.check(start + Duration::from_millis(40)), None);
+        assert_eq!(
+            deadman.check(start + Duration::from_millis(60)),
+            Some(DeadmanEvent::Disengaged)
+        );
+        assert_eq!(deadman.check(start + Duration::from_millis(70)), None);
+    }
+
+    #[test]
+    fn stale_switch_gates_even_without_check() {
+        let mut deadman = deadman().timeout(Duration::from_millis(50));
+        let long_ago = Instant::now().checked_sub(Duration::from_secs(1));
+        let Some(long_ago) = long_ago else { return };
+        deadman.update_at(&held(), long_ago);
+        assert!(!deadman.is_engaged());
+        assert_eq!(deadman.gate(1.0), 0.0);
+    }
+
+    #[test]
+    fn release_disengages_on_errors() {
+        let mut deadman = deadman();
+        deadman.update(&held());
+        assert!(deadman.is_engaged());
+        assert_eq!(deadman.release(), Some(DeadmanEvent::Disengaged));
+        assert!(!deadman.is_engaged());
+    }
+
+    #[test]
+    fn switches_may_capture() {
+        let threshold = 200;
+        let mut deadman = Deadman::new(move |controls: &Controls| controls.r2.pressed(threshold));
+        assert_eq!(deadman.update(&Controls::new()), None);
+    }
+}
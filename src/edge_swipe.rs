@@ -0,0 +1,171 @@
+use crate::calibration::TouchBounds;
+use crate::touch::Touch;
+use std::time::{Duration, Instant};
+
+/// A side of the touchpad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Edge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl Edge {
+    /// How far `(x, y)`, normalized, is from this edge.
+    fn distance(self, x: f32, y: f32) -> f32 {
+        match self {
+            Edge::Left => x,
+            Edge::Right => 1.0 - x,
+            Edge::Top => y,
+            Edge::Bottom => 1.0 - y,
+        }
+    }
+}
+
+/// What an edge swipe asks the remapper or overlay to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SystemGesture {
+    OpenOverlay,
+    NextProfile,
+    PreviousProfile,
+    /// A swipe in from an edge with no action bound to it.
+    Swipe(Edge),
+}
+
+/// A finger that landed near an edge and may yet swipe in from it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate {
+    id: u8,
+    edge: Edge,
+    start: f32,
+    since: Instant,
+    fired: bool,
+}
+
+/// Recognizes swipes that start at a touchpad edge and move inwards, reserving
+/// them for system gestures the way remapper UIs conventionally do.
+///
+/// A finger landing within `margin` of an edge is claimed for as long as it
+/// could still become a swipe; pass touches through `unclaimed` before normal
+/// touch handling so the same finger doesn't also drive a cursor or press a
+/// touch zone. A claimed finger that doesn't travel `travel` inwards within
+/// `timeout` is released back to normal handling.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeSwipes {
+    /// Width of each edge band, as a fraction of the touchpad.
+    pub margin: f32,
+    /// Inward travel that completes a swipe, as a fraction of the touchpad.
+    pub travel: f32,
+    pub timeout: Duration,
+    /// The action for each edge; edges not listed report `SystemGesture::Swipe`.
+    pub bindings: Vec<(Edge, SystemGesture)>,
+    candidates: [Option<Candidate>; 2],
+    /// Finger ids seen in the previous update, to spot new contacts.
+    seen: [Option<u8>; 2],
+}
+
+impl Default for EdgeSwipes {
+    /// Down from the top opens the overlay, and in from either side steps
+    /// through profiles.
+    fn default() -> Self {
+        EdgeSwipes {
+            margin: 0.06,
+            travel: 0.2,
+            timeout: Duration::from_millis(500),
+            bindings: vec![
+                (Edge::Top, SystemGesture::OpenOverlay),
+                (Edge::Right, SystemGesture::NextProfile),
+                (Edge::Left, SystemGesture::PreviousProfile),
+            ],
+            candidates: [None; 2],
+            seen: [None; 2],
+        }
+    }
+}
+
+impl EdgeSwipes {
+    pub fn new() -> Self {
+        EdgeSwipes::default()
+    }
+
+    pub fn bind(mut self, edge: Edge, gesture: SystemGesture) -> Self {
+        self.bindings.retain(|&(e, _)| e != edge);
+        self.bindings.push((edge, gesture));
+        self
+    }
+
+    /// Feeds the latest touches, returning a gesture when a swipe completes.
+    pub fn update(
+        &mut self,
+        touches: &[Touch; 2],
+        bounds: &TouchBounds,
+        at: Instant,
+    ) -> Option<SystemGesture> {
+        let mut gesture = None;
+        for (slot, touch) in touches.iter().enumerate() {
+            let seen = self.seen[slot];
+            self.seen[slot] = touch.active.then_some(touch.id);
+            if !touch.active {
+                self.candidates[slot] = None;
+                continue;
+            }
+            let (x, y) = bounds.normalize(touch.x, touch.y);
+            if seen != Some(touch.id) {
+                self.candidates[slot] = self.landed(touch.id, x, y, at);
+                continue;
+            }
+            let Some(candidate) = &mut self.candidates[slot] else {
+                continue;
+            };
+            if candidate.fired {
+                continue;
+            }
+            if at.saturating_duration_since(candidate.since) > self.timeout {
+                self.candidates[slot] = None;
+                continue;
+            }
+            if candidate.edge.distance(x, y) - candidate.start >= self.travel {
+                candidate.fired = true;
+                let edge = candidate.edge;
+                gesture = Some(
+                    self.bindings
+                        .iter()
+                        .find(|&&(e, _)| e == edge)
+                        .map_or(SystemGesture::Swipe(edge), |&(_, g)| g),
+                );
+            }
+        }
+        gesture
+    }
+
+    /// A new contact at `(x, y)`, claimed if it's in an edge band.
+    fn landed(&self, id: u8, x: f32, y: f32, at: Instant) -> Option<Candidate> {
+        [Edge::Left, Edge::Right, Edge::Top, Edge::Bottom]
+            .into_iter()
+            .map(|edge| (edge, edge.distance(x, y)))
+            .filter(|&(_, distance)| distance < self.margin)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(edge, start)| Candidate {
+                id,
+                edge,
+                start,
+                since: at,
+                fired: false,
+            })
+    }
+
+    /// Whether the finger with this id is reserved for a system gesture.
+    pub fn is_claimed(&self, id: u8) -> bool {
+        self.candidates.iter().flatten().any(|c| c.id == id)
+    }
+
+    /// `touches` with claimed fingers marked inactive, for normal touch
+    /// handling. A finger that completed a swipe stays claimed until lifted.
+    pub fn unclaimed(&self, touches: &[Touch; 2]) -> [Touch; 2] {
+        touches.map(|t| Touch {
+            active: t.active && !self.is_claimed(t.id),
+            ..t
+        })
+    }
+}
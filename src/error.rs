@@ -0,0 +1,159 @@
+use ds4_core::ParseError;
+#[cfg(feature = "hid")]
+use hidapi::HidError;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    #[cfg(feature = "hid")]
+    Hid(HidError),
+    Io(io::Error),
+    ShortReport {
+        len: usize,
+        expected: usize,
+    },
+    InvalidDPad(u8),
+    MalformedReport(&'static str),
+    /// No controller is connected, or the one in use has gone away.
+    NotFound,
+    /// The OS refused access to the device, e.g. for lack of a udev rule.
+    PermissionDenied,
+    /// Another process has the device open exclusively.
+    DeviceBusy,
+    InvalidFormat(&'static str),
+    /// The controller was closed with `Controller::close`.
+    Closed,
+    #[cfg(feature = "scripting")]
+    Script(String),
+    /// Arrow or Parquet failed to encode or write a dataset.
+    #[cfg(feature = "parquet")]
+    Export(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "hid")]
+            Error::Hid(e) => write!(f, "hid error: {}", e),
+            Error::Io(e) => write!(f, "io error: {}", e),
+            Error::ShortReport { len, expected } => {
+                write!(f, "short report: got {} bytes, expected {}", len, expected)
+            }
+            Error::InvalidDPad(b) => write!(f, "invalid dpad value: 0b{:04b}", b),
+            Error::MalformedReport(what) => write!(f, "malformed report: {}", what),
+            Error::NotFound => write!(f, "no controller connected"),
+            Error::PermissionDenied => write!(f, "permission denied opening controller"),
+            Error::DeviceBusy => write!(f, "controller is in use by another process"),
+            Error::InvalidFormat(what) => write!(f, "invalid format: {}", what),
+            Error::Closed => write!(f, "controller closed"),
+            #[cfg(feature = "scripting")]
+            Error::Script(e) => write!(f, "script error: {}", e),
+            #[cfg(feature = "parquet")]
+            Error::Export(e) => write!(f, "export error: {}", e),
+        }
+    }
+}
+
+impl Error {
+    /// Whether the transport itself failed, meaning the device is most likely gone.
+    pub fn is_device_error(&self) -> bool {
+        match self {
+            #[cfg(feature = "hid")]
+            Error::Hid(_) => true,
+            Error::Io(_) | Error::NotFound | Error::PermissionDenied | Error::DeviceBusy => true,
+            _ => false,
+        }
+    }
+
+    /// Classifies an OS error from a device operation by kind, keeping it as `Io`
+    /// when it's none of the specific cases.
+    #[cfg(feature = "hid")]
+    fn from_device_io(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::PermissionDenied => Error::PermissionDenied,
+            io::ErrorKind::ResourceBusy => Error::DeviceBusy,
+            io::ErrorKind::NotFound => Error::NotFound,
+            _ => Error::Io(e),
+        }
+    }
+
+    /// Classifies a failed open of the device at `path`. hidapi often gives no
+    /// reason, so for device nodes the node itself is probed for one.
+    #[cfg(feature = "hid")]
+    pub(crate) fn from_open(e: HidError, path: &std::ffi::CStr) -> Self {
+        let error = Error::from(e);
+        if !matches!(error, Error::Hid(_)) {
+            return error;
+        }
+        match path.to_str() {
+            Ok(path) if path.starts_with("/dev/") => {
+                match std::fs::OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .open(path)
+                {
+                    Err(e) => match Error::from_device_io(e) {
+                        Error::Io(_) => error,
+                        specific => specific,
+                    },
+                    Ok(_) => error,
+                }
+            }
+            _ => error,
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "hid")]
+            Error::Hid(e) => Some(e),
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+#[cfg(feature = "hid")]
+impl From<HidError> for Error {
+    /// hidapi reports OS failures as text, so the common ones are recognised by
+    /// their strerror message.
+    fn from(e: HidError) -> Self {
+        if let HidError::HidApiError { message } = &e {
+            let message = message.to_ascii_lowercase();
+            if message.contains("permission denied") || message.contains("access denied") {
+                return Error::PermissionDenied;
+            }
+            if message.contains("busy") {
+                return Error::DeviceBusy;
+            }
+            if message.contains("no such device") || message.contains("no such file") {
+                return Error::NotFound;
+            }
+        }
+        Error::Hid(e)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        match e {
+            ParseError::ShortReport { len, expected } => Error::ShortReport { len, expected },
+            ParseError::InvalidDPad(b) => Error::InvalidDPad(b),
+            ParseError::UnsupportedVersion(_) => {
+                Error::InvalidFormat("unsupported wire format version")
+            }
+            ParseError::ReservedBits => Error::InvalidFormat("reserved bits set in wire message"),
+        }
+    }
+}
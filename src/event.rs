@@ -0,0 +1,63 @@
+use crate::choreography::Cue;
+use crate::controls::HandlerPanic;
+use crate::lifecycle::Transition;
+use crate::pairing::Pairing;
+use crate::PeripheralState;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    PairingChanged {
+        previous: Option<Pairing>,
+        current: Pairing,
+    },
+    /// Full Bluetooth reports resumed after a run of short ones, e.g. once the pad has
+    /// woken up and the handshake has been redone. Inputs were withheld meanwhile.
+    /// Not sent for the first full reports after connecting.
+    Resumed,
+    PeripheralsChanged {
+        previous: PeripheralState,
+        current: PeripheralState,
+    },
+    /// Output writes have failed `failures` times in a row and are still being retried.
+    OutputStalled {
+        failures: u32,
+        error: String,
+    },
+    /// An output write succeeded after `OutputStalled`.
+    OutputRecovered,
+    /// A control's handler panicked and `PanicPolicy::Isolate` kept the read loop alive.
+    HandlerPanicked(HandlerPanic),
+    /// The pad was resting long enough to re-estimate gyro bias, in raw units.
+    GyroRecalibrated {
+        bias: [f32; 3],
+    },
+    StateChanged(Transition),
+    /// A choreography pattern took over the lightbar and rumble.
+    ChoreographyStarted(Cue),
+    /// The pattern ended and the output it covered was put back.
+    ChoreographyFinished(Cue),
+    /// Reports are backing up between the pad and the app: some were lost from a
+    /// full queue, or the newest one was read well after it was sampled. Lag this
+    /// reports is the host's or the app's, not the pad's or the link's.
+    InputBacklog {
+        /// Reports the pad sent that never arrived, from gaps in its report counter.
+        skipped: u32,
+        /// How long the newest report waited between being sampled and being read.
+        behind: Duration,
+    },
+    /// A saved profile or statistics file couldn't be read, e.g. it's malformed,
+    /// so the pad was opened with defaults instead.
+    ConfigIgnored {
+        path: PathBuf,
+        error: String,
+    },
+    /// An application-defined label from `Controller::mark`, with the host and
+    /// device times at which it was placed.
+    Marker {
+        label: String,
+        at: Instant,
+        device_time: Duration,
+    },
+}
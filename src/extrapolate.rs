@@ -0,0 +1,261 @@
+use crate::{Snapshot, Stick, YAxis};
+use std::time::{Duration, Instant};
+
+/// How far past the newest real sample a prediction may reach by default. A gap
+/// longer than this usually means the link stalled, and guessing further only
+/// overshoots.
+const DEFAULT_HORIZON: Duration = Duration::from_millis(150);
+
+/// A stick position, either as received or extrapolated from earlier samples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StickSample {
+    /// `-1.0..=1.0`, right positive.
+    pub x: f32,
+    /// `-1.0..=1.0`, down positive as in reports.
+    pub y: f32,
+    /// Set when the position is an estimate for a later time than the newest real
+    /// sample, rather than the sample itself.
+    pub predicted: bool,
+}
+
+/// Predicts where a stick will be `lead` from now by carrying its recent velocity
+/// forward, to hide link latency when the pad is driven remotely.
+///
+/// Feed it every real sample with `observe`, stamped with when it arrived, and ask
+/// for the position to show with `predict`. Predictions stop advancing once they
+/// reach `horizon` past the newest sample, so a stalled link holds rather than
+/// sending the stick to the rim.
+#[derive(Debug, Clone)]
+pub struct StickExtrapolator {
+    lead: Duration,
+    horizon: Duration,
+    smoothing: f32,
+    last: Option<(Instant, f32, f32)>,
+    velocity: (f32, f32),
+}
+
+impl StickExtrapolator {
+    pub fn new(lead: Duration) -> Self {
+        StickExtrapolator {
+            lead,
+            horizon: DEFAULT_HORIZON,
+            smoothing: 0.5,
+            last: None,
+            velocity: (0.0, 0.0),
+        }
+    }
+
+    pub fn horizon(mut self, horizon: Duration) -> Self {
+        self.horizon = horizon;
+        self
+    }
+
+    /// Weight of each new velocity estimate, in `0.0..=1.0`; lower rides out
+    /// jitter in when samples arrive but reacts to direction changes later.
+    pub fn smoothing(mut self, alpha: f32) -> Self {
+        self.smoothing = alpha.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn lead(&self) -> Duration {
+        self.lead
+    }
+
+    /// Changes how far ahead to predict, e.g. as the measured round trip changes.
+    pub fn set_lead(&mut self, lead: Duration) {
+        self.lead = lead;
+    }
+
+    /// Velocity in units per second, `(x, y)`.
+    pub fn velocity(&self) -> (f32, f32) {
+        self.velocity
+    }
+
+    /// Records a real sample taken at `at`. Samples older than the newest one are
+    /// ignored.
+    pub fn observe(&mut self, x: f32, y: f32, at: Instant) {
+        if let Some((t, px, py)) = self.last {
+            if at < t {
+                return;
+            }
+            let dt = (at - t).as_secs_f32();
+            if dt > 0.0 {
+                let a = self.smoothing;
+                let (vx, vy) = ((x - px) / dt, (y - py) / dt);
+                self.velocity.0 += a * (vx - self.velocity.0);
+                self.velocity.1 += a * (vy - self.velocity.1);
+            }
+        }
+        self.last = Some((at, x, y));
+    }
+
+    /// The position expected `lead` after `at`. Before any sample has been observed
+    /// this is the center, flagged as predicted.
+    pub fn predict(&self, at: Instant) -> StickSample {
+        let Some((t, x, y)) = self.last else {
+            return StickSample {
+                x: 0.0,
+                y: 0.0,
+                predicted: true,
+            };
+        };
+        let ahead = (at.saturating_duration_since(t) + self.lead).min(self.horizon);
+        if ahead.is_zero() {
+            return StickSample {
+                x,
+                y,
+                predicted: false,
+            };
+        }
+        let s = ahead.as_secs_f32();
+        StickSample {
+            x: (x + self.velocity.0 * s).clamp(-1.0, 1.0),
+            y: (y + self.velocity.1 * s).clamp(-1.0, 1.0),
+            predicted: true,
+        }
+    }
+
+    /// Forgets past samples, e.g. after a reconnect.
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.velocity = (0.0, 0.0);
+    }
+}
+
+/// A snapshot whose sticks may have been extrapolated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PredictedSnapshot {
+    pub snapshot: Snapshot,
+    /// Set when either stick is an estimate rather than the newest real sample.
+    pub predicted: bool,
+}
+
+/// Runs a `StickExtrapolator` on both sticks of a remote pad's snapshots.
+/// Buttons, the dpad and triggers are passed through from the newest snapshot,
+/// since guessing a press that never happened is worse than a late one.
+#[derive(Debug, Clone)]
+pub struct Extrapolator {
+    pub left: StickExtrapolator,
+    pub right: StickExtrapolator,
+    latest: Snapshot,
+}
+
+impl Extrapolator {
+    pub fn new(lead: Duration) -> Self {
+        Extrapolator {
+            left: StickExtrapolator::new(lead),
+            right: StickExtrapolator::new(lead),
+            latest: Snapshot::default(),
+        }
+    }
+
+    pub fn set_lead(&mut self, lead: Duration) {
+        self.left.set_lead(lead);
+        self.right.set_lead(lead);
+    }
+
+    /// Records a snapshot received at `at`.
+    pub fn observe(&mut self, snapshot: &Snapshot, at: Instant) {
+        let left = snapshot.left_stick;
+        let right = snapshot.right_stick;
+        self.left.observe(left.x_f32(), left.y_f32(), at);
+        self.right.observe(right.x_f32(), right.y_f32(), at);
+        self.latest = *snapshot;
+    }
+
+    /// The newest snapshot with both sticks predicted `lead` past `at`.
+    pub fn predict(&self, at: Instant) -> PredictedSnapshot {
+        let left = self.left.predict(at);
+        let right = self.right.predict(at);
+        let mut snapshot = self.latest;
+        snapshot.left_stick = Stick::from_centered(left.x, left.y, YAxis::Down);
+        snapshot.right_stick = Stick::from_centered(right.x, right.y, YAxis::Down);
+        PredictedSnapshot {
+            snapshot,
+            predicted: left.predicted || right.predicted,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.left.reset();
+        self.right.reset();
+        self.latest = Snapshot::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ButtonId;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    /// Moving right at 1 unit per second, sampled every 10 ms.
+    fn moving(lead: Duration, start: Instant) -> StickExtrapolator {
+        let mut stick = StickExtrapolator::new(lead).smoothing(1.0);
+        for i in 0..5 {
+            stick.observe(i as f32 * 0.01, 0.0, start + Duration::from_millis(10 * i));
+        }
+        stick
+    }
+
+    #[test]
+    fn nothing_observed_predicts_the_center() {
+        let stick = StickExtrapolator::new(Duration::from_millis(50));
+        let sample = stick.predict(Instant::now());
+        assert_eq!((sample.x, sample.y, sample.predicted), (0.0, 0.0, true));
+    }
+
+    #[test]
+    fn carries_the_velocity_forward_by_the_lead() {
+        let start = Instant::now();
+        let stick = moving(Duration::from_millis(50), start);
+        assert!(close(stick.velocity().0, 1.0));
+        let sample = stick.predict(start + Duration::from_millis(40));
+        assert!(sample.predicted);
+        assert!(close(sample.x, 0.09), "{}", sample.x);
+    }
+
+    #[test]
+    fn without_lead_the_newest_sample_is_real() {
+        let start = Instant::now();
+        let stick = moving(Duration::ZERO, start);
+        let sample = stick.predict(start + Duration::from_millis(40));
+        assert!(!sample.predicted);
+        assert!(close(sample.x, 0.04));
+    }
+
+    #[test]
+    fn predictions_stop_at_the_horizon() {
+        let start = Instant::now();
+        let stick = moving(Duration::ZERO, start).horizon(Duration::from_millis(100));
+        let sample = stick.predict(start + Duration::from_secs(10));
+        assert!(close(sample.x, 0.14), "{}", sample.x);
+    }
+
+    #[test]
+    fn late_samples_are_ignored() {
+        let start = Instant::now();
+        let mut stick = moving(Duration::ZERO, start);
+        stick.observe(-1.0, 0.0, start);
+        let sample = stick.predict(start + Duration::from_millis(40));
+        assert!(close(sample.x, 0.04));
+    }
+
+    #[test]
+    fn buttons_pass_through_unpredicted() {
+        let start = Instant::now();
+        let mut extrapolator = Extrapolator::new(Duration::ZERO);
+        let mut snapshot = Snapshot::default();
+        snapshot.set_pressed(ButtonId::R1, true);
+        snapshot.left_stick = Stick::new(200, 60);
+        extrapolator.observe(&snapshot, start);
+        let predicted = extrapolator.predict(start);
+        assert!(!predicted.predicted);
+        assert_eq!(predicted.snapshot, snapshot);
+        extrapolator.reset();
+        assert!(extrapolator.predict(start).predicted);
+    }
+}
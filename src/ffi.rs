@@ -0,0 +1,414 @@
+use crate::connection::ReportLayout;
+use crate::output::OUTPUT_REPORT_MAX_LEN;
+use crate::report::{BasicParser, BluetoothParser, InputState, UsbParser};
+use crate::touch::Touch;
+use crate::{Error, OutputReportBuilder};
+use std::cell::RefCell;
+use std::ffi::{c_char, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+/// Bumped whenever a struct layout or function signature changes.
+pub const DS4_ABI_VERSION: u32 = 1;
+/// Room for an output report in either layout. Spelled out so it reaches the
+/// header as a number.
+pub const DS4_OUTPUT_REPORT_MAX_LEN: usize = 78;
+const _: () = assert!(DS4_OUTPUT_REPORT_MAX_LEN == OUTPUT_REPORT_MAX_LEN);
+
+pub const DS4_OK: i32 = 0;
+pub const DS4_ERR_NOT_FOUND: i32 = -1;
+pub const DS4_ERR_PERMISSION_DENIED: i32 = -2;
+pub const DS4_ERR_DEVICE_BUSY: i32 = -3;
+/// The pad went away or was closed; close the handle and open it again.
+pub const DS4_ERR_DISCONNECTED: i32 = -4;
+pub const DS4_ERR_MALFORMED_REPORT: i32 = -5;
+pub const DS4_ERR_INVALID_ARGUMENT: i32 = -6;
+pub const DS4_ERR_BUFFER_TOO_SMALL: i32 = -7;
+/// A bug in the library; the message says where.
+pub const DS4_ERR_PANIC: i32 = -8;
+pub const DS4_ERR_OTHER: i32 = -9;
+
+pub const DS4_BUTTON_TRIANGLE: u32 = 1 << 0;
+pub const DS4_BUTTON_CIRCLE: u32 = 1 << 1;
+pub const DS4_BUTTON_CROSS: u32 = 1 << 2;
+pub const DS4_BUTTON_SQUARE: u32 = 1 << 3;
+pub const DS4_BUTTON_R3: u32 = 1 << 4;
+pub const DS4_BUTTON_L3: u32 = 1 << 5;
+pub const DS4_BUTTON_OPTIONS: u32 = 1 << 6;
+pub const DS4_BUTTON_SHARE: u32 = 1 << 7;
+pub const DS4_BUTTON_R2: u32 = 1 << 8;
+pub const DS4_BUTTON_L2: u32 = 1 << 9;
+pub const DS4_BUTTON_R1: u32 = 1 << 10;
+pub const DS4_BUTTON_L1: u32 = 1 << 11;
+pub const DS4_BUTTON_TOUCHPAD: u32 = 1 << 12;
+pub const DS4_BUTTON_PS: u32 = 1 << 13;
+pub const DS4_DPAD_UP: u32 = 1 << 16;
+pub const DS4_DPAD_DOWN: u32 = 1 << 17;
+pub const DS4_DPAD_LEFT: u32 = 1 << 18;
+pub const DS4_DPAD_RIGHT: u32 = 1 << 19;
+
+/// One finger on the touchpad.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Ds4Touch {
+    pub active: bool,
+    /// Incremented by the pad for each new contact (7 bits).
+    pub id: u8,
+    pub x: u16,
+    pub y: u16,
+}
+
+/// Every control's state at one report.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Ds4State {
+    /// `DS4_BUTTON_*` and `DS4_DPAD_*` bits.
+    pub buttons: u32,
+    /// Raw stick axes, 0 left or up to 255 right or down.
+    pub left_x: u8,
+    pub left_y: u8,
+    pub right_x: u8,
+    pub right_y: u8,
+    pub l2: u8,
+    pub r2: u8,
+    /// Raw IMU readings.
+    pub gyro: [i16; 3],
+    pub accel: [i16; 3],
+    pub touches: [Ds4Touch; 2],
+    /// 0-100, or 255 if unknown. Always unknown from `ds4_parse_report`.
+    pub battery_percent: u8,
+    pub charging: bool,
+    /// The pad's 6-bit report counter.
+    pub counter: u8,
+}
+
+impl Ds4State {
+    fn from_input(input: &InputState) -> Self {
+        let touches = input
+            .touch
+            .latest()
+            .map_or([Touch::default(); 2], |p| p.fingers);
+        Ds4State {
+            buttons: input.buttons.bits(),
+            left_x: input.left_stick.x,
+            left_y: input.left_stick.y,
+            right_x: input.right_stick.x,
+            right_y: input.right_stick.y,
+            l2: input.l2,
+            r2: input.r2,
+            gyro: input.imu.gyro,
+            accel: input.imu.accel,
+            touches: touches.map(Ds4Touch::from),
+            battery_percent: u8::MAX,
+            charging: false,
+            counter: input.counter,
+        }
+    }
+}
+
+impl From<Touch> for Ds4Touch {
+    fn from(t: Touch) -> Self {
+        Ds4Touch {
+            active: t.active,
+            id: t.id,
+            x: t.x,
+            y: t.y,
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+fn set_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = message);
+}
+
+fn code(e: &Error) -> i32 {
+    match e {
+        Error::NotFound => DS4_ERR_NOT_FOUND,
+        Error::PermissionDenied => DS4_ERR_PERMISSION_DENIED,
+        Error::DeviceBusy => DS4_ERR_DEVICE_BUSY,
+        Error::Closed => DS4_ERR_DISCONNECTED,
+        e if e.is_device_error() => DS4_ERR_DISCONNECTED,
+        Error::ShortReport { .. } | Error::MalformedReport(_) | Error::InvalidDPad(_) => {
+            DS4_ERR_MALFORMED_REPORT
+        }
+        _ => DS4_ERR_OTHER,
+    }
+}
+
+/// Runs `f`, turning errors and panics into codes with a message for
+/// `ds4_last_error`, since neither may unwind into C.
+fn guard(f: impl FnOnce() -> Result<i32, (i32, String)>) -> i32 {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(n)) => n,
+        Ok(Err((code, message))) => {
+            set_error(message);
+            code
+        }
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            set_error(format!("panic: {}", message));
+            DS4_ERR_PANIC
+        }
+    }
+}
+
+fn failed(e: Error) -> (i32, String) {
+    (code(&e), e.to_string())
+}
+
+fn invalid(what: &str) -> (i32, String) {
+    (DS4_ERR_INVALID_ARGUMENT, what.to_string())
+}
+
+#[no_mangle]
+pub extern "C" fn ds4_abi_version() -> u32 {
+    DS4_ABI_VERSION
+}
+
+/// Describes the last error returned on this thread. The string stays valid
+/// until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn ds4_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ptr())
+}
+
+/// Decodes a raw input report as read from the device, report id first: the
+/// 64-byte USB report, the 78-byte Bluetooth report 0x11, or the short
+/// Bluetooth report. Returns `DS4_OK` or an error code.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `out` to a writable `Ds4State`.
+#[no_mangle]
+pub unsafe extern "C" fn ds4_parse_report(data: *const u8, len: usize, out: *mut Ds4State) -> i32 {
+    guard(|| {
+        if data.is_null() || out.is_null() {
+            return Err(invalid("null pointer"));
+        }
+        let report = slice::from_raw_parts(data, len);
+        let parsed = match (report.first(), len) {
+            (Some(0x11), BluetoothParser::LEN) => report.try_into().map(BluetoothParser::parse),
+            (Some(0x01), UsbParser::LEN) => report.try_into().map(UsbParser::parse),
+            (Some(0x01), n) if n >= BasicParser::LEN => report[..BasicParser::LEN]
+                .try_into()
+                .map(BasicParser::parse),
+            _ => {
+                return Err(failed(Error::MalformedReport(
+                    "unknown report id or length",
+                )))
+            }
+        };
+        let state = parsed
+            .map_err(|_| invalid("report length"))?
+            .map_err(|e| failed(e.into()))?;
+        out.write(Ds4State::from_input(&state));
+        Ok(DS4_OK)
+    })
+}
+
+/// Encodes an output report setting rumble and lightbar together, for the
+/// Bluetooth layout (with its checksum) if `bluetooth` is set and USB
+/// otherwise, ready to write to the device. Returns its length or an error code.
+///
+/// # Safety
+///
+/// `buf` must point to `cap` writable bytes; `DS4_OUTPUT_REPORT_MAX_LEN` is
+/// always enough.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn ds4_encode_output(
+    bluetooth: bool,
+    strong: u8,
+    weak: u8,
+    r: u8,
+    g: u8,
+    b: u8,
+    buf: *mut u8,
+    cap: usize,
+) -> isize {
+    guard(|| {
+        if buf.is_null() {
+            return Err(invalid("null pointer"));
+        }
+        let report = OutputReportBuilder::new()
+            .rumble(strong, weak)
+            .lightbar(r, g, b)
+            .report();
+        let layout = if bluetooth {
+            ReportLayout::Bluetooth
+        } else {
+            ReportLayout::Usb
+        };
+        let mut scratch = [0u8; OUTPUT_REPORT_MAX_LEN];
+        let bytes = report.encode(layout, &mut scratch);
+        if bytes.len() > cap {
+            return Err((DS4_ERR_BUFFER_TOO_SMALL, "buffer too small".to_string()));
+        }
+        ptr::copy_nonoverlapping(bytes.as_ptr(), buf, bytes.len());
+        Ok(bytes.len() as i32)
+    }) as isize
+}
+
+#[cfg(feature = "hid")]
+mod device {
+    use super::*;
+    use crate::{ButtonFlags, ButtonId, Controller, Snapshot};
+    use hidapi::HidApi;
+    use std::sync::Mutex;
+
+    fn button_bits(snapshot: &Snapshot) -> u32 {
+        let buttons = ButtonId::ALL
+            .into_iter()
+            .filter(|&id| snapshot.pressed(id))
+            .fold(ButtonFlags::from_dpad(snapshot.dpad), |flags, id| {
+                flags | ButtonFlags::from(id)
+            });
+        buttons.bits()
+    }
+
+    /// hidapi allows one context per process, so every handle is opened from
+    /// this one, which is never dropped.
+    static API: Mutex<Option<HidApi>> = Mutex::new(None);
+
+    /// An open controller, owned by the caller until `ds4_close`.
+    pub struct Ds4Controller {
+        controller: Controller,
+    }
+
+    /// Opens the first connected DS4 with its saved profile applied, or returns
+    /// null with the reason in `ds4_last_error`.
+    #[no_mangle]
+    pub extern "C" fn ds4_open() -> *mut Ds4Controller {
+        let mut handle = ptr::null_mut();
+        guard(|| {
+            let mut shared = API.lock().unwrap_or_else(|e| e.into_inner());
+            let api = match shared.as_mut() {
+                Some(api) => {
+                    api.refresh_devices().map_err(|e| failed(e.into()))?;
+                    api
+                }
+                None => shared.insert(HidApi::new().map_err(|e| failed(e.into()))?),
+            };
+            let controller = Controller::open(api).map_err(failed)?;
+            handle = Box::into_raw(Box::new(Ds4Controller { controller }));
+            Ok(DS4_OK)
+        });
+        handle
+    }
+
+    /// Sends any held-back output and frees the handle. Null is ignored.
+    ///
+    /// # Safety
+    ///
+    /// `handle` must come from `ds4_open` and not be used again.
+    #[no_mangle]
+    pub unsafe extern "C" fn ds4_close(handle: *mut Ds4Controller) {
+        if handle.is_null() {
+            return;
+        }
+        guard(|| {
+            let mut handle = Box::from_raw(handle);
+            let _ = handle.controller.close();
+            Ok(DS4_OK)
+        });
+    }
+
+    unsafe fn controller<'a>(
+        handle: *mut Ds4Controller,
+    ) -> Result<&'a mut Controller, (i32, String)> {
+        handle
+            .as_mut()
+            .map(|h| &mut h.controller)
+            .ok_or_else(|| invalid("null handle"))
+    }
+
+    /// Waits for the next input report and fills `out` with the new state.
+    /// Returns `DS4_OK`, or an error code; after `DS4_ERR_DISCONNECTED` only
+    /// `ds4_close` is useful.
+    ///
+    /// # Safety
+    ///
+    /// `handle` must come from `ds4_open` and `out` point to a writable
+    /// `Ds4State`. A handle may move between threads but not be used from two
+    /// at once.
+    #[no_mangle]
+    pub unsafe extern "C" fn ds4_poll(handle: *mut Ds4Controller, out: *mut Ds4State) -> i32 {
+        guard(|| {
+            let controller = controller(handle)?;
+            if out.is_null() {
+                return Err(invalid("null pointer"));
+            }
+            controller.update().map_err(failed)?;
+            let snapshot = controller.controls.snapshot();
+            let battery = controller.battery();
+            out.write(Ds4State {
+                buttons: button_bits(&snapshot),
+                left_x: snapshot.left_stick.x,
+                left_y: snapshot.left_stick.y,
+                right_x: snapshot.right_stick.x,
+                right_y: snapshot.right_stick.y,
+                l2: snapshot.l2_value,
+                r2: snapshot.r2_value,
+                gyro: controller.controls.imu.gyro,
+                accel: controller.controls.imu.accel,
+                touches: controller.controls.touches.state().map(Ds4Touch::from),
+                battery_percent: battery
+                    .percent()
+                    .map_or(u8::MAX, |p| p.round().clamp(0.0, 100.0) as u8),
+                charging: battery.cable_connected(),
+                counter: controller.last_counter().unwrap_or(0),
+            });
+            Ok(DS4_OK)
+        })
+    }
+
+    /// Sets the strong (left) and weak (right) motor speeds.
+    ///
+    /// # Safety
+    ///
+    /// `handle` must come from `ds4_open`.
+    #[no_mangle]
+    pub unsafe extern "C" fn ds4_set_rumble(
+        handle: *mut Ds4Controller,
+        strong: u8,
+        weak: u8,
+    ) -> i32 {
+        guard(|| {
+            controller(handle)?
+                .set_rumble(strong, weak)
+                .map_err(failed)?;
+            Ok(DS4_OK)
+        })
+    }
+
+    /// Sets the lightbar color.
+    ///
+    /// # Safety
+    ///
+    /// `handle` must come from `ds4_open`.
+    #[no_mangle]
+    pub unsafe extern "C" fn ds4_set_lightbar(
+        handle: *mut Ds4Controller,
+        r: u8,
+        g: u8,
+        b: u8,
+    ) -> i32 {
+        guard(|| {
+            controller(handle)?.set_lightbar(r, g, b).map_err(failed)?;
+            Ok(DS4_OK)
+        })
+    }
+}
+
+#[cfg(feature = "hid")]
+pub use device::*;
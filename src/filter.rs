@@ -0,0 +1,160 @@
+use crate::Stick;
+use std::f32::consts::TAU;
+
+/// Smooths one signal sample by sample. `dt` is the time since the previous
+/// sample in seconds, e.g. from the device clock.
+///
+/// Controls already carry calibrated values, so filters sit on the consumer side:
+/// each consumer keeps its own and tunes it for its use.
+pub trait Filter {
+    fn filter(&mut self, value: f32, dt: f32) -> f32;
+
+    /// Forgets past samples, e.g. after a reconnect.
+    fn reset(&mut self);
+}
+
+/// Exponential moving average; `alpha` in `0.0..=1.0` is the weight of each new
+/// sample, so lower is smoother but laggier.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ema {
+    pub alpha: f32,
+    state: Option<f32>,
+}
+
+impl Ema {
+    pub fn new(alpha: f32) -> Self {
+        Ema {
+            alpha: alpha.clamp(0.0, 1.0),
+            state: None,
+        }
+    }
+}
+
+impl Filter for Ema {
+    fn filter(&mut self, value: f32, _dt: f32) -> f32 {
+        let next = match self.state {
+            Some(prev) => prev + self.alpha * (value - prev),
+            None => value,
+        };
+        self.state = Some(next);
+        next
+    }
+
+    fn reset(&mut self) {
+        self.state = None;
+    }
+}
+
+/// The One-Euro filter (Casiez et al. 2012): heavy smoothing while the signal is
+/// still, opening up as it moves so fast motion doesn't lag.
+///
+/// Tune `min_cutoff` (Hz) down until jitter at rest is gone, then `beta` up until
+/// fast motion stops lagging.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OneEuro {
+    pub min_cutoff: f32,
+    pub beta: f32,
+    /// Cutoff for the derivative estimate, in Hz.
+    pub d_cutoff: f32,
+    state: Option<(f32, f32)>,
+}
+
+impl OneEuro {
+    pub fn new(min_cutoff: f32, beta: f32) -> Self {
+        OneEuro {
+            min_cutoff,
+            beta,
+            d_cutoff: 1.0,
+            state: None,
+        }
+    }
+}
+
+impl Default for OneEuro {
+    fn default() -> Self {
+        OneEuro::new(1.0, 0.01)
+    }
+}
+
+fn smoothing(cutoff: f32, dt: f32) -> f32 {
+    let tau = 1.0 / (TAU * cutoff);
+    1.0 / (1.0 + tau / dt)
+}
+
+impl Filter for OneEuro {
+    fn filter(&mut self, value: f32, dt: f32) -> f32 {
+        let Some((prev, prev_dx)) = self.state.filter(|_| dt > 0.0) else {
+            self.state = Some((value, 0.0));
+            return value;
+        };
+        let dx = prev_dx + smoothing(self.d_cutoff, dt) * ((value - prev) / dt - prev_dx);
+        let cutoff = self.min_cutoff + self.beta * dx.abs();
+        let next = prev + smoothing(cutoff, dt) * (value - prev);
+        self.state = Some((next, dx));
+        next
+    }
+
+    fn reset(&mut self) {
+        self.state = None;
+    }
+}
+
+/// A filter per stick axis, producing values in `-1.0..=1.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StickFilter<F> {
+    pub x: F,
+    pub y: F,
+}
+
+impl<F: Filter + Clone> StickFilter<F> {
+    pub fn new(filter: F) -> Self {
+        StickFilter {
+            x: filter.clone(),
+            y: filter,
+        }
+    }
+}
+
+impl<F: Filter> StickFilter<F> {
+    pub fn filter(&mut self, stick: Stick, dt: f32) -> (f32, f32) {
+        (
+            self.x.filter(stick.x_f32(), dt),
+            self.y.filter(stick.y_f32(), dt),
+        )
+    }
+
+    pub fn reset(&mut self) {
+        self.x.reset();
+        self.y.reset();
+    }
+}
+
+/// A filter per gyro or accelerometer axis.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AxesFilter<F> {
+    pub axes: [F; 3],
+}
+
+impl<F: Filter + Clone> AxesFilter<F> {
+    pub fn new(filter: F) -> Self {
+        AxesFilter {
+            axes: [filter.clone(), filter.clone(), filter],
+        }
+    }
+}
+
+impl<F: Filter> AxesFilter<F> {
+    /// Filters raw readings such as `Imu::gyro`.
+    pub fn filter(&mut self, raw: [i16; 3], dt: f32) -> [f32; 3] {
+        let [x, y, z] = &mut self.axes;
+        [
+            x.filter(raw[0] as f32, dt),
+            y.filter(raw[1] as f32, dt),
+            z.filter(raw[2] as f32, dt),
+        ]
+    }
+
+    pub fn reset(&mut self) {
+        self.axes.iter_mut().for_each(Filter::reset);
+    }
+}
@@ -0,0 +1,83 @@
+use crate::PRODUCT_ID;
+
+pub const FIRMWARE_REPORT_ID: u8 = 0xa3;
+pub const FIRMWARE_REPORT_LEN: usize = 49;
+
+/// The version feature report, which genuine pads answer with their build date
+/// and hardware and firmware versions.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Firmware {
+    /// E.g. `"Sep 21 2018 04:50:51"`.
+    pub build_date: String,
+    pub hardware_version: u16,
+    pub firmware_version: u16,
+}
+
+impl Firmware {
+    /// `None` unless the report carries a readable build date; clones tend to
+    /// answer with zeros or garbage.
+    pub fn from_report(report: &[u8]) -> Option<Self> {
+        if report.len() < FIRMWARE_REPORT_LEN || report[0] != FIRMWARE_REPORT_ID {
+            return None;
+        }
+        let date = text(&report[1..17])?;
+        let time = text(&report[17..33])?;
+        Some(Firmware {
+            build_date: format!("{} {}", date, time),
+            hardware_version: u16::from_le_bytes([report[35], report[36]]),
+            firmware_version: u16::from_le_bytes([report[41], report[42]]),
+        })
+    }
+}
+
+/// A NUL-padded printable ASCII field, or `None` if empty or not text.
+fn text(field: &[u8]) -> Option<&str> {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    let s = std::str::from_utf8(&field[..end]).ok()?;
+    (!s.is_empty() && s.bytes().all(|b| b.is_ascii_graphic() || b == b' ')).then_some(s)
+}
+
+/// What the connected pad can be relied on to do. Features a pad lacks are
+/// skipped quietly rather than sent and ignored or misread.
+///
+/// Until the version report has been read every capability is assumed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Capabilities {
+    /// Lightbar colors and flashing do something. When false they are dropped
+    /// from output writes.
+    pub has_lightbar: bool,
+    /// The IMU reports real motion against genuine calibration. When false gyro
+    /// auto-zeroing is turned off, since it would chase noise.
+    pub imu_trustworthy: bool,
+    /// The pad honours a 1 ms Bluetooth report interval, which in practice means a
+    /// genuine second-revision pad. When false `set_report_rate` stops at 500 Hz.
+    pub bt_1khz: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities::FULL
+    }
+}
+
+impl Capabilities {
+    pub const FULL: Capabilities = Capabilities {
+        has_lightbar: true,
+        imu_trustworthy: true,
+        bt_1khz: true,
+    };
+
+    /// Guesses from the product id and the pad's answer to the version report;
+    /// `firmware` is `None` if the answer didn't parse, which only clones do.
+    ///
+    /// A failed or timed-out read says nothing about the pad, so don't call this
+    /// for one; keep the current capabilities and read again later.
+    pub fn detect(product_id: u16, firmware: Option<&Firmware>) -> Self {
+        let genuine = firmware.is_some();
+        Capabilities {
+            has_lightbar: genuine,
+            imu_trustworthy: genuine,
+            bt_1khz: genuine && product_id == PRODUCT_ID,
+        }
+    }
+}
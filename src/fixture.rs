@@ -0,0 +1,303 @@
+use crate::proxy::{CaptureReader, ReportKind};
+use crate::transport::MockTransport;
+use crate::{ButtonId, Connection, ConnectionInfo, Controller, DPad, Error, Result, Snapshot};
+use crate::{PRODUCT_ID, VENDOR_ID};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// A stick counts as reaching its edge within this of either end.
+const STICK_EDGE: u8 = 24;
+/// A trigger counts as fully pulled at or above this.
+const TRIGGER_FULL: u8 = 250;
+const DIRECTIONS: [DPad; 4] = [DPad::North, DPad::East, DPad::South, DPad::West];
+
+/// Something to do with the pad while a fixture is recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scenario {
+    /// Hands off, for resting stick centers and sensor noise.
+    Rest,
+    /// Every button and dpad direction pressed once.
+    Buttons,
+    /// Both sticks rolled around their gates.
+    Sticks,
+    /// Both triggers pulled all the way and released.
+    Triggers,
+}
+
+impl Scenario {
+    pub const ALL: [Scenario; 4] = [
+        Scenario::Rest,
+        Scenario::Buttons,
+        Scenario::Sticks,
+        Scenario::Triggers,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Scenario::Rest => "rest",
+            Scenario::Buttons => "buttons",
+            Scenario::Sticks => "sticks",
+            Scenario::Triggers => "triggers",
+        }
+    }
+
+    /// What to ask whoever is holding the pad to do.
+    pub fn prompt(self) -> &'static str {
+        match self {
+            Scenario::Rest => "Put the pad down and leave it alone.",
+            Scenario::Buttons => "Press each button and dpad direction once, PS last.",
+            Scenario::Sticks => "Roll both sticks slowly around the edge of their gates.",
+            Scenario::Triggers => "Pull both triggers all the way, then let go.",
+        }
+    }
+
+    /// Longest a recording runs. `Rest` always runs this long; the others stop
+    /// once `is_complete`.
+    pub fn duration(self) -> Duration {
+        match self {
+            Scenario::Rest => Duration::from_secs(2),
+            _ => Duration::from_secs(30),
+        }
+    }
+
+    /// Whether `summary` shows the scenario was carried out.
+    pub fn is_complete(self, summary: &Summary) -> bool {
+        let edges = |[min_x, max_x, min_y, max_y]: [u8; 4]| {
+            min_x <= STICK_EDGE
+                && min_y <= STICK_EDGE
+                && max_x >= u8::MAX - STICK_EDGE
+                && max_y >= u8::MAX - STICK_EDGE
+        };
+        match self {
+            Scenario::Rest => false,
+            Scenario::Buttons => {
+                ButtonId::ALL.iter().all(|b| summary.buttons.contains(b))
+                    && DIRECTIONS.iter().all(|d| summary.dpad.contains(d))
+            }
+            Scenario::Sticks => edges(summary.left_stick) && edges(summary.right_stick),
+            Scenario::Triggers => summary.l2_max >= TRIGGER_FULL && summary.r2_max >= TRIGGER_FULL,
+        }
+    }
+}
+
+impl FromStr for Scenario {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Scenario::ALL
+            .into_iter()
+            .find(|s2| s2.name().eq_ignore_ascii_case(s))
+            .ok_or(Error::InvalidFormat("unknown scenario"))
+    }
+}
+
+/// What a recording contains, compared between a fixture's capture and the
+/// expectations generated from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Summary {
+    /// Full input reports decoded.
+    pub reports: usize,
+    /// Each button in the order it was first pressed.
+    pub buttons: Vec<ButtonId>,
+    /// Each dpad direction in the order it was first pressed, diagonals included.
+    pub dpad: Vec<DPad>,
+    /// `[min x, max x, min y, max y]` over the recording.
+    pub left_stick: [u8; 4],
+    pub right_stick: [u8; 4],
+    pub l2_max: u8,
+    pub r2_max: u8,
+}
+
+impl Default for Summary {
+    fn default() -> Self {
+        Summary {
+            reports: 0,
+            buttons: Vec::new(),
+            dpad: Vec::new(),
+            left_stick: [u8::MAX, 0, u8::MAX, 0],
+            right_stick: [u8::MAX, 0, u8::MAX, 0],
+            l2_max: 0,
+            r2_max: 0,
+        }
+    }
+}
+
+impl Summary {
+    pub fn new() -> Self {
+        Summary::default()
+    }
+
+    pub fn record(&mut self, snapshot: &Snapshot) {
+        self.reports += 1;
+        for id in ButtonId::ALL {
+            if snapshot.pressed(id) && !self.buttons.contains(&id) {
+                self.buttons.push(id);
+            }
+        }
+        if snapshot.dpad != DPad::Released && !self.dpad.contains(&snapshot.dpad) {
+            self.dpad.push(snapshot.dpad);
+        }
+        for (range, stick) in [
+            (&mut self.left_stick, snapshot.left_stick),
+            (&mut self.right_stick, snapshot.right_stick),
+        ] {
+            *range = [
+                range[0].min(stick.x),
+                range[1].max(stick.x),
+                range[2].min(stick.y),
+                range[3].max(stick.y),
+            ];
+        }
+        self.l2_max = self.l2_max.max(snapshot.l2_value);
+        self.r2_max = self.r2_max.max(snapshot.r2_value);
+    }
+
+    /// A Rust expression that builds this summary, with `ButtonId`, `DPad` and
+    /// `Summary` in scope.
+    pub fn to_rust(&self) -> String {
+        let list = |items: Vec<String>| items.join(", ");
+        format!(
+            "Summary {{\n            reports: {},\n            buttons: vec![{}],\n            \
+             dpad: vec![{}],\n            left_stick: {:?},\n            right_stick: {:?},\n            \
+             l2_max: {},\n            r2_max: {},\n        }}",
+            self.reports,
+            list(self.buttons.iter().map(|b| format!("ButtonId::{:?}", b)).collect()),
+            list(self.dpad.iter().map(|d| format!("DPad::{:?}", d)).collect()),
+            self.left_stick,
+            self.right_stick,
+            self.l2_max,
+            self.r2_max,
+        )
+    }
+}
+
+/// A capture recorded with `ds4-capture` and what it's expected to decode to,
+/// as built by the module `ds4-capture codegen` writes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fixture {
+    pub name: &'static str,
+    /// A capture in the format `proxy::CaptureWriter` writes.
+    pub capture: &'static [u8],
+    pub expected: Summary,
+}
+
+impl Fixture {
+    /// Decodes the capture as it would be now; a regression shows up as a
+    /// difference from `expected`.
+    pub fn replay(&self) -> Result<Summary> {
+        replay(self.capture)
+    }
+}
+
+/// Feeds a capture's feature and input reports through a controller on a
+/// `MockTransport`, summarizing the input it decodes. Output reports are
+/// ignored, and so are input reports the controller rejects.
+pub fn replay(capture: &[u8]) -> Result<Summary> {
+    let (transport, handle) = MockTransport::new();
+    let mut inputs = 0;
+    for record in CaptureReader::new(capture)? {
+        let record = record?;
+        match record.kind {
+            ReportKind::Input => {
+                handle.send_report(record.data)?;
+                inputs += 1;
+            }
+            ReportKind::Feature => handle.set_feature_report(record.data)?,
+            ReportKind::Output => {}
+        }
+    }
+    let info = ConnectionInfo {
+        connection: Connection::Usb,
+        vendor_id: VENDOR_ID,
+        product_id: PRODUCT_ID,
+        path: None,
+        serial: None,
+        interface: None,
+    };
+    let mut controller = Controller::with_transport(transport, info);
+    let mut summary = Summary::new();
+    for _ in 0..inputs {
+        let before = controller.sampled_at();
+        match controller.update() {
+            Ok(()) if controller.sampled_at() != before => {
+                summary.record(&controller.controls.snapshot())
+            }
+            Ok(()) => {}
+            Err(e) if e.is_device_error() => return Err(e),
+            Err(_) => {}
+        }
+    }
+    Ok(summary)
+}
+
+/// Rust source for a module of fixtures, one function per `.ds4cap` file in
+/// `dir` with its current replay as the expectation, plus a test for each.
+/// Write it to `mod.rs` in `dir`, e.g. `tests/fixtures`, and declare it from an
+/// integration test with `mod fixtures;`.
+pub fn codegen(dir: &Path) -> Result<String> {
+    let mut paths: Vec<_> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "ds4cap"))
+        .collect();
+    paths.sort();
+
+    let mut out = String::from(
+        "// Generated by `ds4-capture codegen` from the captures beside this file.\n\
+         // Rerun it after recording rather than editing by hand.\n\
+         #![allow(dead_code, unused_imports)]\n\n\
+         use ps4hid::fixture::{Fixture, Summary};\n\
+         use ps4hid::{ButtonId, DPad};\n",
+    );
+    let mut names = Vec::new();
+    for path in &paths {
+        let (Some(stem), Some(file)) = (
+            path.file_stem().and_then(|s| s.to_str()),
+            path.file_name().and_then(|s| s.to_str()),
+        ) else {
+            continue;
+        };
+        let name: String = stem
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(Error::InvalidFormat(
+                "fixture names must start with a letter",
+            ));
+        }
+        let summary = replay(&fs::read(path)?)?;
+        let _ = write!(
+            out,
+            "\npub fn {name}() -> Fixture {{\n    Fixture {{\n        name: {stem:?},\n        \
+             capture: include_bytes!({file:?}),\n        expected: {},\n    }}\n}}\n",
+            summary.to_rust()
+        );
+        names.push(name);
+    }
+    let _ = write!(
+        out,
+        "\npub fn all() -> Vec<Fixture> {{\n    vec![{}]\n}}\n",
+        names
+            .iter()
+            .map(|n| format!("{}()", n))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    for name in &names {
+        let _ = write!(
+            out,
+            "\n#[test]\nfn replays_{name}() {{\n    let fixture = {name}();\n    \
+             assert_eq!(fixture.replay().unwrap(), fixture.expected);\n}}\n"
+        );
+    }
+    Ok(out)
+}
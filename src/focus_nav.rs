@@ -0,0 +1,186 @@
+use crate::{ButtonId, DPad, Snapshot};
+use std::time::{Duration, Instant};
+
+/// Stick deflection that counts as a direction; it must fall back below half
+/// this to release it.
+const STICK_THRESHOLD: f32 = 0.6;
+const REPEAT_DELAY: Duration = Duration::from_millis(400);
+const REPEAT_INTERVAL: Duration = Duration::from_millis(110);
+
+/// A focus-navigation step for a UI toolkit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NavCommand {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Tab order, for toolkits like iced that only have next and previous.
+    Next,
+    Previous,
+    /// Activate the focused widget.
+    Confirm,
+    /// Close a popup or leave the focused widget.
+    Cancel,
+}
+
+/// Turns controller input into `NavCommand`s: the dpad or left stick moves,
+/// repeating while held, R1 and L1 step through tab order, and `confirm` and
+/// `cancel` do what they say.
+///
+/// Feed it a snapshot every frame with `update`; with the `egui` feature,
+/// `feed_egui` does that and hands the result to egui's own focus handling.
+#[derive(Debug, Clone)]
+pub struct FocusNav {
+    pub confirm: ButtonId,
+    pub cancel: ButtonId,
+    pub repeat_delay: Duration,
+    pub repeat_interval: Duration,
+    last: Snapshot,
+    /// The direction held and when it next repeats.
+    held: Option<(NavCommand, Instant)>,
+    stick_held: bool,
+}
+
+impl Default for FocusNav {
+    fn default() -> Self {
+        FocusNav {
+            confirm: ButtonId::X,
+            cancel: ButtonId::Circle,
+            repeat_delay: REPEAT_DELAY,
+            repeat_interval: REPEAT_INTERVAL,
+            last: Snapshot::default(),
+            held: None,
+            stick_held: false,
+        }
+    }
+}
+
+impl FocusNav {
+    pub fn new() -> Self {
+        FocusNav::default()
+    }
+
+    /// Circle confirms and Cross cancels, as on Japanese consoles.
+    pub fn swapped(mut self) -> Self {
+        std::mem::swap(&mut self.confirm, &mut self.cancel);
+        self
+    }
+
+    /// The commands for `snapshot` as of `at`, in the order they should be
+    /// applied.
+    pub fn update(&mut self, snapshot: &Snapshot, at: Instant) -> Vec<NavCommand> {
+        let mut commands = Vec::new();
+        let last = self.last;
+        let pressed = |id| snapshot.pressed(id) && !last.pressed(id);
+        for (id, command) in [
+            (ButtonId::L1, NavCommand::Previous),
+            (ButtonId::R1, NavCommand::Next),
+            (self.cancel, NavCommand::Cancel),
+        ] {
+            if pressed(id) {
+                commands.push(command);
+            }
+        }
+
+        match self.direction(snapshot) {
+            Some(direction) => match self.held {
+                Some((held, next)) if held == direction => {
+                    if at >= next {
+                        commands.push(direction);
+                        self.held = Some((direction, at + self.repeat_interval));
+                    }
+                }
+                _ => {
+                    commands.push(direction);
+                    self.held = Some((direction, at + self.repeat_delay));
+                }
+            },
+            None => self.held = None,
+        }
+
+        // Last, so moving and confirming in the same frame activates the
+        // widget moved to.
+        if pressed(self.confirm) {
+            commands.push(NavCommand::Confirm);
+        }
+        self.last = *snapshot;
+        commands
+    }
+
+    /// The direction the dpad, or failing that the left stick, is held in.
+    /// Diagonals count as their vertical half, as lists are the usual layout.
+    fn direction(&mut self, snapshot: &Snapshot) -> Option<NavCommand> {
+        match snapshot.dpad {
+            DPad::North | DPad::NorthEast | DPad::NorthWest => return Some(NavCommand::Up),
+            DPad::South | DPad::SouthEast | DPad::SouthWest => return Some(NavCommand::Down),
+            DPad::East => return Some(NavCommand::Right),
+            DPad::West => return Some(NavCommand::Left),
+            DPad::Released => {}
+        }
+        let x = snapshot.left_stick.x_f32();
+        let y = snapshot.left_stick.y_f32();
+        let threshold = if self.stick_held {
+            STICK_THRESHOLD / 2.0
+        } else {
+            STICK_THRESHOLD
+        };
+        self.stick_held = x.abs().max(y.abs()) > threshold;
+        if !self.stick_held {
+            return None;
+        }
+        Some(match (x.abs() > y.abs(), x > 0.0, y > 0.0) {
+            (true, true, _) => NavCommand::Right,
+            (true, false, _) => NavCommand::Left,
+            // Raw Y points down.
+            (false, _, true) => NavCommand::Down,
+            (false, _, false) => NavCommand::Up,
+        })
+    }
+}
+
+#[cfg(feature = "egui")]
+mod egui_glue {
+    use super::{FocusNav, NavCommand};
+    use crate::Snapshot;
+    use egui::{Event, Key, Modifiers, RawInput};
+    use std::time::Instant;
+
+    impl NavCommand {
+        /// The key egui already maps to this command, and the modifiers with
+        /// it.
+        pub fn egui_key(self) -> (Key, Modifiers) {
+            match self {
+                NavCommand::Up => (Key::ArrowUp, Modifiers::NONE),
+                NavCommand::Down => (Key::ArrowDown, Modifiers::NONE),
+                NavCommand::Left => (Key::ArrowLeft, Modifiers::NONE),
+                NavCommand::Right => (Key::ArrowRight, Modifiers::NONE),
+                NavCommand::Next => (Key::Tab, Modifiers::NONE),
+                NavCommand::Previous => (Key::Tab, Modifiers::SHIFT),
+                NavCommand::Confirm => (Key::Enter, Modifiers::NONE),
+                NavCommand::Cancel => (Key::Escape, Modifiers::NONE),
+            }
+        }
+
+        /// A press and release of `egui_key`.
+        pub fn egui_events(self) -> [Event; 2] {
+            let (key, modifiers) = self.egui_key();
+            [true, false].map(|pressed| Event::Key {
+                key,
+                physical_key: None,
+                pressed,
+                repeat: false,
+                modifiers,
+            })
+        }
+    }
+
+    impl FocusNav {
+        /// Adds this frame's commands to egui's input, e.g. from eframe's
+        /// `App::raw_input_hook`.
+        pub fn feed_egui(&mut self, raw: &mut RawInput, snapshot: &Snapshot) {
+            for command in self.update(snapshot, Instant::now()) {
+                raw.events.extend(command.egui_events());
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::source::{EventQueue, InputEvent, InputSource};
+use crate::transport::encode_input;
+use crate::{ButtonId, Controls, DPad, Error, Result, Snapshot, Stick, YAxis};
+use wasm_bindgen::JsCast;
+use web_sys::{Gamepad, GamepadButton, GamepadMappingType};
+
+/// Button order of the W3C "standard" gamepad mapping, which browsers use for a DS4.
+const STANDARD_BUTTONS: [(usize, ButtonId); 14] = [
+    (0, ButtonId::X),
+    (1, ButtonId::Circle),
+    (2, ButtonId::Square),
+    (3, ButtonId::Triangle),
+    (4, ButtonId::L1),
+    (5, ButtonId::R1),
+    (6, ButtonId::L2),
+    (7, ButtonId::R2),
+    (8, ButtonId::Share),
+    (9, ButtonId::Options),
+    (10, ButtonId::L3),
+    (11, ButtonId::R3),
+    (16, ButtonId::Ps),
+    (17, ButtonId::Tpad),
+];
+
+/// Reads a pad through the browser Gamepad API, for targets without WebHID.
+///
+/// The Gamepad API only exposes buttons and axes, so there is no touch, IMU,
+/// battery or output; `controls` and the events match the other sources otherwise.
+pub struct GamepadSource {
+    pub controls: Controls,
+    index: Option<u32>,
+    events: EventQueue,
+    counter: u8,
+}
+
+impl GamepadSource {
+    /// Follows the first connected gamepad with the standard mapping.
+    pub fn new() -> Self {
+        GamepadSource::with_index(None)
+    }
+
+    /// Follows the gamepad at `navigator.getGamepads()[index]`.
+    pub fn with_index(index: Option<u32>) -> Self {
+        GamepadSource {
+            controls: Controls::new(),
+            index,
+            events: EventQueue::new(),
+            counter: 0,
+        }
+    }
+
+    /// Polls the browser and updates `controls`. Gamepad state only refreshes between
+    /// animation frames, so polling more often than that gains nothing.
+    pub fn update(&mut self) -> Result<()> {
+        let snapshot = self
+            .gamepad()?
+            .map_or_else(Snapshot::default, |pad| snapshot(&pad));
+        self.counter = (self.counter + 1) & 0x3f;
+        self.controls
+            .update(&encode_input(&snapshot, self.counter, 0))
+    }
+
+    fn gamepad(&self) -> Result<Option<Gamepad>> {
+        let window = web_sys::window().ok_or(Error::NotFound)?;
+        let pads = window
+            .navigator()
+            .get_gamepads()
+            .map_err(|_| Error::NotFound)?;
+        Ok(pads
+            .iter()
+            .filter_map(|pad| pad.dyn_into::<Gamepad>().ok())
+            .filter(|pad| pad.connected() && pad.mapping() == GamepadMappingType::Standard)
+            .find(|pad| self.index.is_none_or(|i| pad.index() == i)))
+    }
+}
+
+impl Default for GamepadSource {
+    fn default() -> Self {
+        GamepadSource::new()
+    }
+}
+
+fn snapshot(pad: &Gamepad) -> Snapshot {
+    let buttons = pad.buttons();
+    let button = |i: usize| buttons.get(i as u32).dyn_into::<GamepadButton>().ok();
+    let pressed = |i: usize| button(i).is_some_and(|b| b.pressed());
+    let axes = pad.axes();
+    let axis = |i: u32| axes.get(i).as_f64().unwrap_or(0.0) as f32;
+    let trigger = |i: usize| (button(i).map_or(0.0, |b| b.value()) * 255.0).round() as u8;
+
+    let mut s = Snapshot::default();
+    for (i, id) in STANDARD_BUTTONS {
+        s.set_pressed(id, pressed(i));
+    }
+    let x = pressed(15) as i8 - pressed(14) as i8;
+    let y = pressed(12) as i8 - pressed(13) as i8;
+    s.dpad = DPad::from_xy(x, y);
+    s.left_stick = Stick::from_centered(axis(0), axis(1), YAxis::Down);
+    s.right_stick = Stick::from_centered(axis(2), axis(3), YAxis::Down);
+    s.l2_value = trigger(6);
+    s.r2_value = trigger(7);
+    s
+}
+
+impl InputSource for GamepadSource {
+    /// Never blocks: returns `None` once the changes since the last poll are drained.
+    fn next_event(&mut self) -> Result<Option<InputEvent>> {
+        if self.events.is_empty() {
+            self.update()?;
+            let snapshot = self.controls.snapshot();
+            self.events.push_snapshot(snapshot);
+        }
+        Ok(self.events.pop())
+    }
+
+    fn state(&self) -> Snapshot {
+        self.events.state()
+    }
+    fn pending(&self) -> usize {
+        self.events.len()
+    }
+}
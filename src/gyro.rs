@@ -0,0 +1,115 @@
+use crate::Imu;
+use std::collections::VecDeque;
+
+const DEFAULT_WINDOW: usize = 250;
+
+/// Re-estimates gyro bias whenever the pad is resting, so aiming doesn't drift as
+/// the bias wanders with temperature.
+///
+/// The pad counts as resting when accelerometer variance over the last `window`
+/// reports stays under `accel_variance` and no gyro axis moves more than
+/// `gyro_range` from its mean; the bias is then the mean gyro reading.
+#[derive(Debug, Clone)]
+pub struct GyroAutoZero {
+    /// Reports per estimate; 250 is about a second over USB.
+    pub window: usize,
+    /// Per-axis accelerometer variance, in raw units squared.
+    pub accel_variance: f32,
+    /// Largest allowed gyro deviation from the window mean, in raw units.
+    pub gyro_range: f32,
+    /// Bias changes smaller than this, in raw units, aren't reported.
+    pub min_change: f32,
+    samples: VecDeque<Imu>,
+    bias: [f32; 3],
+}
+
+impl Default for GyroAutoZero {
+    fn default() -> Self {
+        GyroAutoZero {
+            window: DEFAULT_WINDOW,
+            accel_variance: 400.0,
+            gyro_range: 24.0,
+            min_change: 0.5,
+            samples: VecDeque::with_capacity(DEFAULT_WINDOW),
+            bias: [0.0; 3],
+        }
+    }
+}
+
+impl GyroAutoZero {
+    pub fn new() -> Self {
+        GyroAutoZero::default()
+    }
+
+    pub fn bias(&self) -> [f32; 3] {
+        self.bias
+    }
+
+    /// Starts from a known bias, e.g. a calibrated one, until the next estimate.
+    pub fn set_bias(&mut self, bias: [f32; 3]) {
+        self.bias = bias;
+    }
+
+    /// Raw gyro readings with the current bias removed.
+    pub fn correct(&self, gyro: [i16; 3]) -> [f32; 3] {
+        [0, 1, 2].map(|i| gyro[i] as f32 - self.bias[i])
+    }
+
+    /// Feeds one report's readings, returning the new bias if it was re-estimated
+    /// and moved by at least `min_change`.
+    pub fn observe(&mut self, imu: Imu) -> Option<[f32; 3]> {
+        if self.samples.len() >= self.window.max(2) {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(imu);
+        if self.samples.len() < self.window.max(2) || !self.resting() {
+            return None;
+        }
+
+        let bias = self.mean(|imu| imu.gyro);
+        self.samples.clear();
+        let moved = (0..3).any(|i| (bias[i] - self.bias[i]).abs() >= self.min_change);
+        if moved {
+            self.bias = bias;
+            Some(bias)
+        } else {
+            None
+        }
+    }
+
+    /// Drops the current window, e.g. after the pad reconnects.
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+
+    fn resting(&self) -> bool {
+        let accel = self.mean(|imu| imu.accel);
+        let n = self.samples.len() as f32;
+        let still = (0..3).all(|i| {
+            let var = self
+                .samples
+                .iter()
+                .map(|imu| (imu.accel[i] as f32 - accel[i]).powi(2))
+                .sum::<f32>()
+                / n;
+            var < self.accel_variance
+        });
+        let gyro = self.mean(|imu| imu.gyro);
+        still
+            && self
+                .samples
+                .iter()
+                .all(|imu| (0..3).all(|i| (imu.gyro[i] as f32 - gyro[i]).abs() <= self.gyro_range))
+    }
+
+    fn mean(&self, axes: impl Fn(&Imu) -> [i16; 3]) -> [f32; 3] {
+        let n = self.samples.len().max(1) as f32;
+        self.samples.iter().fold([0.0; 3], |mut sum, imu| {
+            let v = axes(imu);
+            for i in 0..3 {
+                sum[i] += v[i] as f32 / n;
+            }
+            sum
+        })
+    }
+}
@@ -0,0 +1,229 @@
+use crate::touch::{TOUCHPAD_HEIGHT, TOUCHPAD_WIDTH};
+use crate::{Controls, Result, Stick};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// Largest stored deflate block.
+const STORED_BLOCK: usize = 65535;
+
+/// A grid of counts over a rectangle, row 0 at the top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram2d {
+    width: usize,
+    height: usize,
+    bins: Vec<u32>,
+}
+
+impl Histogram2d {
+    pub fn new(width: usize, height: usize) -> Self {
+        Histogram2d {
+            width,
+            height,
+            bins: vec![0; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, column: usize, row: usize) -> u32 {
+        self.bins
+            .get(row * self.width + column)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Counts a sample at `(x, y)` in `0.0..1.0`; values outside are clamped to the edge.
+    pub fn add(&mut self, x: f32, y: f32) {
+        let column = ((x * self.width as f32) as usize).min(self.width.saturating_sub(1));
+        let row = ((y * self.height as f32) as usize).min(self.height.saturating_sub(1));
+        if let Some(bin) = self.bins.get_mut(row * self.width + column) {
+            *bin = bin.saturating_add(1);
+        }
+    }
+
+    pub fn max(&self) -> u32 {
+        self.bins.iter().copied().max().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.bins.iter().map(|&c| c as u64).sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.bins.fill(0);
+    }
+
+    /// One line of comma-separated counts per row, top row first.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::new();
+        for row in self.bins.chunks(self.width.max(1)) {
+            for (i, count) in row.iter().enumerate() {
+                let _ = write!(out, "{}{}", if i == 0 { "" } else { "," }, count);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// An RGB PNG drawing each bin as a `scale` by `scale` square, from black
+    /// through red and yellow to white. Counts are log-scaled so a resting stick
+    /// doesn't wash out everything else.
+    pub fn to_png(&self, scale: usize) -> Vec<u8> {
+        let scale = scale.max(1);
+        let (width, height) = (self.width * scale, self.height * scale);
+        let max = (self.max() as f32).ln_1p().max(f32::EPSILON);
+
+        let mut raw = Vec::with_capacity((width * 3 + 1) * height);
+        for row in 0..self.height {
+            let mut line = Vec::with_capacity(width * 3 + 1);
+            // Filter type 0: no filter.
+            line.push(0);
+            for column in 0..self.width {
+                let rgb = heat((self.get(column, row) as f32).ln_1p() / max);
+                for _ in 0..scale {
+                    line.extend_from_slice(&rgb);
+                }
+            }
+            for _ in 0..scale {
+                raw.extend_from_slice(&line);
+            }
+        }
+
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&(width as u32).to_be_bytes());
+        ihdr.extend_from_slice(&(height as u32).to_be_bytes());
+        // 8-bit RGB, default compression and filtering, no interlace.
+        ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        chunk(&mut png, b"IHDR", &ihdr);
+        chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+        chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+/// Heatmaps of where fingers rest on the touchpad and where each stick sits,
+/// accumulated over a session for comparing control schemes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heatmaps {
+    /// About 20 touchpad units per bin.
+    pub touchpad: Histogram2d,
+    pub left_stick: Histogram2d,
+    pub right_stick: Histogram2d,
+}
+
+impl Default for Heatmaps {
+    fn default() -> Self {
+        Heatmaps {
+            touchpad: Histogram2d::new(96, 47),
+            left_stick: Histogram2d::new(64, 64),
+            right_stick: Histogram2d::new(64, 64),
+        }
+    }
+}
+
+impl Heatmaps {
+    pub fn new() -> Self {
+        Heatmaps::default()
+    }
+
+    /// Counts the current stick positions and every finger on the touchpad; call
+    /// once per report so counts are proportional to time.
+    pub fn record(&mut self, controls: &Controls) {
+        for touch in controls.touches.state().iter().filter(|t| t.active) {
+            self.touchpad.add(
+                touch.x as f32 / TOUCHPAD_WIDTH as f32,
+                touch.y as f32 / TOUCHPAD_HEIGHT as f32,
+            );
+        }
+        add_stick(&mut self.left_stick, controls.left_stick.state());
+        add_stick(&mut self.right_stick, controls.right_stick.state());
+    }
+
+    pub fn clear(&mut self) {
+        self.touchpad.clear();
+        self.left_stick.clear();
+        self.right_stick.clear();
+    }
+
+    /// Writes `<name>.csv` and `<name>.png` into `dir` for each of `touchpad`,
+    /// `left_stick` and `right_stick`, creating `dir` if needed.
+    pub fn write(&self, dir: impl AsRef<Path>, scale: usize) -> Result<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        for (name, map) in [
+            ("touchpad", &self.touchpad),
+            ("left_stick", &self.left_stick),
+            ("right_stick", &self.right_stick),
+        ] {
+            fs::write(dir.join(format!("{}.csv", name)), map.to_csv())?;
+            fs::write(dir.join(format!("{}.png", name)), map.to_png(scale))?;
+        }
+        Ok(())
+    }
+}
+
+/// Up is the top row, as the pad reports it.
+fn add_stick(map: &mut Histogram2d, stick: Stick) {
+    map.add(stick.x as f32 / 256.0, stick.y as f32 / 256.0);
+}
+
+fn heat(t: f32) -> [u8; 3] {
+    let t = t.clamp(0.0, 1.0) * 3.0;
+    let channel = |start: f32| ((t - start).clamp(0.0, 1.0) * 255.0) as u8;
+    [channel(0.0), channel(1.0), channel(2.0)]
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    png.extend_from_slice(&crc32(kind.iter().chain(data)).to_be_bytes());
+}
+
+/// Wraps `data` in a zlib stream of uncompressed deflate blocks, which keeps the
+/// encoder trivial at the cost of file size.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        out.push(blocks.peek().is_none() as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    out.extend_from_slice(&((b << 16) | a).to_be_bytes());
+    out
+}
+
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,87 @@
+use crate::{AxisId, ButtonId, Snapshot};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Fixed-size ring of timestamped snapshots, oldest first.
+pub struct History {
+    capacity: usize,
+    entries: VecDeque<(Instant, Snapshot)>,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        History {
+            capacity: capacity.max(1),
+            entries: VecDeque::with_capacity(capacity.max(1)),
+        }
+    }
+
+    pub fn push(&mut self, at: Instant, snapshot: Snapshot) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((at, snapshot));
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn latest(&self) -> Option<&Snapshot> {
+        self.entries.back().map(|(_, s)| s)
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &(Instant, Snapshot)> {
+        self.entries.iter()
+    }
+
+    /// Entries recorded within `window` of now, oldest first.
+    pub fn within(&self, window: Duration) -> impl Iterator<Item = &(Instant, Snapshot)> {
+        let now = Instant::now();
+        self.entries
+            .iter()
+            .filter(move |(at, _)| now.saturating_duration_since(*at) <= window)
+    }
+
+    /// True if `id` went from released to pressed within `window` of now.
+    pub fn was_pressed_within(&self, id: ButtonId, window: Duration) -> bool {
+        self.edge_within(id, window, true)
+    }
+
+    pub fn was_released_within(&self, id: ButtonId, window: Duration) -> bool {
+        self.edge_within(id, window, false)
+    }
+
+    fn edge_within(&self, id: ButtonId, window: Duration, pressed: bool) -> bool {
+        let now = Instant::now();
+        self.entries
+            .iter()
+            .zip(self.entries.iter().skip(1))
+            .rev()
+            .take_while(|(_, (at, _))| now.saturating_duration_since(*at) <= window)
+            .any(|((_, prev), (_, next))| {
+                prev.pressed(id) != pressed && next.pressed(id) == pressed
+            })
+    }
+
+    pub fn axis_average_over(&self, id: AxisId, window: Duration) -> Option<f32> {
+        let (sum, n) = self
+            .within(window)
+            .fold((0.0, 0u32), |(sum, n), (_, s)| (sum + s.axis(id), n + 1));
+        (n > 0).then(|| sum / n as f32)
+    }
+
+    pub fn axis_peak_over(&self, id: AxisId, window: Duration) -> Option<f32> {
+        self.within(window)
+            .map(|(_, s)| s.axis(id))
+            .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+    }
+}
@@ -0,0 +1,166 @@
+use crate::source::{InputEvent, InputSource};
+use crate::{AxisId, ButtonId, DPad, Result, Snapshot, Stick, YAxis};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Where an event from an `InjectedSource` came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Origin {
+    /// The wrapped source, e.g. a real pad.
+    Device,
+    /// An `Injector`.
+    Injected,
+}
+
+/// Queues synthetic events into an `InjectedSource`. Cheap to clone and
+/// `Send`, so a test harness, accessibility tool or agent can drive the app from
+/// another thread.
+#[derive(Debug, Clone, Default)]
+pub struct Injector {
+    queue: Arc<Mutex<VecDeque<InputEvent>>>,
+}
+
+impl Injector {
+    pub fn inject(&self, event: InputEvent) {
+        self.queue
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push_back(event);
+    }
+
+    pub fn press(&self, id: ButtonId) {
+        self.inject(InputEvent::Button { id, pressed: true });
+    }
+
+    pub fn release(&self, id: ButtonId) {
+        self.inject(InputEvent::Button { id, pressed: false });
+    }
+
+    /// Presses and releases `id`, delivered as two consecutive events.
+    pub fn tap(&self, id: ButtonId) {
+        let mut queue = self.queue.lock().unwrap_or_else(PoisonError::into_inner);
+        queue.push_back(InputEvent::Button { id, pressed: true });
+        queue.push_back(InputEvent::Button { id, pressed: false });
+    }
+
+    pub fn dpad(&self, dpad: DPad) {
+        self.inject(InputEvent::DPad(dpad));
+    }
+
+    /// Same ranges as `Snapshot::axis`.
+    pub fn axis(&self, id: AxisId, value: f32) {
+        self.inject(InputEvent::Axis { id, value });
+    }
+
+    /// Events queued but not yet delivered.
+    pub fn pending(&self) -> usize {
+        self.queue
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    fn pop(&self) -> Option<InputEvent> {
+        self.queue
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop_front()
+    }
+}
+
+/// Merges synthetic events from an `Injector` into another source's stream.
+///
+/// Injected events are delivered ahead of the source's own, and both update
+/// `state`, last change winning: an injected press holds until it's released
+/// or the real button changes. Use `next_tagged`, or `last_origin` after
+/// `next_event`, to tell them apart, e.g. to keep automation out of replays.
+pub struct InjectedSource<S> {
+    pub inner: S,
+    injector: Injector,
+    state: Snapshot,
+    origin: Origin,
+}
+
+impl<S: InputSource> InjectedSource<S> {
+    pub fn new(inner: S) -> Self {
+        InjectedSource {
+            state: inner.state(),
+            inner,
+            injector: Injector::default(),
+            origin: Origin::Device,
+        }
+    }
+
+    /// A handle for queueing events into this source.
+    pub fn injector(&self) -> Injector {
+        self.injector.clone()
+    }
+
+    pub fn inject(&self, event: InputEvent) {
+        self.injector.inject(event);
+    }
+
+    /// The next event and where it came from.
+    pub fn next_tagged(&mut self) -> Result<Option<(InputEvent, Origin)>> {
+        let (event, origin) = match self.injector.pop() {
+            Some(event) => (Some(event), Origin::Injected),
+            None => (self.inner.next_event()?, Origin::Device),
+        };
+        let Some(event) = event else {
+            return Ok(None);
+        };
+        apply(&mut self.state, &event);
+        self.origin = origin;
+        Ok(Some((event, origin)))
+    }
+
+    /// Where the event last returned came from.
+    pub fn last_origin(&self) -> Origin {
+        self.origin
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: InputSource> InputSource for InjectedSource<S> {
+    fn next_event(&mut self) -> Result<Option<InputEvent>> {
+        Ok(self.next_tagged()?.map(|(event, _)| event))
+    }
+
+    fn state(&self) -> Snapshot {
+        self.state
+    }
+
+    fn pending(&self) -> usize {
+        self.injector.pending() + self.inner.pending()
+    }
+}
+
+fn apply(state: &mut Snapshot, event: &InputEvent) {
+    match *event {
+        InputEvent::Button { id, pressed } => state.set_pressed(id, pressed),
+        InputEvent::DPad(dpad) => state.dpad = dpad,
+        InputEvent::Axis { id, value } => {
+            let trigger = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+            match id {
+                AxisId::LeftX => state.left_stick = with_x(state.left_stick, value),
+                AxisId::LeftY => state.left_stick = with_y(state.left_stick, value),
+                AxisId::RightX => state.right_stick = with_x(state.right_stick, value),
+                AxisId::RightY => state.right_stick = with_y(state.right_stick, value),
+                AxisId::L2 => state.l2_value = trigger,
+                AxisId::R2 => state.r2_value = trigger,
+            }
+        }
+        InputEvent::Device(_) => {}
+    }
+}
+
+fn with_x(stick: Stick, x: f32) -> Stick {
+    Stick::new(Stick::from_centered(x, 0.0, YAxis::Down).x, stick.y)
+}
+
+fn with_y(stick: Stick, y: f32) -> Stick {
+    Stick::new(stick.x, Stick::from_centered(0.0, y, YAxis::Down).y)
+}
@@ -0,0 +1,159 @@
+use crate::touch::{TOUCHPAD_HEIGHT, TOUCHPAD_WIDTH};
+use crate::{Controls, Imu, Stick};
+use egui::{Color32, Pos2, Rect, Sense, Shape, Stroke, StrokeKind, Ui, Vec2};
+use std::collections::VecDeque;
+
+const DEFAULT_HISTORY: usize = 256;
+const STICK_SIZE: f32 = 80.0;
+const TOUCHPAD_VIEW_WIDTH: f32 = 320.0;
+const GRAPH_HEIGHT: f32 = 60.0;
+const AXIS_COLORS: [Color32; 3] = [Color32::RED, Color32::GREEN, Color32::LIGHT_BLUE];
+
+/// An egui widget showing the full state of a `Controls`: buttons, sticks,
+/// triggers, touchpad and rolling IMU graphs.
+///
+/// Call [`Inspector::ui`] once per frame with the latest controls; the graphs
+/// keep the last `history` frames.
+pub struct Inspector {
+    history: usize,
+    imu: VecDeque<Imu>,
+}
+
+impl Inspector {
+    pub fn new() -> Self {
+        Inspector::with_history(DEFAULT_HISTORY)
+    }
+
+    pub fn with_history(history: usize) -> Self {
+        Inspector {
+            history: history.max(2),
+            imu: VecDeque::new(),
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, controls: &Controls) {
+        if self.imu.len() == self.history {
+            self.imu.pop_front();
+        }
+        self.imu.push_back(controls.imu);
+
+        ui.horizontal_wrapped(|ui| {
+            for (id, pressed) in controls.iter() {
+                let color = if pressed {
+                    Color32::YELLOW
+                } else {
+                    Color32::GRAY
+                };
+                ui.colored_label(color, format!("{:?}", id));
+            }
+            ui.label(format!("DPad: {:?}", controls.dpad.state()));
+        });
+
+        ui.horizontal(|ui| {
+            stick(ui, "L", controls.left_stick.state());
+            stick(ui, "R", controls.right_stick.state());
+            ui.vertical(|ui| {
+                for (name, trigger) in [("L2", &controls.l2), ("R2", &controls.r2)] {
+                    ui.add(
+                        egui::ProgressBar::new(trigger.value_f32())
+                            .desired_width(120.0)
+                            .text(format!("{} {}", name, trigger.value())),
+                    );
+                }
+            });
+        });
+
+        touchpad(ui, controls);
+
+        ui.label(format!(
+            "IMU temperature (raw): {}",
+            controls.imu.temperature
+        ));
+        ui.label("Gyro");
+        graph(ui, self.imu.iter().map(|imu| imu.gyro), self.history);
+        ui.label("Accel");
+        graph(ui, self.imu.iter().map(|imu| imu.accel), self.history);
+    }
+}
+
+impl Default for Inspector {
+    fn default() -> Self {
+        Inspector::new()
+    }
+}
+
+fn stick(ui: &mut Ui, name: &str, stick: Stick) {
+    let (response, painter) = ui.allocate_painter(Vec2::splat(STICK_SIZE), Sense::hover());
+    let rect = response.rect;
+    painter.rect_stroke(
+        rect,
+        2.0,
+        Stroke::new(1.0, Color32::GRAY),
+        StrokeKind::Inside,
+    );
+    painter.circle_stroke(
+        rect.center(),
+        STICK_SIZE / 2.0,
+        Stroke::new(1.0, Color32::DARK_GRAY),
+    );
+    let at = rect.min + Vec2::new(stick.x as f32, stick.y as f32) / 255.0 * STICK_SIZE;
+    painter.circle_filled(at, 4.0, Color32::YELLOW);
+    painter.text(
+        rect.left_top() + Vec2::splat(2.0),
+        egui::Align2::LEFT_TOP,
+        name,
+        egui::FontId::monospace(10.0),
+        Color32::GRAY,
+    );
+}
+
+fn touchpad(ui: &mut Ui, controls: &Controls) {
+    let width = ui.available_width().min(TOUCHPAD_VIEW_WIDTH);
+    let scale = width / TOUCHPAD_WIDTH as f32;
+    let size = Vec2::new(width, TOUCHPAD_HEIGHT as f32 * scale);
+    let (response, painter) = ui.allocate_painter(size, Sense::hover());
+    let rect = response.rect;
+    let fill = if controls.tpad.state() {
+        Color32::from_gray(60)
+    } else {
+        Color32::from_gray(30)
+    };
+    painter.rect_filled(rect, 4.0, fill);
+    for touch in controls.touches.state().iter().filter(|t| t.active) {
+        let at = rect.min + Vec2::new(touch.x as f32, touch.y as f32) * scale;
+        painter.circle_filled(at, 6.0, Color32::YELLOW);
+    }
+}
+
+/// A rolling line graph of three axes, scaled to the largest magnitude shown.
+fn graph(ui: &mut Ui, samples: impl Iterator<Item = [i16; 3]> + Clone, history: usize) {
+    let size = Vec2::new(ui.available_width(), GRAPH_HEIGHT);
+    let (response, painter) = ui.allocate_painter(size, Sense::hover());
+    let rect = response.rect;
+    painter.rect_filled(rect, 2.0, Color32::from_gray(20));
+    painter.line_segment(
+        [rect.left_center(), rect.right_center()],
+        Stroke::new(1.0, Color32::DARK_GRAY),
+    );
+
+    let peak = samples
+        .clone()
+        .flatten()
+        .map(|v| (v as f32).abs())
+        .fold(1.0, f32::max);
+    for (axis, color) in AXIS_COLORS.into_iter().enumerate() {
+        let points = samples
+            .clone()
+            .enumerate()
+            .map(|(i, s)| point(rect, i, history, s[axis] as f32 / peak))
+            .collect();
+        painter.add(Shape::line(points, Stroke::new(1.0, color)));
+    }
+}
+
+fn point(rect: Rect, i: usize, history: usize, value: f32) -> Pos2 {
+    Pos2::new(
+        rect.left() + rect.width() * i as f32 / (history - 1) as f32,
+        rect.center().y - value * rect.height() / 2.0,
+    )
+}
@@ -0,0 +1,42 @@
+use crate::{Controls, YAxis};
+use std::time::Duration;
+
+/// Input accumulated over one fixed game timestep, from [`Controls::integrate`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FrameDelta {
+    /// Stick deflection times `dt` in seconds, Y up.
+    pub left_stick: (f32, f32),
+    pub right_stick: (f32, f32),
+    /// Trigger travel (`0.0..=1.0`) times `dt` in seconds.
+    pub l2: f32,
+    pub r2: f32,
+    pub dt: Duration,
+}
+
+impl FrameDelta {
+    pub fn add(&mut self, other: &FrameDelta) {
+        self.left_stick.0 += other.left_stick.0;
+        self.left_stick.1 += other.left_stick.1;
+        self.right_stick.0 += other.right_stick.0;
+        self.right_stick.1 += other.right_stick.1;
+        self.l2 += other.l2;
+        self.r2 += other.r2;
+        self.dt += other.dt;
+    }
+}
+
+impl Controls {
+    /// Scales the held analog state by `dt`, so a game loop ticking at a fixed
+    /// rate moves by the same amount per second whatever the report rate is.
+    pub fn integrate(&self, dt: Duration) -> FrameDelta {
+        let secs = dt.as_secs_f32();
+        let scale = |(x, y): (f32, f32)| (x * secs, y * secs);
+        FrameDelta {
+            left_stick: scale(self.left_stick.state().centered(YAxis::Up)),
+            right_stick: scale(self.right_stick.state().centered(YAxis::Up)),
+            l2: self.l2.value_f32() * secs,
+            r2: self.r2.value_f32() * secs,
+            dt,
+        }
+    }
+}
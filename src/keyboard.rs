@@ -0,0 +1,296 @@
+use crate::socd::{SocdPolicy, SocdResolver};
+use crate::source::EventQueue;
+use crate::transport::encode_input;
+use crate::{ButtonId, Controls, DPad, Error, Result, Snapshot, Stick, YAxis};
+use std::io;
+use std::mem;
+use std::time::{Duration, Instant};
+
+/// Terminals only report key presses, repeating them while held once the repeat
+/// delay (typically 250–600ms) has passed, so a key counts as released once it
+/// hasn't been seen for this long.
+const DEFAULT_HOLD: Duration = Duration::from_millis(600);
+const POLL_TIMEOUT_MS: libc::c_int = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Tab,
+    Backspace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    Button(ButtonId),
+    /// Adds a direction to the dpad; opposite directions are resolved by the
+    /// `KeySocd` policy.
+    DPad {
+        x: i8,
+        y: i8,
+    },
+    /// Pushes a stick fully in a direction, with Y up.
+    LeftStick {
+        x: i8,
+        y: i8,
+    },
+    RightStick {
+        x: i8,
+        y: i8,
+    },
+}
+
+/// Which key drives which control.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMap {
+    pub bindings: Vec<(Key, Binding)>,
+}
+
+impl Default for KeyMap {
+    /// WASD for the left stick, arrows for the dpad, IJKL for the face buttons,
+    /// Q/E for L1/R1, Z/C for L2/R2, Enter for options, Tab for share and P for PS.
+    fn default() -> Self {
+        use Binding::*;
+        let bindings = vec![
+            (Key::Char('w'), LeftStick { x: 0, y: 1 }),
+            (Key::Char('a'), LeftStick { x: -1, y: 0 }),
+            (Key::Char('s'), LeftStick { x: 0, y: -1 }),
+            (Key::Char('d'), LeftStick { x: 1, y: 0 }),
+            (Key::Up, DPad { x: 0, y: 1 }),
+            (Key::Left, DPad { x: -1, y: 0 }),
+            (Key::Down, DPad { x: 0, y: -1 }),
+            (Key::Right, DPad { x: 1, y: 0 }),
+            (Key::Char('i'), Button(ButtonId::Triangle)),
+            (Key::Char('j'), Button(ButtonId::Square)),
+            (Key::Char('k'), Button(ButtonId::X)),
+            (Key::Char('l'), Button(ButtonId::Circle)),
+            (Key::Char('q'), Button(ButtonId::L1)),
+            (Key::Char('e'), Button(ButtonId::R1)),
+            (Key::Char('z'), Button(ButtonId::L2)),
+            (Key::Char('c'), Button(ButtonId::R2)),
+            (Key::Enter, Button(ButtonId::Options)),
+            (Key::Tab, Button(ButtonId::Share)),
+            (Key::Char('p'), Button(ButtonId::Ps)),
+            (Key::Char('t'), Button(ButtonId::Tpad)),
+        ];
+        KeyMap { bindings }
+    }
+}
+
+/// Which directions a group of keys holds: left, right, up, down.
+#[derive(Default)]
+struct Held([bool; 4]);
+
+impl Held {
+    fn add(&mut self, x: i8, y: i8) {
+        self.0[0] |= x < 0;
+        self.0[1] |= x > 0;
+        self.0[2] |= y > 0;
+        self.0[3] |= y < 0;
+    }
+
+    fn resolve(&self, socd: &mut SocdResolver) -> (i8, i8) {
+        let [left, right, up, down] = self.0;
+        socd.resolve(left, right, up, down)
+    }
+}
+
+/// Opposite-direction resolution for each group of directional keys, so e.g. A
+/// and D held together follow one `SocdPolicy` whether they drive the dpad or a
+/// stick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KeySocd {
+    pub dpad: SocdResolver,
+    pub left_stick: SocdResolver,
+    pub right_stick: SocdResolver,
+}
+
+impl KeySocd {
+    pub fn new(policy: SocdPolicy) -> Self {
+        let resolver = SocdResolver::new(policy);
+        KeySocd {
+            dpad: resolver,
+            left_stick: resolver,
+            right_stick: resolver,
+        }
+    }
+}
+
+impl KeyMap {
+    /// Held keys as a snapshot, with opposite directions cancelling.
+    pub fn snapshot(&self, held: impl Iterator<Item = Key> + Clone) -> Snapshot {
+        self.resolve(held, &mut KeySocd::default())
+    }
+
+    /// Held keys as a snapshot, with opposite directions resolved by `socd`,
+    /// which should persist from one update to the next.
+    pub fn resolve(&self, held: impl Iterator<Item = Key> + Clone, socd: &mut KeySocd) -> Snapshot {
+        let mut s = Snapshot::default();
+        let (mut dpad, mut left, mut right) = (Held::default(), Held::default(), Held::default());
+        for key in held {
+            for (_, binding) in self.bindings.iter().filter(|(k, _)| *k == key) {
+                match *binding {
+                    Binding::Button(id) => s.set_pressed(id, true),
+                    Binding::DPad { x, y } => dpad.add(x, y),
+                    Binding::LeftStick { x, y } => left.add(x, y),
+                    Binding::RightStick { x, y } => right.add(x, y),
+                }
+            }
+        }
+        if s.l2 {
+            s.l2_value = 255;
+        }
+        if s.r2 {
+            s.r2_value = 255;
+        }
+        let (x, y) = dpad.resolve(&mut socd.dpad);
+        s.dpad = DPad::from_xy(x, y);
+        s.left_stick = stick_from(left.resolve(&mut socd.left_stick));
+        s.right_stick = stick_from(right.resolve(&mut socd.right_stick));
+        s
+    }
+}
+
+fn stick_from((x, y): (i8, i8)) -> Stick {
+    Stick::from_centered(x as f32, y as f32, YAxis::Up)
+}
+
+/// Drives a `Controls` from the terminal keyboard, for developing and demoing
+/// without a controller. Handlers and streams fire exactly as they would for a pad.
+///
+/// Puts the terminal in non-canonical, no-echo mode until dropped.
+pub struct KeyboardSource {
+    pub controls: Controls,
+    keymap: KeyMap,
+    socd: KeySocd,
+    held: Vec<(Key, Instant)>,
+    hold: Duration,
+    saved: libc::termios,
+    counter: u8,
+    escape: Vec<u8>,
+    pub(crate) events: EventQueue,
+}
+
+impl KeyboardSource {
+    pub fn new() -> Result<Self> {
+        KeyboardSource::with_keymap(KeyMap::default())
+    }
+
+    pub fn with_keymap(keymap: KeyMap) -> Result<Self> {
+        // SAFETY: termios is plain old data and tcgetattr fully initializes it on success.
+        let mut saved: libc::termios = unsafe { mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut saved) } != 0 {
+            return Err(Error::Io(io::Error::last_os_error()));
+        }
+        let mut raw = saved;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+        raw.c_cc[libc::VMIN] = 0;
+        raw.c_cc[libc::VTIME] = 0;
+        // SAFETY: `raw` is a valid termios derived from the current settings.
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return Err(Error::Io(io::Error::last_os_error()));
+        }
+        Ok(KeyboardSource {
+            controls: Controls::new(),
+            keymap,
+            socd: KeySocd::default(),
+            held: Vec::new(),
+            hold: DEFAULT_HOLD,
+            saved,
+            counter: 0,
+            escape: Vec::new(),
+            events: EventQueue::new(),
+        })
+    }
+
+    /// How long a key stays held after its last press or repeat.
+    pub fn set_hold_time(&mut self, hold: Duration) {
+        self.hold = hold;
+    }
+
+    pub fn keymap_mut(&mut self) -> &mut KeyMap {
+        &mut self.keymap
+    }
+
+    /// How opposite directions held at once resolve; they cancel by default.
+    pub fn set_socd_policy(&mut self, policy: SocdPolicy) {
+        self.socd = KeySocd::new(policy);
+    }
+
+    /// Waits briefly for key presses and updates `controls` from what is held.
+    pub fn update(&mut self) -> Result<()> {
+        let now = Instant::now();
+        for key in self.read_keys()? {
+            self.held.retain(|(k, _)| *k != key);
+            self.held.push((key, now));
+        }
+        let hold = self.hold;
+        self.held.retain(|(_, at)| now.duration_since(*at) < hold);
+
+        let snapshot = self
+            .keymap
+            .resolve(self.held.iter().map(|(k, _)| *k), &mut self.socd);
+        self.counter = (self.counter + 1) & 0x3f;
+        self.controls
+            .update(&encode_input(&snapshot, self.counter, 0))
+    }
+
+    fn read_keys(&mut self) -> Result<Vec<Key>> {
+        let mut pollfd = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `pollfd` is a single valid pollfd.
+        if unsafe { libc::poll(&mut pollfd, 1, POLL_TIMEOUT_MS) } < 0 {
+            return Err(Error::Io(io::Error::last_os_error()));
+        }
+        let mut buf = [0u8; 64];
+        // SAFETY: reads at most `buf.len()` bytes into `buf`.
+        let n = unsafe { libc::read(libc::STDIN_FILENO, buf.as_mut_ptr().cast(), buf.len()) };
+        if n < 0 {
+            return Err(Error::Io(io::Error::last_os_error()));
+        }
+
+        let mut keys = Vec::new();
+        for &b in &buf[..n as usize] {
+            // Arrow keys arrive as ESC [ A..D.
+            if !self.escape.is_empty() || b == 0x1b {
+                self.escape.push(b);
+                match self.escape.as_slice() {
+                    [0x1b] | [0x1b, b'['] => continue,
+                    [0x1b, b'[', code] => {
+                        keys.extend(match code {
+                            b'A' => Some(Key::Up),
+                            b'B' => Some(Key::Down),
+                            b'C' => Some(Key::Right),
+                            b'D' => Some(Key::Left),
+                            _ => None,
+                        });
+                    }
+                    _ => {}
+                }
+                self.escape.clear();
+                continue;
+            }
+            keys.push(match b {
+                b'\n' | b'\r' => Key::Enter,
+                b'\t' => Key::Tab,
+                0x7f | 0x08 => Key::Backspace,
+                _ => Key::Char((b as char).to_ascii_lowercase()),
+            });
+        }
+        Ok(keys)
+    }
+}
+
+impl Drop for KeyboardSource {
+    fn drop(&mut self) {
+        // SAFETY: restores the settings read in `with_keymap`.
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.saved) };
+    }
+}
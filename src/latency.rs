@@ -0,0 +1,149 @@
+use crate::{Controller, Controls, OutputReportBuilder, Result};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+pub struct Histogram {
+    bucket_width: Duration,
+    buckets: Vec<u32>,
+    count: u32,
+    total: Duration,
+    min: Option<Duration>,
+    max: Option<Duration>,
+}
+
+impl Histogram {
+    /// Samples beyond the last bucket are counted in it.
+    pub fn new(bucket_width: Duration, bucket_count: usize) -> Self {
+        Histogram {
+            bucket_width,
+            buckets: vec![0; bucket_count.max(1)],
+            count: 0,
+            total: Duration::ZERO,
+            min: None,
+            max: None,
+        }
+    }
+
+    pub fn record(&mut self, sample: Duration) {
+        let index = (sample.as_nanos() / self.bucket_width.as_nanos().max(1)) as usize;
+        let last = self.buckets.len() - 1;
+        self.buckets[index.min(last)] += 1;
+        self.count += 1;
+        self.total += sample;
+        self.min = Some(self.min.map_or(sample, |m| m.min(sample)));
+        self.max = Some(self.max.map_or(sample, |m| m.max(sample)));
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn buckets(&self) -> &[u32] {
+        &self.buckets
+    }
+
+    pub fn bucket_width(&self) -> Duration {
+        self.bucket_width
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.total / self.count)
+        }
+    }
+
+    /// Approximate `q`-quantile (`0.0..=1.0`), reported as the upper edge of its bucket.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let target = ((self.count as f64 * q.clamp(0.0, 1.0)).ceil() as u32).max(1);
+        let mut seen = 0;
+        for (i, &n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= target {
+                let edge = self.bucket_width * (i as u32 + 1);
+                return Some(edge.min(self.max.unwrap_or(edge)));
+            }
+        }
+        self.max
+    }
+
+    pub fn min(&self) -> Option<Duration> {
+        self.min
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.max
+    }
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (Some(mean), Some(min), Some(max)) = (self.mean(), self.min, self.max) else {
+            return writeln!(f, "no samples");
+        };
+        writeln!(
+            f,
+            "n={} min={:?} mean={:?} max={:?}",
+            self.count, min, mean, max
+        )?;
+
+        let peak = self.buckets.iter().copied().max().unwrap_or(0).max(1);
+        for (i, &n) in self.buckets.iter().enumerate() {
+            if n == 0 {
+                continue;
+            }
+            let start = self.bucket_width * i as u32;
+            let bar = "#".repeat((n * 40 / peak).max(1) as usize);
+            writeln!(f, "{:>10?} {:>5} {}", start, n, bar)?;
+        }
+        Ok(())
+    }
+}
+
+pub type LatencyTrigger = fn(&Controls) -> bool;
+
+/// Toggles the lightbar each time `trigger` goes high and records the time from
+/// the output write completing until the next input report arrives. The write
+/// bypasses the output interval, so coalescing doesn't count as latency.
+pub struct LatencyTester {
+    trigger: LatencyTrigger,
+    triggered: bool,
+    lit: bool,
+    pending: Option<Instant>,
+    pub histogram: Histogram,
+}
+
+impl LatencyTester {
+    pub fn new(trigger: LatencyTrigger) -> Self {
+        LatencyTester {
+            trigger,
+            triggered: false,
+            lit: false,
+            pending: None,
+            histogram: Histogram::new(Duration::from_millis(1), 32),
+        }
+    }
+
+    /// Call once after every `Controller::update`. Returns the latest sample, if any.
+    pub fn update(&mut self, controller: &mut Controller) -> Result<Option<Duration>> {
+        let sample = self.pending.take().map(|written| written.elapsed());
+        if let Some(sample) = sample {
+            self.histogram.record(sample);
+        }
+
+        let triggered = (self.trigger)(&controller.controls);
+        if triggered && !self.triggered {
+            self.lit = !self.lit;
+            let (r, g, b) = if self.lit { (255, 255, 255) } else { (0, 0, 0) };
+            controller.write_now(&OutputReportBuilder::new().lightbar(r, g, b))?;
+            self.pending = Some(Instant::now());
+        }
+        self.triggered = triggered;
+
+        Ok(sample)
+    }
+}
@@ -0,0 +1,121 @@
+#![deny(clippy::unwrap_used, clippy::expect_used, clippy::panic)]
+
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod battery;
+mod button;
+pub mod calibration;
+pub mod choreography;
+pub mod clock;
+pub mod combo;
+mod connection;
+pub mod context;
+mod controller;
+mod controls;
+#[cfg(feature = "server")]
+pub mod daemon;
+#[cfg(feature = "parquet")]
+pub mod dataset;
+#[cfg(all(feature = "dbus", target_os = "linux"))]
+pub mod dbus;
+pub mod deadman;
+pub mod edge_swipe;
+mod error;
+mod event;
+pub mod extrapolate;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod filter;
+pub mod firmware;
+pub mod fixture;
+pub mod focus_nav;
+#[cfg(all(feature = "gamepad", target_arch = "wasm32"))]
+pub mod gamepad;
+pub mod gyro;
+pub mod heatmap;
+mod history;
+pub mod inject;
+#[cfg(feature = "egui")]
+pub mod inspector;
+mod integrate;
+#[cfg(target_os = "linux")]
+pub mod keyboard;
+pub mod latency;
+pub mod lifecycle;
+pub mod lightbar;
+pub mod logger;
+pub mod macros;
+pub mod merge;
+pub mod metrics;
+#[cfg(all(feature = "audio", target_os = "linux"))]
+pub mod mic;
+pub mod netcode;
+#[cfg(feature = "node")]
+pub mod node;
+#[cfg(feature = "server")]
+pub mod notify;
+pub mod one_handed;
+pub mod orientation;
+mod output;
+pub mod pairing;
+pub mod pcapng;
+mod peripheral;
+pub mod priority;
+mod profile;
+pub mod profile_switch;
+pub mod proxy;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod quirks;
+mod rate_limiter;
+pub mod reconnect;
+pub mod remote;
+#[cfg(feature = "noise")]
+mod remote_auth;
+pub mod rumble;
+pub mod scope;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod self_test;
+#[cfg(feature = "shm")]
+pub mod shm;
+pub mod sim;
+mod snapshot;
+pub mod socd;
+pub mod source;
+mod stats;
+pub mod text_input;
+pub mod tilt;
+pub mod trace;
+pub mod transport;
+#[cfg(all(feature = "tray", target_os = "linux"))]
+pub mod tray;
+mod trigger;
+pub mod twist;
+pub mod ui_haptics;
+pub mod virtual_pad;
+mod wake;
+#[cfg(all(feature = "webhid", target_arch = "wasm32"))]
+pub mod webhid;
+#[cfg(all(feature = "webrtc", not(target_arch = "wasm32")))]
+pub mod webrtc;
+
+pub use button::{Button, ButtonHandler, LockMode, PanicPolicy, Subscription};
+pub use connection::{Connection, ConnectionInfo, ReportLayout};
+pub use controller::{
+    Controller, Identity, PRODUCT_ID, PRODUCT_ID_DONGLE, PRODUCT_ID_V1, VENDOR_ID,
+};
+pub use controls::{Controls, HandlerPanic};
+pub use ds4_core::{report, touch, wire};
+pub use ds4_core::{AxisId, ButtonFlags, ButtonId, DPad, Imu, ParseError, Snapshot, Stick, YAxis};
+pub use error::{Error, Result};
+pub use event::Event;
+pub use history::History;
+pub use integrate::FrameDelta;
+pub use output::{OutputReportBuilder, OutputStats};
+pub use peripheral::PeripheralState;
+pub use profile::Profile;
+pub use rate_limiter::RateLimiter;
+pub use stats::UsageStats;
+pub use trigger::{Trigger, TriggerState};
+pub use wake::WakeGuard;
@@ -0,0 +1,115 @@
+use std::fmt;
+use std::time::Instant;
+
+/// Where a controller is in its connection lifecycle.
+///
+/// ```text
+/// Disconnected → Connecting → Handshaking → Streaming ⇄ Degraded
+///       ↑______________|____________|___________|__________|
+/// ```
+///
+/// Any state but `Closed` can fall back to `Disconnected`, and any state can be
+/// closed. `Closed` is final.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LifecycleState {
+    /// No device is open.
+    #[default]
+    Disconnected,
+    /// A device was found and is being opened.
+    Connecting,
+    /// Open, but not yet sending full input reports, e.g. a Bluetooth pad before or
+    /// after sleep.
+    Handshaking,
+    /// Full input reports are arriving.
+    Streaming,
+    /// Reports arrive but something is failing, such as output writes.
+    Degraded,
+    /// Shut down on request.
+    Closed,
+}
+
+impl LifecycleState {
+    pub fn can_transition(self, to: LifecycleState) -> bool {
+        use LifecycleState::*;
+        match (self, to) {
+            (Closed, _) => false,
+            (_, Closed) | (_, Disconnected) => self != to,
+            (Disconnected, Connecting) => true,
+            (Connecting, Handshaking) => true,
+            (Handshaking, Streaming) => true,
+            (Streaming, Degraded) | (Degraded, Streaming) => true,
+            (Streaming, Handshaking) | (Degraded, Handshaking) => true,
+            _ => false,
+        }
+    }
+
+    /// Whether input is flowing, if perhaps with problems.
+    pub fn is_streaming(self) -> bool {
+        matches!(self, LifecycleState::Streaming | LifecycleState::Degraded)
+    }
+}
+
+impl fmt::Display for LifecycleState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LifecycleState::Disconnected => "disconnected",
+            LifecycleState::Connecting => "connecting",
+            LifecycleState::Handshaking => "handshaking",
+            LifecycleState::Streaming => "streaming",
+            LifecycleState::Degraded => "degraded",
+            LifecycleState::Closed => "closed",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    pub previous: LifecycleState,
+    pub current: LifecycleState,
+}
+
+/// The current lifecycle state and when it was entered.
+#[derive(Debug, Clone, Copy)]
+pub struct Lifecycle {
+    state: LifecycleState,
+    since: Instant,
+}
+
+impl Lifecycle {
+    pub fn new(state: LifecycleState) -> Self {
+        Lifecycle {
+            state,
+            since: Instant::now(),
+        }
+    }
+
+    pub fn state(&self) -> LifecycleState {
+        self.state
+    }
+
+    pub fn since(&self) -> Instant {
+        self.since
+    }
+
+    /// Moves to `to` if that's a legal transition, returning it; staying put or an
+    /// illegal move returns `None` and changes nothing.
+    pub fn transition(&mut self, to: LifecycleState) -> Option<Transition> {
+        if !self.state.can_transition(to) {
+            return None;
+        }
+        let previous = std::mem::replace(&mut self.state, to);
+        self.since = Instant::now();
+        Some(Transition {
+            previous,
+            current: to,
+        })
+    }
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Lifecycle::new(LifecycleState::default())
+    }
+}
@@ -0,0 +1,291 @@
+#[cfg(feature = "audio")]
+use crate::audio::{Envelope, LowPass, SampleSource};
+use crate::{Controller, Result};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb::new(0, 0, 0);
+    pub const WHITE: Rgb = Rgb::new(255, 255, 255);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Rgb { r, g, b }
+    }
+
+    /// Scales every channel by `brightness` in `0.0..=1.0`.
+    pub fn scaled(self, brightness: f32) -> Rgb {
+        let s = |c: u8| (c as f32 * brightness.clamp(0.0, 1.0)) as u8;
+        Rgb::new(s(self.r), s(self.g), s(self.b))
+    }
+
+    pub fn lerp(self, other: Rgb, t: f32) -> Rgb {
+        let t = t.clamp(0.0, 1.0);
+        let l = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) as u8;
+        Rgb::new(l(self.r, other.r), l(self.g, other.g), l(self.b, other.b))
+    }
+}
+
+pub trait Animation {
+    fn frame(&mut self, at: Instant) -> Result<Rgb>;
+}
+
+pub struct Solid(pub Rgb);
+
+impl Animation for Solid {
+    fn frame(&mut self, _at: Instant) -> Result<Rgb> {
+        Ok(self.0)
+    }
+}
+
+/// Fades between two colors with the given period.
+pub struct Pulse {
+    pub from: Rgb,
+    pub to: Rgb,
+    pub period: Duration,
+    start: Instant,
+}
+
+impl Pulse {
+    pub fn new(from: Rgb, to: Rgb, period: Duration) -> Self {
+        Pulse {
+            from,
+            to,
+            period,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Animation for Pulse {
+    fn frame(&mut self, at: Instant) -> Result<Rgb> {
+        let phase = at.saturating_duration_since(self.start).as_secs_f32()
+            / self.period.as_secs_f32().max(0.001);
+        let t = 0.5 - 0.5 * (phase * std::f32::consts::TAU).cos();
+        Ok(self.from.lerp(self.to, t))
+    }
+}
+
+/// Runs an animation on a controller's lightbar, writing only when the color changes.
+pub struct Lightbar {
+    animation: Box<dyn Animation>,
+    last: Option<Rgb>,
+}
+
+impl Lightbar {
+    pub fn new(animation: Box<dyn Animation>) -> Self {
+        Lightbar {
+            animation,
+            last: None,
+        }
+    }
+
+    pub fn set_animation(&mut self, animation: Box<dyn Animation>) {
+        self.animation = animation;
+    }
+
+    pub fn update(&mut self, controller: &mut Controller, at: Instant) -> Result<Rgb> {
+        let color = self.animation.frame(at)?;
+        if self.last != Some(color) {
+            controller.set_lightbar(color.r, color.g, color.b)?;
+            self.last = Some(color);
+        }
+        Ok(color)
+    }
+}
+
+/// Who is asking for the lightbar, lowest priority first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LightbarLayer {
+    /// The player or profile color.
+    Player,
+    /// Effects an app shows for a while, e.g. a hit flash.
+    App,
+    /// Warnings such as low battery.
+    Alert,
+}
+
+impl LightbarLayer {
+    pub const ALL: [LightbarLayer; 3] = [
+        LightbarLayer::Player,
+        LightbarLayer::App,
+        LightbarLayer::Alert,
+    ];
+}
+
+/// Lightbar control shared between layers: the highest layer with an animation
+/// drives the lightbar, and clearing it reveals the one below, so e.g. a battery
+/// warning doesn't lose the player color. With no layers set it shows `base`.
+pub struct LightbarStack {
+    pub base: Rgb,
+    layers: [Option<Box<dyn Animation>>; 3],
+    last: Option<Rgb>,
+    /// Whether the alert layer holds `battery_warning`'s pulse.
+    battery_alert: bool,
+}
+
+impl Default for LightbarStack {
+    fn default() -> Self {
+        LightbarStack {
+            base: Rgb::BLACK,
+            layers: [None, None, None],
+            last: None,
+            battery_alert: false,
+        }
+    }
+}
+
+impl LightbarStack {
+    pub fn new() -> Self {
+        LightbarStack::default()
+    }
+
+    pub fn set(&mut self, layer: LightbarLayer, animation: Box<dyn Animation>) {
+        self.layers[layer as usize] = Some(animation);
+        if layer == LightbarLayer::Alert {
+            self.battery_alert = false;
+        }
+    }
+
+    pub fn set_color(&mut self, layer: LightbarLayer, color: Rgb) {
+        self.set(layer, Box::new(Solid(color)));
+    }
+
+    /// Returns whether the layer had an animation.
+    pub fn clear(&mut self, layer: LightbarLayer) -> bool {
+        if layer == LightbarLayer::Alert {
+            self.battery_alert = false;
+        }
+        self.layers[layer as usize].take().is_some()
+    }
+
+    pub fn is_set(&self, layer: LightbarLayer) -> bool {
+        self.layers[layer as usize].is_some()
+    }
+
+    /// The layer currently driving the lightbar, if any.
+    pub fn top(&self) -> Option<LightbarLayer> {
+        LightbarLayer::ALL
+            .into_iter()
+            .rev()
+            .find(|&layer| self.is_set(layer))
+    }
+
+    /// Pulses the alert layer red while `percent` is below `threshold`, and clears it
+    /// otherwise. Leaves the layer alone if something else is using it.
+    pub fn battery_warning(&mut self, percent: Option<f32>, threshold: f32) {
+        let low = percent.is_some_and(|p| p < threshold);
+        if low && !self.is_set(LightbarLayer::Alert) {
+            self.set(
+                LightbarLayer::Alert,
+                Box::new(Pulse::new(
+                    Rgb::BLACK,
+                    Rgb::new(255, 0, 0),
+                    Duration::from_secs(1),
+                )),
+            );
+            self.battery_alert = true;
+        } else if !low && self.battery_alert {
+            self.clear(LightbarLayer::Alert);
+        }
+    }
+
+    /// The color the top layer shows at `at`.
+    pub fn frame(&mut self, at: Instant) -> Result<Rgb> {
+        match self.layers.iter_mut().rev().flatten().next() {
+            Some(animation) => animation.frame(at),
+            None => Ok(self.base),
+        }
+    }
+
+    /// Renders the top layer and writes it to the controller if the color changed.
+    pub fn update(&mut self, controller: &mut Controller, at: Instant) -> Result<Rgb> {
+        let color = self.frame(at)?;
+        if self.last != Some(color) {
+            controller.set_lightbar(color.r, color.g, color.b)?;
+            self.last = Some(color);
+        }
+        Ok(color)
+    }
+}
+
+#[cfg(feature = "audio")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisualizerMode {
+    /// Brightness of a single color follows overall loudness.
+    Loudness(Rgb),
+    /// Red, green and blue follow the low, mid and high bands.
+    Spectrum,
+}
+
+/// Follows the audio from a sample source, consuming as many samples per frame
+/// as have elapsed since the previous one.
+#[cfg(feature = "audio")]
+pub struct AudioVisualizer {
+    source: Box<dyn SampleSource>,
+    pub mode: VisualizerMode,
+    pub gain: f32,
+    low: LowPass,
+    mid: LowPass,
+    envelopes: [Envelope; 4],
+    last: Option<Instant>,
+    buf: Vec<f32>,
+}
+
+#[cfg(feature = "audio")]
+impl AudioVisualizer {
+    pub fn new(source: Box<dyn SampleSource>, mode: VisualizerMode) -> Self {
+        let rate = source.sample_rate();
+        AudioVisualizer {
+            source,
+            mode,
+            gain: 3.0,
+            low: LowPass::new(250.0, rate),
+            mid: LowPass::new(2500.0, rate),
+            envelopes: [Envelope::new(5.0, 150.0, rate); 4],
+            last: None,
+            buf: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+impl Animation for AudioVisualizer {
+    fn frame(&mut self, at: Instant) -> Result<Rgb> {
+        let elapsed = self
+            .last
+            .replace(at)
+            .map_or(Duration::from_millis(16), |last| {
+                at.saturating_duration_since(last)
+            });
+        let len = ((elapsed.as_secs_f32() * self.source.sample_rate() as f32) as usize).max(1);
+        self.buf.resize(len, 0.0);
+        let n = self.source.read(&mut self.buf)?;
+
+        let [loud, low, mid, high] = &mut self.envelopes;
+        for &x in &self.buf[..n] {
+            let l = self.low.process(x);
+            let m = self.mid.process(x) - l;
+            loud.process(x);
+            low.process(l);
+            mid.process(m);
+            high.process(x - l - m);
+        }
+
+        let level = |e: &Envelope| (e.level() * self.gain).clamp(0.0, 1.0);
+        Ok(match self.mode {
+            VisualizerMode::Loudness(color) => color.scaled(level(loud)),
+            VisualizerMode::Spectrum => Rgb::new(
+                (level(low) * 255.0) as u8,
+                (level(mid) * 255.0) as u8,
+                (level(high) * 255.0) as u8,
+            ),
+        })
+    }
+}
@@ -0,0 +1,358 @@
+use crate::source::{InputEvent, InputSource, AXES};
+use crate::trace::Channel;
+use crate::{AxisId, ButtonId, Event, Result, Snapshot};
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const CSV_HEADER: &str = "time,kind,control,value\n";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LogFormat {
+    /// One JSON object per line, e.g.
+    /// `{"time":1.204,"kind":"button","control":"x","value":true}`.
+    JsonLines,
+    /// `time,kind,control,value` rows; snapshots become one row per control.
+    Csv,
+}
+
+/// Writes input events and snapshots to a file with the seconds since the logger
+/// was created, for research data or for finding out afterwards what a pad did.
+///
+/// With `rotate`, a file that would grow past the size limit is renamed to
+/// `<stem>.1.<ext>` (shifting older ones up) and a fresh one started.
+pub struct SessionLogger {
+    path: PathBuf,
+    format: LogFormat,
+    file: BufWriter<File>,
+    written: u64,
+    rotation: Option<(u64, usize)>,
+    start: Instant,
+}
+
+impl SessionLogger {
+    /// Creates or truncates `path`, creating its directory if needed.
+    pub fn create(path: impl AsRef<Path>, format: LogFormat) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut logger = SessionLogger {
+            file: BufWriter::new(File::create(&path)?),
+            path,
+            format,
+            written: 0,
+            rotation: None,
+            start: Instant::now(),
+        };
+        logger.write_header()?;
+        Ok(logger)
+    }
+
+    /// Starts a new file once this one reaches `max_bytes`, keeping the newest
+    /// `keep` old files.
+    pub fn rotate(mut self, max_bytes: u64, keep: usize) -> Self {
+        self.rotation = Some((max_bytes, keep));
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Time since the logger was created, as written in each line.
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    pub fn log(&mut self, event: &InputEvent) -> Result<()> {
+        let (kind, control, value) = describe(event);
+        self.write_record(kind, &control, value)
+    }
+
+    /// Writes a `marker` line labelled `label`, for lining the log up with external
+    /// events such as the start of an experiment trial. Markers from
+    /// `Controller::mark` arrive through `log` instead and carry the device time.
+    pub fn mark(&mut self, label: &str) -> Result<()> {
+        self.write_record("marker", label, Value::Null)
+    }
+
+    fn write_record(&mut self, kind: &str, control: &str, value: Value) -> Result<()> {
+        let time = self.elapsed().as_secs_f64();
+        let line = match self.format {
+            LogFormat::JsonLines => format!(
+                "{{\"time\":{:.6},\"kind\":\"{}\",\"control\":\"{}\",\"value\":{}}}\n",
+                time,
+                kind,
+                json_escape(control),
+                value.json()
+            ),
+            LogFormat::Csv => format!(
+                "{:.6},{},{},{}\n",
+                time,
+                kind,
+                csv_escape(control),
+                value.csv()
+            ),
+        };
+        self.write_line(&line)
+    }
+
+    /// Logs the state of every control at once.
+    pub fn log_snapshot(&mut self, snapshot: &Snapshot) -> Result<()> {
+        let time = self.elapsed().as_secs_f64();
+        let mut line = String::new();
+        match self.format {
+            LogFormat::JsonLines => {
+                let _ = write!(line, "{{\"time\":{:.6},\"kind\":\"snapshot\"", time);
+                for id in ButtonId::ALL {
+                    let _ = write!(line, ",\"{}\":{}", id, snapshot.pressed(id));
+                }
+                let _ = write!(line, ",\"dpad\":\"{}\"", dpad_name(snapshot.dpad));
+                for id in AXES {
+                    let _ = write!(
+                        line,
+                        ",\"{}\":{:.4}",
+                        snapshot_axis_name(id),
+                        snapshot.axis(id)
+                    );
+                }
+                line.push_str("}\n");
+            }
+            LogFormat::Csv => {
+                for id in ButtonId::ALL {
+                    let _ = writeln!(line, "{:.6},snapshot,{},{}", time, id, snapshot.pressed(id));
+                }
+                let _ = writeln!(
+                    line,
+                    "{:.6},snapshot,dpad,{}",
+                    time,
+                    dpad_name(snapshot.dpad)
+                );
+                for id in AXES {
+                    let _ = writeln!(
+                        line,
+                        "{:.6},snapshot,{},{:.4}",
+                        time,
+                        snapshot_axis_name(id),
+                        snapshot.axis(id)
+                    );
+                }
+            }
+        }
+        self.write_line(&line)
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.file.flush()?;
+        Ok(())
+    }
+
+    fn header(&self) -> &'static str {
+        match self.format {
+            LogFormat::JsonLines => "",
+            LogFormat::Csv => CSV_HEADER,
+        }
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        let header = self.header();
+        self.file.write_all(header.as_bytes())?;
+        self.written = header.len() as u64;
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        if let Some((max_bytes, keep)) = self.rotation {
+            // A file holding only its header is never rotated, so a line longer than
+            // `max_bytes` still gets written.
+            let has_lines = self.written > self.header().len() as u64;
+            if has_lines && self.written + line.len() as u64 > max_bytes {
+                self.roll(keep)?;
+            }
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+
+    fn roll(&mut self, keep: usize) -> Result<()> {
+        self.file.flush()?;
+        if keep == 0 {
+            self.file = BufWriter::new(File::create(&self.path)?);
+        } else {
+            let _ = fs::remove_file(self.numbered(keep));
+            for n in (1..keep).rev() {
+                let from = self.numbered(n);
+                if from.exists() {
+                    fs::rename(from, self.numbered(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.numbered(1))?;
+            self.file = BufWriter::new(File::create(&self.path)?);
+        }
+        self.written = 0;
+        self.write_header()
+    }
+
+    /// `logs/session.jsonl` with `n = 2` is `logs/session.2.jsonl`.
+    fn numbered(&self, n: usize) -> PathBuf {
+        let stem = self.path.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match self.path.extension() {
+            Some(ext) => format!("{}.{}.{}", stem, n, ext.to_string_lossy()),
+            None => format!("{}.{}", stem, n),
+        };
+        self.path.with_file_name(name)
+    }
+}
+
+impl Drop for SessionLogger {
+    fn drop(&mut self) {
+        let _ = self.file.flush();
+    }
+}
+
+/// Wraps an input source, logging every event it returns and, optionally, a
+/// snapshot at a fixed interval.
+pub struct LoggingSource<S> {
+    pub source: S,
+    pub logger: SessionLogger,
+    snapshot_interval: Option<Duration>,
+    last_snapshot: Option<Instant>,
+}
+
+impl<S: InputSource> LoggingSource<S> {
+    pub fn new(source: S, logger: SessionLogger) -> Self {
+        LoggingSource {
+            source,
+            logger,
+            snapshot_interval: None,
+            last_snapshot: None,
+        }
+    }
+
+    pub fn snapshot_every(mut self, interval: Duration) -> Self {
+        self.snapshot_interval = Some(interval);
+        self
+    }
+}
+
+impl<S: InputSource> InputSource for LoggingSource<S> {
+    fn next_event(&mut self) -> Result<Option<InputEvent>> {
+        let event = self.source.next_event()?;
+        if let Some(event) = &event {
+            self.logger.log(event)?;
+        }
+        if let Some(interval) = self.snapshot_interval {
+            let now = Instant::now();
+            if self.last_snapshot.is_none_or(|at| now - at >= interval) {
+                self.last_snapshot = Some(now);
+                self.logger.log_snapshot(&self.source.state())?;
+            }
+        }
+        Ok(event)
+    }
+
+    fn state(&self) -> Snapshot {
+        self.source.state()
+    }
+
+    fn pending(&self) -> usize {
+        self.source.pending()
+    }
+}
+
+enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+}
+
+impl Value {
+    fn json(&self) -> String {
+        match self {
+            Value::Null => "null".to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => format!("{:.4}", n),
+            Value::Text(s) => format!("\"{}\"", json_escape(s)),
+        }
+    }
+
+    fn csv(&self) -> String {
+        match self {
+            Value::Null => String::new(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => format!("{:.4}", n),
+            Value::Text(s) => csv_escape(s),
+        }
+    }
+}
+
+fn describe(event: &InputEvent) -> (&'static str, String, Value) {
+    match event {
+        InputEvent::Button { id, pressed } => ("button", id.to_string(), Value::Bool(*pressed)),
+        InputEvent::DPad(dpad) => ("dpad", "dpad".to_string(), Value::Text(dpad_name(*dpad))),
+        InputEvent::Axis { id, value } => (
+            "axis",
+            Channel::Axis(*id).name().to_string(),
+            Value::Number(*value as f64),
+        ),
+        // The value is the device time, for lining markers up with other captures.
+        InputEvent::Device(Event::Marker {
+            label, device_time, ..
+        }) => (
+            "marker",
+            label.clone(),
+            Value::Number(device_time.as_secs_f64()),
+        ),
+        InputEvent::Device(event) => {
+            let debug = format!("{:?}", event);
+            let name = debug
+                .split(|c: char| !c.is_alphanumeric())
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            ("device", name, Value::Text(debug))
+        }
+    }
+}
+
+/// Trigger axes get a suffix so they don't clash with the triggers' digital bits.
+fn snapshot_axis_name(id: AxisId) -> &'static str {
+    match id {
+        AxisId::L2 => "l2_value",
+        AxisId::R2 => "r2_value",
+        _ => Channel::Axis(id).name(),
+    }
+}
+
+fn dpad_name(dpad: crate::DPad) -> String {
+    format!("{:?}", dpad).to_lowercase()
+}
+
+fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn csv_escape(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
@@ -0,0 +1,175 @@
+use crate::virtual_pad::VirtualPad;
+use crate::{ButtonId, Result, Snapshot};
+use std::time::{Duration, Instant};
+
+/// A recorded sequence of snapshots, each stamped with its offset from the start.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Macro {
+    pub frames: Vec<(Duration, Snapshot)>,
+}
+
+impl Macro {
+    pub fn duration(&self) -> Duration {
+        self.frames.last().map_or(Duration::ZERO, |(at, _)| *at)
+    }
+
+    /// The frame in effect `t` after the start.
+    pub fn frame_at(&self, t: Duration) -> Option<&Snapshot> {
+        let i = self.frames.partition_point(|(at, _)| *at <= t);
+        self.frames.get(i.saturating_sub(1)).map(|(_, s)| s)
+    }
+}
+
+#[derive(Default)]
+pub struct MacroRecorder {
+    start: Option<Instant>,
+    recording: Macro,
+}
+
+impl MacroRecorder {
+    pub fn new() -> Self {
+        MacroRecorder::default()
+    }
+
+    /// The first recorded frame defines the macro's start.
+    pub fn record(&mut self, at: Instant, snapshot: Snapshot) {
+        let start = *self.start.get_or_insert(at);
+        self.recording
+            .frames
+            .push((at.saturating_duration_since(start), snapshot));
+    }
+
+    pub fn finish(self) -> Macro {
+        self.recording
+    }
+}
+
+pub struct MacroPlayer {
+    recording: Macro,
+    /// Number of times to play the macro; 0 repeats until stopped.
+    pub loops: u32,
+    /// Playback speed multiplier, e.g. 2.0 plays twice as fast. Negative or NaN
+    /// speeds hold the first frame; one too fast to time jumps to the end.
+    pub speed: f32,
+    started: Option<Instant>,
+}
+
+impl MacroPlayer {
+    pub fn new(recording: Macro) -> Self {
+        MacroPlayer {
+            recording,
+            loops: 1,
+            speed: 1.0,
+            started: None,
+        }
+    }
+
+    pub fn start(&mut self, at: Instant) {
+        self.started = Some(at);
+    }
+
+    pub fn stop(&mut self) {
+        self.started = None;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.started.is_some()
+    }
+
+    /// The frame to output at `at`, or `None` once playback has finished.
+    pub fn frame(&mut self, at: Instant) -> Option<Snapshot> {
+        let started = self.started?;
+        let length = self.recording.duration().max(Duration::from_millis(1));
+        let elapsed = at.saturating_duration_since(started).as_secs_f64();
+        let t = Duration::try_from_secs_f64(elapsed * self.speed.max(0.0) as f64)
+            .unwrap_or(Duration::MAX);
+        let iteration = u64::try_from(t.as_nanos() / length.as_nanos()).unwrap_or(u64::MAX);
+        if self.loops != 0 && iteration >= self.loops as u64 {
+            self.started = None;
+            return None;
+        }
+        let offset = Duration::from_nanos((t.as_nanos() % length.as_nanos()) as u64);
+        self.recording.frame_at(offset).copied()
+    }
+}
+
+/// Forwards live input to a virtual pad, replacing it with macro playback
+/// while the macro is running. Pressing `trigger` starts or cancels playback.
+pub struct MacroForwarder<P> {
+    pub pad: P,
+    pub player: MacroPlayer,
+    pub trigger: ButtonId,
+    triggered: bool,
+}
+
+impl<P: VirtualPad> MacroForwarder<P> {
+    pub fn new(pad: P, player: MacroPlayer, trigger: ButtonId) -> Self {
+        MacroForwarder {
+            pad,
+            player,
+            trigger,
+            triggered: false,
+        }
+    }
+
+    pub fn update(&mut self, at: Instant, live: &Snapshot) -> Result<()> {
+        let triggered = live.pressed(self.trigger);
+        if triggered && !self.triggered {
+            if self.player.is_playing() {
+                self.player.stop();
+            } else {
+                self.player.start(at);
+            }
+        }
+        self.triggered = triggered;
+
+        match self.player.frame(at) {
+            Some(frame) => self.pad.emit(&frame),
+            None => self.pad.emit(live),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player_at(speed: f32) -> MacroPlayer {
+        let mut pressed = Snapshot::default();
+        pressed.set_pressed(ButtonId::X, true);
+        let recording = Macro {
+            frames: vec![
+                (Duration::ZERO, Snapshot::default()),
+                (Duration::from_millis(100), pressed),
+                (Duration::from_millis(200), Snapshot::default()),
+            ],
+        };
+        MacroPlayer {
+            speed,
+            ..MacroPlayer::new(recording)
+        }
+    }
+
+    #[test]
+    fn any_speed_plays_without_panicking() {
+        let start = Instant::now();
+        for speed in [-1.0, 0.0, 2.0, 1e30, f32::INFINITY, f32::NAN] {
+            let mut player = player_at(speed);
+            player.start(start);
+            let _ = player.frame(start);
+            let _ = player.frame(start + Duration::from_secs(3600));
+        }
+    }
+
+    #[test]
+    fn speed_scales_playback() {
+        let start = Instant::now();
+        let mut player = player_at(2.0);
+        player.start(start);
+        let at = start + Duration::from_millis(60);
+        assert!(player.frame(at).is_some_and(|s| s.pressed(ButtonId::X)));
+        let mut slow = player_at(0.5);
+        slow.start(start);
+        assert!(slow.frame(at).is_some_and(|s| !s.pressed(ButtonId::X)));
+    }
+}
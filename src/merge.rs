@@ -0,0 +1,90 @@
+use crate::socd::{SocdPolicy, SocdResolver};
+use crate::{ButtonFlags, Controller, Snapshot, Stick};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AxisPolicy {
+    /// Deflections are added together and clamped.
+    Sum,
+    /// The first source whose axis is outside `deadzone` wins.
+    Priority { deadzone: f32 },
+    /// The largest deflection wins.
+    Largest,
+}
+
+/// Combines several pads into one logical device ("co-pilot" mode): buttons are
+/// OR'd, every source's dpad directions are combined with opposites resolved by
+/// `socd`, and axes follow `policy`. Sources are in priority order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Merger {
+    pub policy: AxisPolicy,
+    pub socd: SocdResolver,
+}
+
+impl Default for Merger {
+    fn default() -> Self {
+        Merger::new(AxisPolicy::Priority { deadzone: 0.1 })
+    }
+}
+
+impl Merger {
+    pub fn new(policy: AxisPolicy) -> Self {
+        Merger {
+            policy,
+            socd: SocdResolver::default(),
+        }
+    }
+
+    pub fn socd_policy(mut self, policy: SocdPolicy) -> Self {
+        self.socd = SocdResolver::new(policy);
+        self
+    }
+
+    /// Call once per update with every source, in the same order each time, so
+    /// `LastWins` and `FirstWins` can tell which direction came first.
+    pub fn merge(&mut self, sources: &[Snapshot]) -> Snapshot {
+        let mut out = Snapshot::default();
+        let pressed = sources
+            .iter()
+            .fold(ButtonFlags::empty(), |flags, s| flags | s.buttons());
+        out.set_buttons(pressed & ButtonFlags::BUTTONS);
+        out.dpad = self.socd.merge(sources.iter().map(|s| s.dpad));
+
+        out.left_stick = self.merge_stick(sources.iter().map(|s| s.left_stick));
+        out.right_stick = self.merge_stick(sources.iter().map(|s| s.right_stick));
+        out.l2_value = self.merge_trigger(sources.iter().map(|s| s.l2_value));
+        out.r2_value = self.merge_trigger(sources.iter().map(|s| s.r2_value));
+        out
+    }
+
+    pub fn merge_controllers(&mut self, controllers: &[Controller]) -> Snapshot {
+        let snapshots: Vec<Snapshot> = controllers.iter().map(|c| c.controls.snapshot()).collect();
+        self.merge(&snapshots)
+    }
+
+    fn merge_stick(&self, sticks: impl Iterator<Item = Stick> + Clone) -> Stick {
+        let x = self.merge_axis(sticks.clone().map(|s| s.x as f32 - 128.0), 128.0);
+        let y = self.merge_axis(sticks.map(|s| s.y as f32 - 128.0), 128.0);
+        Stick::new(
+            (x + 128.0).clamp(0.0, 255.0) as u8,
+            (y + 128.0).clamp(0.0, 255.0) as u8,
+        )
+    }
+
+    fn merge_trigger(&self, values: impl Iterator<Item = u8>) -> u8 {
+        self.merge_axis(values.map(|v| v as f32), 255.0)
+            .clamp(0.0, 255.0) as u8
+    }
+
+    /// `values` are offsets from rest; `range` is the offset at full deflection.
+    fn merge_axis(&self, mut values: impl Iterator<Item = f32>, range: f32) -> f32 {
+        match self.policy {
+            AxisPolicy::Sum => values.sum(),
+            AxisPolicy::Priority { deadzone } => {
+                values.find(|v| v.abs() > deadzone * range).unwrap_or(0.0)
+            }
+            AxisPolicy::Largest => values
+                .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+                .unwrap_or(0.0),
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use crate::latency::Histogram;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[cfg(feature = "server")]
+pub use server::{serve, serve_on};
+
+/// Counters shared between a controller and a metrics endpoint.
+pub struct Metrics {
+    pub reports: AtomicU64,
+    pub parse_errors: AtomicU64,
+    /// Reports skipped according to the pad's 6-bit report counter.
+    pub dropped_reports: AtomicU64,
+    pub reconnects: AtomicU64,
+    pub output_writes: AtomicU64,
+    /// Output changes merged into an already-pending write.
+    pub coalesced_outputs: AtomicU64,
+    pub connected: AtomicBool,
+    battery_percent: AtomicU32,
+    imu_temperature: AtomicU32,
+    intervals: Mutex<Histogram>,
+    dispatch: Mutex<Histogram>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            reports: AtomicU64::new(0),
+            parse_errors: AtomicU64::new(0),
+            dropped_reports: AtomicU64::new(0),
+            reconnects: AtomicU64::new(0),
+            output_writes: AtomicU64::new(0),
+            coalesced_outputs: AtomicU64::new(0),
+            connected: AtomicBool::new(false),
+            battery_percent: AtomicU32::new(f32::NAN.to_bits()),
+            imu_temperature: AtomicU32::new(u32::MAX),
+            intervals: Mutex::new(Histogram::new(Duration::from_micros(250), 200)),
+            dispatch: Mutex::new(Histogram::new(Duration::from_micros(5), 400)),
+        }
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics::default()
+    }
+
+    pub fn set_battery_percent(&self, percent: f32) {
+        self.battery_percent
+            .store(percent.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn set_imu_temperature(&self, raw: u8) {
+        self.imu_temperature.store(raw as u32, Ordering::Relaxed);
+    }
+
+    pub fn record_interval(&self, interval: Duration) {
+        if let Ok(mut h) = self.intervals.lock() {
+            h.record(interval);
+        }
+    }
+
+    /// Records how long a report took from being read to its handlers having
+    /// run.
+    pub fn record_dispatch(&self, latency: Duration) {
+        if let Ok(mut h) = self.dispatch.lock() {
+            h.record(latency);
+        }
+    }
+
+    /// Renders the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            let _ = writeln!(out, "{} {}", name, value);
+        };
+        let load = |c: &AtomicU64| c.load(Ordering::Relaxed).to_string();
+
+        metric(
+            "ds4_reports_total",
+            "counter",
+            "Input reports read.",
+            load(&self.reports),
+        );
+        metric(
+            "ds4_parse_errors_total",
+            "counter",
+            "Input reports that failed to parse.",
+            load(&self.parse_errors),
+        );
+        metric(
+            "ds4_dropped_reports_total",
+            "counter",
+            "Input reports lost according to the report counter.",
+            load(&self.dropped_reports),
+        );
+        metric(
+            "ds4_reconnects_total",
+            "counter",
+            "Times the controller was reopened.",
+            load(&self.reconnects),
+        );
+        metric(
+            "ds4_output_writes_total",
+            "counter",
+            "Output reports written.",
+            load(&self.output_writes),
+        );
+        metric(
+            "ds4_output_coalesced_total",
+            "counter",
+            "Output changes merged into a pending write instead of sent alone.",
+            load(&self.coalesced_outputs),
+        );
+        metric(
+            "ds4_connected",
+            "gauge",
+            "Whether a controller is open.",
+            (self.connected.load(Ordering::Relaxed) as u8).to_string(),
+        );
+        let battery = f32::from_bits(self.battery_percent.load(Ordering::Relaxed));
+        if !battery.is_nan() {
+            metric(
+                "ds4_battery_percent",
+                "gauge",
+                "Smoothed battery charge.",
+                battery.to_string(),
+            );
+        }
+        let temperature = self.imu_temperature.load(Ordering::Relaxed);
+        if temperature != u32::MAX {
+            metric(
+                "ds4_imu_temperature_raw",
+                "gauge",
+                "Raw IMU temperature reading; correlates with gyro bias drift.",
+                temperature.to_string(),
+            );
+        }
+
+        if let Ok(h) = self.intervals.lock() {
+            summary(
+                &mut out,
+                "ds4_report_interval_seconds",
+                "Time between input reports.",
+                &h,
+            );
+        }
+        if let Ok(h) = self.dispatch.lock() {
+            summary(
+                &mut out,
+                "ds4_dispatch_latency_seconds",
+                "Time from reading an input report to its handlers having run.",
+                &h,
+            );
+        }
+        out
+    }
+}
+
+fn summary(out: &mut String, name: &str, help: &str, h: &Histogram) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} summary", name);
+    for q in [0.5, 0.9, 0.99] {
+        if let Some(v) = h.quantile(q) {
+            let _ = writeln!(out, "{}{{quantile=\"{}\"}} {}", name, q, v.as_secs_f64());
+        }
+    }
+    let sum = h.mean().map_or(0.0, |m| m.as_secs_f64() * h.count() as f64);
+    let _ = writeln!(out, "{}_sum {}", name, sum);
+    let _ = writeln!(out, "{}_count {}", name, h.count());
+}
+
+#[cfg(feature = "server")]
+mod server {
+    use super::Metrics;
+    use std::io::{self, BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread::{self, JoinHandle};
+    use std::time::Duration;
+
+    /// A client that hasn't sent its request line, or read the response, by
+    /// then is dropped.
+    const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+    /// Longer request lines are refused; `GET /metrics HTTP/1.1` is 24 bytes.
+    const MAX_REQUEST_LINE: u64 = 1024;
+    /// Connections served at once; more are closed straight away.
+    const MAX_CLIENTS: usize = 16;
+
+    /// Serves `GET /metrics` on a background thread, each client on a thread of
+    /// its own so a slow one can't hold up the rest.
+    pub fn serve(addr: impl ToSocketAddrs, metrics: Arc<Metrics>) -> io::Result<JoinHandle<()>> {
+        Ok(serve_on(TcpListener::bind(addr)?, metrics))
+    }
+
+    /// Like `serve`, on a listener that's already bound.
+    pub fn serve_on(listener: TcpListener, metrics: Arc<Metrics>) -> JoinHandle<()> {
+        let clients = Arc::new(AtomicUsize::new(0));
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if clients.fetch_add(1, Ordering::AcqRel) >= MAX_CLIENTS {
+                    clients.fetch_sub(1, Ordering::AcqRel);
+                    continue;
+                }
+                let metrics = metrics.clone();
+                let active = clients.clone();
+                let spawned = thread::Builder::new()
+                    .name("ds4-metrics-client".into())
+                    .spawn(move || {
+                        let _ = respond(&stream, &metrics);
+                        active.fetch_sub(1, Ordering::AcqRel);
+                    });
+                if spawned.is_err() {
+                    clients.fetch_sub(1, Ordering::AcqRel);
+                }
+            }
+        })
+    }
+
+    fn respond(stream: &TcpStream, metrics: &Metrics) -> io::Result<()> {
+        stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+        let mut request_line = String::new();
+        BufReader::new(stream.take(MAX_REQUEST_LINE)).read_line(&mut request_line)?;
+        let response = if !request_line.ends_with('\n') {
+            "HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+        } else if request_line.split_whitespace().nth(1) == Some("/metrics") {
+            let body = metrics.render();
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        } else {
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+        };
+        let mut stream = stream;
+        stream.write_all(response.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exports_dispatch_latency_apart_from_report_interval() {
+        let metrics = Metrics::new();
+        metrics.record_interval(Duration::from_millis(4));
+        metrics.record_dispatch(Duration::from_micros(30));
+        let text = metrics.render();
+        assert!(text.contains("ds4_report_interval_seconds_count 1"));
+        assert!(text.contains("ds4_dispatch_latency_seconds_count 1"));
+    }
+
+    #[cfg(feature = "server")]
+    mod server {
+        use super::super::*;
+        use std::io::{Read, Write};
+        use std::net::{TcpListener, TcpStream};
+        use std::sync::Arc;
+
+        fn get(addr: std::net::SocketAddr, request: &[u8]) -> String {
+            let mut response = String::new();
+            if let Ok(mut stream) = TcpStream::connect(addr) {
+                let _ = stream.write_all(request);
+                let _ = stream.read_to_string(&mut response);
+            }
+            response
+        }
+
+        #[test]
+        fn idle_client_does_not_block_others() {
+            let Ok(listener) = TcpListener::bind("127.0.0.1:0") else {
+                return;
+            };
+            let Ok(addr) = listener.local_addr() else {
+                return;
+            };
+            serve_on(listener, Arc::new(Metrics::new()));
+            let _idle = TcpStream::connect(addr);
+            let response = get(addr, b"GET /metrics HTTP/1.1\r\n\r\n");
+            assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+            assert!(response.contains("ds4_reports_total 0"));
+        }
+
+        #[test]
+        fn overlong_request_line_is_refused() {
+            let Ok(listener) = TcpListener::bind("127.0.0.1:0") else {
+                return;
+            };
+            let Ok(addr) = listener.local_addr() else {
+                return;
+            };
+            serve_on(listener, Arc::new(Metrics::new()));
+            let mut request = b"GET /".to_vec();
+            // Exactly the cap, so the server reads it all and closes cleanly.
+            request.resize(1024, b'a');
+            let response = get(addr, &request);
+            assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::audio::{PcmSource, SampleSource};
+use crate::{Error, Result};
+use std::fs;
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+/// The DS4's USB audio interface records mono 16 kHz from the headset jack.
+pub const MIC_SAMPLE_RATE: u32 = 16000;
+
+/// Finds the ALSA card id of a USB-connected DS4, e.g. `"Controller"`.
+pub fn find_card() -> Option<String> {
+    let cards = fs::read_to_string("/proc/asound/cards").ok()?;
+    cards
+        .lines()
+        .filter(|line| line.contains("Wireless Controller"))
+        .find_map(|line| {
+            let start = line.find('[')? + 1;
+            let end = line[start..].find(']')? + start;
+            Some(line[start..end].trim().to_string())
+        })
+}
+
+/// Captures the headset microphone through ALSA. Only available over USB: the
+/// pad doesn't stream mic audio over Bluetooth to non-console hosts.
+///
+/// Recording runs `arecord`, so alsa-utils must be installed at runtime; without
+/// it opening fails with `Error::Io` of kind `NotFound`.
+pub struct MicCapture {
+    child: Child,
+    pcm: PcmSource<ChildStdout>,
+    /// Samples for `read_pcm` to convert, kept between calls.
+    samples: Vec<f32>,
+}
+
+impl MicCapture {
+    pub fn open() -> Result<Self> {
+        let card = find_card().ok_or(Error::NotFound)?;
+        MicCapture::open_card(&card)
+    }
+
+    pub fn open_card(card: &str) -> Result<Self> {
+        let mut child = Command::new("arecord")
+            .args(["-q", "-t", "raw", "-f", "S16_LE", "-c", "1"])
+            .args(["-r", &MIC_SAMPLE_RATE.to_string()])
+            .args(["-D", &format!("plughw:CARD={}", card)])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or(Error::InvalidFormat("arecord has no stdout"))?;
+        Ok(MicCapture {
+            child,
+            pcm: PcmSource::new(stdout, MIC_SAMPLE_RATE, 1),
+            samples: Vec::new(),
+        })
+    }
+
+    /// Reads signed 16-bit PCM frames; returns how many were read (0 at end of stream).
+    /// Allocates only when `frames` is longer than on any earlier call.
+    pub fn read_pcm(&mut self, frames: &mut [i16]) -> Result<usize> {
+        self.samples.resize(frames.len(), 0.0);
+        let n = self.pcm.read(&mut self.samples)?;
+        for (out, s) in frames.iter_mut().zip(&self.samples[..n]) {
+            *out = (s * 32768.0).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+        }
+        Ok(n)
+    }
+}
+
+impl SampleSource for MicCapture {
+    fn sample_rate(&self) -> u32 {
+        MIC_SAMPLE_RATE
+    }
+
+    fn read(&mut self, buf: &mut [f32]) -> Result<usize> {
+        self.pcm.read(buf)
+    }
+}
+
+impl Drop for MicCapture {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
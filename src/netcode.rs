@@ -0,0 +1,301 @@
+use crate::wire::{self, WIRE_LEN, WIRE_VERSION};
+use crate::Snapshot;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+pub const TICK_INPUT_LEN: usize = WIRE_LEN - 1;
+/// Ticks of local input `InputTicker` keeps for resimulation.
+const LOCAL_HISTORY: usize = 256;
+
+/// The full control state for one simulation tick: a `wire` message without its
+/// version byte, since peers settle on a version when the session starts.
+///
+/// The encoding is exact, so peers that exchange it simulate from identical input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TickInput([u8; TICK_INPUT_LEN]);
+
+impl Default for TickInput {
+    fn default() -> Self {
+        TickInput::NEUTRAL
+    }
+}
+
+impl TickInput {
+    /// Nothing pressed, sticks centered.
+    pub const NEUTRAL: TickInput = TickInput([0, 0, 0x08, 0x80, 0x80, 0x80, 0x80, 0, 0]);
+
+    pub fn from_snapshot(snapshot: &Snapshot) -> Self {
+        let mut input = [0; TICK_INPUT_LEN];
+        input.copy_from_slice(&wire::encode(snapshot)[1..]);
+        TickInput(input)
+    }
+
+    pub fn to_snapshot(&self) -> Snapshot {
+        // Every constructor validates, so this always decodes.
+        wire::decode(&self.message()).unwrap_or_default()
+    }
+
+    /// `None` unless `bytes` is a valid `TICK_INPUT_LEN`-byte input, e.g. one
+    /// received from a peer.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let input = TickInput(bytes.try_into().ok()?);
+        wire::decode(&input.message()).ok()?;
+        Some(input)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; TICK_INPUT_LEN] {
+        &self.0
+    }
+
+    fn message(&self) -> [u8; WIRE_LEN] {
+        let mut message = [WIRE_VERSION; WIRE_LEN];
+        message[1..].copy_from_slice(&self.0);
+        message
+    }
+}
+
+/// Buckets live input into fixed simulation ticks counted from `epoch`, scheduling
+/// each tick's input `delay` ticks later to hide network latency.
+///
+/// Call `sample` with the latest state each frame; every tick boundary crossed
+/// since the last call yields one input, so a slow frame still produces a
+/// contiguous run of ticks.
+#[derive(Debug, Clone)]
+pub struct InputTicker {
+    tick: Duration,
+    delay: u64,
+    epoch: Instant,
+    /// The next tick `sample` will produce input for, before delay.
+    next: u64,
+    history: VecDeque<(u64, TickInput)>,
+}
+
+impl InputTicker {
+    pub fn new(tick_hz: u32, delay: u64, epoch: Instant) -> Self {
+        InputTicker {
+            tick: Duration::from_secs(1) / tick_hz.max(1),
+            delay,
+            epoch,
+            next: 0,
+            history: VecDeque::with_capacity(LOCAL_HISTORY),
+        }
+    }
+
+    pub fn tick_length(&self) -> Duration {
+        self.tick
+    }
+
+    pub fn delay(&self) -> u64 {
+        self.delay
+    }
+
+    /// The simulation tick in progress at `at`.
+    pub fn tick_at(&self, at: Instant) -> u64 {
+        (at.saturating_duration_since(self.epoch).as_nanos() / self.tick.as_nanos().max(1)) as u64
+    }
+
+    /// Records `snapshot` for every tick that has ended by `at`, returning
+    /// `(tick, input)` pairs with the delay already applied.
+    pub fn sample(&mut self, snapshot: &Snapshot, at: Instant) -> Vec<(u64, TickInput)> {
+        let input = TickInput::from_snapshot(snapshot);
+        let now = self.tick_at(at);
+        let mut produced = Vec::new();
+        while self.next < now {
+            let tick = self.next + self.delay;
+            if self.history.len() == LOCAL_HISTORY {
+                self.history.pop_front();
+            }
+            self.history.push_back((tick, input));
+            produced.push((tick, input));
+            self.next += 1;
+        }
+        produced
+    }
+
+    /// The local input scheduled for `tick`. Ticks inside the initial delay have
+    /// neutral input; `None` means the tick hasn't been sampled yet or has fallen
+    /// out of the history.
+    pub fn input(&self, tick: u64) -> Option<TickInput> {
+        if tick < self.delay {
+            return Some(TickInput::NEUTRAL);
+        }
+        self.history
+            .iter()
+            .find(|&&(t, _)| t == tick)
+            .map(|&(_, input)| input)
+    }
+}
+
+/// A remote player's inputs for rollback netcode: confirmed ticks are served as
+/// received, unconfirmed ones are predicted by repeating the last confirmed
+/// input, and a confirmation that contradicts a prediction reports the tick to
+/// roll back to.
+#[derive(Debug, Clone, Default)]
+pub struct RollbackBuffer {
+    confirmed: BTreeMap<u64, TickInput>,
+    predicted: BTreeMap<u64, TickInput>,
+}
+
+impl RollbackBuffer {
+    pub fn new() -> Self {
+        RollbackBuffer::default()
+    }
+
+    /// The input to simulate `tick` with, predicting it if unconfirmed.
+    pub fn input(&mut self, tick: u64) -> TickInput {
+        if let Some(&input) = self.confirmed.get(&tick) {
+            return input;
+        }
+        let guess = self.prediction(tick);
+        self.predicted.insert(tick, guess);
+        guess
+    }
+
+    pub fn is_confirmed(&self, tick: u64) -> bool {
+        self.confirmed.contains_key(&tick)
+    }
+
+    /// The newest tick confirmed so far.
+    pub fn last_confirmed(&self) -> Option<u64> {
+        self.confirmed.keys().next_back().copied()
+    }
+
+    /// Stores the real input for `tick`. Returns the earliest tick that was
+    /// simulated with a wrong prediction and must be resimulated, if any; later
+    /// predictions that no longer match are dropped so `input` predicts them again.
+    pub fn confirm(&mut self, tick: u64, input: TickInput) -> Option<u64> {
+        self.confirmed.insert(tick, input);
+        let mispredicted = self.predicted.remove(&tick).is_some_and(|p| p != input);
+        let stale: Vec<u64> = self
+            .predicted
+            .range(tick + 1..)
+            .filter(|&(&t, &p)| self.prediction(t) != p)
+            .map(|(&t, _)| t)
+            .collect();
+        for t in &stale {
+            self.predicted.remove(t);
+        }
+        if mispredicted {
+            Some(tick)
+        } else {
+            stale.first().copied()
+        }
+    }
+
+    /// The newest confirmed input before `tick`, or neutral input.
+    fn prediction(&self, tick: u64) -> TickInput {
+        self.confirmed
+            .range(..tick)
+            .next_back()
+            .map(|(_, &input)| input)
+            .unwrap_or(TickInput::NEUTRAL)
+    }
+
+    /// Forgets ticks before `tick`, e.g. once every peer has confirmed them. The
+    /// newest confirmed input is kept for prediction.
+    pub fn discard_before(&mut self, tick: u64) {
+        let keep = self
+            .confirmed
+            .range(..tick)
+            .next_back()
+            .map(|(&t, &i)| (t, i));
+        self.confirmed = self.confirmed.split_off(&tick);
+        if let Some((t, input)) = keep {
+            self.confirmed.entry(t).or_insert(input);
+        }
+        self.predicted = self.predicted.split_off(&tick);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ButtonId;
+
+    fn pressed(id: ButtonId) -> TickInput {
+        let mut snapshot = Snapshot::default();
+        snapshot.set_pressed(id, true);
+        TickInput::from_snapshot(&snapshot)
+    }
+
+    #[test]
+    fn tick_input_round_trips() {
+        let input = pressed(ButtonId::X);
+        assert_eq!(TickInput::from_bytes(input.as_bytes()), Some(input));
+        assert!(input.to_snapshot().pressed(ButtonId::X));
+        assert_eq!(
+            TickInput::from_snapshot(&Snapshot::default()),
+            TickInput::NEUTRAL
+        );
+        assert_eq!(TickInput::from_bytes(&[0; TICK_INPUT_LEN - 1]), None);
+    }
+
+    #[test]
+    fn slow_frames_yield_every_tick_after_the_delay() {
+        let epoch = Instant::now();
+        let mut ticker = InputTicker::new(100, 2, epoch);
+        let input = pressed(ButtonId::Circle);
+        let produced = ticker.sample(&input.to_snapshot(), epoch + Duration::from_millis(35));
+        let ticks: Vec<u64> = produced.iter().map(|&(t, _)| t).collect();
+        assert_eq!(ticks, [2, 3, 4]);
+        assert_eq!(ticker.input(0), Some(TickInput::NEUTRAL));
+        assert_eq!(ticker.input(3), Some(input));
+        assert_eq!(ticker.input(5), None);
+    }
+
+    #[test]
+    fn unconfirmed_ticks_repeat_the_last_confirmed_input() {
+        let mut remote = RollbackBuffer::new();
+        assert_eq!(remote.input(0), TickInput::NEUTRAL);
+        let input = pressed(ButtonId::Square);
+        assert_eq!(remote.confirm(0, input), Some(0));
+        assert_eq!(remote.input(1), input);
+        assert_eq!(remote.input(2), input);
+        assert!(!remote.is_confirmed(2));
+        assert_eq!(remote.last_confirmed(), Some(0));
+    }
+
+    #[test]
+    fn correct_predictions_need_no_rollback() {
+        let mut remote = RollbackBuffer::new();
+        let input = pressed(ButtonId::Triangle);
+        remote.confirm(0, input);
+        remote.input(1);
+        remote.input(2);
+        assert_eq!(remote.confirm(1, input), None);
+        assert_eq!(remote.confirm(2, input), None);
+    }
+
+    #[test]
+    fn a_misprediction_rolls_back_to_its_tick() {
+        let mut remote = RollbackBuffer::new();
+        remote.confirm(0, TickInput::NEUTRAL);
+        for tick in 1..=4 {
+            remote.input(tick);
+        }
+        let input = pressed(ButtonId::L1);
+        assert_eq!(remote.confirm(2, input), Some(2));
+        // Ticks 3 and 4 are predicted again from the new input.
+        assert_eq!(remote.input(3), input);
+        assert_eq!(remote.confirm(3, input), None);
+    }
+
+    #[test]
+    fn an_earlier_confirmation_rolls_back_to_the_first_stale_prediction() {
+        let mut remote = RollbackBuffer::new();
+        remote.input(5);
+        remote.input(6);
+        assert_eq!(remote.confirm(3, pressed(ButtonId::R1)), Some(5));
+    }
+
+    #[test]
+    fn discarding_keeps_the_input_to_predict_from() {
+        let mut remote = RollbackBuffer::new();
+        let input = pressed(ButtonId::Options);
+        remote.confirm(1, input);
+        remote.confirm(2, input);
+        remote.discard_before(10);
+        assert_eq!(remote.last_confirmed(), Some(2));
+        assert_eq!(remote.input(10), input);
+    }
+}
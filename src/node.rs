@@ -0,0 +1,462 @@
+use crate::{ButtonId, Connection, Controller, Error, Event};
+use hidapi::HidApi;
+use napi::bindgen_prelude::AsyncTask;
+use napi::{Env, Status, Task};
+use napi_derive::napi;
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+/// hidapi allows one context per process, so every controller is opened from
+/// this one, which is never dropped.
+static API: Mutex<Option<HidApi>> = Mutex::new(None);
+
+/// Events kept for `events`; older ones are dropped if JavaScript never asks.
+const EVENT_LIMIT: usize = 256;
+
+fn failed(e: Error) -> napi::Error {
+    napi::Error::new(Status::GenericFailure, e.to_string())
+}
+
+/// One finger on the touchpad.
+#[napi(object, js_name = "Touch")]
+#[derive(Clone)]
+pub struct NodeTouch {
+    /// Incremented by the pad for each new contact (7 bits).
+    pub id: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+/// Every control's state at one report.
+#[napi(object, js_name = "State")]
+#[derive(Clone)]
+pub struct NodeState {
+    /// Names of the pressed buttons, e.g. `"x"` and `"l1"`.
+    pub buttons: Vec<String>,
+    /// `"released"`, or a compass direction such as `"north"` or `"southeast"`.
+    pub dpad: String,
+    /// Raw stick axes, 0 left or up to 255 right or down.
+    pub left_x: u32,
+    pub left_y: u32,
+    pub right_x: u32,
+    pub right_y: u32,
+    pub l2: u32,
+    pub r2: u32,
+    /// Raw IMU readings.
+    pub gyro: Vec<i32>,
+    pub accel: Vec<i32>,
+    /// Fingers on the touchpad.
+    pub touches: Vec<NodeTouch>,
+    pub battery_percent: Option<u32>,
+    pub charging: bool,
+}
+
+impl NodeState {
+    fn from_controller(controller: &Controller) -> Self {
+        let snapshot = controller.controls.snapshot();
+        let battery = controller.battery();
+        NodeState {
+            buttons: ButtonId::ALL
+                .into_iter()
+                .filter(|&id| snapshot.pressed(id))
+                .map(|id| id.name().to_string())
+                .collect(),
+            dpad: format!("{:?}", snapshot.dpad).to_lowercase(),
+            left_x: snapshot.left_stick.x.into(),
+            left_y: snapshot.left_stick.y.into(),
+            right_x: snapshot.right_stick.x.into(),
+            right_y: snapshot.right_stick.y.into(),
+            l2: snapshot.l2_value.into(),
+            r2: snapshot.r2_value.into(),
+            gyro: controller.controls.imu.gyro.map(i32::from).to_vec(),
+            accel: controller.controls.imu.accel.map(i32::from).to_vec(),
+            touches: controller
+                .controls
+                .touches
+                .state()
+                .iter()
+                .filter(|t| t.active)
+                .map(|t| NodeTouch {
+                    id: t.id.into(),
+                    x: t.x.into(),
+                    y: t.y.into(),
+                })
+                .collect(),
+            battery_percent: battery
+                .percent()
+                .map(|p| p.round().clamp(0.0, 100.0) as u32),
+            charging: battery.cable_connected(),
+        }
+    }
+}
+
+/// Something the controller noticed besides input, such as a paired host
+/// changing or output stalling. `kind` names the variant of `ps4hid::Event`.
+#[napi(discriminant = "kind", js_name = "Event")]
+pub enum NodeEvent {
+    PairingChanged {
+        /// Bluetooth addresses, e.g. `"a4:ae:12:00:00:01"`.
+        device: String,
+        host: String,
+        previous_host: Option<String>,
+    },
+    Resumed,
+    PeripheralsChanged {
+        headphones: bool,
+        microphone: bool,
+        extension: bool,
+    },
+    OutputStalled {
+        failures: u32,
+        error: String,
+    },
+    OutputRecovered,
+    HandlerPanicked {
+        control: String,
+        message: String,
+    },
+    GyroRecalibrated {
+        /// Raw units per axis.
+        bias: Vec<f64>,
+    },
+    StateChanged {
+        /// Lifecycle states such as `"handshaking"` and `"streaming"`.
+        previous: String,
+        current: String,
+    },
+    ChoreographyStarted {
+        /// `"connect"`, `"disconnect"` or `"identify"`.
+        cue: String,
+    },
+    ChoreographyFinished {
+        cue: String,
+    },
+    InputBacklog {
+        skipped: u32,
+        behind_ms: f64,
+    },
+    ConfigIgnored {
+        path: String,
+        error: String,
+    },
+    Marker {
+        label: String,
+        /// Device time of the mark, in the pad's clock.
+        device_time_ms: f64,
+    },
+}
+
+impl From<Event> for NodeEvent {
+    fn from(event: Event) -> Self {
+        let ms = |d: std::time::Duration| d.as_secs_f64() * 1000.0;
+        let cue = |cue| format!("{:?}", cue).to_lowercase();
+        match event {
+            Event::PairingChanged { previous, current } => NodeEvent::PairingChanged {
+                device: current.device.to_string(),
+                host: current.host.to_string(),
+                previous_host: previous.map(|p| p.host.to_string()),
+            },
+            Event::Resumed => NodeEvent::Resumed,
+            Event::PeripheralsChanged { current, .. } => NodeEvent::PeripheralsChanged {
+                headphones: current.headphones,
+                microphone: current.microphone,
+                extension: current.extension,
+            },
+            Event::OutputStalled { failures, error } => {
+                NodeEvent::OutputStalled { failures, error }
+            }
+            Event::OutputRecovered => NodeEvent::OutputRecovered,
+            Event::HandlerPanicked(panic) => NodeEvent::HandlerPanicked {
+                control: panic.control.to_string(),
+                message: panic.message,
+            },
+            Event::GyroRecalibrated { bias } => NodeEvent::GyroRecalibrated {
+                bias: bias.map(f64::from).to_vec(),
+            },
+            Event::StateChanged(transition) => NodeEvent::StateChanged {
+                previous: transition.previous.to_string(),
+                current: transition.current.to_string(),
+            },
+            Event::ChoreographyStarted(c) => NodeEvent::ChoreographyStarted { cue: cue(c) },
+            Event::ChoreographyFinished(c) => NodeEvent::ChoreographyFinished { cue: cue(c) },
+            Event::InputBacklog { skipped, behind } => NodeEvent::InputBacklog {
+                skipped,
+                behind_ms: ms(behind),
+            },
+            Event::ConfigIgnored { path, error } => NodeEvent::ConfigIgnored {
+                path: path.display().to_string(),
+                error,
+            },
+            Event::Marker {
+                label, device_time, ..
+            } => NodeEvent::Marker {
+                label,
+                device_time_ms: ms(device_time),
+            },
+        }
+    }
+}
+
+/// What the reader thread last saw, for the JavaScript side to pick up.
+struct Latest {
+    /// Bumped for each report read, good or not.
+    reports: u64,
+    state: NodeState,
+    /// Why the most recent report was rejected, if it was.
+    failure: Option<String>,
+    events: VecDeque<NodeEvent>,
+    connection: Connection,
+    /// Why the reader stopped; `None` while it runs.
+    closed: Option<String>,
+}
+
+/// The latest-state cell, written by the reader thread and waited on by `update`.
+struct Shared {
+    latest: Mutex<Latest>,
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Latest> {
+        self.latest.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn stop(&self, reason: String) {
+        self.lock().closed = Some(reason);
+        self.changed.notify_all();
+    }
+}
+
+/// Output for the reader thread to apply between reads.
+enum Request {
+    Rumble(u8, u8),
+    Lightbar(u8, u8, u8),
+    Close,
+}
+
+/// Owns the controller: reads every report into `shared` and applies
+/// requests in between, so no lock is held while waiting on the pad.
+fn read_loop(
+    mut controller: Controller,
+    shared: &Shared,
+    requests: Receiver<Request>,
+) -> crate::Result<()> {
+    loop {
+        loop {
+            let result = match requests.try_recv() {
+                Ok(Request::Rumble(strong, weak)) => controller.set_rumble(strong, weak),
+                Ok(Request::Lightbar(r, g, b)) => controller.set_lightbar(r, g, b),
+                Err(TryRecvError::Empty) => break,
+                // The JavaScript object was collected without `close`; let go
+                // of the pad rather than read it for no one.
+                Ok(Request::Close) | Err(TryRecvError::Disconnected) => {
+                    shared.stop(Error::Closed.to_string());
+                    return controller.close();
+                }
+            };
+            // Other failures are retried by the output scheduler, which
+            // reports stalls as events.
+            if let Err(e) = result {
+                if e.is_device_error() {
+                    shared.stop(e.to_string());
+                    return Ok(());
+                }
+            }
+        }
+        let result = controller.update();
+        let mut latest = shared.lock();
+        for event in controller.events() {
+            if latest.events.len() == EVENT_LIMIT {
+                latest.events.pop_front();
+            }
+            latest.events.push_back(event.into());
+        }
+        latest.reports += 1;
+        latest.connection = controller.connection();
+        match result {
+            Ok(()) => {
+                latest.state = NodeState::from_controller(&controller);
+                latest.failure = None;
+            }
+            // Nothing more will come from this pad; `isOpen` tells the caller
+            // to open it again.
+            Err(e) if e.is_device_error() => {
+                latest.closed = Some(e.to_string());
+                drop(latest);
+                shared.changed.notify_all();
+                return Ok(());
+            }
+            Err(e) => latest.failure = Some(e.to_string()),
+        }
+        drop(latest);
+        shared.changed.notify_all();
+    }
+}
+
+/// Waits on the libuv thread pool for the reader to get past report `after`,
+/// so the JavaScript thread never blocks on the pad.
+pub struct Update {
+    shared: Arc<Shared>,
+    after: u64,
+}
+
+impl Task for Update {
+    type Output = NodeState;
+    type JsValue = NodeState;
+
+    fn compute(&mut self) -> napi::Result<NodeState> {
+        let mut latest = self.shared.lock();
+        loop {
+            if let Some(reason) = &latest.closed {
+                return Err(napi::Error::new(Status::GenericFailure, reason.clone()));
+            }
+            if latest.reports > self.after {
+                return match &latest.failure {
+                    Some(e) => Err(napi::Error::new(Status::GenericFailure, e.clone())),
+                    None => Ok(latest.state.clone()),
+                };
+            }
+            latest = self
+                .shared
+                .changed
+                .wait(latest)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    fn resolve(&mut self, _env: Env, state: NodeState) -> napi::Result<NodeState> {
+        Ok(state)
+    }
+}
+
+/// The first connected DS4, opened with its saved profile applied.
+///
+/// A thread of its own reads the pad; `update` resolves with each new state
+/// and output calls are queued for it, so neither waits on a read.
+#[napi(js_name = "Controller")]
+pub struct NodeController {
+    shared: Arc<Shared>,
+    requests: Sender<Request>,
+    reader: Mutex<Option<JoinHandle<crate::Result<()>>>>,
+}
+
+impl NodeController {
+    fn request(&self, request: Request) -> napi::Result<()> {
+        if let Some(reason) = &self.shared.lock().closed {
+            return Err(napi::Error::new(Status::GenericFailure, reason.clone()));
+        }
+        self.requests
+            .send(request)
+            .map_err(|_| failed(Error::Closed))
+    }
+}
+
+#[napi]
+impl NodeController {
+    #[napi(constructor)]
+    pub fn new() -> napi::Result<Self> {
+        let controller = {
+            let mut shared = API.lock().unwrap_or_else(|e| e.into_inner());
+            let api = match shared.as_mut() {
+                Some(api) => {
+                    api.refresh_devices().map_err(|e| failed(Error::from(e)))?;
+                    api
+                }
+                None => shared.insert(HidApi::new().map_err(|e| failed(Error::from(e)))?),
+            };
+            Controller::open(api).map_err(failed)?
+        };
+        let shared = Arc::new(Shared {
+            latest: Mutex::new(Latest {
+                reports: 0,
+                state: NodeState::from_controller(&controller),
+                failure: None,
+                events: VecDeque::new(),
+                connection: controller.connection(),
+                closed: None,
+            }),
+            changed: Condvar::new(),
+        });
+        let (requests, received) = channel();
+        let reading = shared.clone();
+        let reader = thread::Builder::new()
+            .name("ps4hid-reader".into())
+            .spawn(move || read_loop(controller, &reading, received))
+            .map_err(|e| failed(Error::Io(e)))?;
+        Ok(NodeController {
+            shared,
+            requests,
+            reader: Mutex::new(Some(reader)),
+        })
+    }
+
+    /// Resolves with the state once the next input report arrives. If it
+    /// rejects and `isOpen` is false, the pad has gone.
+    #[napi(ts_return_type = "Promise<State>")]
+    pub fn update(&self) -> AsyncTask<Update> {
+        AsyncTask::new(Update {
+            shared: self.shared.clone(),
+            after: self.shared.lock().reports,
+        })
+    }
+
+    /// The state as of the latest report.
+    #[napi]
+    pub fn state(&self) -> napi::Result<NodeState> {
+        let latest = self.shared.lock();
+        match &latest.closed {
+            Some(reason) => Err(napi::Error::new(Status::GenericFailure, reason.clone())),
+            None => Ok(latest.state.clone()),
+        }
+    }
+
+    /// Events that have accumulated since the last call, oldest first.
+    #[napi]
+    pub fn events(&self) -> Vec<NodeEvent> {
+        self.shared.lock().events.drain(..).collect()
+    }
+
+    /// Sets the strong (left) and weak (right) motor speeds, 0-255.
+    #[napi]
+    pub fn set_rumble(&self, strong: u8, weak: u8) -> napi::Result<()> {
+        self.request(Request::Rumble(strong, weak))
+    }
+
+    #[napi]
+    pub fn set_lightbar(&self, r: u8, g: u8, b: u8) -> napi::Result<()> {
+        self.request(Request::Lightbar(r, g, b))
+    }
+
+    /// `"usb"`, `"bluetooth"` or `"dongle"`.
+    #[napi(getter)]
+    pub fn connection(&self) -> &'static str {
+        match self.shared.lock().connection {
+            Connection::Usb => "usb",
+            Connection::Bluetooth => "bluetooth",
+            Connection::Dongle => "dongle",
+        }
+    }
+
+    /// False once the controller is closed or the pad has gone.
+    #[napi(getter)]
+    pub fn is_open(&self) -> bool {
+        self.shared.lock().closed.is_none()
+    }
+
+    /// Sends any held-back output and lets go of the pad, once the reader has
+    /// finished the report it's waiting for. Closing twice does nothing.
+    #[napi]
+    pub fn close(&self) -> napi::Result<()> {
+        let Some(reader) = self.reader.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+            return Ok(());
+        };
+        // Fails only if the reader has already stopped, which `join` reports.
+        let _ = self.requests.send(Request::Close);
+        match reader.join() {
+            Ok(result) => result.map_err(failed),
+            Err(_) => Err(failed(Error::Closed)),
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use crate::daemon::DaemonEvent;
+use std::io;
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Shows a desktop notification: `notify-send` on Linux, a toast on Windows and
+/// Notification Center on macOS.
+pub fn show(summary: &str, body: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "windows") {
+        let script = format!(
+            "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+             $t = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+             $x = $t.GetElementsByTagName('text'); $x.Item(0).AppendChild($t.CreateTextNode('{}')) > $null; $x.Item(1).AppendChild($t.CreateTextNode('{}')) > $null; \
+             [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('ps4hid').Show([Windows.UI.Notifications.ToastNotification]::new($t))",
+            summary.replace('\'', "''"),
+            body.replace('\'', "''")
+        );
+        let mut c = Command::new("powershell");
+        c.args(["-NoProfile", "-Command", &script]);
+        c
+    } else if cfg!(target_os = "macos") {
+        let mut c = Command::new("osascript");
+        c.arg("-e").arg(format!(
+            "display notification {} with title {}",
+            applescript_string(body),
+            applescript_string(summary)
+        ));
+        c
+    } else {
+        let mut c = Command::new("notify-send");
+        c.args(["--app-name=ps4hid", summary, body]);
+        c
+    };
+    let mut child = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reap the helper without blocking the caller.
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// A quoted AppleScript string literal, in which only `\` and `"` are special.
+fn applescript_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// How far above a threshold the battery must climb before it warns there
+/// again, so plugging in briefly or a reading wavering at the line doesn't.
+const REARM_MARGIN: f32 = 5.0;
+
+/// Turns daemon events into notifications. Each battery threshold fires once per
+/// discharge and re-arms once the pad is charged `REARM_MARGIN` above it; a drop
+/// past several at once warns just once.
+pub struct Notifier {
+    pub on_connect: bool,
+    pub on_disconnect: bool,
+    /// Percentages, highest first, at which to warn about the battery.
+    pub battery_thresholds: Vec<f32>,
+    fired: Vec<bool>,
+}
+
+impl Default for Notifier {
+    fn default() -> Self {
+        Notifier::new(vec![20.0, 10.0])
+    }
+}
+
+impl Notifier {
+    pub fn new(mut battery_thresholds: Vec<f32>) -> Self {
+        battery_thresholds.sort_by(|a, b| b.total_cmp(a));
+        Notifier {
+            on_connect: true,
+            on_disconnect: true,
+            fired: vec![false; battery_thresholds.len()],
+            battery_thresholds,
+        }
+    }
+
+    pub fn handle(&mut self, event: &DaemonEvent) {
+        match event {
+            DaemonEvent::Connected { connection, name } if self.on_connect => {
+                let who = name.as_deref().unwrap_or("Controller");
+                let _ = show(
+                    &format!("{} connected", who),
+                    &format!("via {}", connection),
+                );
+            }
+            DaemonEvent::Disconnected if self.on_disconnect => {
+                let _ = show("Controller disconnected", "");
+            }
+            DaemonEvent::Battery { percent, .. } if self.battery_crossed(*percent) => {
+                let _ = show(
+                    "Controller battery low",
+                    &format!("{:.0}% remaining", percent),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// Updates which thresholds have fired; true if `percent` newly crossed any.
+    fn battery_crossed(&mut self, percent: f32) -> bool {
+        let mut crossed = false;
+        for (threshold, fired) in self.battery_thresholds.iter().zip(self.fired.iter_mut()) {
+            if percent > *threshold + REARM_MARGIN {
+                *fired = false;
+            } else if percent <= *threshold && !*fired {
+                *fired = true;
+                crossed = true;
+            }
+        }
+        crossed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applescript_strings_escape_only_backslash_and_quote() {
+        assert_eq!(applescript_string(r#"a\b"c'é"#), r#""a\\b\"c'é""#);
+    }
+
+    #[test]
+    fn dropping_past_several_thresholds_warns_once() {
+        let mut notifier = Notifier::new(vec![10.0, 20.0]);
+        assert!(notifier.battery_crossed(5.0));
+        assert!(!notifier.battery_crossed(4.0));
+    }
+
+    #[test]
+    fn each_threshold_warns_once_per_discharge() {
+        let mut notifier = Notifier::default();
+        assert!(!notifier.battery_crossed(50.0));
+        assert!(notifier.battery_crossed(20.0));
+        assert!(!notifier.battery_crossed(15.0));
+        assert!(notifier.battery_crossed(10.0));
+        assert!(!notifier.battery_crossed(8.0));
+    }
+
+    #[test]
+    fn rearms_only_past_the_margin() {
+        let mut notifier = Notifier::default();
+        assert!(notifier.battery_crossed(19.0));
+        // Plugged in and unplugged again without charging far.
+        assert!(!notifier.battery_crossed(22.0));
+        assert!(!notifier.battery_crossed(19.0));
+        // Charged well past it, so the next discharge warns again.
+        assert!(!notifier.battery_crossed(30.0));
+        assert!(notifier.battery_crossed(20.0));
+    }
+}
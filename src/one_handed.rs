@@ -0,0 +1,185 @@
+use crate::calibration::TouchBounds;
+use crate::touch::Touch;
+use crate::{ButtonId, Controller, Profile, Snapshot, Stick, YAxis};
+
+/// Turns angular velocity into a stick deflection, so turning the pad aims.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GyroStick {
+    /// Raw gyro rate, with bias removed, that deflects the stick fully. The DS4
+    /// reports about 16 units per degree per second, so 3000 is roughly 180°/s.
+    pub full_rate: f32,
+    /// Rates below this, in raw units, leave the stick centered, so hand tremor
+    /// doesn't drift the view.
+    pub deadzone: f32,
+    /// Multipliers for the stick's x (from yaw) and y (from pitch) with Y up;
+    /// negate one to invert that axis.
+    pub scale: [f32; 2],
+}
+
+impl Default for GyroStick {
+    fn default() -> Self {
+        GyroStick {
+            full_rate: 3000.0,
+            deadzone: 60.0,
+            scale: [1.0, 1.0],
+        }
+    }
+}
+
+impl GyroStick {
+    /// The stick for gyro readings as from `Controller::gyro`: turning the pad to
+    /// the right pushes right, tipping its top away pushes up.
+    pub fn stick(&self, gyro: [f32; 3]) -> Stick {
+        let axis = |rate: f32, scale: f32| {
+            let magnitude = (rate.abs() - self.deadzone).max(0.0);
+            let span = (self.full_rate - self.deadzone).max(1.0);
+            (magnitude / span).min(1.0) * rate.signum() * scale
+        };
+        // Gyro x is pitch and y is yaw.
+        Stick::from_centered(
+            axis(-gyro[1], self.scale[0]),
+            axis(gyro[0], self.scale[1]),
+            YAxis::Up,
+        )
+    }
+}
+
+/// A rectangle of the touchpad in `0.0..=1.0` coordinates, origin top left,
+/// that acts as a button.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchZone {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub button: ButtonId,
+}
+
+impl TouchZone {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        (self.left..self.right).contains(&x) && (self.top..self.bottom).contains(&y)
+    }
+}
+
+/// Touchpad areas that act as buttons while a finger is on them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TouchZones {
+    pub zones: Vec<TouchZone>,
+    /// Only press a zone while the touchpad is also clicked, so a resting thumb
+    /// doesn't press anything. The click itself is then not reported as `Tpad`.
+    pub require_click: bool,
+}
+
+impl Default for TouchZones {
+    fn default() -> Self {
+        TouchZones::face_buttons()
+    }
+}
+
+impl TouchZones {
+    /// The face buttons in the four quadrants, each in the corner nearest its
+    /// place in the diamond: square and triangle above, cross and circle below.
+    pub fn face_buttons() -> Self {
+        let quadrant = |left, top, button| TouchZone {
+            left,
+            top,
+            right: left + 0.5,
+            bottom: top + 0.5,
+            button,
+        };
+        TouchZones {
+            zones: vec![
+                quadrant(0.0, 0.0, ButtonId::Square),
+                quadrant(0.5, 0.0, ButtonId::Triangle),
+                quadrant(0.0, 0.5, ButtonId::X),
+                quadrant(0.5, 0.5, ButtonId::Circle),
+            ],
+            require_click: true,
+        }
+    }
+
+    /// Presses the zones under each active finger in `out`.
+    pub fn apply(&self, touches: &[Touch; 2], bounds: &TouchBounds, out: &mut Snapshot) {
+        if self.require_click {
+            if !out.tpad {
+                return;
+            }
+            out.tpad = false;
+        }
+        for touch in touches.iter().filter(|t| t.active) {
+            let (x, y) = bounds.normalize(touch.x, touch.y);
+            for zone in self.zones.iter().filter(|z| z.contains(x, y)) {
+                out.set_pressed(zone.button, true);
+            }
+        }
+    }
+}
+
+/// Deflection past which the real right stick overrides the gyro.
+const STICK_OVERRIDE: f32 = 0.25;
+
+/// A left-handed layout: the left stick, dpad and shoulder buttons stay put,
+/// the face buttons move to touchpad zones, the right stick follows the gyro,
+/// and the buttons the right hand would press are remapped into reach by
+/// `profile`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OneHanded {
+    pub gyro: GyroStick,
+    pub zones: TouchZones,
+}
+
+impl OneHanded {
+    pub fn new() -> Self {
+        OneHanded::default()
+    }
+
+    /// The remap half of the layout: L1 fires (R2) while L2 still aims, L3
+    /// clicks the right stick and share opens the menu. Copy its `remap` into a
+    /// controller's profile to keep that pad's calibration, or save it whole as a
+    /// starting point.
+    pub fn profile() -> Profile {
+        Profile {
+            name: Some("One-handed (left)".to_string()),
+            remap: vec![
+                (ButtonId::L1, ButtonId::R2),
+                (ButtonId::L3, ButtonId::R3),
+                (ButtonId::Share, ButtonId::Options),
+            ],
+            ..Profile::default()
+        }
+    }
+
+    /// Applies the touch zones and gyro stick to an already remapped snapshot.
+    pub fn apply(
+        &self,
+        snapshot: &Snapshot,
+        gyro: [f32; 3],
+        touches: &[Touch; 2],
+        bounds: &TouchBounds,
+    ) -> Snapshot {
+        let mut out = *snapshot;
+        self.zones.apply(touches, bounds, &mut out);
+        let right = out.right_stick;
+        if right.x_f32().hypot(right.y_f32()) < STICK_OVERRIDE {
+            out.right_stick = self.gyro.stick(gyro);
+        }
+        // The remap only moves the digital bit, and games read R2 as an axis.
+        if out.r2 && out.r2_value == 0 {
+            out.r2_value = 255;
+        }
+        out
+    }
+
+    /// The controller's input as the game should see it, with the controller's
+    /// profile remap applied first; forward it to a `VirtualPad`.
+    pub fn snapshot(&self, controller: &Controller) -> Snapshot {
+        let profile = controller.profile();
+        let remapped = profile.remapped(&controller.controls.snapshot());
+        self.apply(
+            &remapped,
+            controller.gyro(),
+            &controller.controls.touches.state(),
+            &profile.calibration.touchpad,
+        )
+    }
+}
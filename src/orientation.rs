@@ -0,0 +1,209 @@
+use crate::{Error, Result, Snapshot, Stick};
+use std::fmt;
+use std::str::FromStr;
+
+/// One axis of one stick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StickAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+}
+
+impl StickAxis {
+    pub fn name(self) -> &'static str {
+        match self {
+            StickAxis::LeftX => "lx",
+            StickAxis::LeftY => "ly",
+            StickAxis::RightX => "rx",
+            StickAxis::RightY => "ry",
+        }
+    }
+
+    fn read(self, snapshot: &Snapshot) -> u8 {
+        match self {
+            StickAxis::LeftX => snapshot.left_stick.x,
+            StickAxis::LeftY => snapshot.left_stick.y,
+            StickAxis::RightX => snapshot.right_stick.x,
+            StickAxis::RightY => snapshot.right_stick.y,
+        }
+    }
+}
+
+/// Where an output axis takes its value from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AxisSource {
+    pub axis: StickAxis,
+    /// Mirrors the value about the center.
+    pub invert: bool,
+}
+
+impl AxisSource {
+    pub const fn new(axis: StickAxis) -> Self {
+        AxisSource {
+            axis,
+            invert: false,
+        }
+    }
+
+    pub const fn inverted(axis: StickAxis) -> Self {
+        AxisSource { axis, invert: true }
+    }
+
+    fn read(self, snapshot: &Snapshot) -> u8 {
+        let v = self.axis.read(snapshot);
+        if self.invert {
+            // About 0x80, so a centered stick stays centered.
+            (0x100 - v as u16).min(0xff) as u8
+        } else {
+            v
+        }
+    }
+}
+
+impl fmt::Display for AxisSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.invert {
+            f.write_str("-")?;
+        }
+        f.write_str(self.axis.name())
+    }
+}
+
+impl FromStr for AxisSource {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (invert, name) = match s.strip_prefix('-') {
+            Some(name) => (true, name),
+            None => (false, s),
+        };
+        let axis = match name.to_ascii_lowercase().as_str() {
+            "lx" => StickAxis::LeftX,
+            "ly" => StickAxis::LeftY,
+            "rx" => StickAxis::RightX,
+            "ry" => StickAxis::RightY,
+            _ => return Err(Error::InvalidFormat("unknown stick axis")),
+        };
+        Ok(AxisSource { axis, invert })
+    }
+}
+
+/// Which physical axis drives each stick axis games see, like the stick layout
+/// options in console accessibility menus.
+///
+/// Written as the sources for `lx ly rx ry` in order, each optionally prefixed
+/// with `-` to invert it: southpaw is `rx ry lx ly`, and inverted look is
+/// `lx ly rx -ry`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Orientation {
+    pub left_x: AxisSource,
+    pub left_y: AxisSource,
+    pub right_x: AxisSource,
+    pub right_y: AxisSource,
+}
+
+impl Default for Orientation {
+    fn default() -> Self {
+        Orientation::DEFAULT
+    }
+}
+
+impl Orientation {
+    pub const DEFAULT: Orientation = Orientation {
+        left_x: AxisSource::new(StickAxis::LeftX),
+        left_y: AxisSource::new(StickAxis::LeftY),
+        right_x: AxisSource::new(StickAxis::RightX),
+        right_y: AxisSource::new(StickAxis::RightY),
+    };
+
+    /// Sticks swapped: move on the right, look on the left.
+    pub const SOUTHPAW: Orientation = Orientation {
+        left_x: AxisSource::new(StickAxis::RightX),
+        left_y: AxisSource::new(StickAxis::RightY),
+        right_x: AxisSource::new(StickAxis::LeftX),
+        right_y: AxisSource::new(StickAxis::LeftY),
+    };
+
+    /// Move and turn on the left stick, look and strafe on the right, as in
+    /// older shooters.
+    pub const LEGACY: Orientation = Orientation {
+        left_x: AxisSource::new(StickAxis::RightX),
+        left_y: AxisSource::new(StickAxis::LeftY),
+        right_x: AxisSource::new(StickAxis::LeftX),
+        right_y: AxisSource::new(StickAxis::RightY),
+    };
+
+    /// `LEGACY` mirrored: look and strafe on the left, move and turn on the right.
+    pub const LEGACY_SOUTHPAW: Orientation = Orientation {
+        left_x: AxisSource::new(StickAxis::LeftX),
+        left_y: AxisSource::new(StickAxis::RightY),
+        right_x: AxisSource::new(StickAxis::RightX),
+        right_y: AxisSource::new(StickAxis::LeftY),
+    };
+
+    /// The presets selectable by name, e.g. with `orientation = southpaw` in a
+    /// profile.
+    pub const BUILTIN: &'static [(&'static str, Orientation)] = &[
+        ("default", Orientation::DEFAULT),
+        ("southpaw", Orientation::SOUTHPAW),
+        ("legacy", Orientation::LEGACY),
+        ("legacy-southpaw", Orientation::LEGACY_SOUTHPAW),
+    ];
+
+    pub fn builtin(name: &str) -> Option<Orientation> {
+        Orientation::BUILTIN
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|&(_, o)| o)
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Orientation::DEFAULT
+    }
+
+    /// `snapshot` with its stick axes rearranged; everything else is unchanged.
+    pub fn apply(&self, snapshot: &Snapshot) -> Snapshot {
+        let mut out = *snapshot;
+        out.left_stick = Stick::new(self.left_x.read(snapshot), self.left_y.read(snapshot));
+        out.right_stick = Stick::new(self.right_x.read(snapshot), self.right_y.read(snapshot));
+        out
+    }
+}
+
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.left_x, self.left_y, self.right_x, self.right_y
+        )
+    }
+}
+
+/// A built-in preset name or four axis sources.
+impl FromStr for Orientation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(preset) = Orientation::builtin(s.trim()) {
+            return Ok(preset);
+        }
+        let sources = s
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<AxisSource>>>()?;
+        match sources.as_slice() {
+            &[left_x, left_y, right_x, right_y] => Ok(Orientation {
+                left_x,
+                left_y,
+                right_x,
+                right_y,
+            }),
+            _ => Err(Error::InvalidFormat(
+                "orientation expects lx ly rx ry sources",
+            )),
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use crate::connection::ReportLayout;
+use std::time::{Duration, Instant};
+
+pub(crate) const FLAG_RUMBLE: u8 = 0x01;
+pub(crate) const FLAG_LIGHTBAR: u8 = 0x02;
+pub(crate) const FLAG_FLASH: u8 = 0x04;
+
+const USB_REPORT_LEN: usize = 32;
+const BT_REPORT_LEN: usize = 78;
+/// Room for an output report in either layout.
+pub(crate) const OUTPUT_REPORT_MAX_LEN: usize = BT_REPORT_LEN;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct OutputReport {
+    pub flags: u8,
+    pub rumble_weak: u8,
+    pub rumble_strong: u8,
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub flash_on: u8,
+    pub flash_off: u8,
+    /// Bluetooth input report interval in milliseconds; 0 lets the pad choose.
+    pub bt_interval: u8,
+}
+
+impl OutputReport {
+    /// Encodes into `buf`, which can be reused from one write to the next,
+    /// returning the report's bytes.
+    pub fn encode<'a>(
+        &self,
+        layout: ReportLayout,
+        buf: &'a mut [u8; OUTPUT_REPORT_MAX_LEN],
+    ) -> &'a [u8] {
+        buf.fill(0);
+        let payload = [
+            self.rumble_weak,
+            self.rumble_strong,
+            self.red,
+            self.green,
+            self.blue,
+            self.flash_on,
+            self.flash_off,
+        ];
+        match layout {
+            ReportLayout::Usb => {
+                let report = &mut buf[..USB_REPORT_LEN];
+                report[0] = 0x05;
+                report[1] = self.flags;
+                report[4..11].copy_from_slice(&payload);
+                report
+            }
+            ReportLayout::Bluetooth | ReportLayout::BluetoothShort => {
+                let report = &mut buf[..BT_REPORT_LEN];
+                report[0] = 0x11;
+                report[1] = 0xc0 | (self.bt_interval & 0x3f);
+                report[3] = self.flags;
+                report[6..13].copy_from_slice(&payload);
+                let crc = bt_crc(&report[..BT_REPORT_LEN - 4]);
+                report[BT_REPORT_LEN - 4..].copy_from_slice(&crc.to_le_bytes());
+                report
+            }
+        }
+    }
+}
+
+/// CRC32 over the report prefixed with the HID output transaction header 0xa2.
+fn bt_crc(report: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in [0xa2].iter().chain(report) {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Rumble, lightbar and flash changes gathered into one output write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OutputReportBuilder {
+    report: OutputReport,
+}
+
+impl OutputReportBuilder {
+    pub fn new() -> Self {
+        OutputReportBuilder::default()
+    }
+
+    pub fn rumble(mut self, strong: u8, weak: u8) -> Self {
+        self.report.flags |= FLAG_RUMBLE;
+        self.report.rumble_strong = strong;
+        self.report.rumble_weak = weak;
+        self
+    }
+
+    pub fn lightbar(mut self, r: u8, g: u8, b: u8) -> Self {
+        self.report.flags |= FLAG_LIGHTBAR;
+        self.report.red = r;
+        self.report.green = g;
+        self.report.blue = b;
+        self
+    }
+
+    /// Blinks the lightbar, with both durations in units of 10ms; `(0, 0)` stops it.
+    pub fn flash(mut self, on: u8, off: u8) -> Self {
+        self.report.flags |= FLAG_FLASH;
+        self.report.flash_on = on;
+        self.report.flash_off = off;
+        self
+    }
+
+    #[cfg(feature = "ffi")]
+    pub(crate) fn report(&self) -> OutputReport {
+        self.report
+    }
+
+    /// Drops the parts selected by `flags`, e.g. for a pad without a lightbar.
+    pub(crate) fn without(mut self, flags: u8) -> Self {
+        self.report.flags &= !flags;
+        self
+    }
+
+    /// Copies only the parts this builder set onto `state`.
+    pub(crate) fn apply(&self, state: &mut OutputReport) {
+        let r = &self.report;
+        if r.flags & FLAG_RUMBLE != 0 {
+            state.rumble_strong = r.rumble_strong;
+            state.rumble_weak = r.rumble_weak;
+        }
+        if r.flags & FLAG_LIGHTBAR != 0 {
+            (state.red, state.green, state.blue) = (r.red, r.green, r.blue);
+        }
+        if r.flags & FLAG_FLASH != 0 {
+            (state.flash_on, state.flash_off) = (r.flash_on, r.flash_off);
+        }
+        state.flags |= r.flags;
+    }
+}
+
+const RETRY_BACKOFF_MAX: Duration = Duration::from_millis(500);
+pub(crate) const STALL_AFTER_FAILURES: u32 = 5;
+
+/// Counts of what the output scheduler did with the changes it was given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct OutputStats {
+    /// Reports written successfully.
+    pub writes: u64,
+    /// Changes merged into a report that was already waiting, so they never
+    /// needed a write of their own.
+    pub coalesced: u64,
+    /// Writes that failed and were retried.
+    pub failures: u64,
+}
+
+/// Holds the pad's full desired output state and writes it at most once per
+/// interval, so separate setters never clobber each other or flood the link.
+///
+/// Pending changes coalesce into that one state, so the outgoing queue never grows
+/// past a single report however fast callers set things. Failed writes keep it
+/// pending and are retried with exponential backoff.
+pub(crate) struct OutputScheduler {
+    pub state: OutputReport,
+    pub min_interval: Duration,
+    pub stats: OutputStats,
+    dirty: bool,
+    last_write: Option<Instant>,
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl OutputScheduler {
+    pub fn new(min_interval: Duration) -> Self {
+        OutputScheduler {
+            state: OutputReport::default(),
+            min_interval,
+            stats: OutputStats::default(),
+            dirty: false,
+            last_write: None,
+            failures: 0,
+            retry_at: None,
+        }
+    }
+
+    /// Merges `builder` into the pending state, returning whether the change was
+    /// coalesced into a report that was already waiting.
+    pub fn apply(&mut self, builder: &OutputReportBuilder) -> bool {
+        let before = self.state;
+        builder.apply(&mut self.state);
+        if self.state == before {
+            return false;
+        }
+        self.mark_dirty()
+    }
+
+    pub fn mark_dirty(&mut self) -> bool {
+        let coalesced = std::mem::replace(&mut self.dirty, true);
+        self.stats.coalesced += coalesced as u64;
+        coalesced
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// The report to write now, if anything changed and the interval has passed.
+    pub fn poll(&mut self, now: Instant) -> Option<OutputReport> {
+        let due = self
+            .last_write
+            .is_none_or(|at| now.saturating_duration_since(at) >= self.min_interval)
+            && self.retry_at.is_none_or(|at| now >= at);
+        (self.dirty && due).then(|| self.take(now))
+    }
+
+    /// The pending report regardless of the interval.
+    pub fn take(&mut self, now: Instant) -> OutputReport {
+        self.dirty = false;
+        self.last_write = Some(now);
+        self.state
+    }
+
+    /// Puts the report back after a failed write, returning the failure count.
+    pub fn failed(&mut self, now: Instant) -> u32 {
+        self.dirty = true;
+        self.failures += 1;
+        self.stats.failures += 1;
+        let backoff =
+            self.min_interval.max(Duration::from_millis(1)) * 2u32.pow(self.failures.min(10));
+        self.retry_at = Some(now + backoff.min(RETRY_BACKOFF_MAX));
+        self.failures
+    }
+
+    /// Clears the failure count, returning whether the writes had stalled.
+    pub fn succeeded(&mut self) -> bool {
+        let stalled = self.failures >= STALL_AFTER_FAILURES;
+        self.failures = 0;
+        self.retry_at = None;
+        stalled
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt;
+use std::fs;
+
+pub const PAIRING_REPORT_ID: u8 = 0x12;
+pub const PAIRING_REPORT_LEN: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MacAddr(pub [u8; 6]);
+
+impl MacAddr {
+    /// The DS4 stores addresses least significant byte first.
+    fn from_le(bytes: &[u8]) -> Self {
+        let mut addr = [0u8; 6];
+        for (i, b) in bytes.iter().take(6).enumerate() {
+            addr[5 - i] = *b;
+        }
+        MacAddr(addr)
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut addr = [0u8; 6];
+        let mut parts = s.trim().split(':');
+        for byte in addr.iter_mut() {
+            *byte = u8::from_str_radix(parts.next()?, 16).ok()?;
+        }
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(MacAddr(addr))
+    }
+}
+
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let a = self.0;
+        write!(
+            f,
+            "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            a[0], a[1], a[2], a[3], a[4], a[5]
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pairing {
+    pub device: MacAddr,
+    pub host: MacAddr,
+}
+
+impl Pairing {
+    pub fn from_report(report: &[u8]) -> Option<Self> {
+        if report.len() < PAIRING_REPORT_LEN || report[0] != PAIRING_REPORT_ID {
+            return None;
+        }
+        Some(Pairing {
+            device: MacAddr::from_le(&report[1..7]),
+            host: MacAddr::from_le(&report[10..16]),
+        })
+    }
+
+    pub fn is_paired_to(&self, host: MacAddr) -> bool {
+        self.host == host
+    }
+
+    pub fn is_paired_to_this_host(&self) -> bool {
+        local_host_addresses().contains(&self.host)
+    }
+}
+
+/// Addresses of the local Bluetooth adapters. Only implemented on Linux (via sysfs).
+pub fn local_host_addresses() -> Vec<MacAddr> {
+    let Ok(entries) = fs::read_dir("/sys/class/bluetooth") else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| fs::read_to_string(entry.ok()?.path().join("address")).ok())
+        .filter_map(|s| MacAddr::parse(&s))
+        .collect()
+}
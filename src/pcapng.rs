@@ -0,0 +1,224 @@
+use crate::proxy::{Record, ReportKind};
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECTION_HEADER: u32 = 0x0a0d_0d0a;
+const INTERFACE_DESCRIPTION: u32 = 1;
+const ENHANCED_PACKET: u32 = 6;
+const BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+
+const OPT_END: u16 = 0;
+const OPT_COMMENT: u16 = 1;
+const OPT_EPB_FLAGS: u16 = 2;
+const OPT_IF_TSRESOL: u16 = 9;
+
+const INBOUND: u32 = 1;
+const OUTBOUND: u32 = 2;
+
+/// Bus and device numbers written into usbmon headers. Captures don't record
+/// where the pad was attached, so these are placeholders.
+const USBMON_BUS: u16 = 1;
+const USBMON_DEVICE: u8 = 1;
+const USBMON_HEADER_LEN: usize = 64;
+const EP_INPUT: u8 = 0x84;
+const EP_OUTPUT: u8 = 0x03;
+const EP_CONTROL_IN: u8 = 0x80;
+
+/// How each report is framed in the exported packets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkType {
+    /// `LINKTYPE_USER0`: the `ReportKind` byte (0 input, 1 output, 2 feature)
+    /// followed by the report, for a custom Wireshark dissector.
+    User0,
+    /// `LINKTYPE_USB_LINUX_MMAPPED`: reports wrapped as usbmon URBs, so
+    /// Wireshark's USB HID dissector reads them like a live USB capture. Feature
+    /// reports become a GET_REPORT control request and its completion.
+    Usbmon,
+}
+
+impl LinkType {
+    fn code(self) -> u16 {
+        match self {
+            LinkType::User0 => 147,
+            LinkType::Usbmon => 220,
+        }
+    }
+}
+
+/// Writes captured reports as a pcapng file for Wireshark, one packet per
+/// report. Each packet carries the record's annotation as its comment.
+pub struct PcapngWriter<W> {
+    sink: W,
+    link: LinkType,
+    started: SystemTime,
+    next_urb: u64,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> PcapngWriter<W> {
+    /// `started` is when the capture began, e.g. `CaptureReader::started`;
+    /// record offsets are added to it to get packet timestamps.
+    pub fn new(mut sink: W, link: LinkType, started: SystemTime) -> io::Result<Self> {
+        let mut buf = Vec::new();
+        block(&mut buf, SECTION_HEADER, |b| {
+            b.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+            b.extend_from_slice(&1u16.to_le_bytes());
+            b.extend_from_slice(&0u16.to_le_bytes());
+            // Section length unknown.
+            b.extend_from_slice(&(-1i64).to_le_bytes());
+        });
+        block(&mut buf, INTERFACE_DESCRIPTION, |b| {
+            b.extend_from_slice(&link.code().to_le_bytes());
+            b.extend_from_slice(&0u16.to_le_bytes());
+            // No snap length limit.
+            b.extend_from_slice(&0u32.to_le_bytes());
+            // Timestamps in microseconds.
+            option(b, OPT_IF_TSRESOL, &[6]);
+            option(b, OPT_END, &[]);
+        });
+        sink.write_all(&buf)?;
+        Ok(PcapngWriter {
+            sink,
+            link,
+            started,
+            next_urb: 1,
+            buf,
+        })
+    }
+
+    pub fn write(&mut self, record: &Record) -> io::Result<()> {
+        let micros = (self.started + record.at)
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_micros() as u64);
+        let direction = match record.kind {
+            ReportKind::Output => OUTBOUND,
+            ReportKind::Input | ReportKind::Feature => INBOUND,
+        };
+        let comment = record.annotation();
+        self.buf.clear();
+        match self.link {
+            LinkType::User0 => {
+                let mut packet = Vec::with_capacity(record.data.len() + 1);
+                packet.push(record.kind.to_byte());
+                packet.extend_from_slice(&record.data);
+                packet_block(&mut self.buf, micros, &packet, direction, &comment);
+            }
+            LinkType::Usbmon => {
+                let urb = self.next_urb;
+                self.next_urb += 1;
+                let packet = match record.kind {
+                    ReportKind::Input => usbmon(urb, b'C', 1, EP_INPUT, micros, None, &record.data),
+                    ReportKind::Output => {
+                        usbmon(urb, b'S', 1, EP_OUTPUT, micros, None, &record.data)
+                    }
+                    ReportKind::Feature => {
+                        // The request, as the host sent it; the report id is in
+                        // the low byte of wValue, 3 selects a feature report.
+                        let id = record.data.first().copied().unwrap_or(0);
+                        let len = record.data.len() as u16;
+                        let mut setup = [0xa1, 0x01, id, 0x03, 0, 0, 0, 0];
+                        setup[6..].copy_from_slice(&len.to_le_bytes());
+                        let request = usbmon(urb, b'S', 2, EP_CONTROL_IN, micros, Some(setup), &[]);
+                        packet_block(&mut self.buf, micros, &request, OUTBOUND, "");
+                        usbmon(urb, b'C', 2, EP_CONTROL_IN, micros, None, &record.data)
+                    }
+                };
+                packet_block(&mut self.buf, micros, &packet, direction, &comment);
+            }
+        }
+        self.sink.write_all(&self.buf)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.sink.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.sink
+    }
+}
+
+/// Appends a block: type, total length, the body `fill` writes, then the length
+/// again.
+fn block(buf: &mut Vec<u8>, kind: u32, fill: impl FnOnce(&mut Vec<u8>)) {
+    let start = buf.len();
+    buf.extend_from_slice(&kind.to_le_bytes());
+    buf.extend_from_slice(&0u32.to_le_bytes());
+    fill(buf);
+    let len = (buf.len() - start + 4) as u32;
+    buf[start + 4..start + 8].copy_from_slice(&len.to_le_bytes());
+    buf.extend_from_slice(&len.to_le_bytes());
+}
+
+/// Appends `value` padded to 4 bytes.
+fn padded(buf: &mut Vec<u8>, value: &[u8]) {
+    buf.extend_from_slice(value);
+    buf.resize(buf.len() + (4 - value.len() % 4) % 4, 0);
+}
+
+fn option(buf: &mut Vec<u8>, code: u16, value: &[u8]) {
+    buf.extend_from_slice(&code.to_le_bytes());
+    buf.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    padded(buf, value);
+}
+
+fn packet_block(buf: &mut Vec<u8>, micros: u64, packet: &[u8], direction: u32, comment: &str) {
+    block(buf, ENHANCED_PACKET, |b| {
+        // Interface 0.
+        b.extend_from_slice(&0u32.to_le_bytes());
+        b.extend_from_slice(&((micros >> 32) as u32).to_le_bytes());
+        b.extend_from_slice(&(micros as u32).to_le_bytes());
+        b.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+        b.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+        padded(b, packet);
+        option(b, OPT_EPB_FLAGS, &direction.to_le_bytes());
+        if !comment.is_empty() {
+            option(b, OPT_COMMENT, comment.as_bytes());
+        }
+        option(b, OPT_END, &[]);
+    });
+}
+
+/// A usbmon mmapped-format URB header followed by `data`. `event` is `S` for
+/// submission or `C` for completion; `transfer` is 1 for interrupt, 2 for
+/// control.
+fn usbmon(
+    urb: u64,
+    event: u8,
+    transfer: u8,
+    endpoint: u8,
+    micros: u64,
+    setup: Option<[u8; 8]>,
+    data: &[u8],
+) -> Vec<u8> {
+    let mut header = Vec::with_capacity(USBMON_HEADER_LEN + data.len());
+    header.extend_from_slice(&urb.to_le_bytes());
+    header.push(event);
+    header.push(transfer);
+    header.push(endpoint);
+    header.push(USBMON_DEVICE);
+    header.extend_from_slice(&USBMON_BUS.to_le_bytes());
+    // flag_setup is 0 when a setup packet follows, '-' otherwise; flag_data is 0
+    // when data follows.
+    header.push(if setup.is_some() { 0 } else { b'-' });
+    header.push(if data.is_empty() { b'<' } else { 0 });
+    header.extend_from_slice(&((micros / 1_000_000) as i64).to_le_bytes());
+    header.extend_from_slice(&((micros % 1_000_000) as i32).to_le_bytes());
+    // Status: 0 on completion, -EINPROGRESS while submitted.
+    let status: i32 = if event == b'C' { 0 } else { -115 };
+    header.extend_from_slice(&status.to_le_bytes());
+    let urb_len = setup.map_or(data.len() as u32, |s| {
+        u16::from_le_bytes([s[6], s[7]]) as u32
+    });
+    header.extend_from_slice(&urb_len.to_le_bytes());
+    header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    header.extend_from_slice(&setup.unwrap_or_default());
+    // Interval, start frame, transfer flags, iso descriptor count.
+    let interval: i32 = if transfer == 1 { 1 } else { 0 };
+    header.extend_from_slice(&interval.to_le_bytes());
+    header.extend_from_slice(&0i32.to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes());
+    header.extend_from_slice(data);
+    header
+}
@@ -0,0 +1,23 @@
+/// What is plugged into the headset jack and extension port, from the status byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PeripheralState {
+    pub headphones: bool,
+    pub microphone: bool,
+    pub extension: bool,
+}
+
+impl PeripheralState {
+    pub fn from_status(status: u8) -> Self {
+        PeripheralState {
+            headphones: status & 0x20 > 0,
+            microphone: status & 0x40 > 0,
+            extension: status & 0x80 > 0,
+        }
+    }
+
+    /// True for headphones, a headset (headphones and mic), or a mic on its own.
+    pub fn headset(&self) -> bool {
+        self.headphones || self.microphone
+    }
+}
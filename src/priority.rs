@@ -0,0 +1,136 @@
+use std::io;
+
+/// Scheduling class for the thread that reads the controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ThreadPriority {
+    /// Leave the thread as created.
+    #[default]
+    Normal,
+    /// Nice level -10 on Linux; needs `CAP_SYS_NICE` or a raised `RLIMIT_NICE`.
+    High,
+    /// `SCHED_FIFO` at this priority, clamped to `1..=99`; needs `CAP_SYS_NICE` or a
+    /// raised `RLIMIT_RTPRIO`. The thread preempts everything below it, so it must
+    /// block on reads rather than spin.
+    Realtime(u8),
+}
+
+/// Priority and CPU affinity to request for a reader thread, so input isn't
+/// starved by render threads on a loaded system and reports aren't missed.
+///
+/// Call `apply` at the top of the thread that drives `Controller::update`. The
+/// hints are only requests: without the privileges noted on each priority the OS
+/// refuses them, and the thread keeps running as before.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ThreadHints {
+    pub priority: ThreadPriority,
+    /// CPUs the thread may run on; empty allows any.
+    pub affinity: Vec<usize>,
+}
+
+impl ThreadHints {
+    pub fn new() -> Self {
+        ThreadHints::default()
+    }
+
+    pub fn priority(mut self, priority: ThreadPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Restricts the thread to `cpus`, e.g. a core the game's render threads
+    /// don't use.
+    pub fn pin_to(mut self, cpus: impl IntoIterator<Item = usize>) -> Self {
+        self.affinity = cpus.into_iter().collect();
+        self
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == ThreadHints::default()
+    }
+
+    /// Applies the hints to the calling thread. Fails with
+    /// `io::ErrorKind::Unsupported` on platforms without an implementation, and
+    /// with the OS error, typically `PermissionDenied`, when a request is refused.
+    pub fn apply(&self) -> io::Result<()> {
+        if self.is_default() {
+            return Ok(());
+        }
+        imp::apply(self)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use super::{ThreadHints, ThreadPriority};
+    use std::{io, mem};
+
+    const HIGH_NICE: libc::c_int = -10;
+
+    pub(super) fn apply(hints: &ThreadHints) -> io::Result<()> {
+        if !hints.affinity.is_empty() {
+            set_affinity(&hints.affinity)?;
+        }
+        match hints.priority {
+            ThreadPriority::Normal => Ok(()),
+            ThreadPriority::High => {
+                // Linux keeps a nice value per thread, addressed by its tid.
+                // SAFETY: gettid and setpriority take and return plain integers.
+                let rc = unsafe {
+                    libc::setpriority(libc::PRIO_PROCESS, libc::gettid() as libc::id_t, HIGH_NICE)
+                };
+                if rc == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            }
+            ThreadPriority::Realtime(priority) => {
+                let param = libc::sched_param {
+                    sched_priority: priority.clamp(1, 99) as libc::c_int,
+                };
+                // SAFETY: `param` is a valid sched_param for the duration of the call.
+                let rc = unsafe {
+                    libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param)
+                };
+                if rc != 0 {
+                    return Err(io::Error::from_raw_os_error(rc));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn set_affinity(cpus: &[usize]) -> io::Result<()> {
+        // SAFETY: cpu_set_t is a plain bitmask; all-zeroes is the empty set.
+        let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+        for &cpu in cpus {
+            if cpu >= libc::CPU_SETSIZE as usize {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("no such CPU: {}", cpu),
+                ));
+            }
+            // SAFETY: `cpu` was checked against the set's size above.
+            unsafe { libc::CPU_SET(cpu, &mut set) };
+        }
+        // Pid 0 is the calling thread.
+        // SAFETY: `set` is a live cpu_set_t of the size passed.
+        let rc = unsafe { libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &set) };
+        if rc == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use super::ThreadHints;
+    use std::io;
+
+    pub(super) fn apply(_hints: &ThreadHints) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "thread hints are only implemented on Linux",
+        ))
+    }
+}
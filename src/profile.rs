@@ -0,0 +1,356 @@
+use crate::calibration::{Calibration, Deadzones, TouchBounds, TriggerRange};
+use crate::lightbar::Rgb;
+use crate::orientation::Orientation;
+use crate::{ButtonId, Error, Result, Snapshot, Stick};
+use std::fmt::Write as _;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// Per-controller settings, persisted as `<config dir>/ps4hid/<key>.profile` where the
+/// key is the controller's serial number or Bluetooth address.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Profile {
+    /// User-assigned name, e.g. "Alice's pad".
+    pub name: Option<String>,
+    /// Lightbar color set whenever this controller is opened.
+    pub color: Option<Rgb>,
+    pub calibration: Calibration,
+    pub deadzones: Deadzones,
+    /// `(from, to)` pairs: `from` on the pad behaves as `to`.
+    pub remap: Vec<(ButtonId, ButtonId)>,
+    /// Stick layout applied after the button remap.
+    pub orientation: Orientation,
+    /// User-defined orientations, selectable by name like the built-in ones.
+    pub presets: Vec<(String, Orientation)>,
+}
+
+impl Profile {
+    pub fn dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("ps4hid"))
+    }
+
+    pub fn path(key: &str) -> Option<PathBuf> {
+        config_path(key, "profile")
+    }
+
+    /// Returns `Ok(None)` if no profile has been saved for `key`, or there's
+    /// nowhere it could have been saved, such as with no home directory.
+    pub fn load(key: &str) -> Result<Option<Profile>> {
+        read_config(key, "profile")?
+            .map(|text| Profile::parse(&text))
+            .transpose()
+    }
+
+    /// Fails with `Error::InvalidFormat` if the name has a line break or a preset
+    /// name has whitespace, either of which would read back as something else.
+    pub fn save(&self, key: &str) -> Result<()> {
+        if let Some(name) = &self.name {
+            check_name(name)?;
+        }
+        if self
+            .presets
+            .iter()
+            .any(|(name, _)| name.is_empty() || name.contains(char::is_whitespace))
+        {
+            return Err(Error::InvalidFormat("preset names must be one word"));
+        }
+        let path = Profile::path(key).ok_or(Error::InvalidFormat("no config directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    pub fn parse(text: &str) -> Result<Profile> {
+        let mut profile = Profile::default();
+        // Resolved once every preset has been read, so order doesn't matter.
+        let mut orientation = None;
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(Error::InvalidFormat("profile line without '='"))?;
+            let values: Vec<&str> = value.split_whitespace().collect();
+            let c = &mut profile.calibration;
+            match key.trim() {
+                "name" => profile.name = Some(value.trim().to_string()),
+                "color" => {
+                    profile.color = Some(match values.as_slice() {
+                        [r, g, b] => r
+                            .parse()
+                            .ok()
+                            .zip(g.parse().ok())
+                            .zip(b.parse().ok())
+                            .map(|((r, g), b)| Rgb::new(r, g, b))
+                            .ok_or(Error::InvalidFormat("bad color"))?,
+                        _ => return Err(Error::InvalidFormat("color expects r g b")),
+                    })
+                }
+                "left_center" => c.left_center = parse_stick(&values)?,
+                "right_center" => c.right_center = parse_stick(&values)?,
+                "l2_range" => c.l2 = parse_range(&values)?,
+                "r2_range" => c.r2 = parse_range(&values)?,
+                "gyro_bias" => match values.as_slice() {
+                    [x, y, z] => c.gyro_bias = [parse_value(x)?, parse_value(y)?, parse_value(z)?],
+                    _ => return Err(Error::InvalidFormat("gyro_bias expects x y z")),
+                },
+                "touchpad" => match values.as_slice() {
+                    [x0, y0, x1, y1] => {
+                        c.touchpad = TouchBounds {
+                            min_x: parse_value(x0)?,
+                            min_y: parse_value(y0)?,
+                            max_x: parse_value(x1)?,
+                            max_y: parse_value(y1)?,
+                        }
+                    }
+                    _ => return Err(Error::InvalidFormat("touchpad expects x0 y0 x1 y1")),
+                },
+                "left_deadzone" => profile.deadzones.left = parse_one(&values)?,
+                "right_deadzone" => profile.deadzones.right = parse_one(&values)?,
+                "remap" => match values.as_slice() {
+                    [from, to] => profile.remap.push((parse_button(from)?, parse_button(to)?)),
+                    _ => return Err(Error::InvalidFormat("remap expects two buttons")),
+                },
+                "orientation" => orientation = Some(value.trim()),
+                "preset" => match value.trim().split_once(char::is_whitespace) {
+                    Some((name, spec)) => profile.presets.push((name.to_string(), spec.parse()?)),
+                    None => return Err(Error::InvalidFormat("preset expects a name and axes")),
+                },
+                // Written by a newer version; keep what this one understands.
+                _ => {}
+            }
+        }
+        if let Some(orientation) = orientation {
+            profile.orientation = match profile.preset(orientation) {
+                Some(preset) => preset,
+                None => orientation.parse()?,
+            };
+        }
+        Ok(profile)
+    }
+
+    /// A user-defined orientation by name, falling back to the built-in ones so
+    /// users can redefine those.
+    pub fn preset(&self, name: &str) -> Option<Orientation> {
+        self.presets
+            .iter()
+            .rev()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|&(_, o)| o)
+            .or_else(|| Orientation::builtin(name))
+    }
+
+    /// Switches to the named orientation.
+    pub fn select_orientation(&mut self, name: &str) -> Result<()> {
+        self.orientation = self
+            .preset(name)
+            .ok_or(Error::InvalidFormat("unknown orientation preset"))?;
+        Ok(())
+    }
+
+    /// The name the current orientation is saved under, if it matches a preset.
+    pub fn orientation_name(&self) -> Option<&str> {
+        self.presets
+            .iter()
+            .rev()
+            .find(|(_, o)| *o == self.orientation)
+            .map(|(n, _)| n.as_str())
+            .or_else(|| {
+                Orientation::BUILTIN
+                    .iter()
+                    .find(|(_, o)| *o == self.orientation)
+                    .map(|&(n, _)| n)
+            })
+    }
+
+    /// `snapshot` as the profile's button remap and orientation present it.
+    pub fn remapped(&self, snapshot: &Snapshot) -> Snapshot {
+        let mut out = *snapshot;
+        for &(from, _) in &self.remap {
+            out.set_pressed(from, false);
+        }
+        for &(from, to) in &self.remap {
+            if snapshot.pressed(from) {
+                out.set_pressed(to, true);
+            }
+        }
+        self.orientation.apply(&out)
+    }
+}
+
+impl std::fmt::Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let c = &self.calibration;
+        let mut s = String::new();
+        if let Some(name) = &self.name {
+            let _ = writeln!(s, "name = {}", name);
+        }
+        if let Some(Rgb { r, g, b }) = self.color {
+            let _ = writeln!(s, "color = {} {} {}", r, g, b);
+        }
+        let _ = writeln!(s, "left_center = {} {}", c.left_center.x, c.left_center.y);
+        let _ = writeln!(
+            s,
+            "right_center = {} {}",
+            c.right_center.x, c.right_center.y
+        );
+        let _ = writeln!(s, "l2_range = {} {}", c.l2.min, c.l2.max);
+        let _ = writeln!(s, "r2_range = {} {}", c.r2.min, c.r2.max);
+        let [gx, gy, gz] = c.gyro_bias;
+        let _ = writeln!(s, "gyro_bias = {} {} {}", gx, gy, gz);
+        let t = &c.touchpad;
+        let _ = writeln!(
+            s,
+            "touchpad = {} {} {} {}",
+            t.min_x, t.min_y, t.max_x, t.max_y
+        );
+        let _ = writeln!(s, "left_deadzone = {}", self.deadzones.left);
+        let _ = writeln!(s, "right_deadzone = {}", self.deadzones.right);
+        for (from, to) in &self.remap {
+            let _ = writeln!(s, "remap = {} {}", from, to);
+        }
+        for (name, preset) in &self.presets {
+            let _ = writeln!(s, "preset = {} {}", name, preset);
+        }
+        let orientation = self
+            .orientation_name()
+            .map_or_else(|| self.orientation.to_string(), str::to_string);
+        let _ = writeln!(s, "orientation = {}", orientation);
+        f.write_str(&s)
+    }
+}
+
+/// `<config dir>/ps4hid/<key>.<extension>`, with every byte of `key` other than
+/// ASCII letters, digits, `-` and `_` percent-encoded, so distinct keys never
+/// share a file.
+pub(crate) fn config_path(key: &str, extension: &str) -> Option<PathBuf> {
+    let mut name = String::with_capacity(key.len());
+    for b in key.bytes() {
+        if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' {
+            name.push(b as char);
+        } else {
+            let _ = write!(name, "%{:02X}", b);
+        }
+    }
+    Profile::dir().map(|d| d.join(format!("{}.{}", name, extension)))
+}
+
+/// Where older versions saved `key`, with every other character replaced by `_`.
+fn legacy_config_path(key: &str, extension: &str) -> Option<PathBuf> {
+    let name: String = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    Profile::dir().map(|d| d.join(format!("{}.{}", name, extension)))
+}
+
+/// The text saved for `key`, falling back to the file an older version wrote.
+pub(crate) fn read_config(key: &str, extension: &str) -> Result<Option<String>> {
+    let paths = [
+        config_path(key, extension),
+        legacy_config_path(key, extension),
+    ];
+    for path in paths.into_iter().flatten() {
+        match fs::read_to_string(path) {
+            Ok(text) => return Ok(Some(text)),
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(None)
+}
+
+/// Profile names are saved on a line of their own.
+pub(crate) fn check_name(name: &str) -> Result<()> {
+    if name.contains(['\n', '\r']) {
+        return Err(Error::InvalidFormat(
+            "profile names can't contain line breaks",
+        ));
+    }
+    Ok(())
+}
+
+fn parse_value<T: std::str::FromStr>(v: &str) -> Result<T> {
+    v.parse()
+        .map_err(|_| Error::InvalidFormat("bad profile value"))
+}
+
+fn parse_one<T: std::str::FromStr>(values: &[&str]) -> Result<T> {
+    match values {
+        [v] => parse_value(v),
+        _ => Err(Error::InvalidFormat("expected one value")),
+    }
+}
+
+fn parse_pair(values: &[&str]) -> Result<(u8, u8)> {
+    match values {
+        [a, b] => a
+            .parse()
+            .ok()
+            .zip(b.parse().ok())
+            .ok_or(Error::InvalidFormat("bad profile value")),
+        _ => Err(Error::InvalidFormat("expected two values")),
+    }
+}
+
+fn parse_stick(values: &[&str]) -> Result<Stick> {
+    parse_pair(values).map(|(x, y)| Stick::new(x, y))
+}
+
+fn parse_range(values: &[&str]) -> Result<TriggerRange> {
+    parse_pair(values).map(|(min, max)| TriggerRange { min, max })
+}
+
+fn parse_button(s: &str) -> Result<ButtonId> {
+    s.parse()
+        .map_err(|_| Error::InvalidFormat("unknown button name"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_keys_are_ignored() {
+        let profile = Profile::parse("name = pad\nhaptics_curve = 0.5 0.5\ncolor = 1 2 3\n");
+        assert_eq!(
+            profile.ok().map(|p| (p.name, p.color)),
+            Some((Some("pad".to_string()), Some(Rgb::new(1, 2, 3))))
+        );
+    }
+
+    #[test]
+    fn keys_get_distinct_paths() {
+        let a = config_path("aa:bb", "profile");
+        let b = config_path("aa_bb", "profile");
+        let c = config_path("aa%3Abb", "profile");
+        assert!(a.is_some());
+        assert_ne!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(b, c);
+    }
+
+    #[test]
+    fn names_that_would_not_read_back_are_refused() {
+        let profile = Profile {
+            name: Some("pad\ncolor = 1 2 3".to_string()),
+            ..Profile::default()
+        };
+        assert!(matches!(profile.save("test"), Err(Error::InvalidFormat(_))));
+        let profile = Profile {
+            presets: vec![("two words".to_string(), Orientation::default())],
+            ..Profile::default()
+        };
+        assert!(matches!(profile.save("test"), Err(Error::InvalidFormat(_))));
+    }
+
+    #[test]
+    fn malformed_values_are_errors() {
+        assert!(Profile::parse("color = red\n").is_err());
+        assert!(Profile::parse("just some text\n").is_err());
+    }
+}
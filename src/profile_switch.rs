@@ -0,0 +1,184 @@
+use crate::lightbar::Rgb;
+use crate::{ButtonId, Controller, DPad, Error, Profile, Result, Snapshot};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// Lightbar colors for mapping profiles that don't set their own, by position.
+const PALETTE: [Rgb; 6] = [
+    Rgb::new(0, 0, 255),
+    Rgb::new(255, 0, 0),
+    Rgb::new(0, 255, 0),
+    Rgb::new(255, 0, 255),
+    Rgb::new(0, 255, 255),
+    Rgb::new(255, 128, 0),
+];
+
+/// What a chord direction does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChordAction {
+    Next,
+    Previous,
+    /// Jumps to the profile at this position.
+    Select(usize),
+}
+
+/// Hold `hold`, then press a dpad direction bound in `bindings`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileChord {
+    pub hold: ButtonId,
+    pub bindings: Vec<(DPad, ChordAction)>,
+}
+
+impl Default for ProfileChord {
+    /// PS with right for the next profile, left for the previous, and up for
+    /// the first.
+    fn default() -> Self {
+        ProfileChord {
+            hold: ButtonId::Ps,
+            bindings: vec![
+                (DPad::East, ChordAction::Next),
+                (DPad::West, ChordAction::Previous),
+                (DPad::North, ChordAction::Select(0)),
+            ],
+        }
+    }
+}
+
+/// Switches a controller between mapping profiles with a chord pressed on the
+/// pad itself, showing the active one on the lightbar.
+///
+/// A mapping profile contributes its remap, orientation and color; the pad's
+/// own calibration and deadzones are kept. A profile without a color gets one
+/// from a fixed palette by position, so each is still recognizable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileSwitcher {
+    pub profiles: Vec<Profile>,
+    pub chord: ProfileChord,
+    active: usize,
+    /// The direction last pressed with the chord, until it's released.
+    pressed: Option<DPad>,
+}
+
+impl ProfileSwitcher {
+    pub fn new(profiles: Vec<Profile>) -> Self {
+        ProfileSwitcher {
+            profiles,
+            chord: ProfileChord::default(),
+            active: 0,
+            pressed: None,
+        }
+    }
+
+    pub fn chord(mut self, chord: ProfileChord) -> Self {
+        self.chord = chord;
+        self
+    }
+
+    /// Where mapping profiles are kept: `<config dir>/ps4hid/mappings`.
+    pub fn dir() -> Option<PathBuf> {
+        Profile::dir().map(|d| d.join("mappings"))
+    }
+
+    /// Every `.profile` in `dir()`, in file name order, each named after its
+    /// file unless it sets a name. Empty if the directory doesn't exist.
+    pub fn load() -> Result<Vec<Profile>> {
+        let dir = ProfileSwitcher::dir().ok_or(Error::InvalidFormat("no config directory"))?;
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "profile"))
+            .collect();
+        paths.sort();
+        let mut profiles = Vec::new();
+        for path in paths {
+            let mut profile = Profile::parse(&fs::read_to_string(&path)?)?;
+            if profile.name.is_none() {
+                profile.name = path.file_stem().map(|s| s.to_string_lossy().into_owned());
+            }
+            profiles.push(profile);
+        }
+        Ok(profiles)
+    }
+
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    pub fn active_profile(&self) -> Option<&Profile> {
+        self.profiles.get(self.active)
+    }
+
+    /// The lightbar color showing the profile at `index`.
+    pub fn color(&self, index: usize) -> Rgb {
+        self.profiles
+            .get(index)
+            .and_then(|p| p.color)
+            .unwrap_or(PALETTE[index % PALETTE.len()])
+    }
+
+    /// Makes the profile at `index` active on `controller`. Fails if there's
+    /// no such profile.
+    pub fn select(&mut self, index: usize, controller: &mut Controller) -> Result<()> {
+        if index >= self.profiles.len() {
+            return Err(Error::InvalidFormat("no mapping profile at that position"));
+        }
+        self.active = index;
+        self.apply(controller)
+    }
+
+    /// Applies the active profile, e.g. to a newly connected controller.
+    pub fn apply(&self, controller: &mut Controller) -> Result<()> {
+        let Some(mapping) = self.active_profile() else {
+            return Ok(());
+        };
+        let profile = controller.profile_mut();
+        profile.remap = mapping.remap.clone();
+        profile.orientation = mapping.orientation;
+        let Rgb { r, g, b } = self.color(self.active);
+        controller.set_lightbar(r, g, b)
+    }
+
+    /// Checks the controller's latest input for the chord, switching profile
+    /// when it's pressed. Returns the newly active position if it changed.
+    pub fn update(&mut self, controller: &mut Controller) -> Result<Option<usize>> {
+        let snapshot = controller.controls.snapshot();
+        let direction = if snapshot.pressed(self.chord.hold) && snapshot.dpad != DPad::Released {
+            Some(snapshot.dpad)
+        } else {
+            None
+        };
+        let fresh = direction.filter(|&d| self.pressed != Some(d));
+        self.pressed = direction;
+        let Some(direction) = fresh else {
+            return Ok(None);
+        };
+        let count = self.profiles.len();
+        let target = match self.chord.bindings.iter().find(|&&(d, _)| d == direction) {
+            _ if count == 0 => return Ok(None),
+            Some((_, ChordAction::Next)) => (self.active + 1) % count,
+            Some((_, ChordAction::Previous)) => (self.active + count - 1) % count,
+            Some(&(_, ChordAction::Select(index))) if index < count => index,
+            _ => return Ok(None),
+        };
+        if target == self.active {
+            return Ok(None);
+        }
+        self.select(target, controller)?;
+        Ok(Some(target))
+    }
+
+    /// `snapshot` with the dpad released while the chord's hold button is
+    /// down, so switching doesn't also move in the game.
+    pub fn filter(&self, snapshot: &Snapshot) -> Snapshot {
+        let mut out = *snapshot;
+        if snapshot.pressed(self.chord.hold) {
+            out.dpad = DPad::Released;
+        }
+        out
+    }
+}
@@ -0,0 +1,377 @@
+use crate::firmware::{Firmware, FIRMWARE_REPORT_ID};
+use crate::pairing::{Pairing, PAIRING_REPORT_ID};
+use crate::report::{BasicParser, BluetoothParser, InputState, UsbParser};
+use crate::transport::Transport;
+use crate::{Error, Result};
+use std::fmt::{self, Write as _};
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const MAGIC: &[u8; 6] = b"DS4CAP";
+const VERSION: u16 = 1;
+const HEADER_LEN: usize = 16;
+const RECORD_HEADER_LEN: usize = 11;
+
+/// Which way a captured report went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReportKind {
+    /// Read from the pad.
+    Input,
+    /// Written to the pad.
+    Output,
+    /// A feature report as the pad answered it.
+    Feature,
+}
+
+impl ReportKind {
+    pub(crate) fn to_byte(self) -> u8 {
+        match self {
+            ReportKind::Input => 0,
+            ReportKind::Output => 1,
+            ReportKind::Feature => 2,
+        }
+    }
+
+    fn from_byte(b: u8) -> Option<Self> {
+        Some(match b {
+            0 => ReportKind::Input,
+            1 => ReportKind::Output,
+            2 => ReportKind::Feature,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for ReportKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ReportKind::Input => "in",
+            ReportKind::Output => "out",
+            ReportKind::Feature => "feature",
+        })
+    }
+}
+
+/// One report as it crossed the wire.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// Since the capture started.
+    pub at: Duration,
+    pub kind: ReportKind,
+    /// The whole report, starting with its id.
+    pub data: Vec<u8>,
+}
+
+impl Record {
+    /// What the report means as far as this crate knows, e.g. stick positions
+    /// and pressed buttons for input or the lightbar color for output. Empty for
+    /// reports it can't decode, which are the interesting ones when
+    /// reverse-engineering.
+    pub fn annotation(&self) -> String {
+        match self.kind {
+            ReportKind::Input => annotate_input(&self.data),
+            ReportKind::Output => annotate_output(&self.data),
+            ReportKind::Feature => annotate_feature(&self.data),
+        }
+        .unwrap_or_default()
+    }
+}
+
+/// `seconds kind length: hex bytes`, then the annotation on the next line if
+/// there is one.
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>10.6} {:<7} {:>2}:",
+            self.at.as_secs_f64(),
+            self.kind,
+            self.data.len()
+        )?;
+        for b in &self.data {
+            write!(f, " {:02x}", b)?;
+        }
+        let annotation = self.annotation();
+        if !annotation.is_empty() {
+            write!(f, "\n{:>21} {}", "", annotation)?;
+        }
+        Ok(())
+    }
+}
+
+fn annotate_input(data: &[u8]) -> Option<String> {
+    let state = match (data.first()?, data.len()) {
+        (0x11, _) => BluetoothParser::parse(data.get(..BluetoothParser::LEN)?.try_into().ok()?),
+        (0x01, len) if len >= UsbParser::LEN => {
+            UsbParser::parse(data.get(..UsbParser::LEN)?.try_into().ok()?)
+        }
+        (0x01, _) => BasicParser::parse(data.get(..BasicParser::LEN)?.try_into().ok()?),
+        _ => return None,
+    }
+    .ok()?;
+    Some(describe_input(&state))
+}
+
+fn describe_input(state: &InputState) -> String {
+    let mut out = format!(
+        "#{} L({},{}) R({},{}) L2 {} R2 {}",
+        state.counter,
+        state.left_stick.x,
+        state.left_stick.y,
+        state.right_stick.x,
+        state.right_stick.y,
+        state.l2,
+        state.r2
+    );
+    for id in state.buttons.ids() {
+        let _ = write!(out, " {}", id);
+    }
+    if state.imu.gyro != [0; 3] || state.imu.accel != [0; 3] {
+        let _ = write!(
+            out,
+            " gyro {:?} accel {:?}",
+            state.imu.gyro, state.imu.accel
+        );
+    }
+    out
+}
+
+fn annotate_output(data: &[u8]) -> Option<String> {
+    // Flags, then rumble weak/strong, RGB and flash on/off.
+    let (interval, flags, payload) = match *data.first()? {
+        0x05 => (None, *data.get(1)?, data.get(4..11)?),
+        0x11 => (Some(data.get(1)? & 0x3f), *data.get(3)?, data.get(6..13)?),
+        _ => return None,
+    };
+    let mut out = format!("flags {:#04x}", flags);
+    if flags & 0x01 != 0 {
+        let _ = write!(out, " rumble {}/{}", payload[1], payload[0]);
+    }
+    if flags & 0x02 != 0 {
+        let _ = write!(
+            out,
+            " lightbar #{:02x}{:02x}{:02x}",
+            payload[2], payload[3], payload[4]
+        );
+    }
+    if flags & 0x04 != 0 {
+        let _ = write!(out, " flash {}/{}", payload[5], payload[6]);
+    }
+    if let Some(ms) = interval.filter(|&ms| ms != 0) {
+        let _ = write!(out, " interval {}ms", ms);
+    }
+    Some(out)
+}
+
+fn annotate_feature(data: &[u8]) -> Option<String> {
+    Some(match *data.first()? {
+        0x02 => "calibration (USB)".to_string(),
+        0x05 => "calibration (Bluetooth)".to_string(),
+        PAIRING_REPORT_ID => match Pairing::from_report(data) {
+            Some(p) => format!("pairing: device {} host {}", p.device, p.host),
+            None => "pairing (unreadable)".to_string(),
+        },
+        FIRMWARE_REPORT_ID => match Firmware::from_report(data) {
+            Some(f) => format!(
+                "version: {} hw {:#06x} fw {:#06x}",
+                f.build_date, f.hardware_version, f.firmware_version
+            ),
+            None => "version (unreadable)".to_string(),
+        },
+        _ => return None,
+    })
+}
+
+/// Writes reports to a capture file: a 16-byte header holding the start time,
+/// then one length-prefixed record per report with its offset from the start.
+/// Read one back with `CaptureReader`.
+pub struct CaptureWriter<W> {
+    sink: W,
+    started: Instant,
+    started_wall: SystemTime,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    pub fn new(mut sink: W) -> io::Result<Self> {
+        let started_wall = SystemTime::now();
+        let micros = started_wall
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_micros() as u64);
+        let mut header = [0u8; HEADER_LEN];
+        header[..6].copy_from_slice(MAGIC);
+        header[6..8].copy_from_slice(&VERSION.to_le_bytes());
+        header[8..].copy_from_slice(&micros.to_le_bytes());
+        sink.write_all(&header)?;
+        Ok(CaptureWriter {
+            sink,
+            started: Instant::now(),
+            started_wall,
+            buf: Vec::new(),
+        })
+    }
+
+    /// Wall-clock time the capture started.
+    pub fn started(&self) -> SystemTime {
+        self.started_wall
+    }
+
+    /// Appends a report stamped with the time since the capture started. Each
+    /// record goes out in a single write, so a capture cut short by Ctrl-C ends on
+    /// a record boundary.
+    pub fn record(&mut self, kind: ReportKind, data: &[u8]) -> io::Result<Record> {
+        let record = Record {
+            at: self.started.elapsed(),
+            kind,
+            data: data.to_vec(),
+        };
+        self.write(&record)?;
+        Ok(record)
+    }
+
+    /// Appends an already timestamped record, e.g. when filtering another capture.
+    pub fn write(&mut self, record: &Record) -> io::Result<()> {
+        let len = u16::try_from(record.data.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "report too long"))?;
+        self.buf.clear();
+        self.buf
+            .extend_from_slice(&(record.at.as_micros() as u64).to_le_bytes());
+        self.buf.push(record.kind.to_byte());
+        self.buf.extend_from_slice(&len.to_le_bytes());
+        self.buf.extend_from_slice(&record.data);
+        self.sink.write_all(&self.buf)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.sink.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.sink
+    }
+}
+
+/// Iterates over the records of a capture written by `CaptureWriter`.
+pub struct CaptureReader<R> {
+    source: R,
+    started: SystemTime,
+}
+
+impl<R: Read> CaptureReader<R> {
+    pub fn new(mut source: R) -> Result<Self> {
+        let mut header = [0u8; HEADER_LEN];
+        source.read_exact(&mut header)?;
+        if &header[..6] != MAGIC {
+            return Err(Error::InvalidFormat("not a DS4 capture"));
+        }
+        if u16::from_le_bytes([header[6], header[7]]) != VERSION {
+            return Err(Error::InvalidFormat("unsupported capture version"));
+        }
+        let mut micros = [0u8; 8];
+        micros.copy_from_slice(&header[8..]);
+        Ok(CaptureReader {
+            source,
+            started: UNIX_EPOCH + Duration::from_micros(u64::from_le_bytes(micros)),
+        })
+    }
+
+    /// Wall-clock time the capture started.
+    pub fn started(&self) -> SystemTime {
+        self.started
+    }
+
+    fn next_record(&mut self) -> Result<Option<Record>> {
+        let mut header = [0u8; RECORD_HEADER_LEN];
+        match self.source.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let mut micros = [0u8; 8];
+        micros.copy_from_slice(&header[..8]);
+        let kind = ReportKind::from_byte(header[8])
+            .ok_or(Error::InvalidFormat("unknown capture record kind"))?;
+        let mut data = vec![0u8; u16::from_le_bytes([header[9], header[10]]) as usize];
+        self.source.read_exact(&mut data)?;
+        Ok(Some(Record {
+            at: Duration::from_micros(u64::from_le_bytes(micros)),
+            kind,
+            data,
+        }))
+    }
+}
+
+/// Stops at the end of the capture; a record cut off part way is an error.
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+/// A transport that passes everything through to the real device and captures
+/// each report on the way, for working out undocumented behavior.
+///
+/// Hand it to `Controller::with_transport` in place of the device, as
+/// `ds4ctl capture` does.
+pub struct InspectingTransport<T> {
+    inner: T,
+    capture: CaptureWriter<Box<dyn Write + Send>>,
+    log: Option<Box<dyn Write + Send>>,
+}
+
+impl<T: Transport> InspectingTransport<T> {
+    pub fn new(inner: T, capture: impl Write + Send + 'static) -> io::Result<Self> {
+        Ok(InspectingTransport {
+            inner,
+            capture: CaptureWriter::new(Box::new(capture) as Box<dyn Write + Send>)?,
+            log: None,
+        })
+    }
+
+    /// Also prints each report with its annotation to `log` as it passes.
+    pub fn log_to(mut self, log: impl Write + Send + 'static) -> Self {
+        self.log = Some(Box::new(log));
+        self
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn capture(&mut self, kind: ReportKind, data: &[u8]) -> Result<()> {
+        let record = self.capture.record(kind, data)?;
+        if let Some(log) = self.log.as_mut() {
+            writeln!(log, "{}", record)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Transport> Transport for InspectingTransport<T> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = self.inner.read(buf)?;
+        // hidapi returns 0 on a read timeout; nothing crossed the wire.
+        if n > 0 {
+            self.capture(ReportKind::Input, &buf[..n])?;
+        }
+        Ok(n)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<usize> {
+        let n = self.inner.write(data)?;
+        self.capture(ReportKind::Output, data)?;
+        Ok(n)
+    }
+
+    fn get_feature_report(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = self.inner.get_feature_report(buf)?;
+        self.capture(ReportKind::Feature, &buf[..n.min(buf.len())])?;
+        Ok(n)
+    }
+}
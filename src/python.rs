@@ -0,0 +1,360 @@
+use crate::{ButtonId, Connection, Controller, Error, Event, Snapshot};
+use hidapi::HidApi;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use std::sync::{Mutex, MutexGuard};
+
+create_exception!(
+    ps4hid,
+    Ds4Error,
+    PyException,
+    "Raised for any failure reported by the library."
+);
+create_exception!(
+    ps4hid,
+    DisconnectedError,
+    Ds4Error,
+    "The pad went away or was closed; open it again to carry on."
+);
+
+/// hidapi allows one context per process, so every controller is opened from
+/// this one, which is never dropped.
+static API: Mutex<Option<HidApi>> = Mutex::new(None);
+
+fn failed(e: Error) -> PyErr {
+    if matches!(e, Error::Closed) || e.is_device_error() {
+        DisconnectedError::new_err(e.to_string())
+    } else {
+        Ds4Error::new_err(e.to_string())
+    }
+}
+
+/// Every control's state at one report.
+#[pyclass(name = "State", module = "ps4hid", frozen, get_all)]
+#[derive(Debug, Clone)]
+pub struct PyState {
+    /// Names of the pressed buttons, e.g. `"x"` and `"l1"`.
+    buttons: Vec<&'static str>,
+    /// `"released"`, or a compass direction such as `"north"` or `"southeast"`.
+    dpad: String,
+    /// Raw stick axes, 0 left or up to 255 right or down.
+    left_stick: (u8, u8),
+    right_stick: (u8, u8),
+    l2: u8,
+    r2: u8,
+    /// Raw IMU readings.
+    gyro: [i16; 3],
+    accel: [i16; 3],
+    /// `(id, x, y)` for each finger on the touchpad.
+    touches: Vec<(u8, u16, u16)>,
+    battery_percent: Option<u8>,
+    charging: bool,
+}
+
+impl PyState {
+    fn from_controller(controller: &Controller) -> Self {
+        let snapshot: Snapshot = controller.controls.snapshot();
+        let battery = controller.battery();
+        PyState {
+            buttons: ButtonId::ALL
+                .into_iter()
+                .filter(|&id| snapshot.pressed(id))
+                .map(|id| id.name())
+                .collect(),
+            dpad: format!("{:?}", snapshot.dpad).to_lowercase(),
+            left_stick: (snapshot.left_stick.x, snapshot.left_stick.y),
+            right_stick: (snapshot.right_stick.x, snapshot.right_stick.y),
+            l2: snapshot.l2_value,
+            r2: snapshot.r2_value,
+            gyro: controller.controls.imu.gyro,
+            accel: controller.controls.imu.accel,
+            touches: controller
+                .controls
+                .touches
+                .state()
+                .iter()
+                .filter(|t| t.active)
+                .map(|t| (t.id, t.x, t.y))
+                .collect(),
+            battery_percent: battery.percent().map(|p| p.round().clamp(0.0, 100.0) as u8),
+            charging: battery.cable_connected(),
+        }
+    }
+}
+
+#[pymethods]
+impl PyState {
+    /// Whether the button named `name` is held, e.g. `state.pressed("square")`.
+    fn pressed(&self, name: &str) -> PyResult<bool> {
+        let id: ButtonId = name
+            .parse()
+            .map_err(|_| PyValueError::new_err(format!("unknown button {:?}", name)))?;
+        Ok(self.buttons.contains(&id.name()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "State(buttons={:?}, dpad={:?}, left_stick={:?}, right_stick={:?}, l2={}, r2={})",
+            self.buttons, self.dpad, self.left_stick, self.right_stick, self.l2, self.r2
+        )
+    }
+}
+
+/// Something the controller noticed besides input, such as a paired host
+/// changing or output stalling. Each kind is a subclass with its own fields,
+/// e.g. `Event.OutputStalled(failures, error)`, so events can be matched on.
+#[pyclass(name = "Event", module = "ps4hid")]
+#[derive(Debug, Clone)]
+pub enum PyEvent {
+    PairingChanged {
+        /// Bluetooth addresses, e.g. `"a4:ae:12:00:00:01"`.
+        device: String,
+        host: String,
+        previous_host: Option<String>,
+    },
+    Resumed {},
+    PeripheralsChanged {
+        headphones: bool,
+        microphone: bool,
+        extension: bool,
+    },
+    OutputStalled {
+        failures: u32,
+        error: String,
+    },
+    OutputRecovered {},
+    HandlerPanicked {
+        control: String,
+        message: String,
+    },
+    GyroRecalibrated {
+        /// Raw units per axis.
+        bias: [f32; 3],
+    },
+    StateChanged {
+        /// Lifecycle states such as `"handshaking"` and `"streaming"`.
+        previous: String,
+        current: String,
+    },
+    ChoreographyStarted {
+        /// `"connect"`, `"disconnect"` or `"identify"`.
+        cue: String,
+    },
+    ChoreographyFinished {
+        cue: String,
+    },
+    InputBacklog {
+        skipped: u32,
+        /// Seconds.
+        behind: f64,
+    },
+    ConfigIgnored {
+        path: String,
+        error: String,
+    },
+    Marker {
+        label: String,
+        /// Seconds, in the pad's clock.
+        device_time: f64,
+    },
+}
+
+impl From<Event> for PyEvent {
+    fn from(event: Event) -> Self {
+        let cue = |cue| format!("{:?}", cue).to_lowercase();
+        match event {
+            Event::PairingChanged { previous, current } => PyEvent::PairingChanged {
+                device: current.device.to_string(),
+                host: current.host.to_string(),
+                previous_host: previous.map(|p| p.host.to_string()),
+            },
+            Event::Resumed => PyEvent::Resumed {},
+            Event::PeripheralsChanged { current, .. } => PyEvent::PeripheralsChanged {
+                headphones: current.headphones,
+                microphone: current.microphone,
+                extension: current.extension,
+            },
+            Event::OutputStalled { failures, error } => PyEvent::OutputStalled { failures, error },
+            Event::OutputRecovered => PyEvent::OutputRecovered {},
+            Event::HandlerPanicked(panic) => PyEvent::HandlerPanicked {
+                control: panic.control.to_string(),
+                message: panic.message,
+            },
+            Event::GyroRecalibrated { bias } => PyEvent::GyroRecalibrated { bias },
+            Event::StateChanged(transition) => PyEvent::StateChanged {
+                previous: transition.previous.to_string(),
+                current: transition.current.to_string(),
+            },
+            Event::ChoreographyStarted(c) => PyEvent::ChoreographyStarted { cue: cue(c) },
+            Event::ChoreographyFinished(c) => PyEvent::ChoreographyFinished { cue: cue(c) },
+            Event::InputBacklog { skipped, behind } => PyEvent::InputBacklog {
+                skipped,
+                behind: behind.as_secs_f64(),
+            },
+            Event::ConfigIgnored { path, error } => PyEvent::ConfigIgnored {
+                path: path.display().to_string(),
+                error,
+            },
+            Event::Marker {
+                label, device_time, ..
+            } => PyEvent::Marker {
+                label,
+                device_time: device_time.as_secs_f64(),
+            },
+        }
+    }
+}
+
+#[pymethods]
+impl PyEvent {
+    /// The variant of `ps4hid::Event`, e.g. `"OutputStalled"`.
+    #[getter]
+    fn kind(&self) -> &'static str {
+        match self {
+            PyEvent::PairingChanged { .. } => "PairingChanged",
+            PyEvent::Resumed {} => "Resumed",
+            PyEvent::PeripheralsChanged { .. } => "PeripheralsChanged",
+            PyEvent::OutputStalled { .. } => "OutputStalled",
+            PyEvent::OutputRecovered {} => "OutputRecovered",
+            PyEvent::HandlerPanicked { .. } => "HandlerPanicked",
+            PyEvent::GyroRecalibrated { .. } => "GyroRecalibrated",
+            PyEvent::StateChanged { .. } => "StateChanged",
+            PyEvent::ChoreographyStarted { .. } => "ChoreographyStarted",
+            PyEvent::ChoreographyFinished { .. } => "ChoreographyFinished",
+            PyEvent::InputBacklog { .. } => "InputBacklog",
+            PyEvent::ConfigIgnored { .. } => "ConfigIgnored",
+            PyEvent::Marker { .. } => "Marker",
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Event.{:?}", self)
+    }
+}
+
+/// The first connected DS4, opened with its saved profile applied.
+///
+/// `update` releases the GIL while it waits for the pad, so other Python
+/// threads keep running; the controller may be shared between threads, which
+/// take turns.
+#[pyclass(name = "Controller", module = "ps4hid")]
+pub struct PyController {
+    controller: Mutex<Option<Controller>>,
+}
+
+impl PyController {
+    fn lock(&self) -> MutexGuard<'_, Option<Controller>> {
+        self.controller.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Runs `f` on the open controller without the GIL, since another thread
+    /// may hold the controller in a blocking `update`.
+    fn with<T: Send>(
+        &self,
+        py: Python<'_>,
+        f: impl FnOnce(&mut Controller) -> crate::Result<T> + Send,
+    ) -> PyResult<T> {
+        py.detach(|| {
+            let mut controller = self.lock();
+            let controller = controller.as_mut().ok_or_else(|| failed(Error::Closed))?;
+            f(controller).map_err(failed)
+        })
+    }
+}
+
+#[pymethods]
+impl PyController {
+    #[new]
+    fn new(py: Python<'_>) -> PyResult<Self> {
+        let controller = py.detach(|| {
+            let mut shared = API.lock().unwrap_or_else(|e| e.into_inner());
+            let api = match shared.as_mut() {
+                Some(api) => {
+                    api.refresh_devices().map_err(Error::from)?;
+                    api
+                }
+                None => shared.insert(HidApi::new()?),
+            };
+            Controller::open(api)
+        });
+        Ok(PyController {
+            controller: Mutex::new(Some(controller.map_err(failed)?)),
+        })
+    }
+
+    /// Waits for the next input report and returns the new state. Raises
+    /// `DisconnectedError` once the pad is gone.
+    fn update(&self, py: Python<'_>) -> PyResult<PyState> {
+        self.with(py, |c| {
+            c.update()?;
+            Ok(PyState::from_controller(c))
+        })
+    }
+
+    /// The state as of the latest `update`.
+    fn state(&self, py: Python<'_>) -> PyResult<PyState> {
+        self.with(py, |c| Ok(PyState::from_controller(c)))
+    }
+
+    /// Events that have accumulated since the last call, oldest first.
+    fn events(&self, py: Python<'_>) -> PyResult<Vec<PyEvent>> {
+        self.with(py, |c| Ok(c.events().map(PyEvent::from).collect()))
+    }
+
+    /// Sets the strong (left) and weak (right) motor speeds, 0-255.
+    fn set_rumble(&self, py: Python<'_>, strong: u8, weak: u8) -> PyResult<()> {
+        self.with(py, |c| c.set_rumble(strong, weak))
+    }
+
+    fn set_lightbar(&self, py: Python<'_>, r: u8, g: u8, b: u8) -> PyResult<()> {
+        self.with(py, |c| c.set_lightbar(r, g, b))
+    }
+
+    /// `"usb"`, `"bluetooth"` or `"dongle"`.
+    #[getter]
+    fn connection(&self, py: Python<'_>) -> PyResult<&'static str> {
+        self.with(py, |c| {
+            Ok(match c.connection() {
+                Connection::Usb => "usb",
+                Connection::Bluetooth => "bluetooth",
+                Connection::Dongle => "dongle",
+            })
+        })
+    }
+
+    /// Sends any held-back output and lets go of the pad. Later calls raise
+    /// `DisconnectedError`; closing twice does nothing.
+    fn close(&self, py: Python<'_>) -> PyResult<()> {
+        py.detach(|| match self.lock().take() {
+            Some(mut controller) => controller.close().map_err(failed),
+            None => Ok(()),
+        })
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &self,
+        py: Python<'_>,
+        _exc_type: Py<PyAny>,
+        _exc_value: Py<PyAny>,
+        _traceback: Py<PyAny>,
+    ) -> PyResult<bool> {
+        self.close(py)?;
+        Ok(false)
+    }
+}
+
+/// The `ps4hid` Python extension module.
+#[pymodule]
+fn ps4hid(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyController>()?;
+    m.add_class::<PyState>()?;
+    m.add_class::<PyEvent>()?;
+    m.add("Ds4Error", m.py().get_type::<Ds4Error>())?;
+    m.add("DisconnectedError", m.py().get_type::<DisconnectedError>())?;
+    Ok(())
+}
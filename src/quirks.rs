@@ -0,0 +1,134 @@
+use crate::firmware::{Capabilities, Firmware};
+use std::borrow::Cow;
+use std::sync::RwLock;
+
+/// Workarounds for a family of third-party pads, matched by USB ids and
+/// optionally the version report's build date.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quirk {
+    pub name: Cow<'static, str>,
+    pub vendor_id: u16,
+    /// `None` matches every product from the vendor.
+    pub product_id: Option<u16>,
+    /// Matches only pads whose `Firmware::build_date` contains this.
+    pub firmware: Option<Cow<'static, str>>,
+    /// Extra bytes before the controls in each input report, for pads that pad
+    /// or shift the standard layout.
+    pub input_offset: usize,
+    /// Used instead of detecting capabilities from the version report.
+    pub capabilities: Option<Capabilities>,
+    /// Don't read the version or pairing feature reports at all, for pads that
+    /// stall or disconnect when asked.
+    pub no_feature_reports: bool,
+    /// The report counter doesn't count, so gaps in it aren't lost reports.
+    pub static_counter: bool,
+}
+
+impl Quirk {
+    /// A quirk that changes nothing yet; chain the workarounds that apply.
+    pub const fn new(name: &'static str, vendor_id: u16, product_id: Option<u16>) -> Self {
+        Quirk {
+            name: Cow::Borrowed(name),
+            vendor_id,
+            product_id,
+            firmware: None,
+            input_offset: 0,
+            capabilities: None,
+            no_feature_reports: false,
+            static_counter: false,
+        }
+    }
+
+    pub const fn capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = Some(capabilities);
+        self
+    }
+
+    /// Restricts the quirk to pads whose build date contains `date`.
+    pub fn firmware(mut self, date: impl Into<Cow<'static, str>>) -> Self {
+        self.firmware = Some(date.into());
+        self
+    }
+
+    pub const fn input_offset(mut self, bytes: usize) -> Self {
+        self.input_offset = bytes;
+        self
+    }
+
+    pub const fn no_feature_reports(mut self) -> Self {
+        self.no_feature_reports = true;
+        self
+    }
+
+    pub const fn static_counter(mut self) -> Self {
+        self.static_counter = true;
+        self
+    }
+
+    pub fn matches(&self, vendor_id: u16, product_id: u16, firmware: Option<&Firmware>) -> bool {
+        self.vendor_id == vendor_id
+            && self.product_id.is_none_or(|p| p == product_id)
+            && self
+                .firmware
+                .as_deref()
+                .is_none_or(|date| firmware.is_some_and(|f| f.build_date.contains(date)))
+    }
+}
+
+const NO_EXTRAS: Capabilities = Capabilities {
+    has_lightbar: false,
+    imu_trustworthy: false,
+    bt_1khz: false,
+};
+
+const WIRED_FULL: Capabilities = Capabilities {
+    bt_1khz: false,
+    ..Capabilities::FULL
+};
+
+/// Licensed and common clone pads that present the DS4 report layout.
+pub const BUILTIN: &[Quirk] = &[
+    // Wired HORIPADs have no lightbar or motion sensors.
+    Quirk::new("Hori", 0x0f0d, None).capabilities(NO_EXTRAS),
+    Quirk::new("Nacon Revolution Pro", 0x146b, Some(0x0d01)).capabilities(WIRED_FULL),
+    Quirk::new("Nacon Revolution Pro 2", 0x146b, Some(0x0d02)).capabilities(WIRED_FULL),
+    Quirk::new("Razer Raiju", 0x1532, Some(0x1000)).capabilities(WIRED_FULL),
+    // Cheap wired clones seen under this id answer feature reports with garbage
+    // and never advance the report counter.
+    Quirk::new("Generic clone", 0x7545, Some(0x0104))
+        .capabilities(NO_EXTRAS)
+        .no_feature_reports()
+        .static_counter(),
+];
+
+static CUSTOM: RwLock<Vec<Quirk>> = RwLock::new(Vec::new());
+
+/// Adds a quirk for controllers opened from now on. Registered quirks are
+/// checked before the built-in ones, newest first, so they can also override them.
+pub fn register(quirk: Quirk) {
+    CUSTOM
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(quirk);
+}
+
+/// The quirk for a pad, if any. `firmware` is `None` before the version report
+/// has been read, which only matches quirks that don't need it.
+pub fn lookup(vendor_id: u16, product_id: u16, firmware: Option<&Firmware>) -> Option<Quirk> {
+    let custom = CUSTOM.read().unwrap_or_else(|e| e.into_inner());
+    custom
+        .iter()
+        .rev()
+        .chain(BUILTIN)
+        .find(|q| q.matches(vendor_id, product_id, firmware))
+        .cloned()
+}
+
+/// Whether any quirk, built-in or registered, covers pads with these ids, so
+/// enumeration can include vendors other than Sony.
+pub fn is_known(vendor_id: u16, product_id: u16) -> bool {
+    let matches =
+        |q: &Quirk| q.vendor_id == vendor_id && q.product_id.is_none_or(|p| p == product_id);
+    let custom = CUSTOM.read().unwrap_or_else(|e| e.into_inner());
+    BUILTIN.iter().chain(custom.iter()).any(matches)
+}
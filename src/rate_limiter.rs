@@ -0,0 +1,26 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub struct RateLimiter {
+    interval: Duration,
+    last_iter: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_iter: Instant::now() - interval,
+        }
+    }
+
+    pub fn wait(&mut self) {
+        let last_iter_duration = Instant::now() - self.last_iter;
+        if last_iter_duration < self.interval {
+            let delay = self.interval - last_iter_duration;
+            thread::sleep(delay);
+        }
+
+        self.last_iter = Instant::now();
+    }
+}
@@ -0,0 +1,114 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Decides how long to wait before each reconnection attempt.
+pub trait ReconnectPolicy: Send {
+    /// The delay before attempt `attempt` (1 for the first retry), or `None` to
+    /// give up.
+    fn next_delay(&mut self, attempt: u32) -> Option<Duration>;
+
+    /// Called once a connection succeeds.
+    fn reset(&mut self) {}
+}
+
+/// Retries at a fixed interval forever.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedInterval(pub Duration);
+
+impl ReconnectPolicy for FixedInterval {
+    fn next_delay(&mut self, _attempt: u32) -> Option<Duration> {
+        Some(self.0)
+    }
+}
+
+/// Exponential backoff: `initial * multiplier^(attempt - 1)`, capped at `max`, with
+/// up to `jitter` (a fraction in `0.0..=1.0`) of each delay randomly taken off so
+/// several hosts don't retry in lockstep. A `multiplier` below 1 (or NaN) is
+/// treated as 1, so delays never shrink.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+    pub multiplier: f64,
+    pub jitter: f64,
+    /// Give up after this many attempts; `None` retries forever.
+    pub max_attempts: Option<u32>,
+    seed: u64,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            initial: Duration::from_millis(250),
+            max: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_attempts: None,
+            seed: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(1, |d| d.as_nanos() as u64)
+                | 1,
+        }
+    }
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Backoff {
+            initial,
+            max,
+            ..Backoff::default()
+        }
+    }
+
+    /// xorshift64; plenty for spreading retries out.
+    fn random(&mut self) -> f64 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        (self.seed >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl ReconnectPolicy for Backoff {
+    fn next_delay(&mut self, attempt: u32) -> Option<Duration> {
+        if self.max_attempts.is_some_and(|max| attempt > max) {
+            return None;
+        }
+        let exponent = attempt.saturating_sub(1).min(64) as i32;
+        let delay = (self.initial.as_secs_f64() * self.multiplier.max(1.0).powi(exponent))
+            .min(self.max.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0) * self.random();
+        Some(Duration::try_from_secs_f64(delay * (1.0 - jitter)).unwrap_or(self.max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bad_parameters_fall_back_to_sane_delays() {
+        for multiplier in [-2.0, 0.5, f64::NAN, f64::INFINITY] {
+            let mut backoff = Backoff {
+                multiplier,
+                jitter: f64::NAN,
+                ..Backoff::new(Duration::from_millis(100), Duration::from_secs(1))
+            };
+            for attempt in [1, 2, 30, u32::MAX] {
+                let delay = backoff.next_delay(attempt);
+                assert!(delay.is_some_and(|d| d <= Duration::from_secs(1)));
+            }
+        }
+    }
+
+    #[test]
+    fn delays_grow_to_the_cap() {
+        let mut backoff = Backoff {
+            jitter: 0.0,
+            ..Backoff::new(Duration::from_millis(100), Duration::from_secs(1))
+        };
+        assert_eq!(backoff.next_delay(1), Some(Duration::from_millis(100)));
+        assert_eq!(backoff.next_delay(2), Some(Duration::from_millis(200)));
+        assert_eq!(backoff.next_delay(10), Some(Duration::from_secs(1)));
+    }
+}
@@ -0,0 +1,571 @@
+use crate::source::{EventQueue, InputEvent, InputSource};
+use crate::transport::encode_input;
+use crate::wire::{self, WIRE_LEN};
+use crate::{Controls, Error, Result, Snapshot};
+use std::io::{self, ErrorKind};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "noise")]
+use crate::remote_auth::{ClientAuth, ServerAuth};
+#[cfg(feature = "noise")]
+pub use crate::remote_auth::{KeyStore, PairedKey, PAIRING_WINDOW};
+#[cfg(feature = "mdns")]
+pub use discovery::{default_name, hostname, Advertisement, RemoteEndpoint, SERVICE_TYPE};
+
+/// The port `RemoteServer` listens on unless told otherwise.
+pub const DEFAULT_PORT: u16 = 9871;
+
+const MAGIC: [u8; 4] = *b"DS4R";
+const VERSION: u8 = 1;
+pub(crate) const HELLO: u8 = 0;
+pub(crate) const STATE: u8 = 1;
+pub(crate) const BYE: u8 = 2;
+pub(crate) const HEADER_LEN: usize = 6;
+const STATE_LEN: usize = HEADER_LEN + 4 + WIRE_LEN;
+
+/// How often a `RemoteController` renews its subscription.
+const KEEPALIVE: Duration = Duration::from_secs(1);
+/// A subscriber or server silent for this long is considered gone.
+const LINK_TIMEOUT: Duration = Duration::from_secs(3);
+/// Longest `RemoteController::update` waits for a state.
+const RECV_WAIT: Duration = Duration::from_millis(100);
+/// A sequence number this far behind the last one means the server restarted
+/// rather than that the datagram was reordered.
+const REORDER_WINDOW: i32 = 64;
+
+/// Datagrams start with a 6-byte header, `DS4R`, the protocol version and a
+/// kind:
+///
+/// | kind | sender | body |
+/// |------|--------|------|
+/// | 0 hello | client | none; subscribes, and renews every `KEEPALIVE` |
+/// | 1 state | server | sequence number, u32 little-endian, then a `wire` message |
+/// | 2 bye | client | none; unsubscribes |
+///
+/// With the `noise` feature, a server that requires pairing only talks to
+/// clients holding a key agreed with a pairing code, and ignores plain hellos:
+///
+/// | kind | sender | body |
+/// |------|--------|------|
+/// | 3 pair | client | key id, 8 bytes, then a SPAKE2 message |
+/// | 4 pair reply | server | a SPAKE2 message |
+/// | 5 handshake | client | key id, then the first `Noise_NNpsk0` message; subscribes |
+/// | 6 handshake reply | server | the second `Noise_NNpsk0` message |
+/// | 7 sealed | either | nonce, u64 little-endian, then an encrypted kind and body |
+///
+/// A sealed state's nonce is its sequence number, so it has no other.
+pub(crate) fn header(kind: u8) -> [u8; HEADER_LEN] {
+    [MAGIC[0], MAGIC[1], MAGIC[2], MAGIC[3], VERSION, kind]
+}
+
+pub(crate) fn kind(datagram: &[u8]) -> Option<u8> {
+    match datagram {
+        [m0, m1, m2, m3, VERSION, kind, ..] if [*m0, *m1, *m2, *m3] == MAGIC => Some(*kind),
+        _ => None,
+    }
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
+
+/// The sending half of the network proxy: streams one controller's state over
+/// UDP to every `RemoteController` subscribed to it.
+///
+/// There's no thread: each `publish` first handles subscriptions that have
+/// arrived, so call it for every update of the pad being shared.
+pub struct RemoteServer {
+    socket: UdpSocket,
+    subscribers: Vec<(SocketAddr, Instant)>,
+    seq: u32,
+    #[cfg(feature = "noise")]
+    auth: Option<ServerAuth>,
+}
+
+impl RemoteServer {
+    /// Listens on `addr`, e.g. `("0.0.0.0", DEFAULT_PORT)`.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(RemoteServer {
+            socket,
+            subscribers: Vec::new(),
+            seq: 0,
+            #[cfg(feature = "noise")]
+            auth: None,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    pub fn subscribers(&self) -> impl Iterator<Item = SocketAddr> + '_ {
+        self.subscribers.iter().map(|&(addr, _)| addr)
+    }
+
+    /// Sends `snapshot` to every subscriber.
+    pub fn publish(&mut self, snapshot: &Snapshot) -> io::Result<()> {
+        self.poll()?;
+        self.seq = self.seq.wrapping_add(1);
+        let mut message = [0; STATE_LEN];
+        message[..HEADER_LEN].copy_from_slice(&header(STATE));
+        message[HEADER_LEN..HEADER_LEN + 4].copy_from_slice(&self.seq.to_le_bytes());
+        message[HEADER_LEN + 4..].copy_from_slice(&wire::encode(snapshot));
+        for &(addr, _) in &self.subscribers {
+            #[cfg(feature = "noise")]
+            if let Some(auth) = &mut self.auth {
+                let mut inner = [0; 1 + WIRE_LEN];
+                inner[0] = STATE;
+                inner[1..].copy_from_slice(&message[HEADER_LEN + 4..]);
+                if let Some(sealed) = auth.seal(addr, &inner) {
+                    let _ = self.socket.send_to(&sealed, addr);
+                }
+                continue;
+            }
+            // One unreachable subscriber shouldn't stop the rest; it expires
+            // when its keepalives stop.
+            let _ = self.socket.send_to(&message, addr);
+        }
+        Ok(())
+    }
+
+    /// Handles hellos and byes that have arrived, and drops subscribers that
+    /// have gone quiet.
+    fn poll(&mut self) -> io::Result<()> {
+        let mut buf = [0; 256];
+        loop {
+            let (n, from) = match self.socket.recv_from(&mut buf) {
+                Ok(received) => received,
+                Err(e) if is_timeout(&e) => break,
+                // ICMP unreachable from a vanished subscriber, on some platforms.
+                Err(e) if e.kind() == ErrorKind::ConnectionReset => continue,
+                Err(e) => return Err(e),
+            };
+            #[allow(unused_mut)]
+            let mut kind = kind(&buf[..n]);
+            #[cfg(feature = "noise")]
+            if let Some(auth) = &mut self.auth {
+                kind = auth.handle(&self.socket, from, &buf[..n]);
+                if kind == Some(BYE) {
+                    auth.end(from);
+                }
+            }
+            match kind {
+                Some(HELLO) => match self.subscribers.iter_mut().find(|(a, _)| *a == from) {
+                    Some((_, seen)) => *seen = Instant::now(),
+                    None => self.subscribers.push((from, Instant::now())),
+                },
+                Some(BYE) => self.subscribers.retain(|(a, _)| *a != from),
+                _ => {}
+            }
+        }
+        #[cfg(feature = "noise")]
+        if let Some(auth) = &mut self.auth {
+            for &(addr, seen) in &self.subscribers {
+                if seen.elapsed() >= LINK_TIMEOUT {
+                    auth.end(addr);
+                }
+            }
+        }
+        self.subscribers
+            .retain(|(_, seen)| seen.elapsed() < LINK_TIMEOUT);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "noise")]
+impl RemoteServer {
+    /// Only streams to clients in `keys`, or that pair using a code from
+    /// `start_pairing`, and encrypts everything sent to them.
+    pub fn require_pairing(mut self, keys: KeyStore) -> Self {
+        self.auth = Some(ServerAuth::new(keys));
+        self.subscribers.clear();
+        self
+    }
+
+    /// Makes a new pairing code, valid for `PAIRING_WINDOW` and a single
+    /// attempt, to show to whoever runs the client. Requires pairing from now
+    /// on if the server didn't already, dropping current subscribers.
+    pub fn start_pairing(&mut self) -> io::Result<String> {
+        if self.auth.is_none() {
+            self.subscribers.clear();
+        }
+        self.auth
+            .get_or_insert_with(|| ServerAuth::new(KeyStore::new()))
+            .start_pairing()
+    }
+
+    /// The current pairing code, until it's used or expires.
+    pub fn pairing_code(&self) -> Option<&str> {
+        self.auth.as_ref()?.pairing_code()
+    }
+
+    /// A client that paired since the last call, already added to `keys`.
+    /// Save the store so it stays paired across restarts.
+    pub fn take_paired(&mut self) -> Option<PairedKey> {
+        self.auth.as_mut()?.take_paired()
+    }
+
+    /// The clients allowed to subscribe, if pairing is required.
+    pub fn keys(&self) -> Option<&KeyStore> {
+        self.auth.as_ref().map(|auth| &auth.keys)
+    }
+}
+
+/// The receiving half of the network proxy: a controller on another machine,
+/// shared there by a `RemoteServer`.
+///
+/// Only buttons, sticks and triggers cross the network, so like `GamepadSource`
+/// there is no touch, IMU, battery or output.
+pub struct RemoteController {
+    pub controls: Controls,
+    socket: UdpSocket,
+    server: SocketAddr,
+    last_seq: Option<u32>,
+    last_hello: Instant,
+    last_state: Option<Instant>,
+    connected: Instant,
+    counter: u8,
+    events: EventQueue,
+    #[cfg(feature = "noise")]
+    auth: Option<ClientAuth>,
+}
+
+impl RemoteController {
+    /// Subscribes to the server at `addr`. UDP has no handshake, so this
+    /// succeeds even if nothing is listening; `update` fails once no state has
+    /// arrived for a few seconds.
+    pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let (socket, server) = Self::socket(addr)?;
+        socket.send(&header(HELLO))?;
+        Ok(Self::new(socket, server))
+    }
+
+    /// A socket connected to the server at `addr`, and its address.
+    fn socket(addr: impl ToSocketAddrs) -> io::Result<(UdpSocket, SocketAddr)> {
+        let server = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "no address to connect to"))?;
+        let local: SocketAddr = if server.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(server)?;
+        Ok((socket, server))
+    }
+
+    fn new(socket: UdpSocket, server: SocketAddr) -> Self {
+        RemoteController {
+            controls: Controls::new(),
+            socket,
+            server,
+            last_seq: None,
+            last_hello: Instant::now(),
+            last_state: None,
+            connected: Instant::now(),
+            counter: 0,
+            events: EventQueue::new(),
+            #[cfg(feature = "noise")]
+            auth: None,
+        }
+    }
+
+    pub fn server(&self) -> SocketAddr {
+        self.server
+    }
+
+    /// Whether a state has arrived recently.
+    pub fn is_connected(&self) -> bool {
+        self.last_state
+            .is_some_and(|at| at.elapsed() < LINK_TIMEOUT)
+    }
+
+    /// Waits briefly for the next state and updates `controls` from it. Fails
+    /// with `Error::NotFound` when the server has gone quiet.
+    pub fn update(&mut self) -> Result<()> {
+        if self.last_hello.elapsed() >= KEEPALIVE {
+            match self.keepalive().and_then(|hello| self.socket.send(&hello)) {
+                // Refused only means the server isn't up yet.
+                Err(e) if e.kind() != ErrorKind::ConnectionRefused => return Err(e.into()),
+                _ => {}
+            }
+            self.last_hello = Instant::now();
+        }
+        let quiet_since = self.last_state.unwrap_or(self.connected);
+        if quiet_since.elapsed() >= LINK_TIMEOUT {
+            return Err(Error::NotFound);
+        }
+        let deadline = Instant::now() + RECV_WAIT;
+        let mut buf = [0; 256];
+        loop {
+            let wait = deadline.saturating_duration_since(Instant::now());
+            if wait.is_zero() {
+                return Ok(());
+            }
+            self.socket.set_read_timeout(Some(wait))?;
+            let n = match self.socket.recv(&mut buf) {
+                Ok(n) => n,
+                Err(e) if is_timeout(&e) => return Ok(()),
+                // Nothing listening yet; keep waiting for the server to start.
+                Err(e) if e.kind() == ErrorKind::ConnectionRefused => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if let Some(mut snapshot) = self.accept(&buf[..n]) {
+                // Skip to the newest state if several queued up, e.g. while the
+                // caller was busy, rather than replaying them late.
+                self.socket.set_nonblocking(true)?;
+                while let Ok(n) = self.socket.recv(&mut buf) {
+                    if let Some(newer) = self.accept(&buf[..n]) {
+                        snapshot = newer;
+                    }
+                }
+                self.socket.set_nonblocking(false)?;
+                self.last_state = Some(Instant::now());
+                self.counter = (self.counter + 1) & 0x3f;
+                return self
+                    .controls
+                    .update(&encode_input(&snapshot, self.counter, 0));
+            }
+        }
+    }
+
+    fn keepalive(&mut self) -> io::Result<Vec<u8>> {
+        #[cfg(feature = "noise")]
+        if let Some(auth) = &mut self.auth {
+            // No state for a while may mean the server restarted and lost the
+            // session, so start a new one.
+            let quiet_since = self.last_state.unwrap_or(self.connected);
+            if quiet_since.elapsed() >= 2 * KEEPALIVE {
+                auth.reset();
+            }
+            return auth.keepalive();
+        }
+        Ok(header(HELLO).to_vec())
+    }
+
+    /// The state in a datagram, unless it's malformed or older than the last.
+    fn accept(&mut self, datagram: &[u8]) -> Option<Snapshot> {
+        #[cfg(feature = "noise")]
+        if let Some(auth) = &mut self.auth {
+            let (_, inner) = auth.open(datagram)?;
+            return match inner.split_first() {
+                Some((&STATE, message)) => wire::decode(message).ok(),
+                _ => None,
+            };
+        }
+        if kind(datagram) != Some(STATE) || datagram.len() < STATE_LEN {
+            return None;
+        }
+        let seq = u32::from_le_bytes(datagram[HEADER_LEN..HEADER_LEN + 4].try_into().ok()?);
+        if let Some(last) = self.last_seq {
+            let ahead = seq.wrapping_sub(last) as i32;
+            if (-REORDER_WINDOW..=0).contains(&ahead) {
+                return None;
+            }
+        }
+        let snapshot = wire::decode(&datagram[HEADER_LEN + 4..]).ok()?;
+        self.last_seq = Some(seq);
+        Some(snapshot)
+    }
+}
+
+impl Drop for RemoteController {
+    fn drop(&mut self) {
+        #[cfg(feature = "noise")]
+        if let Some(auth) = &mut self.auth {
+            if let Some(bye) = auth.seal(&[BYE]) {
+                let _ = self.socket.send(&bye);
+            }
+            return;
+        }
+        let _ = self.socket.send(&header(BYE));
+    }
+}
+
+#[cfg(feature = "noise")]
+impl RemoteController {
+    /// Pairs with the server at `addr` using the code it's showing, returning
+    /// the key to save, e.g. in `KeyStore::servers_path()`, and pass to
+    /// `connect_paired`. Fails with `PermissionDenied` if the code was wrong or
+    /// expired.
+    pub fn pair(addr: impl ToSocketAddrs, code: &str) -> io::Result<PairedKey> {
+        let (socket, server) = Self::socket(addr)?;
+        crate::remote_auth::pair(&socket, code, server.to_string())
+    }
+
+    /// Subscribes to a server that requires pairing, using the key from
+    /// pairing with it. Like `connect`, this succeeds even if the server isn't
+    /// up or doesn't accept the key; `update` fails once no state has arrived.
+    pub fn connect_paired(addr: impl ToSocketAddrs, key: PairedKey) -> io::Result<Self> {
+        let (socket, server) = Self::socket(addr)?;
+        let mut auth = ClientAuth::new(key);
+        socket.send(&auth.keepalive()?)?;
+        let mut controller = Self::new(socket, server);
+        controller.auth = Some(auth);
+        Ok(controller)
+    }
+}
+
+impl InputSource for RemoteController {
+    fn next_event(&mut self) -> Result<Option<InputEvent>> {
+        if self.events.is_empty() {
+            self.update()?;
+            let snapshot = self.controls.snapshot();
+            self.events.push_snapshot(snapshot);
+        }
+        Ok(self.events.pop())
+    }
+
+    fn state(&self) -> Snapshot {
+        self.events.state()
+    }
+    fn pending(&self) -> usize {
+        self.events.len()
+    }
+}
+
+#[cfg(feature = "mdns")]
+mod discovery {
+    use super::{RemoteController, RemoteServer};
+    use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+    use std::fmt;
+    use std::io;
+    use std::net::SocketAddr;
+    use std::time::{Duration, Instant};
+
+    /// The DNS-SD service type servers are advertised under.
+    pub const SERVICE_TYPE: &str = "_ds4._udp.local.";
+
+    fn other(e: mdns_sd::Error) -> io::Error {
+        io::Error::other(e.to_string())
+    }
+
+    /// This machine's name, without any domain.
+    pub fn hostname() -> String {
+        #[cfg(target_os = "linux")]
+        {
+            let mut buf = [0u8; 256];
+            // SAFETY: the buffer is valid for its whole length, and gethostname
+            // writes at most that many bytes.
+            if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } == 0 {
+                let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+                if let Ok(name) = std::str::from_utf8(&buf[..len]) {
+                    return name.split('.').next().unwrap_or(name).to_string();
+                }
+            }
+        }
+        std::env::var("COMPUTERNAME")
+            .or_else(|_| std::env::var("HOSTNAME"))
+            .unwrap_or_else(|_| "ds4".to_string())
+    }
+
+    /// "DS4 on <hostname>", the name `RemoteServer::advertise` is usually given.
+    pub fn default_name() -> String {
+        format!("DS4 on {}", hostname())
+    }
+
+    /// Keeps a `RemoteServer` advertised on the local network until dropped.
+    pub struct Advertisement {
+        daemon: ServiceDaemon,
+        fullname: String,
+    }
+
+    impl Drop for Advertisement {
+        fn drop(&mut self) {
+            // Tell browsers the service is gone rather than letting it expire.
+            if let Ok(done) = self.daemon.unregister(&self.fullname) {
+                let _ = done.recv_timeout(Duration::from_secs(1));
+            }
+            let _ = self.daemon.shutdown();
+        }
+    }
+
+    impl RemoteServer {
+        /// Advertises the server over mDNS as `name`, e.g. `default_name()`, on
+        /// every interface.
+        pub fn advertise(&self, name: &str) -> io::Result<Advertisement> {
+            let daemon = ServiceDaemon::new().map_err(other)?;
+            let host = format!("{}.local.", hostname());
+            let port = self.local_addr()?.port();
+            let properties = [("v", "1")];
+            let service = ServiceInfo::new(SERVICE_TYPE, name, &host, "", port, &properties[..])
+                .map_err(other)?
+                .enable_addr_auto();
+            let fullname = service.get_fullname().to_string();
+            daemon.register(service).map_err(other)?;
+            Ok(Advertisement { daemon, fullname })
+        }
+    }
+
+    /// A server found by `RemoteController::discover`.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct RemoteEndpoint {
+        /// The advertised name, e.g. "DS4 on living-room-pi".
+        pub name: String,
+        /// The server's mDNS host name, e.g. "living-room-pi.local.".
+        pub host: String,
+        pub addr: SocketAddr,
+    }
+
+    impl RemoteEndpoint {
+        pub fn connect(&self) -> io::Result<RemoteController> {
+            RemoteController::connect(self.addr)
+        }
+    }
+
+    impl fmt::Display for RemoteEndpoint {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{} at {}", self.name, self.addr)
+        }
+    }
+
+    impl RemoteController {
+        /// Browses the local network for `timeout` and returns every server
+        /// that answered, by name. Prefers an IPv4 address for each.
+        pub fn discover(timeout: Duration) -> io::Result<Vec<RemoteEndpoint>> {
+            let daemon = ServiceDaemon::new().map_err(other)?;
+            let events = daemon.browse(SERVICE_TYPE).map_err(other)?;
+            let deadline = Instant::now() + timeout;
+            let mut found: Vec<RemoteEndpoint> = Vec::new();
+            loop {
+                let wait = deadline.saturating_duration_since(Instant::now());
+                let Ok(event) = events.recv_timeout(wait) else {
+                    break;
+                };
+                let ServiceEvent::ServiceResolved(service) = event else {
+                    continue;
+                };
+                let mut addrs: Vec<_> =
+                    service.addresses.iter().map(|ip| ip.to_ip_addr()).collect();
+                addrs.sort_by_key(|ip| !ip.is_ipv4());
+                let Some(&ip) = addrs.first() else {
+                    continue;
+                };
+                let name = service
+                    .fullname
+                    .strip_suffix(SERVICE_TYPE)
+                    .unwrap_or(&service.fullname)
+                    .trim_end_matches('.')
+                    .replace("\\.", ".");
+                let endpoint = RemoteEndpoint {
+                    name,
+                    host: service.host.clone(),
+                    addr: SocketAddr::new(ip, service.port),
+                };
+                match found.iter_mut().find(|e| e.name == endpoint.name) {
+                    Some(existing) => *existing = endpoint,
+                    None => found.push(endpoint),
+                }
+            }
+            let _ = daemon.stop_browse(SERVICE_TYPE);
+            let _ = daemon.shutdown();
+            found.sort_by(|a, b| a.name.cmp(&b.name));
+            Ok(found)
+        }
+    }
+}
@@ -0,0 +1,677 @@
+use crate::remote::{header, kind, HEADER_LEN, HELLO};
+use crate::{Error, Profile, Result};
+use snow::{Builder, HandshakeState, StatelessTransportState};
+use spake2::{Ed25519Group, Identity, Password, Spake2};
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::net::{SocketAddr, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const PAIR: u8 = 3;
+const PAIR_REPLY: u8 = 4;
+const HANDSHAKE: u8 = 5;
+const HANDSHAKE_REPLY: u8 = 6;
+const SEALED: u8 = 7;
+
+/// The PSK comes from pairing and is high-entropy, so it can go first and the
+/// first message already authenticates the client.
+const PATTERN: &str = "Noise_NNpsk0_25519_ChaChaPoly_BLAKE2s";
+const ID_LEN: usize = 8;
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 8;
+const MAX_MESSAGE: usize = 256;
+const CODE_DIGITS: u32 = 6;
+/// How long a pairing code from `RemoteServer::start_pairing` stays valid.
+pub const PAIRING_WINDOW: Duration = Duration::from_secs(120);
+/// How long `RemoteController::pair` waits for each reply.
+const PAIR_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn random<const N: usize>() -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    getrandom::fill(&mut bytes).map_err(|e| io::Error::other(e.to_string()))?;
+    Ok(bytes)
+}
+
+fn noise(e: snow::Error) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, e.to_string())
+}
+
+/// Binds the handshake to the client's key id, so it can't be swapped in
+/// transit.
+fn prologue(id: &[u8; ID_LEN]) -> Vec<u8> {
+    let mut prologue = b"DS4R".to_vec();
+    prologue.extend_from_slice(id);
+    prologue
+}
+
+fn handshake(key: &PairedKey, initiator: bool) -> io::Result<HandshakeState> {
+    let params = PATTERN.parse().map_err(noise)?;
+    let prologue = prologue(&key.id);
+    let builder = Builder::new(params)
+        .psk(0, &key.key)
+        .and_then(|b| b.prologue(&prologue))
+        .map_err(noise)?;
+    if initiator {
+        builder.build_initiator()
+    } else {
+        builder.build_responder()
+    }
+    .map_err(noise)
+}
+
+fn spake(code: &str, client: bool) -> (Spake2<Ed25519Group>, Vec<u8>) {
+    let password = Password::new(code.trim().as_bytes());
+    let (a, b) = (Identity::new(b"ds4r client"), Identity::new(b"ds4r server"));
+    if client {
+        Spake2::<Ed25519Group>::start_a(&password, &a, &b)
+    } else {
+        Spake2::<Ed25519Group>::start_b(&password, &a, &b)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut s, b| {
+        let _ = write!(s, "{:02x}", b);
+        s
+    })
+}
+
+fn unhex<const N: usize>(s: &str) -> Option<[u8; N]> {
+    if s.len() != N * 2 || !s.is_ascii() {
+        return None;
+    }
+    let mut bytes = [0; N];
+    for (i, b) in bytes.iter_mut().enumerate() {
+        *b = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+/// A secret shared by one client and one server, agreed when they paired. The
+/// server knows it by `id`, which the client sends in the clear.
+#[derive(Clone, PartialEq, Eq)]
+pub struct PairedKey {
+    pub id: [u8; ID_LEN],
+    pub key: [u8; KEY_LEN],
+    /// Who is on the other end, e.g. the server's address or name; only for
+    /// people reading the key store.
+    pub label: String,
+}
+
+impl fmt::Debug for PairedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PairedKey")
+            .field("id", &hex(&self.id))
+            .field("label", &self.label)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Paired keys kept in a file, one `ID KEY LABEL` line each in hex.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyStore {
+    pub keys: Vec<PairedKey>,
+}
+
+impl KeyStore {
+    pub fn new() -> Self {
+        KeyStore::default()
+    }
+
+    /// Where a server keeps the clients it has paired with.
+    pub fn clients_path() -> Option<PathBuf> {
+        Profile::dir().map(|d| d.join("remote_clients"))
+    }
+
+    /// Where a client keeps the servers it has paired with.
+    pub fn servers_path() -> Option<PathBuf> {
+        Profile::dir().map(|d| d.join("remote_servers"))
+    }
+
+    /// An empty store if there's no file yet.
+    pub fn load(path: &Path) -> Result<KeyStore> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(KeyStore::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut store = KeyStore::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.splitn(3, ' ');
+            let id = fields.next().and_then(unhex);
+            let key = fields.next().and_then(unhex);
+            let (Some(id), Some(key)) = (id, key) else {
+                return Err(Error::InvalidFormat("bad line in key store"));
+            };
+            let label = fields.next().unwrap_or("").trim().to_string();
+            store.insert(PairedKey { id, key, label });
+        }
+        Ok(store)
+    }
+
+    /// Writes the store, readable only by the current user where the platform
+    /// allows.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut text = String::new();
+        for k in &self.keys {
+            let _ = writeln!(text, "{} {} {}", hex(&k.id), hex(&k.key), k.label);
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        io::Write::write_all(&mut options.open(path)?, text.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn get(&self, id: &[u8; ID_LEN]) -> Option<&PairedKey> {
+        self.keys.iter().find(|k| k.id == *id)
+    }
+
+    pub fn find(&self, label: &str) -> Option<&PairedKey> {
+        self.keys.iter().find(|k| k.label == label)
+    }
+
+    /// Adds `key`, replacing any with the same id.
+    pub fn insert(&mut self, key: PairedKey) {
+        self.remove(&key.id);
+        self.keys.push(key);
+    }
+
+    pub fn remove(&mut self, id: &[u8; ID_LEN]) -> bool {
+        let len = self.keys.len();
+        self.keys.retain(|k| k.id != *id);
+        self.keys.len() != len
+    }
+}
+
+/// Encrypts `inner` as a sealed datagram.
+fn seal(transport: &StatelessTransportState, nonce: u64, inner: &[u8]) -> Option<Vec<u8>> {
+    let mut datagram = vec![0; MAX_MESSAGE];
+    datagram[..HEADER_LEN].copy_from_slice(&header(SEALED));
+    datagram[HEADER_LEN..HEADER_LEN + NONCE_LEN].copy_from_slice(&nonce.to_le_bytes());
+    let body = HEADER_LEN + NONCE_LEN;
+    let n = transport
+        .write_message(nonce, inner, &mut datagram[body..])
+        .ok()?;
+    datagram.truncate(body + n);
+    Some(datagram)
+}
+
+/// Decrypts a sealed datagram, returning its nonce and contents.
+fn open(transport: &StatelessTransportState, datagram: &[u8]) -> Option<(u64, Vec<u8>)> {
+    let body = HEADER_LEN + NONCE_LEN;
+    let nonce = u64::from_le_bytes(datagram.get(HEADER_LEN..body)?.try_into().ok()?);
+    let mut inner = vec![0; MAX_MESSAGE];
+    let n = transport
+        .read_message(nonce, &datagram[body..], &mut inner)
+        .ok()?;
+    inner.truncate(n);
+    Some((nonce, inner))
+}
+
+struct Pairing {
+    code: String,
+    expires: Instant,
+    /// The one attempt a code allows: the request, the reply sent to it (so a
+    /// lost reply can be resent) and the key it would pair.
+    attempt: Option<(Vec<u8>, Vec<u8>, PairedKey)>,
+}
+
+struct Session {
+    addr: SocketAddr,
+    transport: StatelessTransportState,
+    sent: u64,
+    received: Option<u64>,
+}
+
+/// `RemoteServer`'s side: pairing, handshakes and the sessions they open.
+#[derive(Default)]
+pub(crate) struct ServerAuth {
+    pub(crate) keys: KeyStore,
+    pairing: Option<Pairing>,
+    sessions: Vec<Session>,
+    paired: Vec<PairedKey>,
+}
+
+impl ServerAuth {
+    pub(crate) fn new(keys: KeyStore) -> Self {
+        ServerAuth {
+            keys,
+            ..ServerAuth::default()
+        }
+    }
+
+    /// A new `CODE_DIGITS`-digit code, replacing any previous one.
+    pub(crate) fn start_pairing(&mut self) -> io::Result<String> {
+        let limit = 10u32.pow(CODE_DIGITS);
+        // Rejection sampling, so every code is equally likely.
+        let zone = u32::MAX - u32::MAX % limit;
+        let n = loop {
+            let n = u32::from_le_bytes(random()?);
+            if n < zone {
+                break n % limit;
+            }
+        };
+        let code = format!("{:0width$}", n, width = CODE_DIGITS as usize);
+        self.pairing = Some(Pairing {
+            code: code.clone(),
+            expires: Instant::now() + PAIRING_WINDOW,
+            attempt: None,
+        });
+        Ok(code)
+    }
+
+    pub(crate) fn pairing_code(&self) -> Option<&str> {
+        self.pairing
+            .as_ref()
+            .filter(|p| Instant::now() < p.expires)
+            .map(|p| p.code.as_str())
+    }
+
+    pub(crate) fn take_paired(&mut self) -> Option<PairedKey> {
+        self.paired.pop()
+    }
+
+    /// Handles a pairing or session datagram. Returns the inner kind for a
+    /// sealed one, and `HELLO` for a completed handshake, which subscribes.
+    pub(crate) fn handle(
+        &mut self,
+        socket: &UdpSocket,
+        from: SocketAddr,
+        datagram: &[u8],
+    ) -> Option<u8> {
+        let body = datagram.get(HEADER_LEN..)?;
+        match kind(datagram)? {
+            PAIR => {
+                let reply = self.pair(from, datagram)?;
+                let _ = socket.send_to(&reply, from);
+                None
+            }
+            HANDSHAKE => {
+                let reply = self.accept(from, body)?;
+                let _ = socket.send_to(&reply, from);
+                Some(HELLO)
+            }
+            SEALED => {
+                let session = self.sessions.iter_mut().find(|s| s.addr == from)?;
+                let (nonce, inner) = open(&session.transport, datagram)?;
+                // Replayed or reordered keepalives carry nothing new.
+                if session.received.is_some_and(|last| nonce <= last) {
+                    return None;
+                }
+                session.received = Some(nonce);
+                inner.first().copied()
+            }
+            _ => None,
+        }
+    }
+
+    /// Runs the server half of SPAKE2 for the current code, once.
+    fn pair(&mut self, from: SocketAddr, datagram: &[u8]) -> Option<Vec<u8>> {
+        let pairing = self
+            .pairing
+            .as_mut()
+            .filter(|p| Instant::now() < p.expires)?;
+        if let Some((request, reply, _)) = &pairing.attempt {
+            return (request == datagram).then(|| reply.clone());
+        }
+        let body = &datagram[HEADER_LEN..];
+        let id: [u8; ID_LEN] = body.get(..ID_LEN)?.try_into().ok()?;
+        let (state, message) = spake(&pairing.code, false);
+        let shared = state.finish(&body[ID_LEN..]).ok()?;
+        let key = PairedKey {
+            id,
+            key: shared.get(..KEY_LEN)?.try_into().ok()?,
+            label: from.to_string(),
+        };
+        let mut reply = header(PAIR_REPLY).to_vec();
+        reply.extend_from_slice(&message);
+        pairing.attempt = Some((datagram.to_vec(), reply.clone(), key));
+        Some(reply)
+    }
+
+    /// Answers a handshake from a paired client, or one pairing now: a client
+    /// that gets this far with the pairing key knew the code.
+    fn accept(&mut self, from: SocketAddr, body: &[u8]) -> Option<Vec<u8>> {
+        let id: [u8; ID_LEN] = body.get(..ID_LEN)?.try_into().ok()?;
+        let pending = self
+            .pairing
+            .as_ref()
+            .and_then(|p| p.attempt.as_ref())
+            .map(|(_, _, key)| key)
+            .filter(|key| key.id == id);
+        let key = self.keys.get(&id).or(pending)?.clone();
+        let mut responder = handshake(&key, false).ok()?;
+        let mut payload = [0; MAX_MESSAGE];
+        if responder
+            .read_message(&body[ID_LEN..], &mut payload)
+            .is_err()
+        {
+            // A wrong code: that was the code's one try.
+            if pending.is_some() {
+                self.pairing = None;
+            }
+            return None;
+        }
+        let mut reply = header(HANDSHAKE_REPLY).to_vec();
+        let mut message = [0; MAX_MESSAGE];
+        let n = responder.write_message(&[], &mut message).ok()?;
+        reply.extend_from_slice(&message[..n]);
+        let transport = responder.into_stateless_transport_mode().ok()?;
+        if self.keys.get(&id).is_none() {
+            self.keys.insert(key.clone());
+            self.paired.push(key);
+            self.pairing = None;
+        }
+        self.sessions.retain(|s| s.addr != from);
+        self.sessions.push(Session {
+            addr: from,
+            transport,
+            sent: 0,
+            received: None,
+        });
+        Some(reply)
+    }
+
+    pub(crate) fn seal(&mut self, addr: SocketAddr, inner: &[u8]) -> Option<Vec<u8>> {
+        let session = self.sessions.iter_mut().find(|s| s.addr == addr)?;
+        session.sent += 1;
+        seal(&session.transport, session.sent, inner)
+    }
+
+    pub(crate) fn end(&mut self, addr: SocketAddr) {
+        self.sessions.retain(|s| s.addr != addr);
+    }
+}
+
+/// `RemoteController`'s side of a session with a paired server.
+pub(crate) struct ClientAuth {
+    key: PairedKey,
+    handshake: Option<HandshakeState>,
+    transport: Option<StatelessTransportState>,
+    sent: u64,
+    received: Option<u64>,
+}
+
+impl ClientAuth {
+    pub(crate) fn new(key: PairedKey) -> Self {
+        ClientAuth {
+            key,
+            handshake: None,
+            transport: None,
+            sent: 0,
+            received: None,
+        }
+    }
+
+    /// The datagram that keeps the session alive: a sealed hello once the
+    /// handshake is done, a fresh handshake until then.
+    pub(crate) fn keepalive(&mut self) -> io::Result<Vec<u8>> {
+        if self.transport.is_some() {
+            return self
+                .seal(&[HELLO])
+                .ok_or_else(|| io::Error::other("couldn't seal a keepalive"));
+        }
+        let mut initiator = handshake(&self.key, true)?;
+        let mut datagram = header(HANDSHAKE).to_vec();
+        datagram.extend_from_slice(&self.key.id);
+        let mut message = [0; MAX_MESSAGE];
+        let n = initiator.write_message(&[], &mut message).map_err(noise)?;
+        datagram.extend_from_slice(&message[..n]);
+        self.handshake = Some(initiator);
+        Ok(datagram)
+    }
+
+    /// Forgets the session, e.g. because the server restarted and lost it, so
+    /// the next keepalive handshakes again.
+    pub(crate) fn reset(&mut self) {
+        self.transport = None;
+        self.handshake = None;
+    }
+
+    pub(crate) fn is_established(&self) -> bool {
+        self.transport.is_some()
+    }
+
+    pub(crate) fn seal(&mut self, inner: &[u8]) -> Option<Vec<u8>> {
+        let transport = self.transport.as_ref()?;
+        self.sent += 1;
+        seal(transport, self.sent, inner)
+    }
+
+    /// Completes a handshake from its reply, or opens a sealed datagram newer
+    /// than the last, returning its nonce and contents.
+    pub(crate) fn open(&mut self, datagram: &[u8]) -> Option<(u64, Vec<u8>)> {
+        match kind(datagram)? {
+            HANDSHAKE_REPLY => {
+                let mut initiator = self.handshake.take()?;
+                let mut payload = [0; MAX_MESSAGE];
+                initiator
+                    .read_message(&datagram[HEADER_LEN..], &mut payload)
+                    .ok()?;
+                self.transport = Some(initiator.into_stateless_transport_mode().ok()?);
+                self.sent = 0;
+                self.received = None;
+                None
+            }
+            SEALED => {
+                let (nonce, inner) = open(self.transport.as_ref()?, datagram)?;
+                if self.received.is_some_and(|last| nonce <= last) {
+                    return None;
+                }
+                self.received = Some(nonce);
+                Some((nonce, inner))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Pairs with the server `socket` is connected to using the code it showed,
+/// then proves the key with a handshake.
+pub(crate) fn pair(socket: &UdpSocket, code: &str, label: String) -> io::Result<PairedKey> {
+    let id = random::<ID_LEN>()?;
+    let (state, message) = spake(code, true);
+    let mut request = header(PAIR).to_vec();
+    request.extend_from_slice(&id);
+    request.extend_from_slice(&message);
+
+    let failed = || {
+        io::Error::new(
+            ErrorKind::PermissionDenied,
+            "pairing failed: wrong or expired code",
+        )
+    };
+    let reply = exchange(socket, &request, PAIR_REPLY)?.ok_or_else(failed)?;
+    let shared = state.finish(&reply[HEADER_LEN..]).map_err(|_| failed())?;
+    let key = PairedKey {
+        id,
+        key: shared
+            .get(..KEY_LEN)
+            .and_then(|k| k.try_into().ok())
+            .ok_or_else(failed)?,
+        label,
+    };
+    // A wrong code leaves each side with a different key, so the server won't
+    // answer the handshake.
+    let mut auth = ClientAuth::new(key.clone());
+    let hello = auth.keepalive()?;
+    let reply = exchange(socket, &hello, HANDSHAKE_REPLY)?.ok_or_else(failed)?;
+    auth.open(&reply);
+    if !auth.is_established() {
+        return Err(failed());
+    }
+    Ok(key)
+}
+
+/// Sends `request` until a reply of kind `expect` arrives, resending each
+/// second in case either was lost.
+fn exchange(socket: &UdpSocket, request: &[u8], expect: u8) -> io::Result<Option<Vec<u8>>> {
+    let deadline = Instant::now() + PAIR_TIMEOUT;
+    let mut buf = [0; MAX_MESSAGE];
+    while Instant::now() < deadline {
+        socket.send(request)?;
+        socket.set_read_timeout(Some(Duration::from_secs(1)))?;
+        match socket.recv(&mut buf) {
+            Ok(n) if kind(&buf[..n]) == Some(expect) => return Ok(Some(buf[..n].to_vec())),
+            Ok(_) => {}
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) if e.kind() == ErrorKind::ConnectionRefused => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> PairedKey {
+        PairedKey {
+            id: [1; ID_LEN],
+            key: [byte; KEY_LEN],
+            label: "test".to_string(),
+        }
+    }
+
+    fn from() -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], 9000))
+    }
+
+    /// Runs the handshake between `client` and `server`, returning whether the
+    /// server answered it.
+    fn connect(client: &mut ClientAuth, server: &mut ServerAuth) -> bool {
+        let Ok(hello) = client.keepalive() else {
+            return false;
+        };
+        let Some(reply) = server.accept(from(), &hello[HEADER_LEN..]) else {
+            return false;
+        };
+        client.open(&reply);
+        true
+    }
+
+    /// The client's half of pairing with `code`, answered by `server`.
+    fn pair_with(server: &mut ServerAuth, code: &str) -> Option<PairedKey> {
+        let id = [2; ID_LEN];
+        let (state, message) = spake(code, true);
+        let mut request = header(PAIR).to_vec();
+        request.extend_from_slice(&id);
+        request.extend_from_slice(&message);
+        let reply = server.pair(from(), &request)?;
+        let shared = state.finish(&reply[HEADER_LEN..]).ok()?;
+        Some(PairedKey {
+            id,
+            key: shared.get(..KEY_LEN)?.try_into().ok()?,
+            label: "server".to_string(),
+        })
+    }
+
+    #[test]
+    fn paired_keys_open_a_session_both_ways() {
+        let mut keys = KeyStore::new();
+        keys.insert(key(7));
+        let mut server = ServerAuth::new(keys);
+        let mut client = ClientAuth::new(key(7));
+        assert!(connect(&mut client, &mut server));
+        assert!(client.is_established());
+
+        let Ok(socket) = UdpSocket::bind("127.0.0.1:0") else {
+            return;
+        };
+        let sealed = client.seal(&[HELLO]);
+        assert!(sealed.is_some());
+        let Some(sealed) = sealed else { return };
+        assert_eq!(server.handle(&socket, from(), &sealed), Some(HELLO));
+        // A replay is dropped.
+        assert_eq!(server.handle(&socket, from(), &sealed), None);
+
+        let sealed = server.seal(from(), b"state");
+        assert!(sealed.is_some());
+        let Some(sealed) = sealed else { return };
+        assert_eq!(client.open(&sealed), Some((1, b"state".to_vec())));
+        assert_eq!(client.open(&sealed), None);
+    }
+
+    #[test]
+    fn a_wrong_key_gets_no_answer() {
+        let mut keys = KeyStore::new();
+        keys.insert(key(7));
+        let mut server = ServerAuth::new(keys);
+        let mut client = ClientAuth::new(key(8));
+        assert!(!connect(&mut client, &mut server));
+        assert!(!client.is_established());
+        assert!(server.seal(from(), b"state").is_none());
+    }
+
+    #[test]
+    fn an_unknown_client_gets_no_answer() {
+        let mut server = ServerAuth::new(KeyStore::new());
+        let mut client = ClientAuth::new(key(7));
+        assert!(!connect(&mut client, &mut server));
+    }
+
+    #[test]
+    fn pairing_with_the_code_stores_the_key() {
+        let mut server = ServerAuth::new(KeyStore::new());
+        let code = server.start_pairing();
+        assert!(code.is_ok());
+        let Ok(code) = code else { return };
+        assert_eq!(code.len(), CODE_DIGITS as usize);
+
+        let key = pair_with(&mut server, &code);
+        assert!(key.is_some());
+        let Some(key) = key else { return };
+        let mut client = ClientAuth::new(key.clone());
+        assert!(connect(&mut client, &mut server));
+        assert!(client.is_established());
+        // The server labels the key with the client's address.
+        let paired = server.take_paired().map(|k| (k.id, k.key));
+        assert_eq!(paired, Some((key.id, key.key)));
+        assert!(server.keys.get(&key.id).is_some_and(|k| k.key == key.key));
+        assert_eq!(server.pairing_code(), None);
+    }
+
+    #[test]
+    fn a_wrong_code_spends_the_pairing() {
+        let mut server = ServerAuth::new(KeyStore::new());
+        let code = server.start_pairing();
+        assert!(code.is_ok());
+        let Ok(code) = code else { return };
+        let wrong = if code == "000000" { "000001" } else { "000000" };
+
+        let key = pair_with(&mut server, wrong);
+        assert!(key.is_some());
+        let Some(key) = key else { return };
+        let mut client = ClientAuth::new(key);
+        assert!(!connect(&mut client, &mut server));
+        assert_eq!(server.take_paired(), None);
+        assert_eq!(server.pairing_code(), None);
+    }
+
+    #[test]
+    fn key_store_lines_round_trip() {
+        assert_eq!(
+            unhex::<4>(&hex(&[0, 1, 0xab, 0xff])),
+            Some([0, 1, 0xab, 0xff])
+        );
+        assert_eq!(unhex::<2>("zz00"), None);
+        let mut store = KeyStore::new();
+        store.insert(key(7));
+        store.insert(key(9));
+        assert_eq!(store.keys.len(), 1);
+        assert_eq!(store.find("test").map(|k| k.key), Some([9; KEY_LEN]));
+        assert!(store.remove(&[1; ID_LEN]));
+        assert!(store.get(&[1; ID_LEN]).is_none());
+    }
+}
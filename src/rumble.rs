@@ -0,0 +1,148 @@
+use crate::{Controller, Result};
+use std::time::{Duration, Instant};
+
+/// How a `RumbleMixer` combines effects playing at the same time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MixPolicy {
+    /// Each motor runs at the strongest request.
+    #[default]
+    Max,
+    /// Each motor runs at the sum of all requests, clamped to 255.
+    SumClamped,
+    /// Only the highest-priority effects play, mixed by `Max`; lower ones resume
+    /// once those end.
+    Preempt,
+}
+
+/// A rumble request from one subsystem, e.g. UI feedback or game damage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RumbleEffect {
+    pub strong: u8,
+    pub weak: u8,
+    /// Higher wins under `MixPolicy::Preempt`; ignored by the other policies.
+    pub priority: u8,
+    /// `None` plays until stopped.
+    pub duration: Option<Duration>,
+}
+
+impl RumbleEffect {
+    pub fn new(strong: u8, weak: u8) -> Self {
+        RumbleEffect {
+            strong,
+            weak,
+            priority: 0,
+            duration: None,
+        }
+    }
+
+    pub fn priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn lasting(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+}
+
+/// Identifies a playing effect so it can be stopped early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RumbleId(u64);
+
+#[derive(Debug, Clone)]
+struct Playing {
+    id: RumbleId,
+    effect: RumbleEffect,
+    ends: Option<Instant>,
+}
+
+/// Combines rumble requests from several sources so none silently overwrites
+/// another, and writes the mix to a controller only when it changes.
+#[derive(Debug, Clone, Default)]
+pub struct RumbleMixer {
+    pub policy: MixPolicy,
+    playing: Vec<Playing>,
+    next_id: u64,
+    last: Option<(u8, u8)>,
+}
+
+impl RumbleMixer {
+    pub fn new(policy: MixPolicy) -> Self {
+        RumbleMixer {
+            policy,
+            ..RumbleMixer::default()
+        }
+    }
+
+    pub fn play(&mut self, effect: RumbleEffect) -> RumbleId {
+        self.play_at(effect, Instant::now())
+    }
+
+    /// Starts `effect` as of `at`, which times its `duration`.
+    pub fn play_at(&mut self, effect: RumbleEffect, at: Instant) -> RumbleId {
+        let id = RumbleId(self.next_id);
+        self.next_id += 1;
+        self.playing.push(Playing {
+            id,
+            effect,
+            ends: effect.duration.map(|d| at + d),
+        });
+        id
+    }
+
+    /// Returns whether `id` was still playing.
+    pub fn stop(&mut self, id: RumbleId) -> bool {
+        let len = self.playing.len();
+        self.playing.retain(|p| p.id != id);
+        self.playing.len() != len
+    }
+
+    pub fn clear(&mut self) {
+        self.playing.clear();
+    }
+
+    pub fn is_playing(&self, id: RumbleId) -> bool {
+        self.playing.iter().any(|p| p.id == id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.playing.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.playing.is_empty()
+    }
+
+    /// Drops effects that have ended by `at` and returns `(strong, weak)` for the rest.
+    pub fn mix(&mut self, at: Instant) -> (u8, u8) {
+        self.playing.retain(|p| p.ends.is_none_or(|ends| at < ends));
+        let top = self.playing.iter().map(|p| p.effect.priority).max();
+        let effects = self
+            .playing
+            .iter()
+            .map(|p| p.effect)
+            .filter(|e| self.policy != MixPolicy::Preempt || Some(e.priority) == top);
+        match self.policy {
+            MixPolicy::Max | MixPolicy::Preempt => {
+                effects.fold((0, 0), |(s, w), e| (s.max(e.strong), w.max(e.weak)))
+            }
+            MixPolicy::SumClamped => {
+                let (s, w) = effects.fold((0u32, 0u32), |(s, w), e| {
+                    (s + e.strong as u32, w + e.weak as u32)
+                });
+                (s.min(255) as u8, w.min(255) as u8)
+            }
+        }
+    }
+
+    /// Mixes as of `at` and sets the controller's rumble if the mix changed.
+    pub fn update(&mut self, controller: &mut Controller, at: Instant) -> Result<(u8, u8)> {
+        let mix = self.mix(at);
+        if self.last != Some(mix) {
+            controller.set_rumble(mix.0, mix.1)?;
+            self.last = Some(mix);
+        }
+        Ok(mix)
+    }
+}
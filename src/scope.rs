@@ -0,0 +1,99 @@
+use crate::{Button, ButtonId, Controller, Controls, Subscription};
+
+/// A group of handler subscriptions that are all removed when the scope is dropped,
+/// e.g. the handlers of a menu screen, so none outlive the screen.
+#[derive(Default)]
+#[must_use = "dropping a Scope unsubscribes its handlers immediately"]
+pub struct Scope {
+    subscriptions: Vec<Subscription>,
+}
+
+impl Scope {
+    pub fn new() -> Self {
+        Scope::default()
+    }
+
+    /// Ties an existing subscription to this scope.
+    pub fn add(&mut self, subscription: Subscription) {
+        self.subscriptions.push(subscription);
+    }
+
+    pub fn len(&self) -> usize {
+        self.subscriptions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscriptions.is_empty()
+    }
+}
+
+/// Subscribes handlers into a `Scope`; see `Controls::scope`.
+pub struct Registrar<'a> {
+    controls: &'a mut Controls,
+    scope: &'a mut Scope,
+}
+
+impl Registrar<'_> {
+    /// Subscribes to the control chosen by `select`, e.g. `|c| &mut c.left_stick`.
+    pub fn on<T, F>(&mut self, select: impl FnOnce(&mut Controls) -> &mut Button<T>, handler: F)
+    where
+        T: Default + Eq + Copy,
+        F: FnMut(T, T) + Send + 'static,
+    {
+        let subscription = select(self.controls).subscribe(handler);
+        self.scope.add(subscription);
+    }
+
+    /// Subscribes to a digital button by id; for L2/R2 the handler sees the
+    /// trigger's digital bit.
+    pub fn button(&mut self, id: ButtonId, mut handler: impl FnMut(bool, bool) + Send + 'static) {
+        let subscription = match id {
+            ButtonId::L2 | ButtonId::R2 => {
+                let trigger = if id == ButtonId::L2 {
+                    &mut self.controls.l2
+                } else {
+                    &mut self.controls.r2
+                };
+                trigger.subscribe(move |old, new| {
+                    if old.digital != new.digital {
+                        handler(old.digital, new.digital);
+                    }
+                })
+            }
+            _ => match self.controls.button_mut(id) {
+                Some(button) => button.subscribe(handler),
+                None => return,
+            },
+        };
+        self.scope.add(subscription);
+    }
+}
+
+impl Controls {
+    /// Registers a group of handlers that are all unsubscribed when the returned
+    /// scope is dropped:
+    ///
+    /// ```ignore
+    /// let menu = controls.scope(|reg| {
+    ///     reg.button(ButtonId::X, |_, pressed| if pressed { select() });
+    ///     reg.on(|c| &mut c.dpad, |_, dpad| move_cursor(dpad));
+    /// });
+    /// // ... later, leaving the menu:
+    /// drop(menu);
+    /// ```
+    pub fn scope(&mut self, register: impl FnOnce(&mut Registrar<'_>)) -> Scope {
+        let mut scope = Scope::new();
+        register(&mut Registrar {
+            controls: self,
+            scope: &mut scope,
+        });
+        scope
+    }
+}
+
+impl Controller {
+    /// See `Controls::scope`.
+    pub fn scope(&mut self, register: impl FnOnce(&mut Registrar<'_>)) -> Scope {
+        self.controls.scope(register)
+    }
+}
@@ -0,0 +1,238 @@
+use crate::tilt::{TiltEstimator, ACCEL_PER_G, GYRO_PER_DEG_S};
+use crate::{AxisId, ButtonId, Controller, Error, Result, Snapshot, Stick, YAxis};
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use std::path::Path;
+use std::time::Instant;
+
+/// Default for `Script::max_operations`: generous for per-frame bindings, yet a
+/// runaway loop fails within a few milliseconds instead of hanging the read loop.
+const MAX_OPERATIONS: u64 = 100_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_COLLECTION_LEN: usize = 10_000;
+
+const AXES: [(&str, AxisId); 6] = [
+    ("left_x", AxisId::LeftX),
+    ("left_y", AxisId::LeftY),
+    ("right_x", AxisId::RightX),
+    ("right_y", AxisId::RightY),
+    ("l2_value", AxisId::L2),
+    ("r2_value", AxisId::R2),
+];
+
+/// Per-frame bindings written in Rhai. The script must define `fn frame(pad)`, which
+/// receives the pad state as a map and returns it, possibly modified:
+///
+/// ```text
+/// fn frame(pad) {
+///     if pad.pitch > 30.0 { pad.l1 = true; }
+///     pad
+/// }
+/// ```
+///
+/// Buttons are booleans named as in `ButtonId::name`; `left_x`, `left_y`, `right_x`
+/// and `right_y` are in `-1.0..=1.0` (Y down) and `l2_value`/`r2_value` in `0.0..=1.0`.
+///
+/// From `frame_controller` the map also has the motion sensors: `gyro_x`, `gyro_y`
+/// and `gyro_z` in degrees per second, `accel_x`, `accel_y` and `accel_z` in g, and
+/// the fused `pitch` and `roll` in degrees as `Tilt` defines them. Changing them
+/// has no effect.
+///
+/// A call that runs more than `max_operations` steps, such as a stray `loop {}`,
+/// fails with `Error::Script` rather than stalling input.
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    tilt: TiltEstimator,
+}
+
+/// One report's motion, as the script sees it.
+struct Motion {
+    gyro: [f32; 3],
+    accel: [f32; 3],
+    pitch: f32,
+    roll: f32,
+}
+
+impl Script {
+    pub fn new(source: &str) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(MAX_OPERATIONS)
+            .set_max_call_levels(MAX_CALL_LEVELS)
+            .set_max_array_size(MAX_COLLECTION_LEN)
+            .set_max_map_size(MAX_COLLECTION_LEN)
+            .set_max_string_size(MAX_COLLECTION_LEN);
+        let ast = engine
+            .compile(source)
+            .map_err(|e| Error::Script(e.to_string()))?;
+        Ok(Script {
+            engine,
+            ast,
+            scope: Scope::new(),
+            tilt: TiltEstimator::new(),
+        })
+    }
+
+    /// Steps a single `frame` call may take; 0 removes the limit.
+    pub fn max_operations(mut self, operations: u64) -> Self {
+        self.engine.set_max_operations(operations);
+        self
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Script::new(&std::fs::read_to_string(path)?)
+    }
+
+    /// Runs the script on `snapshot` alone, without motion fields.
+    pub fn frame(&mut self, snapshot: &Snapshot) -> Result<Snapshot> {
+        self.run(snapshot, None)
+    }
+
+    /// Runs the script on the controller's latest report, motion included.
+    pub fn frame_controller(&mut self, controller: &Controller) -> Result<Snapshot> {
+        let gyro = controller.gyro();
+        let accel = controller.controls.imu.accel;
+        let at = controller.sampled_at().unwrap_or_else(Instant::now);
+        let tilt = self.tilt.update(gyro, accel, at);
+        let motion = Motion {
+            gyro: gyro.map(|g| g / GYRO_PER_DEG_S),
+            accel: accel.map(|a| a as f32 / ACCEL_PER_G),
+            pitch: tilt.pitch.to_degrees(),
+            roll: tilt.roll.to_degrees(),
+        };
+        self.run(&controller.controls.snapshot(), Some(motion))
+    }
+
+    fn run(&mut self, snapshot: &Snapshot, motion: Option<Motion>) -> Result<Snapshot> {
+        let mut pad = Map::new();
+        for id in ButtonId::ALL {
+            pad.insert(id.name().into(), Dynamic::from_bool(snapshot.pressed(id)));
+        }
+        for (name, id) in AXES {
+            pad.insert(name.into(), Dynamic::from_float(snapshot.axis(id) as f64));
+        }
+        if let Some(m) = motion {
+            let fields = [
+                ("gyro_x", m.gyro[0]),
+                ("gyro_y", m.gyro[1]),
+                ("gyro_z", m.gyro[2]),
+                ("accel_x", m.accel[0]),
+                ("accel_y", m.accel[1]),
+                ("accel_z", m.accel[2]),
+                ("pitch", m.pitch),
+                ("roll", m.roll),
+            ];
+            for (name, value) in fields {
+                pad.insert(name.into(), Dynamic::from_float(value as f64));
+            }
+        }
+
+        let pad: Map = self
+            .engine
+            .call_fn(&mut self.scope, &self.ast, "frame", (pad,))
+            .map_err(|e| Error::Script(e.to_string()))?;
+
+        let mut out = *snapshot;
+        for id in ButtonId::ALL {
+            if let Some(pressed) = pad.get(id.name()).and_then(|v| v.as_bool().ok()) {
+                out.set_pressed(id, pressed);
+            }
+        }
+        let axis = |name: &str| {
+            pad.get(name)
+                .and_then(|v| v.as_float().ok())
+                .map(|v| v as f32)
+        };
+        // A value the script left alone keeps its raw byte: 0 and 1 both read as -1.0.
+        let stick_axis = |name: &str, raw: u8| match axis(name) {
+            Some(v) if v != Stick::new(raw, raw).x_f32() => {
+                Stick::from_centered(v, 0.0, YAxis::Down).x
+            }
+            _ => raw,
+        };
+        let stick =
+            |x: &str, y: &str, raw: Stick| Stick::new(stick_axis(x, raw.x), stick_axis(y, raw.y));
+        out.left_stick = stick("left_x", "left_y", snapshot.left_stick);
+        out.right_stick = stick("right_x", "right_y", snapshot.right_stick);
+        let trigger = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        out.l2_value = axis("l2_value").map_or(snapshot.l2_value, trigger);
+        out.r2_value = axis("r2_value").map_or(snapshot.r2_value, trigger);
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runaway_scripts_fail_instead_of_hanging() {
+        let script = Script::new("fn frame(pad) { loop { } pad }");
+        let result = script.and_then(|mut s| s.frame(&Snapshot::default()));
+        assert!(matches!(result, Err(Error::Script(_))));
+    }
+
+    #[test]
+    fn unbounded_recursion_fails() {
+        let script = Script::new("fn deeper(n) { deeper(n + 1) } fn frame(pad) { deeper(0) }");
+        let result = script.and_then(|mut s| s.frame(&Snapshot::default()));
+        assert!(matches!(result, Err(Error::Script(_))));
+    }
+
+    #[test]
+    fn untouched_axes_round_trip() {
+        let script = Script::new("fn frame(pad) { pad }");
+        assert!(script.is_ok());
+        let Ok(mut script) = script else { return };
+        let snapshot = Snapshot {
+            left_stick: Stick::new(0, 128),
+            right_stick: Stick::new(255, 1),
+            l2_value: 77,
+            r2_value: 255,
+            ..Snapshot::default()
+        };
+        assert!(script.frame(&snapshot).is_ok_and(|out| out == snapshot));
+    }
+
+    #[test]
+    fn written_axes_map_like_from_centered() {
+        let script = Script::new(
+            "fn frame(pad) { pad.left_x = -1.0; pad.left_y = 0.0; pad.right_x = 1.0; pad }",
+        );
+        assert!(script.is_ok());
+        let Ok(mut script) = script else { return };
+        let snapshot = Snapshot {
+            left_stick: Stick::new(200, 10),
+            ..Snapshot::default()
+        };
+        let out = script.frame(&snapshot);
+        assert!(out.is_ok());
+        let Ok(out) = out else { return };
+        let min = Stick::from_centered(-1.0, 0.0, YAxis::Down).x;
+        assert_eq!(out.left_stick, Stick::new(min, 128));
+        assert_eq!(out.right_stick.x, 255);
+    }
+
+    #[test]
+    fn motion_fields_reach_the_script() {
+        let script = Script::new(
+            "fn frame(pad) { if pad.pitch > 30.0 && pad.accel_y > 0.5 { pad.l1 = true; } pad }",
+        );
+        assert!(script.is_ok());
+        let Ok(mut script) = script else { return };
+        let motion = |pitch: f32| Motion {
+            gyro: [0.0; 3],
+            accel: [0.0, 0.9, 0.0],
+            pitch,
+            roll: 0.0,
+        };
+        let snapshot = Snapshot::default();
+        assert!(script
+            .run(&snapshot, Some(motion(45.0)))
+            .is_ok_and(|s| s.l1));
+        assert!(script
+            .run(&snapshot, Some(motion(10.0)))
+            .is_ok_and(|s| !s.l1));
+    }
+}
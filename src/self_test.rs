@@ -0,0 +1,197 @@
+use crate::{Controller, Error, OutputReportBuilder, ReportLayout};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+const INPUT_WINDOW: Duration = Duration::from_millis(500);
+/// Fewer reports than this in `INPUT_WINDOW` fails the input check; a healthy pad
+/// sends over 100.
+const MIN_REPORTS: u32 = 20;
+const PULSE: Duration = Duration::from_millis(400);
+const LIGHTBAR_STEP: Duration = Duration::from_millis(300);
+/// Accepted accelerometer magnitude at rest, in raw units (about 8192 per g).
+const ACCEL_RANGE: std::ops::Range<f32> = 4096.0..16384.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelfTestStep {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// The outcome of `Controller::self_test`, one step per check.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SelfTestReport {
+    pub steps: Vec<SelfTestStep>,
+    /// The pad went away mid-test; the last step says how, and the checks
+    /// after it didn't run.
+    pub disconnected: bool,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(|s| s.passed)
+    }
+
+    pub fn step(&self, name: &str) -> Option<&SelfTestStep> {
+        self.steps.iter().find(|s| s.name == name)
+    }
+
+    fn push(&mut self, name: &'static str, passed: bool, detail: String) {
+        self.steps.push(SelfTestStep {
+            name,
+            passed,
+            detail,
+        });
+    }
+
+    fn disconnect(mut self, e: Error) -> Self {
+        self.push("connection", false, e.to_string());
+        self.disconnected = true;
+        self
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            let mark = if step.passed { "ok  " } else { "FAIL" };
+            writeln!(f, "{} {:<12} {}", mark, step.name, step.detail)?;
+        }
+        if self.disconnected {
+            writeln!(f, "controller disconnected; test stopped")?;
+        }
+        Ok(())
+    }
+}
+
+impl Controller {
+    /// Checks input report flow and the motion sensor, then pulses each motor and
+    /// cycles the lightbar through red, green and blue. Takes about two seconds;
+    /// keep the pad still on a table.
+    ///
+    /// Motors and LEDs give no feedback, so those steps only show that the writes
+    /// went through and need a human to confirm them. The previous output state is
+    /// restored afterwards. A device error ends the test early with the report
+    /// marked `disconnected`.
+    pub fn self_test(&mut self) -> SelfTestReport {
+        let mut report = SelfTestReport::default();
+
+        let (reports, errors) = match self.pump(INPUT_WINDOW) {
+            Ok(counts) => counts,
+            Err(e) => return report.disconnect(e),
+        };
+        let rate = reports as f32 / INPUT_WINDOW.as_secs_f32();
+        report.push(
+            "input",
+            reports >= MIN_REPORTS,
+            format!("{} reports ({:.0} Hz), {} errors", reports, rate, errors),
+        );
+
+        let layout = self.report_layout();
+        report.push(
+            "full reports",
+            matches!(layout, Some(ReportLayout::Usb | ReportLayout::Bluetooth)),
+            format!("{:?}", layout),
+        );
+
+        let accel = self.controls.imu.accel;
+        let magnitude = accel
+            .iter()
+            .map(|&a| (a as f32).powi(2))
+            .sum::<f32>()
+            .sqrt();
+        report.push(
+            "motion",
+            ACCEL_RANGE.contains(&magnitude),
+            format!(
+                "accel {:?} (|a| = {:.2} g), temperature {}",
+                accel,
+                magnitude / 8192.0,
+                self.controls.imu.temperature
+            ),
+        );
+
+        let saved = self.output_state();
+        for (name, strong, weak) in [("strong motor", 255, 0), ("weak motor", 0, 255)] {
+            let result = self
+                .write_now(&OutputReportBuilder::new().rumble(strong, weak))
+                .and_then(|()| self.pump(PULSE))
+                .and_then(|_| self.write_now(&OutputReportBuilder::new().rumble(0, 0)));
+            match result {
+                Err(e) if e.is_device_error() => return report.disconnect(e),
+                result => report.push(
+                    name,
+                    result.is_ok(),
+                    outcome(result, "pulsed; confirm by feel"),
+                ),
+            }
+        }
+
+        let mut result = Ok(());
+        for (r, g, b) in [(255, 0, 0), (0, 255, 0), (0, 0, 255)] {
+            result = self
+                .write_now(&OutputReportBuilder::new().lightbar(r, g, b))
+                .and_then(|()| self.pump(LIGHTBAR_STEP).map(|_| ()));
+            if result.is_err() {
+                break;
+            }
+        }
+        match result {
+            Err(e) if e.is_device_error() => return report.disconnect(e),
+            result => report.push(
+                "lightbar",
+                result.is_ok(),
+                outcome(result, "cycled red, green, blue; confirm by eye"),
+            ),
+        }
+
+        match self.restore_output(saved) {
+            Err(e) if e.is_device_error() => return report.disconnect(e),
+            Err(e) => report.push("restore", false, e.to_string()),
+            Ok(()) => {}
+        }
+        report
+    }
+
+    /// Reads reports for `duration`, returning how many arrived and how many failed.
+    /// Stops at the first device error, since the pad is gone.
+    fn pump(&mut self, duration: Duration) -> crate::Result<(u32, u32)> {
+        let start = Instant::now();
+        let (mut reports, mut errors) = (0, 0);
+        let mut last = self.sampled_at();
+        while start.elapsed() < duration {
+            match self.update() {
+                Ok(()) if self.sampled_at() != last => {
+                    last = self.sampled_at();
+                    reports += 1;
+                }
+                Ok(()) => {}
+                Err(e) if e.is_device_error() => return Err(e),
+                Err(_) => errors += 1,
+            }
+        }
+        Ok((reports, errors))
+    }
+}
+
+fn outcome(result: crate::Result<()>, ok: &str) -> String {
+    match result {
+        Ok(()) => ok.to_string(),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disconnection_fails_the_report() {
+        let mut report = SelfTestReport::default();
+        report.push("input", true, String::new());
+        let report = report.disconnect(Error::NotFound);
+        assert!(report.disconnected);
+        assert!(!report.passed());
+        assert!(report.to_string().contains("disconnected"));
+    }
+}
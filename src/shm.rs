@@ -0,0 +1,412 @@
+use crate::touch::Touch;
+use crate::wire::{self, WIRE_LEN};
+use crate::{Controller, Snapshot};
+use memmap2::{Mmap, MmapMut};
+use std::fs::{File, OpenOptions};
+use std::hint;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::slice;
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const MAGIC: &[u8; 4] = b"DS4S";
+const VERSION: u32 = 1;
+const HEADER_WORDS: usize = 2;
+const PAYLOAD_LEN: usize = 64;
+const PAYLOAD_WORDS: usize = PAYLOAD_LEN / 8;
+/// Size of the shared region.
+pub const SHARED_LEN: usize = (HEADER_WORDS + PAYLOAD_WORDS) * 8;
+/// A reader that sees the same odd sequence this many times in a row assumes the
+/// writer died part way through a write.
+const SPIN_LIMIT: u32 = 100_000;
+const NO_BATTERY: u8 = 0xff;
+const FLAG_CONNECTED: u8 = 0x01;
+const FLAG_CHARGING: u8 = 0x02;
+
+/// Where `name`'s region lives: in `/dev/shm` on Linux, so it never touches
+/// disk, and in the temporary directory elsewhere.
+pub fn path(name: &str) -> PathBuf {
+    let file = format!("ps4hid-{}", name);
+    if cfg!(target_os = "linux") {
+        Path::new("/dev/shm").join(file)
+    } else {
+        std::env::temp_dir().join(file)
+    }
+}
+
+/// The latest input state as published to other processes.
+///
+/// In the shared region it's laid out as follows, all integers little-endian:
+///
+/// | byte  | contents |
+/// |-------|----------|
+/// | 0-9   | snapshot in `wire` format |
+/// | 10-15 | gyro x, y, z, `i16` |
+/// | 16-21 | accel x, y, z, `i16` |
+/// | 22-31 | two touches: active in bit 7 and id in bits 0-6, then x and y as `u16` |
+/// | 32    | battery percent, or 0xff if unknown |
+/// | 33    | bit 0 connected, bit 1 charging |
+/// | 40-47 | sampled at, microseconds since the Unix epoch, or 0 if unknown |
+/// | 48-55 | frame, `u64` |
+///
+/// Other bytes are zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SharedState {
+    pub connected: bool,
+    pub snapshot: Snapshot,
+    /// Raw IMU readings.
+    pub gyro: [i16; 3],
+    pub accel: [i16; 3],
+    pub touches: [Touch; 2],
+    pub battery_percent: Option<u8>,
+    pub charging: bool,
+    pub sampled_at: Option<SystemTime>,
+    /// How many states the writer has published, this one included.
+    pub frame: u64,
+}
+
+impl SharedState {
+    /// The controller's latest input, connected.
+    pub fn from_controller(controller: &Controller) -> Self {
+        let battery = controller.battery();
+        let now = (Instant::now(), SystemTime::now());
+        SharedState {
+            connected: true,
+            snapshot: controller.controls.snapshot(),
+            gyro: controller.controls.imu.gyro,
+            accel: controller.controls.imu.accel,
+            touches: controller.controls.touches.state(),
+            battery_percent: battery.percent().map(|p| p.round().clamp(0.0, 100.0) as u8),
+            charging: battery.cable_connected(),
+            sampled_at: controller
+                .sampled_at()
+                .map(|at| now.1 - now.0.saturating_duration_since(at)),
+            frame: 0,
+        }
+    }
+
+    fn encode(&self) -> [u8; PAYLOAD_LEN] {
+        let mut out = [0u8; PAYLOAD_LEN];
+        out[..WIRE_LEN].copy_from_slice(&wire::encode(&self.snapshot));
+        for (i, v) in self.gyro.iter().chain(&self.accel).enumerate() {
+            out[10 + i * 2..12 + i * 2].copy_from_slice(&v.to_le_bytes());
+        }
+        for (t, touch) in self.touches.iter().enumerate() {
+            let at = 22 + t * 5;
+            out[at] = (touch.active as u8) << 7 | touch.id & 0x7f;
+            out[at + 1..at + 3].copy_from_slice(&touch.x.to_le_bytes());
+            out[at + 3..at + 5].copy_from_slice(&touch.y.to_le_bytes());
+        }
+        out[32] = self.battery_percent.unwrap_or(NO_BATTERY);
+        out[33] = if self.connected { FLAG_CONNECTED } else { 0 }
+            | if self.charging { FLAG_CHARGING } else { 0 };
+        let micros = self
+            .sampled_at
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_micros() as u64);
+        out[40..48].copy_from_slice(&micros.to_le_bytes());
+        out[48..56].copy_from_slice(&self.frame.to_le_bytes());
+        out
+    }
+
+    fn decode(bytes: &[u8; PAYLOAD_LEN]) -> Self {
+        let i16_at = |at: usize| i16::from_le_bytes([bytes[at], bytes[at + 1]]);
+        let u16_at = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+        let u64_at = |at: usize| {
+            let mut b = [0u8; 8];
+            b.copy_from_slice(&bytes[at..at + 8]);
+            u64::from_le_bytes(b)
+        };
+        let touch = |at: usize| Touch {
+            active: bytes[at] & 0x80 != 0,
+            id: bytes[at] & 0x7f,
+            x: u16_at(at + 1),
+            y: u16_at(at + 3),
+        };
+        let micros = u64_at(40);
+        SharedState {
+            connected: bytes[33] & FLAG_CONNECTED != 0,
+            snapshot: wire::decode(&bytes[..WIRE_LEN]).unwrap_or_default(),
+            gyro: [i16_at(10), i16_at(12), i16_at(14)],
+            accel: [i16_at(16), i16_at(18), i16_at(20)],
+            touches: [touch(22), touch(27)],
+            battery_percent: (bytes[32] != NO_BATTERY).then_some(bytes[32]),
+            charging: bytes[33] & FLAG_CHARGING != 0,
+            sampled_at: (micros != 0).then(|| UNIX_EPOCH + Duration::from_micros(micros)),
+            frame: u64_at(48),
+        }
+    }
+}
+
+/// The region as words, the first two being the header.
+///
+/// # Safety
+///
+/// `region` must point to at least `SHARED_LEN` bytes, be 8-byte aligned, and
+/// only ever be accessed through atomics while the returned slice is alive, by
+/// this process or any other. Stores through the slice need `region` to come
+/// from a mutable pointer, such as `MmapMut::as_mut_ptr`.
+unsafe fn words<'a>(region: *const u8) -> &'a [AtomicU64] {
+    slice::from_raw_parts(region.cast::<AtomicU64>(), SHARED_LEN / 8)
+}
+
+fn header() -> u64 {
+    let mut b = [0u8; 8];
+    b[..4].copy_from_slice(MAGIC);
+    b[4..].copy_from_slice(&VERSION.to_le_bytes());
+    u64::from_le_bytes(b)
+}
+
+/// Publishes controller state into a file-backed shared-memory region that
+/// other processes, such as overlays and telemetry HUDs, map with
+/// `SharedStateReader` and read without any IPC round trip.
+///
+/// Writes are guarded by a seqlock: the header's sequence word is odd while a
+/// write is under way, so a reader that sees it change or odd retries. The
+/// region starts with `DS4S`, the layout version as a `u32`, and the sequence
+/// as a `u64`, followed by the payload described on `SharedState`; readers in
+/// other languages can follow the same protocol.
+pub struct SharedStateWriter {
+    map: MmapMut,
+    path: PathBuf,
+    frame: u64,
+}
+
+impl SharedStateWriter {
+    /// Creates or takes over the region at `path`, e.g. from `shm::path`.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        file.set_len(SHARED_LEN as u64)?;
+        // Safety: the file is ours to size, and nothing in this process holds
+        // another mapping of it.
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        let mut writer = SharedStateWriter {
+            map,
+            path,
+            frame: 0,
+        };
+        writer.words()[0].store(header(), Ordering::Release);
+        Ok(writer)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn words(&mut self) -> &[AtomicU64] {
+        // Safety: the map is `SHARED_LEN` long and page aligned, is only
+        // touched through these atomics, and the pointer is a mutable one.
+        unsafe { words(self.map.as_mut_ptr()) }
+    }
+
+    /// Replaces the published state, numbering it as the next frame.
+    pub fn publish(&mut self, state: &SharedState) {
+        self.frame += 1;
+        let payload = SharedState {
+            frame: self.frame,
+            ..*state
+        }
+        .encode();
+        let words = self.words();
+        let sequence = words[1].load(Ordering::Relaxed);
+        // An odd sequence left by a writer that died part way is finished off.
+        let start = sequence | 1;
+        words[1].store(start, Ordering::Relaxed);
+        fence(Ordering::Release);
+        for (word, chunk) in words[HEADER_WORDS..].iter().zip(payload.chunks_exact(8)) {
+            let mut b = [0u8; 8];
+            b.copy_from_slice(chunk);
+            word.store(u64::from_ne_bytes(b), Ordering::Relaxed);
+        }
+        words[1].store(start.wrapping_add(1), Ordering::Release);
+    }
+
+    pub fn publish_controller(&mut self, controller: &Controller) {
+        self.publish(&SharedState::from_controller(controller));
+    }
+
+    /// Publishes that no controller is connected.
+    pub fn disconnected(&mut self) {
+        self.publish(&SharedState::default());
+    }
+}
+
+impl Drop for SharedStateWriter {
+    /// Leaves the region saying the controller is gone, so readers don't keep
+    /// showing its last input.
+    fn drop(&mut self) {
+        self.disconnected();
+    }
+}
+
+/// Maps a region published by `SharedStateWriter` and reads it lock-free.
+pub struct SharedStateReader {
+    map: Mmap,
+}
+
+impl SharedStateReader {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        if file.metadata()?.len() < SHARED_LEN as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "shared state region is too short",
+            ));
+        }
+        // Safety: the region is only read, through atomics, and the writer
+        // never shrinks it.
+        let map = unsafe { Mmap::map(&file)? };
+        let reader = SharedStateReader { map };
+        if reader.words()[0].load(Ordering::Acquire) != header() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a ps4hid shared state region, or a different version",
+            ));
+        }
+        Ok(reader)
+    }
+
+    fn words(&self) -> &[AtomicU64] {
+        // Safety: checked to be `SHARED_LEN` long in `open`; page aligned; and
+        // the writer only touches it through atomics.
+        unsafe { words(self.map.as_ptr()) }
+    }
+
+    /// The latest published state, or `None` if the writer stopped part way
+    /// through a write and never finished it.
+    pub fn read(&self) -> Option<SharedState> {
+        let words = self.words();
+        let mut stuck = 0;
+        let mut last = None;
+        loop {
+            let before = words[1].load(Ordering::Acquire);
+            if before & 1 != 0 {
+                // Only a write that never moves on counts against the limit; a
+                // busy writer just means trying again.
+                stuck = if last == Some(before) { stuck + 1 } else { 0 };
+                last = Some(before);
+                if stuck >= SPIN_LIMIT {
+                    return None;
+                }
+                hint::spin_loop();
+                continue;
+            }
+            let mut payload = [0u8; PAYLOAD_LEN];
+            for (chunk, word) in payload.chunks_exact_mut(8).zip(&words[HEADER_WORDS..]) {
+                chunk.copy_from_slice(&word.load(Ordering::Relaxed).to_ne_bytes());
+            }
+            fence(Ordering::Acquire);
+            if words[1].load(Ordering::Relaxed) == before {
+                return Some(SharedState::decode(&payload));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DPad, Stick};
+
+    /// A region unique to this test, removed when dropped.
+    struct Region(PathBuf);
+
+    impl Region {
+        fn new(test: &str) -> Self {
+            Region(std::env::temp_dir().join(format!(
+                "ps4hid-test-{}-{}",
+                test,
+                std::process::id()
+            )))
+        }
+    }
+
+    impl Drop for Region {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn busy_state() -> SharedState {
+        SharedState {
+            connected: true,
+            snapshot: Snapshot {
+                circle: true,
+                r2: true,
+                ps: true,
+                dpad: DPad::SouthWest,
+                left_stick: Stick::new(0, 255),
+                right_stick: Stick::new(17, 200),
+                l2_value: 3,
+                r2_value: 250,
+                ..Snapshot::default()
+            },
+            gyro: [-1, i16::MAX, i16::MIN],
+            accel: [8192, -8192, 12],
+            touches: [
+                Touch {
+                    active: true,
+                    id: 0x7f,
+                    x: 1919,
+                    y: 941,
+                },
+                Touch {
+                    active: false,
+                    id: 5,
+                    x: 0,
+                    y: u16::MAX,
+                },
+            ],
+            battery_percent: Some(42),
+            charging: true,
+            sampled_at: Some(UNIX_EPOCH + Duration::from_micros(1_700_000_000_123_456)),
+            frame: u64::MAX - 1,
+        }
+    }
+
+    #[test]
+    fn encode_decode_keeps_every_field() {
+        let state = busy_state();
+        assert_eq!(SharedState::decode(&state.encode()), state);
+        let idle = SharedState::default();
+        assert_eq!(SharedState::decode(&idle.encode()), idle);
+    }
+
+    #[test]
+    fn published_state_reads_back() {
+        let region = Region::new("round-trip");
+        let writer = SharedStateWriter::create(&region.0);
+        assert!(writer.is_ok());
+        let Ok(mut writer) = writer else { return };
+        let reader = SharedStateReader::open(&region.0);
+        assert!(reader.is_ok());
+        let Ok(reader) = reader else { return };
+        writer.publish(&busy_state());
+        writer.publish(&busy_state());
+        let expected = SharedState {
+            frame: 2,
+            ..busy_state()
+        };
+        assert_eq!(reader.read(), Some(expected));
+    }
+
+    #[test]
+    fn unfinished_write_reads_as_none() {
+        let region = Region::new("torn");
+        let writer = SharedStateWriter::create(&region.0);
+        assert!(writer.is_ok());
+        let Ok(mut writer) = writer else { return };
+        writer.publish(&busy_state());
+        let words = writer.words();
+        words[1].store(words[1].load(Ordering::Relaxed) | 1, Ordering::Release);
+        let reader = SharedStateReader::open(&region.0);
+        assert!(reader.is_ok_and(|r| r.read().is_none()));
+    }
+}
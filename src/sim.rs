@@ -0,0 +1,181 @@
+use crate::connection::ConnectionInfo;
+use crate::transport::{MockHandle, MockTransport};
+use crate::{ButtonId, Controller, DPad, Error, Result, Snapshot, Stick, YAxis};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const TAP_HOLD: Duration = Duration::from_millis(50);
+
+/// One line of a simulator script.
+///
+/// ```text
+/// press x          release x        tap options
+/// dpad north       dpad released
+/// stick left 0.0 1.0               # centered, Y up
+/// trigger r2 0.5
+/// wait 250         # milliseconds
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SimCommand {
+    Press(ButtonId),
+    Release(ButtonId),
+    Tap(ButtonId),
+    DPad(DPad),
+    Stick { right: bool, x: f32, y: f32 },
+    Trigger { right: bool, value: f32 },
+    Wait(Duration),
+}
+
+impl FromStr for SimCommand {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let bad = || Error::InvalidFormat("unrecognised simulator command");
+        let mut words = line.split_whitespace();
+        let verb = words.next().ok_or(bad())?;
+        let mut arg = || words.next().ok_or(bad());
+        let button = |s: &str| s.parse::<ButtonId>().map_err(|_| bad());
+        let side = |s: &str| match s {
+            "left" | "l2" => Ok(false),
+            "right" | "r2" => Ok(true),
+            _ => Err(bad()),
+        };
+        let num = |s: &str| s.parse::<f32>().map_err(|_| bad());
+        Ok(match verb {
+            "press" => SimCommand::Press(button(arg()?)?),
+            "release" => SimCommand::Release(button(arg()?)?),
+            "tap" => SimCommand::Tap(button(arg()?)?),
+            "dpad" => SimCommand::DPad(parse_dpad(arg()?).ok_or(bad())?),
+            "stick" => SimCommand::Stick {
+                right: side(arg()?)?,
+                x: num(arg()?)?,
+                y: num(arg()?)?,
+            },
+            "trigger" => SimCommand::Trigger {
+                right: side(arg()?)?,
+                value: num(arg()?)?,
+            },
+            "wait" => SimCommand::Wait(Duration::from_millis(arg()?.parse().map_err(|_| bad())?)),
+            _ => return Err(bad()),
+        })
+    }
+}
+
+fn parse_dpad(s: &str) -> Option<DPad> {
+    Some(match s {
+        "released" | "none" => DPad::Released,
+        "north" | "up" => DPad::North,
+        "northeast" => DPad::NorthEast,
+        "east" | "right" => DPad::East,
+        "southeast" => DPad::SouthEast,
+        "south" | "down" => DPad::South,
+        "southwest" => DPad::SouthWest,
+        "west" | "left" => DPad::West,
+        "northwest" => DPad::NorthWest,
+        _ => return None,
+    })
+}
+
+/// A fake pad streaming input reports into a [`MockTransport`] at a fixed rate,
+/// with its state set directly or by script.
+pub struct Simulator {
+    state: Arc<Mutex<Snapshot>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Simulator {
+    /// A controller wired to a new simulator sending reports at `rate_hz`.
+    pub fn controller(rate_hz: u32) -> (Controller, Simulator) {
+        let (transport, handle) = MockTransport::new();
+        let controller = Controller::with_transport(transport, ConnectionInfo::default());
+        (controller, Simulator::start(handle, rate_hz))
+    }
+
+    pub fn start(mut handle: MockHandle, rate_hz: u32) -> Simulator {
+        let state = Arc::new(Mutex::new(Snapshot::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let interval = Duration::from_secs(1) / rate_hz.max(1);
+        let thread = {
+            let (state, stop) = (state.clone(), stop.clone());
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let snapshot = *state.lock().unwrap_or_else(PoisonError::into_inner);
+                    if handle
+                        .send_snapshot(&snapshot, interval.as_micros() as u32)
+                        .is_err()
+                    {
+                        break;
+                    }
+                    thread::sleep(interval);
+                }
+            })
+        };
+        Simulator {
+            state,
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        *self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn set(&self, f: impl FnOnce(&mut Snapshot)) {
+        f(&mut self.state.lock().unwrap_or_else(PoisonError::into_inner));
+    }
+
+    /// Applies one command; `Wait` and `Tap` block the caller.
+    pub fn apply(&self, command: &SimCommand) {
+        match *command {
+            SimCommand::Press(id) => self.set(|s| s.set_pressed(id, true)),
+            SimCommand::Release(id) => self.set(|s| s.set_pressed(id, false)),
+            SimCommand::Tap(id) => {
+                self.set(|s| s.set_pressed(id, true));
+                thread::sleep(TAP_HOLD);
+                self.set(|s| s.set_pressed(id, false));
+            }
+            SimCommand::DPad(dpad) => self.set(|s| s.dpad = dpad),
+            SimCommand::Stick { right, x, y } => {
+                let stick = Stick::from_centered(x, y, YAxis::Up);
+                self.set(|s| match right {
+                    false => s.left_stick = stick,
+                    true => s.right_stick = stick,
+                });
+            }
+            SimCommand::Trigger { right, value } => {
+                let raw = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+                self.set(|s| match right {
+                    false => (s.l2_value, s.l2) = (raw, raw > 0),
+                    true => (s.r2_value, s.r2) = (raw, raw > 0),
+                });
+            }
+            SimCommand::Wait(duration) => thread::sleep(duration),
+        }
+    }
+
+    /// Runs a script one line at a time. Blank lines and `#` comments are skipped.
+    pub fn run_script(&self, script: &str) -> Result<()> {
+        for line in script.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if !line.is_empty() {
+                self.apply(&line.parse()?);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Simulator {
+    /// Stops the report thread; the controller then reads as disconnected.
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{Button, ButtonFlags, ButtonId, Controls, LockMode, Snapshot};
+
+impl Controls {
+    /// Whether a button is held; for L2/R2 this is the trigger's digital bit.
+    pub fn get(&self, id: ButtonId) -> bool {
+        match id {
+            ButtonId::L2 => self.l2.digital(),
+            ButtonId::R2 => self.r2.digital(),
+            _ => self.button(id).is_some_and(|b| b.state()),
+        }
+    }
+
+    /// Every button with its state, in `ButtonId::ALL` order.
+    pub fn iter(&self) -> impl Iterator<Item = (ButtonId, bool)> + '_ {
+        ButtonId::ALL.into_iter().map(|id| (id, self.get(id)))
+    }
+
+    /// The control behind a digital button. `None` for L2/R2, which are `Trigger`s.
+    pub fn button(&self, id: ButtonId) -> Option<&Button<bool>> {
+        Some(match id {
+            ButtonId::Triangle => &self.triangle,
+            ButtonId::Circle => &self.circle,
+            ButtonId::X => &self.x,
+            ButtonId::Square => &self.square,
+            ButtonId::R3 => &self.r3,
+            ButtonId::L3 => &self.l3,
+            ButtonId::Options => &self.options,
+            ButtonId::Share => &self.share,
+            ButtonId::R1 => &self.r1,
+            ButtonId::L1 => &self.l1,
+            ButtonId::Tpad => &self.tpad,
+            ButtonId::Ps => &self.ps,
+            ButtonId::R2 | ButtonId::L2 => return None,
+        })
+    }
+
+    /// Mutable access, e.g. to set handlers from a binding table.
+    pub fn button_mut(&mut self, id: ButtonId) -> Option<&mut Button<bool>> {
+        Some(match id {
+            ButtonId::Triangle => &mut self.triangle,
+            ButtonId::Circle => &mut self.circle,
+            ButtonId::X => &mut self.x,
+            ButtonId::Square => &mut self.square,
+            ButtonId::R3 => &mut self.r3,
+            ButtonId::L3 => &mut self.l3,
+            ButtonId::Options => &mut self.options,
+            ButtonId::Share => &mut self.share,
+            ButtonId::R1 => &mut self.r1,
+            ButtonId::L1 => &mut self.l1,
+            ButtonId::Tpad => &mut self.tpad,
+            ButtonId::Ps => &mut self.ps,
+            ButtonId::R2 | ButtonId::L2 => return None,
+        })
+    }
+
+    /// Disables or re-enables a button, including L2/R2 as whole triggers. Sticks
+    /// are locked through `Button::set_enabled` on the stick fields.
+    pub fn set_enabled(&mut self, id: ButtonId, enabled: bool) {
+        match id {
+            ButtonId::L2 => self.l2.set_enabled(enabled),
+            ButtonId::R2 => self.r2.set_enabled(enabled),
+            _ => {
+                if let Some(button) = self.button_mut(id) {
+                    button.set_enabled(enabled);
+                }
+            }
+        }
+    }
+
+    pub fn is_enabled(&self, id: ButtonId) -> bool {
+        match id {
+            ButtonId::L2 => self.l2.is_enabled(),
+            ButtonId::R2 => self.r2.is_enabled(),
+            _ => self.button(id).is_none_or(Button::is_enabled),
+        }
+    }
+
+    pub fn set_lock_mode(&mut self, id: ButtonId, mode: LockMode) {
+        match id {
+            ButtonId::L2 => self.l2.set_lock_mode(mode),
+            ButtonId::R2 => self.r2.set_lock_mode(mode),
+            _ => {
+                if let Some(button) = self.button_mut(id) {
+                    button.set_lock_mode(mode);
+                }
+            }
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            triangle: self.triangle.state(),
+            circle: self.circle.state(),
+            x: self.x.state(),
+            square: self.square.state(),
+            dpad: self.dpad.state(),
+            r3: self.r3.state(),
+            l3: self.l3.state(),
+            options: self.options.state(),
+            share: self.share.state(),
+            r2: self.r2.digital(),
+            l2: self.l2.digital(),
+            r1: self.r1.state(),
+            l1: self.l1.state(),
+            tpad: self.tpad.state(),
+            ps: self.ps.state(),
+            left_stick: self.left_stick.state(),
+            right_stick: self.right_stick.state(),
+            l2_value: self.l2.value(),
+            r2_value: self.r2.value(),
+        }
+    }
+
+    pub fn buttons(&self) -> ButtonFlags {
+        self.iter().filter(|&(_, pressed)| pressed).fold(
+            ButtonFlags::from_dpad(self.dpad.state()),
+            |flags, (id, _)| flags | id.into(),
+        )
+    }
+}
@@ -0,0 +1,151 @@
+use crate::DPad;
+
+/// What to do when opposite cardinal directions are held at once, e.g. left and
+/// right from two merged pads or two keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SocdPolicy {
+    /// Opposites cancel to neither, as most fighting game rulesets require.
+    #[default]
+    Neutral,
+    /// The direction pressed most recently wins, so a strafe can be reversed
+    /// without letting go of the first key.
+    LastWins,
+    /// The direction held first wins until it's released.
+    FirstWins,
+}
+
+/// One pair of opposite directions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Axis {
+    negative: bool,
+    positive: bool,
+    resolved: i8,
+}
+
+impl Axis {
+    fn resolve(&mut self, policy: SocdPolicy, negative: bool, positive: bool) -> i8 {
+        let pressed_negative = negative && !self.negative;
+        let pressed_positive = positive && !self.positive;
+        self.resolved = match (negative, positive) {
+            (false, false) => 0,
+            (true, false) => -1,
+            (false, true) => 1,
+            // Pressed together in the same update: nothing to order them by.
+            (true, true) if pressed_negative && pressed_positive => 0,
+            (true, true) => match policy {
+                SocdPolicy::Neutral => 0,
+                SocdPolicy::LastWins if pressed_negative => -1,
+                SocdPolicy::LastWins if pressed_positive => 1,
+                SocdPolicy::LastWins | SocdPolicy::FirstWins => self.resolved,
+            },
+        };
+        self.negative = negative;
+        self.positive = positive;
+        self.resolved
+    }
+}
+
+/// Resolves held directions to at most one per axis under a `SocdPolicy`.
+///
+/// `LastWins` and `FirstWins` depend on the order in which directions were
+/// pressed, so keep one resolver per input and feed it every update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SocdResolver {
+    pub policy: SocdPolicy,
+    horizontal: Axis,
+    vertical: Axis,
+}
+
+impl SocdResolver {
+    pub fn new(policy: SocdPolicy) -> Self {
+        SocdResolver {
+            policy,
+            ..SocdResolver::default()
+        }
+    }
+
+    /// `(x, y)` unit steps with Y up.
+    pub fn resolve(&mut self, left: bool, right: bool, up: bool, down: bool) -> (i8, i8) {
+        (
+            self.horizontal.resolve(self.policy, left, right),
+            self.vertical.resolve(self.policy, down, up),
+        )
+    }
+
+    pub fn resolve_dpad(&mut self, left: bool, right: bool, up: bool, down: bool) -> DPad {
+        let (x, y) = self.resolve(left, right, up, down);
+        DPad::from_xy(x, y)
+    }
+
+    /// Combines several dpads, each possibly in a different direction, into one.
+    pub fn merge(&mut self, dpads: impl IntoIterator<Item = DPad>) -> DPad {
+        let (mut left, mut right, mut up, mut down) = (false, false, false, false);
+        for dpad in dpads {
+            let (x, y) = dpad.to_xy();
+            left |= x < 0;
+            right |= x > 0;
+            up |= y > 0;
+            down |= y < 0;
+        }
+        self.resolve_dpad(left, right, up, down)
+    }
+
+    /// Forgets which directions were held, e.g. when a source goes away.
+    pub fn reset(&mut self) {
+        self.horizontal = Axis::default();
+        self.vertical = Axis::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neutral_cancels_opposites() {
+        let mut socd = SocdResolver::new(SocdPolicy::Neutral);
+        assert_eq!(socd.resolve(true, false, false, false), (-1, 0));
+        assert_eq!(socd.resolve(true, true, false, false), (0, 0));
+        assert_eq!(socd.resolve(false, false, true, true), (0, 0));
+        assert_eq!(socd.resolve(false, true, true, false), (1, 1));
+    }
+
+    #[test]
+    fn last_wins_follows_the_newest_press() {
+        let mut socd = SocdResolver::new(SocdPolicy::LastWins);
+        assert_eq!(socd.resolve(true, false, false, false), (-1, 0));
+        assert_eq!(socd.resolve(true, true, false, false), (1, 0));
+        assert_eq!(socd.resolve(true, true, false, false), (1, 0));
+        // Releasing the newer one hands it back to the one still held.
+        assert_eq!(socd.resolve(true, false, false, false), (-1, 0));
+    }
+
+    #[test]
+    fn first_wins_holds_until_released() {
+        let mut socd = SocdResolver::new(SocdPolicy::FirstWins);
+        assert_eq!(socd.resolve(false, false, true, false), (0, 1));
+        assert_eq!(socd.resolve(false, false, true, true), (0, 1));
+        assert_eq!(socd.resolve(false, false, false, true), (0, -1));
+    }
+
+    #[test]
+    fn simultaneous_presses_are_neutral_under_any_policy() {
+        for policy in [
+            SocdPolicy::Neutral,
+            SocdPolicy::LastWins,
+            SocdPolicy::FirstWins,
+        ] {
+            let mut socd = SocdResolver::new(policy);
+            assert_eq!(socd.resolve(true, true, false, false), (0, 0));
+        }
+    }
+
+    #[test]
+    fn merging_pads_resolves_their_directions() {
+        let mut socd = SocdResolver::new(SocdPolicy::Neutral);
+        assert_eq!(socd.merge([DPad::West, DPad::East]), DPad::Released);
+        assert_eq!(socd.merge([DPad::North, DPad::East]), DPad::NorthEast);
+        socd.reset();
+        assert_eq!(socd.merge([]), DPad::Released);
+    }
+}
@@ -0,0 +1,199 @@
+use crate::macros::MacroPlayer;
+use crate::{AxisId, ButtonId, Snapshot};
+use crate::{Controller, DPad, Event, Result};
+#[cfg(feature = "hid")]
+use hidapi::HidApi;
+use std::collections::VecDeque;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub(crate) const AXES: [AxisId; 6] = [
+    AxisId::LeftX,
+    AxisId::LeftY,
+    AxisId::RightX,
+    AxisId::RightY,
+    AxisId::L2,
+    AxisId::R2,
+];
+const REPLAY_TICK: Duration = Duration::from_millis(4);
+/// Enough for every control changing at once plus a few device events, so the
+/// queue doesn't allocate as it fills.
+const QUEUE_CAPACITY: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    Button {
+        id: ButtonId,
+        pressed: bool,
+    },
+    DPad(DPad),
+    /// Same ranges as `Snapshot::axis`.
+    Axis {
+        id: AxisId,
+        value: f32,
+    },
+    /// A device-level event; only real controllers produce these.
+    Device(Event),
+}
+
+/// Anything that produces controller input: a real pad, a simulator, a replay or the
+/// keyboard. Applications written against this can swap sources at runtime.
+pub trait InputSource {
+    /// Waits briefly for new input, returning the next change if there is one.
+    fn next_event(&mut self) -> Result<Option<InputEvent>>;
+    /// Events queued but not yet delivered, which `next_event` returns without
+    /// waiting for input.
+    fn pending(&self) -> usize {
+        0
+    }
+    /// The state of every control as of the events returned so far.
+    fn state(&self) -> Snapshot;
+}
+
+/// Turns successive snapshots into change events.
+#[derive(Debug, Clone)]
+pub struct EventQueue {
+    last: Snapshot,
+    pending: VecDeque<InputEvent>,
+}
+
+impl Default for EventQueue {
+    fn default() -> Self {
+        EventQueue {
+            last: Snapshot::default(),
+            pending: VecDeque::with_capacity(QUEUE_CAPACITY),
+        }
+    }
+}
+
+impl EventQueue {
+    pub fn new() -> Self {
+        EventQueue::default()
+    }
+
+    pub fn push_snapshot(&mut self, next: Snapshot) {
+        for (id, pressed) in next.buttons().changes(self.last.buttons()) {
+            self.pending.push_back(InputEvent::Button { id, pressed });
+        }
+        if self.last.dpad != next.dpad {
+            self.pending.push_back(InputEvent::DPad(next.dpad));
+        }
+        for id in AXES {
+            if self.last.axis(id) != next.axis(id) {
+                self.pending.push_back(InputEvent::Axis {
+                    id,
+                    value: next.axis(id),
+                });
+            }
+        }
+        self.last = next;
+    }
+
+    pub fn push(&mut self, event: InputEvent) {
+        self.pending.push_back(event);
+    }
+
+    pub fn pop(&mut self) -> Option<InputEvent> {
+        self.pending.pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn state(&self) -> Snapshot {
+        self.last
+    }
+}
+
+/// Plays a recorded macro back as an input source, paced in real time.
+pub struct ReplaySource {
+    pub player: MacroPlayer,
+    queue: EventQueue,
+}
+
+impl ReplaySource {
+    /// Starts playback immediately.
+    pub fn new(mut player: MacroPlayer) -> Self {
+        player.start(Instant::now());
+        ReplaySource {
+            player,
+            queue: EventQueue::new(),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.player.is_playing()
+    }
+}
+
+impl InputSource for ReplaySource {
+    fn next_event(&mut self) -> Result<Option<InputEvent>> {
+        if self.queue.is_empty() {
+            thread::sleep(REPLAY_TICK);
+            if let Some(frame) = self.player.frame(Instant::now()) {
+                self.queue.push_snapshot(frame);
+            }
+        }
+        Ok(self.queue.pop())
+    }
+
+    fn state(&self) -> Snapshot {
+        self.queue.state()
+    }
+    fn pending(&self) -> usize {
+        self.queue.len()
+    }
+}
+
+impl InputSource for Controller {
+    fn next_event(&mut self) -> Result<Option<InputEvent>> {
+        if self.input_events.is_empty() {
+            self.update()?;
+            self.queue_input_events();
+        }
+        Ok(self.input_events.pop())
+    }
+
+    fn state(&self) -> Snapshot {
+        self.input_events.state()
+    }
+    fn pending(&self) -> usize {
+        self.input_events.len()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl InputSource for crate::keyboard::KeyboardSource {
+    fn next_event(&mut self) -> Result<Option<InputEvent>> {
+        if self.events.is_empty() {
+            self.update()?;
+            let snapshot = self.controls.snapshot();
+            self.events.push_snapshot(snapshot);
+        }
+        Ok(self.events.pop())
+    }
+
+    fn state(&self) -> Snapshot {
+        self.events.state()
+    }
+    fn pending(&self) -> usize {
+        self.events.len()
+    }
+}
+
+#[cfg(feature = "hid")]
+/// The first connected controller, falling back to the keyboard (on Linux) when
+/// there is none.
+pub fn first_available(api: &HidApi) -> Result<Box<dyn InputSource>> {
+    match Controller::open(api) {
+        Ok(controller) => Ok(Box::new(controller)),
+        #[cfg(target_os = "linux")]
+        Err(crate::Error::NotFound) => Ok(Box::new(crate::keyboard::KeyboardSource::new()?)),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,228 @@
+use crate::profile::{config_path, read_config};
+use crate::{ButtonId, Error, Result, Snapshot, YAxis};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Raw trigger value that counts as a full pull.
+const FULL_PULL: u8 = 250;
+/// A trigger must fall back below this before the next full pull counts.
+const PULL_RELEASE: u8 = 128;
+/// Stick movement smaller than this, in full-deflection units, is treated as noise.
+const TRAVEL_NOISE: f32 = 3.0 / 127.0;
+/// Gaps between reports longer than this don't count towards play time.
+const MAX_GAP: Duration = Duration::from_millis(100);
+
+/// Lifetime usage counters for one controller, persisted as
+/// `<config dir>/ps4hid/<key>.stats` next to its profile.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UsageStats {
+    /// Presses per button, in `ButtonId::ALL` order.
+    presses: [u64; ButtonId::ALL.len()],
+    /// Distance each stick has moved, in full deflections (center to edge is 1.0).
+    pub left_travel: f64,
+    pub right_travel: f64,
+    pub l2_full_pulls: u64,
+    pub r2_full_pulls: u64,
+    /// Time spent receiving reports, across all sessions.
+    pub play_time: Duration,
+    /// How many times the controller has been opened.
+    pub sessions: u64,
+    session_time: Duration,
+    tracker: Option<Tracker>,
+}
+
+/// What `record` compares the next snapshot against.
+#[derive(Debug, Clone, PartialEq)]
+struct Tracker {
+    previous: Snapshot,
+    left_anchor: (f32, f32),
+    right_anchor: (f32, f32),
+}
+
+impl UsageStats {
+    pub fn new() -> Self {
+        UsageStats::default()
+    }
+
+    pub fn presses(&self, id: ButtonId) -> u64 {
+        self.presses[index(id)]
+    }
+
+    pub fn total_presses(&self) -> u64 {
+        self.presses.iter().sum()
+    }
+
+    /// Play time since this value was created or loaded.
+    pub fn session_time(&self) -> Duration {
+        self.session_time
+    }
+
+    /// Counts a new session; the next `record` starts fresh instead of comparing
+    /// against the last session's final state.
+    pub fn begin_session(&mut self) {
+        self.sessions += 1;
+        self.session_time = Duration::ZERO;
+        self.tracker = None;
+    }
+
+    /// Folds in one report, `dt` after the previous one.
+    pub fn record(&mut self, snapshot: &Snapshot, dt: Duration) {
+        let left = snapshot.left_stick.centered(YAxis::Up);
+        let right = snapshot.right_stick.centered(YAxis::Up);
+        let Some(tracker) = self.tracker.as_mut() else {
+            self.tracker = Some(Tracker {
+                previous: *snapshot,
+                left_anchor: left,
+                right_anchor: right,
+            });
+            return;
+        };
+        if dt <= MAX_GAP {
+            self.play_time += dt;
+            self.session_time += dt;
+        }
+        let previous = tracker.previous;
+        let (pressed, _) = snapshot.buttons().diff(previous.buttons());
+        for id in pressed.ids() {
+            self.presses[index(id)] += 1;
+        }
+        self.left_travel += travel(&mut tracker.left_anchor, left);
+        self.right_travel += travel(&mut tracker.right_anchor, right);
+        self.l2_full_pulls += full_pull(previous.l2_value, snapshot.l2_value);
+        self.r2_full_pulls += full_pull(previous.r2_value, snapshot.r2_value);
+        // Triggers between the two thresholds keep their old value so a pull that
+        // wobbles around `FULL_PULL` counts once.
+        tracker.previous = Snapshot {
+            l2_value: hold(previous.l2_value, snapshot.l2_value),
+            r2_value: hold(previous.r2_value, snapshot.r2_value),
+            ..*snapshot
+        };
+    }
+
+    /// Clears every counter, keeping the session count.
+    pub fn reset(&mut self) {
+        *self = UsageStats {
+            sessions: self.sessions,
+            ..UsageStats::default()
+        };
+    }
+
+    pub fn path(key: &str) -> Option<PathBuf> {
+        config_path(key, "stats")
+    }
+
+    /// Returns `Ok(None)` if no statistics have been saved for `key`, or there's
+    /// nowhere they could have been saved.
+    pub fn load(key: &str) -> Result<Option<UsageStats>> {
+        read_config(key, "stats")?
+            .map(|text| UsageStats::parse(&text))
+            .transpose()
+    }
+
+    pub fn save(&self, key: &str) -> Result<()> {
+        let path = UsageStats::path(key).ok_or(Error::InvalidFormat("no config directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    pub fn parse(text: &str) -> Result<UsageStats> {
+        let mut stats = UsageStats::default();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(Error::InvalidFormat("stats line without '='"))?;
+            let value = value.trim();
+            match key.trim() {
+                "sessions" => stats.sessions = parse_value(value)?,
+                "play_time" => {
+                    stats.play_time = Duration::try_from_secs_f64(parse_value(value)?)
+                        .map_err(|_| Error::InvalidFormat("bad play time"))?
+                }
+                "left_travel" => stats.left_travel = parse_value(value)?,
+                "right_travel" => stats.right_travel = parse_value(value)?,
+                "l2_full_pulls" => stats.l2_full_pulls = parse_value(value)?,
+                "r2_full_pulls" => stats.r2_full_pulls = parse_value(value)?,
+                "presses" => {
+                    let (button, count) = value
+                        .split_once(char::is_whitespace)
+                        .ok_or(Error::InvalidFormat("presses expects a button and a count"))?;
+                    let id: ButtonId = button
+                        .parse()
+                        .map_err(|_| Error::InvalidFormat("unknown button name"))?;
+                    stats.presses[index(id)] = parse_value(count.trim())?;
+                }
+                _ => {}
+            }
+        }
+        Ok(stats)
+    }
+}
+
+impl std::fmt::Display for UsageStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut s = String::new();
+        let _ = writeln!(s, "sessions = {}", self.sessions);
+        let _ = writeln!(s, "play_time = {:.1}", self.play_time.as_secs_f64());
+        let _ = writeln!(s, "left_travel = {:.2}", self.left_travel);
+        let _ = writeln!(s, "right_travel = {:.2}", self.right_travel);
+        let _ = writeln!(s, "l2_full_pulls = {}", self.l2_full_pulls);
+        let _ = writeln!(s, "r2_full_pulls = {}", self.r2_full_pulls);
+        for (id, count) in ButtonId::ALL.iter().zip(self.presses) {
+            let _ = writeln!(s, "presses = {} {}", id, count);
+        }
+        f.write_str(&s)
+    }
+}
+
+/// `ButtonId` is declared in `ButtonId::ALL` order.
+fn index(id: ButtonId) -> usize {
+    id as usize
+}
+
+/// Distance from `anchor` to `position`, moving the anchor once it exceeds the
+/// noise floor.
+fn travel(anchor: &mut (f32, f32), position: (f32, f32)) -> f64 {
+    let distance = (position.0 - anchor.0).hypot(position.1 - anchor.1);
+    if distance < TRAVEL_NOISE {
+        return 0.0;
+    }
+    *anchor = position;
+    distance as f64
+}
+
+fn full_pull(previous: u8, current: u8) -> u64 {
+    (previous < FULL_PULL && current >= FULL_PULL) as u64
+}
+
+fn hold(previous: u8, current: u8) -> u8 {
+    if previous >= FULL_PULL && current >= PULL_RELEASE {
+        previous
+    } else {
+        current
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| Error::InvalidFormat("bad stats value"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_keys_are_ignored() {
+        let stats = UsageStats::parse("sessions = 3\ngyro_travel = 12.5\n");
+        assert_eq!(stats.ok().map(|s| s.sessions), Some(3));
+    }
+}
@@ -0,0 +1,175 @@
+use crate::source::InputEvent;
+use crate::{AxisId, ButtonId, DPad};
+
+const LOWER: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl-", "zxcvbnm,.'"];
+const UPPER: [&str; 4] = ["1234567890", "QWERTYUIOP", "ASDFGHJKL_", "ZXCVBNM;:\""];
+const SYMBOLS: [&str; 4] = ["!@#$%^&*()", "~`+=[]{}\\|", "<>/?€£¥§°¬", "¡¿«»•…×÷±¤"];
+/// Stick deflection that counts as a cursor move; it must fall back below half
+/// this before the next one.
+const STICK_THRESHOLD: f32 = 0.6;
+
+/// Which grid of keys is showing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Layer {
+    #[default]
+    Lower,
+    Upper,
+    Symbols,
+}
+
+impl Layer {
+    pub fn rows(self) -> [&'static str; 4] {
+        match self {
+            Layer::Lower => LOWER,
+            Layer::Upper => UPPER,
+            Layer::Symbols => SYMBOLS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextEvent {
+    /// The cursor moved to `(row, column)`.
+    Moved {
+        row: usize,
+        column: usize,
+    },
+    LayerChanged(Layer),
+    Char(char),
+    Backspace,
+    /// Options was pressed; carries the finished text and clears it.
+    Submit(String),
+    /// Circle was pressed; the text is kept.
+    Cancel,
+}
+
+/// On-screen keyboard driven by a controller: the dpad or left stick moves a cursor
+/// over a grid of keys and X types the key under it.
+///
+/// Square is backspace, Triangle types a space, L1 toggles upper case, R1 toggles
+/// symbols, Options submits and Circle cancels. The cursor wraps at the edges.
+#[derive(Debug, Clone, Default)]
+pub struct TextInput {
+    layer: Layer,
+    row: usize,
+    column: usize,
+    text: String,
+    /// Whether the left stick has been held past the threshold on each axis.
+    stick_held: (bool, bool),
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        TextInput::default()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+    }
+
+    pub fn layer(&self) -> Layer {
+        self.layer
+    }
+
+    /// `(row, column)` of the highlighted key.
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.column)
+    }
+
+    pub fn key(&self) -> char {
+        self.layer.rows()[self.row]
+            .chars()
+            .nth(self.column)
+            .unwrap_or(' ')
+    }
+
+    /// Feeds one input event, returning what it did, if anything.
+    pub fn handle(&mut self, event: &InputEvent) -> Option<TextEvent> {
+        match *event {
+            InputEvent::DPad(dpad) => {
+                let (dx, dy) = match dpad {
+                    DPad::North => (0, -1),
+                    DPad::South => (0, 1),
+                    DPad::West => (-1, 0),
+                    DPad::East => (1, 0),
+                    _ => return None,
+                };
+                Some(self.move_cursor(dx, dy))
+            }
+            InputEvent::Axis { id, value } => self.stick(id, value),
+            InputEvent::Button { id, pressed: true } => self.press(id),
+            _ => None,
+        }
+    }
+
+    fn stick(&mut self, id: AxisId, value: f32) -> Option<TextEvent> {
+        let held = match id {
+            AxisId::LeftX => &mut self.stick_held.0,
+            AxisId::LeftY => &mut self.stick_held.1,
+            _ => return None,
+        };
+        if *held {
+            *held = value.abs() > STICK_THRESHOLD / 2.0;
+            return None;
+        }
+        if value.abs() < STICK_THRESHOLD {
+            return None;
+        }
+        *held = true;
+        let step = value.signum() as isize;
+        Some(match id {
+            AxisId::LeftX => self.move_cursor(step, 0),
+            _ => self.move_cursor(0, step),
+        })
+    }
+
+    fn press(&mut self, id: ButtonId) -> Option<TextEvent> {
+        Some(match id {
+            ButtonId::X => self.type_char(self.key()),
+            ButtonId::Triangle => self.type_char(' '),
+            ButtonId::Square => {
+                self.text.pop()?;
+                TextEvent::Backspace
+            }
+            ButtonId::L1 => self.toggle(Layer::Upper),
+            ButtonId::R1 => self.toggle(Layer::Symbols),
+            ButtonId::Options => TextEvent::Submit(std::mem::take(&mut self.text)),
+            ButtonId::Circle => TextEvent::Cancel,
+            _ => return None,
+        })
+    }
+
+    fn type_char(&mut self, c: char) -> TextEvent {
+        self.text.push(c);
+        TextEvent::Char(c)
+    }
+
+    fn toggle(&mut self, layer: Layer) -> TextEvent {
+        self.layer = if self.layer == layer {
+            Layer::Lower
+        } else {
+            layer
+        };
+        self.column = self.column.min(self.row_len(self.row).saturating_sub(1));
+        TextEvent::LayerChanged(self.layer)
+    }
+
+    fn row_len(&self, row: usize) -> usize {
+        self.layer.rows()[row].chars().count()
+    }
+
+    fn move_cursor(&mut self, dx: isize, dy: isize) -> TextEvent {
+        let rows = self.layer.rows().len() as isize;
+        self.row = (self.row as isize + dy).rem_euclid(rows) as usize;
+        let columns = self.row_len(self.row).max(1) as isize;
+        self.column = (self.column as isize + dx).rem_euclid(columns) as usize;
+        TextEvent::Moved {
+            row: self.row,
+            column: self.column,
+        }
+    }
+}
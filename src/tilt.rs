@@ -0,0 +1,216 @@
+use crate::{ButtonId, Controller, Snapshot, Stick, YAxis};
+use std::time::{Duration, Instant};
+
+/// Raw gyro units per degree per second.
+pub(crate) const GYRO_PER_DEG_S: f32 = 16.0;
+/// Raw accelerometer units per g.
+pub(crate) const ACCEL_PER_G: f32 = 8192.0;
+/// Accelerometer readings further than this from 1 g are mostly the pad being
+/// waved about rather than gravity, so they don't correct the estimate.
+const ACCEL_TOLERANCE: f32 = 0.25;
+/// Gaps longer than this, e.g. after a dropped connection, restart the estimate
+/// from the accelerometer instead of integrating across them.
+const MAX_STEP: Duration = Duration::from_millis(100);
+/// Deflection past which the real right stick overrides the tilt.
+const STICK_OVERRIDE: f32 = 0.25;
+
+/// How far the pad is tipped, in radians. Pitch is positive with the top edge
+/// raised and roll with the right side lowered.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Tilt {
+    pub pitch: f32,
+    pub roll: f32,
+}
+
+/// Estimates tilt by fusing the gyro and accelerometer with a complementary
+/// filter: the gyro gives smooth short-term motion and gravity corrects its
+/// drift. Yaw has no gravity reference, so it isn't tracked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TiltEstimator {
+    /// How much each accelerometer reading pulls the estimate back towards
+    /// gravity, from 0 (gyro only) to 1 (accelerometer only).
+    pub accel_weight: f32,
+    tilt: Tilt,
+    last: Option<Instant>,
+}
+
+impl Default for TiltEstimator {
+    fn default() -> Self {
+        TiltEstimator {
+            accel_weight: 0.02,
+            tilt: Tilt::default(),
+            last: None,
+        }
+    }
+}
+
+impl TiltEstimator {
+    pub fn new() -> Self {
+        TiltEstimator::default()
+    }
+
+    pub fn tilt(&self) -> Tilt {
+        self.tilt
+    }
+
+    /// Starts again from the next accelerometer reading.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// Adds one report's readings, sampled at `at`, with the gyro as from
+    /// `Controller::gyro`.
+    pub fn update(&mut self, gyro: [f32; 3], accel: [i16; 3], at: Instant) -> Tilt {
+        let [ax, ay, az] = accel.map(|a| a as f32 / ACCEL_PER_G);
+        let gravity = Tilt {
+            pitch: (-az).atan2(ay),
+            roll: ax.atan2(ay),
+        };
+        let step = self.last.map(|last| at.saturating_duration_since(last));
+        self.last = Some(at);
+        let Some(step) = step.filter(|&step| step <= MAX_STEP) else {
+            self.tilt = gravity;
+            return self.tilt;
+        };
+
+        // Gyro x is pitch and z is roll.
+        let rate = |raw: f32| (raw / GYRO_PER_DEG_S).to_radians() * step.as_secs_f32();
+        self.tilt.pitch += rate(gyro[0]);
+        self.tilt.roll += rate(gyro[2]);
+        let g = (ax * ax + ay * ay + az * az).sqrt();
+        if (g - 1.0).abs() < ACCEL_TOLERANCE {
+            let w = self.accel_weight.clamp(0.0, 1.0);
+            self.tilt.pitch += (gravity.pitch - self.tilt.pitch) * w;
+            self.tilt.roll += (gravity.roll - self.tilt.roll) * w;
+        }
+        self.tilt
+    }
+}
+
+/// Emulates the right stick with tilt, for games without gyro support: rolling
+/// the pad pushes the stick sideways and pitching it pushes it up or down.
+/// Forward the result to a `VirtualPad`.
+///
+/// Each press of `toggle` turns it on or off, and the pose held when turning it
+/// on becomes center. The toggle press itself isn't passed on, and the real
+/// stick still wins whenever it's pushed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TiltStick {
+    /// Tilt in degrees that deflects the stick fully.
+    pub range: f32,
+    /// Tilt in degrees that leaves the stick centered.
+    pub deadzone: f32,
+    /// Response exponent: 1 is linear, above 1 gives finer control near center.
+    pub curve: f32,
+    /// Multipliers for the stick's x (from roll) and y (from pitch) with Y up;
+    /// negate one to invert that axis.
+    pub scale: [f32; 2],
+    pub toggle: Option<ButtonId>,
+    pub estimator: TiltEstimator,
+    enabled: bool,
+    center: Tilt,
+    toggle_held: bool,
+}
+
+impl Default for TiltStick {
+    fn default() -> Self {
+        TiltStick {
+            range: 30.0,
+            deadzone: 2.0,
+            curve: 1.0,
+            scale: [1.0, 1.0],
+            toggle: Some(ButtonId::R3),
+            estimator: TiltEstimator::new(),
+            enabled: false,
+            center: Tilt::default(),
+            toggle_held: false,
+        }
+    }
+}
+
+impl TiltStick {
+    pub fn new() -> Self {
+        TiltStick::default()
+    }
+
+    pub fn range(mut self, degrees: f32) -> Self {
+        self.range = degrees;
+        self
+    }
+
+    pub fn curve(mut self, exponent: f32) -> Self {
+        self.curve = exponent;
+        self
+    }
+
+    /// `None` keeps it on all the time.
+    pub fn toggle(mut self, button: Option<ButtonId>) -> Self {
+        self.toggle = button;
+        self.enabled = button.is_none();
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Turns it on, centered on the current pose, or off.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.center = self.estimator.tilt();
+    }
+
+    /// The stick for `tilt` relative to center.
+    pub fn stick(&self, tilt: Tilt) -> Stick {
+        let axis = |angle: f32, scale: f32| {
+            let magnitude = (angle.to_degrees().abs() - self.deadzone).max(0.0);
+            let span = (self.range - self.deadzone).max(1.0);
+            (magnitude / span).min(1.0).powf(self.curve.max(0.1)) * angle.signum() * scale
+        };
+        Stick::from_centered(
+            axis(tilt.roll - self.center.roll, self.scale[0]),
+            axis(tilt.pitch - self.center.pitch, self.scale[1]),
+            YAxis::Up,
+        )
+    }
+
+    /// Updates the estimate with one report's IMU readings and applies it to
+    /// `snapshot`.
+    pub fn apply(
+        &mut self,
+        snapshot: &Snapshot,
+        gyro: [f32; 3],
+        accel: [i16; 3],
+        at: Instant,
+    ) -> Snapshot {
+        let tilt = self.estimator.update(gyro, accel, at);
+        let mut out = *snapshot;
+        if let Some(button) = self.toggle {
+            let pressed = out.pressed(button);
+            if pressed && !self.toggle_held {
+                self.set_enabled(!self.enabled);
+            }
+            self.toggle_held = pressed;
+            out.set_pressed(button, false);
+        }
+        let right = out.right_stick;
+        if self.enabled && right.x_f32().hypot(right.y_f32()) < STICK_OVERRIDE {
+            out.right_stick = self.stick(tilt);
+        }
+        out
+    }
+
+    /// The controller's input with tilt applied, using its remapped snapshot
+    /// and the time its latest report was sampled.
+    pub fn snapshot(&mut self, controller: &Controller) -> Snapshot {
+        let remapped = controller
+            .profile()
+            .remapped(&controller.controls.snapshot());
+        self.apply(
+            &remapped,
+            controller.gyro(),
+            controller.controls.imu.accel,
+            controller.sampled_at().unwrap_or_else(Instant::now),
+        )
+    }
+}
@@ -0,0 +1,216 @@
+use crate::{AxisId, Controls, Error, Result};
+use std::fmt::Write as _;
+use std::str::FromStr;
+use std::time::Duration;
+
+const SPARK: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const COLORS: [&str; 6] = [
+    "#e6194b", "#3cb44b", "#4363d8", "#f58231", "#911eb4", "#42d4f4",
+];
+
+/// A recordable input channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Channel {
+    /// In `-1.0..=1.0` for sticks, `0.0..=1.0` for triggers.
+    Axis(AxisId),
+    /// Raw gyro reading about axis 0 (x), 1 (y) or 2 (z).
+    Gyro(usize),
+    /// Raw accelerometer reading along axis 0 (x), 1 (y) or 2 (z).
+    Accel(usize),
+}
+
+impl Channel {
+    pub fn sample(self, controls: &Controls) -> f32 {
+        match self {
+            Channel::Axis(AxisId::LeftX) => controls.left_stick.state().x_f32(),
+            Channel::Axis(AxisId::LeftY) => controls.left_stick.state().y_f32(),
+            Channel::Axis(AxisId::RightX) => controls.right_stick.state().x_f32(),
+            Channel::Axis(AxisId::RightY) => controls.right_stick.state().y_f32(),
+            Channel::Axis(AxisId::L2) => controls.l2.value_f32(),
+            Channel::Axis(AxisId::R2) => controls.r2.value_f32(),
+            Channel::Gyro(i) => controls.imu.gyro.get(i).copied().unwrap_or(0) as f32,
+            Channel::Accel(i) => controls.imu.accel.get(i).copied().unwrap_or(0) as f32,
+        }
+    }
+
+    /// Short name as accepted by `FromStr`, e.g. `"lx"` or `"gz"`.
+    pub fn name(self) -> &'static str {
+        const GYRO: [&str; 3] = ["gx", "gy", "gz"];
+        const ACCEL: [&str; 3] = ["ax", "ay", "az"];
+        match self {
+            Channel::Axis(AxisId::LeftX) => "lx",
+            Channel::Axis(AxisId::LeftY) => "ly",
+            Channel::Axis(AxisId::RightX) => "rx",
+            Channel::Axis(AxisId::RightY) => "ry",
+            Channel::Axis(AxisId::L2) => "l2",
+            Channel::Axis(AxisId::R2) => "r2",
+            Channel::Gyro(i) => GYRO.get(i).unwrap_or(&"g?"),
+            Channel::Accel(i) => ACCEL.get(i).unwrap_or(&"a?"),
+        }
+    }
+}
+
+impl FromStr for Channel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "lx" => Channel::Axis(AxisId::LeftX),
+            "ly" => Channel::Axis(AxisId::LeftY),
+            "rx" => Channel::Axis(AxisId::RightX),
+            "ry" => Channel::Axis(AxisId::RightY),
+            "l2" => Channel::Axis(AxisId::L2),
+            "r2" => Channel::Axis(AxisId::R2),
+            "gx" => Channel::Gyro(0),
+            "gy" => Channel::Gyro(1),
+            "gz" => Channel::Gyro(2),
+            "ax" => Channel::Accel(0),
+            "ay" => Channel::Accel(1),
+            "az" => Channel::Accel(2),
+            _ => return Err(Error::InvalidFormat("unknown trace channel")),
+        })
+    }
+}
+
+/// Records time series of chosen channels for offline inspection of drift,
+/// jitter and deadzones.
+pub struct Trace {
+    channels: Vec<Channel>,
+    times: Vec<Duration>,
+    values: Vec<Vec<f32>>,
+}
+
+impl Trace {
+    pub fn new(channels: Vec<Channel>) -> Self {
+        let values = vec![Vec::new(); channels.len()];
+        Trace {
+            channels,
+            times: Vec::new(),
+            values,
+        }
+    }
+
+    pub fn channels(&self) -> &[Channel] {
+        &self.channels
+    }
+
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    /// Samples every channel at `at`, e.g. `Controller::device_clock().device_time()`.
+    pub fn record(&mut self, controls: &Controls, at: Duration) {
+        self.times.push(at);
+        for (channel, values) in self.channels.iter().zip(&mut self.values) {
+            values.push(channel.sample(controls));
+        }
+    }
+
+    pub fn values(&self, channel: Channel) -> Option<&[f32]> {
+        let i = self.channels.iter().position(|&c| c == channel)?;
+        Some(&self.values[i])
+    }
+
+    /// One line of block characters per channel over the last `width` samples,
+    /// each scaled to its own range, for printing to a terminal.
+    pub fn sparklines(&self, width: usize) -> String {
+        let mut out = String::new();
+        for (channel, values) in self.channels.iter().zip(&self.values) {
+            let tail = &values[values.len().saturating_sub(width)..];
+            let (min, max) = range(tail);
+            let line: String = tail
+                .iter()
+                .map(|&v| {
+                    let t = if max > min {
+                        (v - min) / (max - min)
+                    } else {
+                        0.5
+                    };
+                    SPARK[((t * 7.0).round() as usize).min(7)]
+                })
+                .collect();
+            let _ = writeln!(
+                out,
+                "{:>2} {} [{:.3}, {:.3}]",
+                channel.name(),
+                line,
+                min,
+                max
+            );
+        }
+        out
+    }
+
+    /// Renders every channel on its own strip of a `width` by `height` SVG, each
+    /// scaled to its own range, with time along the x axis.
+    pub fn to_svg(&self, width: u32, height: u32) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = width,
+            h = height,
+        );
+        let _ = writeln!(out, r#"<rect width="100%" height="100%" fill="white"/>"#);
+
+        let start = self.times.first().copied().unwrap_or_default();
+        let span = self
+            .times
+            .last()
+            .map_or(0.0, |&t| (t - start).as_secs_f32())
+            .max(f32::EPSILON);
+        let strip = height as f32 / self.channels.len().max(1) as f32;
+
+        for (i, (channel, values)) in self.channels.iter().zip(&self.values).enumerate() {
+            let top = i as f32 * strip;
+            let (min, max) = range(values);
+            let scale = if max > min { max - min } else { 1.0 };
+            let color = COLORS[i % COLORS.len()];
+
+            let mut points = String::new();
+            for (&t, &v) in self.times.iter().zip(values) {
+                let x = (t - start).as_secs_f32() / span * width as f32;
+                let y = top + strip - (v - min) / scale * strip;
+                let _ = write!(points, "{:.1},{:.1} ", x, y);
+            }
+            let _ = writeln!(
+                out,
+                r#"<line x1="0" y1="{y:.1}" x2="{w}" y2="{y:.1}" stroke="silver"/>"#,
+                y = top + strip,
+                w = width,
+            );
+            let _ = writeln!(
+                out,
+                r#"<polyline fill="none" stroke="{}" stroke-width="1" points="{}"/>"#,
+                color,
+                points.trim_end(),
+            );
+            let _ = writeln!(
+                out,
+                r#"<text x="4" y="{:.1}" font-family="monospace" font-size="12" fill="{}">{} [{:.3}, {:.3}]</text>"#,
+                top + 14.0,
+                color,
+                channel.name(),
+                min,
+                max,
+            );
+        }
+        out.push_str("</svg>\n");
+        out
+    }
+}
+
+fn range(values: &[f32]) -> (f32, f32) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    values
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &v| {
+            (lo.min(v), hi.max(v))
+        })
+}
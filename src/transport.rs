@@ -0,0 +1,172 @@
+use crate::battery::STATUS_OFFSET;
+use crate::touch::TOUCH_OFFSET;
+use crate::{Error, Result, Snapshot};
+#[cfg(feature = "hid")]
+use hidapi::HidDevice;
+use std::io;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+/// The raw report I/O a `Controller` needs, so it can run over something other
+/// than a real HID device.
+pub trait Transport {
+    /// Blocks until an input report arrives, returning its length.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+    fn write(&mut self, data: &[u8]) -> Result<usize>;
+    /// `buf[0]` holds the report id on entry.
+    fn get_feature_report(&mut self, buf: &mut [u8]) -> Result<usize>;
+}
+
+#[cfg(feature = "hid")]
+impl Transport for HidDevice {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        Ok(HidDevice::read(self, buf)?)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<usize> {
+        Ok(HidDevice::write(self, data)?)
+    }
+
+    fn get_feature_report(&mut self, buf: &mut [u8]) -> Result<usize> {
+        Ok(HidDevice::get_feature_report(self, buf)?)
+    }
+}
+
+/// In-memory transport fed by a [`MockHandle`], for running without hardware.
+pub struct MockTransport {
+    input: Receiver<Vec<u8>>,
+    output: Sender<Vec<u8>>,
+    feature: Receiver<(u8, Vec<u8>)>,
+    features: Vec<(u8, Vec<u8>)>,
+}
+
+/// The pad's side of a [`MockTransport`]: sends input reports and sees output.
+pub struct MockHandle {
+    input: Sender<Vec<u8>>,
+    output: Receiver<Vec<u8>>,
+    feature: Sender<(u8, Vec<u8>)>,
+    counter: u8,
+    timestamp: u16,
+}
+
+impl MockTransport {
+    pub fn new() -> (MockTransport, MockHandle) {
+        let (input_tx, input_rx) = channel();
+        let (output_tx, output_rx) = channel();
+        let (feature_tx, feature_rx) = channel();
+        let transport = MockTransport {
+            input: input_rx,
+            output: output_tx,
+            feature: feature_rx,
+            features: Vec::new(),
+        };
+        let handle = MockHandle {
+            input: input_tx,
+            output: output_rx,
+            feature: feature_tx,
+            counter: 0,
+            timestamp: 0,
+        };
+        (transport, handle)
+    }
+}
+
+impl Transport for MockTransport {
+    /// Fails once the handle has been dropped, like a disconnected device.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let report = self.input.recv().map_err(|_| disconnected())?;
+        let len = report.len().min(buf.len());
+        buf[..len].copy_from_slice(&report[..len]);
+        Ok(len)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<usize> {
+        self.output
+            .send(data.to_vec())
+            .map_err(|_| disconnected())?;
+        Ok(data.len())
+    }
+
+    /// Answers with the last report set through `MockHandle::set_feature_report`,
+    /// or zeros.
+    fn get_feature_report(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.features.extend(self.feature.try_iter());
+        let id = buf.first().copied().unwrap_or(0);
+        buf.iter_mut().skip(1).for_each(|b| *b = 0);
+        if let Some((_, data)) = self.features.iter().rev().find(|(i, _)| *i == id) {
+            let len = data.len().min(buf.len());
+            buf[..len].copy_from_slice(&data[..len]);
+        }
+        Ok(buf.len())
+    }
+}
+
+impl MockHandle {
+    pub fn send_report(&self, report: Vec<u8>) -> Result<()> {
+        self.input.send(report).map_err(|_| disconnected())
+    }
+
+    /// Sends a USB input report carrying `snapshot`, advancing the report counter and
+    /// device clock as if one report interval of `interval_us` had passed.
+    pub fn send_snapshot(&mut self, snapshot: &Snapshot, interval_us: u32) -> Result<()> {
+        let report = encode_input(snapshot, self.counter, self.timestamp);
+        self.counter = (self.counter + 1) & 0x3f;
+        self.timestamp = self
+            .timestamp
+            .wrapping_add((interval_us as f64 * 3.0 / 16.0) as u16);
+        self.send_report(report)
+    }
+
+    /// Sets the reply to feature report `report[0]`.
+    pub fn set_feature_report(&self, report: Vec<u8>) -> Result<()> {
+        let id = report.first().copied().unwrap_or(0);
+        self.feature.send((id, report)).map_err(|_| disconnected())
+    }
+
+    /// Output reports the controller has written since the last call.
+    pub fn written(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        self.output.try_iter()
+    }
+}
+
+/// Builds a 64-byte USB input report for `snapshot`, with no touches and a full,
+/// cable-connected battery.
+pub fn encode_input(snapshot: &Snapshot, counter: u8, timestamp: u16) -> Vec<u8> {
+    let s = snapshot;
+    let bit = |on: bool, mask: u8| if on { mask } else { 0 };
+    let mut report = vec![0u8; 64];
+    report[0] = 0x01;
+    report[1] = s.left_stick.x;
+    report[2] = s.left_stick.y;
+    report[3] = s.right_stick.x;
+    report[4] = s.right_stick.y;
+    report[5] = s.dpad.to_byte()
+        | bit(s.square, 0x10)
+        | bit(s.x, 0x20)
+        | bit(s.circle, 0x40)
+        | bit(s.triangle, 0x80);
+    report[6] = bit(s.l1, 0x01)
+        | bit(s.r1, 0x02)
+        | bit(s.l2, 0x04)
+        | bit(s.r2, 0x08)
+        | bit(s.share, 0x10)
+        | bit(s.options, 0x20)
+        | bit(s.l3, 0x40)
+        | bit(s.r3, 0x80);
+    report[7] = (counter << 2) | bit(s.tpad, 0x02) | bit(s.ps, 0x01);
+    report[8] = s.l2_value;
+    report[9] = s.r2_value;
+    report[10..12].copy_from_slice(&timestamp.to_le_bytes());
+    report[STATUS_OFFSET] = 0x10 | 0x0b;
+    report[TOUCH_OFFSET] = 1;
+    // Both fingers up.
+    report[TOUCH_OFFSET + 2] = 0x80;
+    report[TOUCH_OFFSET + 6] = 0x80;
+    report
+}
+
+fn disconnected() -> Error {
+    Error::Io(io::Error::new(
+        io::ErrorKind::BrokenPipe,
+        "mock controller disconnected",
+    ))
+}
@@ -0,0 +1,125 @@
+use crate::daemon::{Command, DaemonHandle, Status};
+use crate::lightbar::Rgb;
+use ksni::blocking::{Handle, TrayMethods};
+use ksni::menu::{StandardItem, SubMenu};
+use ksni::{MenuItem, ToolTip, Tray};
+
+const COLORS: [(&str, Rgb); 6] = [
+    ("Red", Rgb::new(255, 0, 0)),
+    ("Green", Rgb::new(0, 255, 0)),
+    ("Blue", Rgb::new(0, 0, 255)),
+    ("Purple", Rgb::new(160, 0, 255)),
+    ("White", Rgb::new(255, 255, 255)),
+    ("Off", Rgb::new(0, 0, 0)),
+];
+
+/// A StatusNotifierItem showing the daemon's status, with lightbar and power
+/// actions in its menu. The item reads the status whenever the panel asks, so call
+/// `Handle::update` on daemon events to make it ask.
+pub struct StatusTray {
+    handle: DaemonHandle,
+}
+
+impl StatusTray {
+    pub fn new(handle: DaemonHandle) -> Self {
+        StatusTray { handle }
+    }
+
+    /// Registers the item with the desktop's tray, returning a handle to refresh it.
+    pub fn spawn(self) -> Result<Handle<StatusTray>, ksni::Error> {
+        TrayMethods::spawn(self)
+    }
+}
+
+fn summary(status: &Status) -> String {
+    if !status.connected {
+        return "No controller".to_string();
+    }
+    let name = status.name.as_deref().unwrap_or("Controller");
+    let mut summary = match status.connection {
+        Some(connection) => format!("{} ({})", name, connection),
+        None => name.to_string(),
+    };
+    if let Some(percent) = status.battery_percent {
+        summary += &format!(" – {:.0}%", percent);
+        if status.charging {
+            summary += ", charging";
+        }
+    }
+    summary
+}
+
+impl Tray for StatusTray {
+    fn id(&self) -> String {
+        "ps4hid".to_string()
+    }
+
+    fn title(&self) -> String {
+        summary(&self.handle.status())
+    }
+
+    fn icon_name(&self) -> String {
+        let status = self.handle.status();
+        let icon = match status.battery_percent {
+            _ if !status.connected => "input-gaming-symbolic",
+            Some(p) if p < 10.0 => "battery-caution-symbolic",
+            Some(p) if p < 30.0 => "battery-low-symbolic",
+            _ => "input-gaming",
+        };
+        icon.to_string()
+    }
+
+    fn tool_tip(&self) -> ToolTip {
+        ToolTip {
+            title: summary(&self.handle.status()),
+            ..ToolTip::default()
+        }
+    }
+
+    fn menu(&self) -> Vec<MenuItem<Self>> {
+        let status = self.handle.status();
+        let lightbar = COLORS
+            .iter()
+            .map(|&(label, color)| {
+                StandardItem {
+                    label: label.to_string(),
+                    activate: Box::new(move |tray: &mut Self| {
+                        tray.handle.send(Command::SetLightbar(color))
+                    }),
+                    ..StandardItem::default()
+                }
+                .into()
+            })
+            .collect();
+        vec![
+            StandardItem {
+                label: summary(&status),
+                enabled: false,
+                ..StandardItem::default()
+            }
+            .into(),
+            MenuItem::Separator,
+            SubMenu {
+                label: "Lightbar".to_string(),
+                enabled: status.connected,
+                submenu: lightbar,
+                ..SubMenu::default()
+            }
+            .into(),
+            StandardItem {
+                label: "Power off".to_string(),
+                enabled: status.connection == Some(crate::Connection::Bluetooth),
+                activate: Box::new(|tray: &mut Self| tray.handle.send(Command::PowerOff)),
+                ..StandardItem::default()
+            }
+            .into(),
+            MenuItem::Separator,
+            StandardItem {
+                label: "Quit".to_string(),
+                activate: Box::new(|_| std::process::exit(0)),
+                ..StandardItem::default()
+            }
+            .into(),
+        ]
+    }
+}
@@ -0,0 +1,45 @@
+use crate::Button;
+
+/// Both views of an analog trigger: its travel and the pad's own digital bit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TriggerState {
+    pub value: u8,
+    pub digital: bool,
+}
+
+/// Handlers and streams of a trigger see both the analog and digital state.
+pub type Trigger = Button<TriggerState>;
+
+impl Button<TriggerState> {
+    pub fn value(&self) -> u8 {
+        self.state().value
+    }
+
+    /// The trigger's travel in `0.0..=1.0`.
+    pub fn value_f32(&self) -> f32 {
+        self.value() as f32 / 255.0
+    }
+
+    /// The digital bit the pad reports, which sets at a small amount of travel.
+    pub fn digital(&self) -> bool {
+        self.state().digital
+    }
+
+    pub fn pressed(&self, threshold: u8) -> bool {
+        self.value() >= threshold
+    }
+
+    /// True if the most recent update moved the trigger across `threshold`, either way.
+    pub fn just_crossed(&self, threshold: u8) -> bool {
+        (self.previous().value >= threshold) != self.pressed(threshold)
+    }
+
+    pub fn just_pressed(&self, threshold: u8) -> bool {
+        self.previous().value < threshold && self.pressed(threshold)
+    }
+
+    pub fn just_released(&self, threshold: u8) -> bool {
+        self.previous().value >= threshold && !self.pressed(threshold)
+    }
+}
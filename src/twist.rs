@@ -0,0 +1,127 @@
+use crate::deadman::DeadmanSwitch;
+use crate::Controls;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Vec3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Vec3 {
+    pub const ZERO: Vec3 = Vec3::new(0.0, 0.0, 0.0);
+    pub const ONE: Vec3 = Vec3::new(1.0, 1.0, 1.0);
+
+    pub const fn new(x: f32, y: f32, z: f32) -> Self {
+        Vec3 { x, y, z }
+    }
+
+    fn scale(self, s: Vec3) -> Vec3 {
+        Vec3::new(self.x * s.x, self.y * s.y, self.z * s.z)
+    }
+}
+
+/// Velocity command in the ROS `geometry_msgs/Twist` convention: x forward, y left, z up.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Twist {
+    pub linear: Vec3,
+    pub angular: Vec3,
+}
+
+pub struct TwistConfig {
+    pub linear_scale: Vec3,
+    pub angular_scale: Vec3,
+    pub deadzone: f32,
+    /// When set, a zero twist is produced unless this returns true.
+    pub deadman: Option<DeadmanSwitch>,
+}
+
+impl Default for TwistConfig {
+    fn default() -> Self {
+        TwistConfig {
+            linear_scale: Vec3::ONE,
+            angular_scale: Vec3::ONE,
+            deadzone: 0.05,
+            deadman: Some(Box::new(|controls| controls.l1.state())),
+        }
+    }
+}
+
+impl TwistConfig {
+    /// Maps the left stick to planar motion, the triggers to vertical motion
+    /// (R2 up, L2 down) and the right stick to yaw and pitch.
+    pub fn twist(&self, controls: &Controls) -> Twist {
+        if let Some(deadman) = &self.deadman {
+            if !deadman(controls) {
+                return Twist::default();
+            }
+        }
+
+        let left = controls.left_stick.state();
+        let right = controls.right_stick.state();
+        let lift = controls.r2.value_f32() - controls.l2.value_f32();
+
+        let linear = Vec3::new(
+            self.apply_deadzone(-left.y_f32()),
+            self.apply_deadzone(-left.x_f32()),
+            self.apply_deadzone(lift),
+        );
+        let angular = Vec3::new(
+            0.0,
+            self.apply_deadzone(-right.y_f32()),
+            self.apply_deadzone(-right.x_f32()),
+        );
+
+        Twist {
+            linear: linear.scale(self.linear_scale),
+            angular: angular.scale(self.angular_scale),
+        }
+    }
+
+    /// A deadzone of 1 or more leaves no travel to rescale, so gives 0.
+    fn apply_deadzone(&self, v: f32) -> f32 {
+        if self.deadzone >= 1.0 || self.deadzone.is_nan() || v.abs() < self.deadzone {
+            return 0.0;
+        }
+        let deadzone = self.deadzone.max(0.0);
+        (v.abs() - deadzone) / (1.0 - deadzone) * v.signum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(deadzone: f32) -> TwistConfig {
+        TwistConfig {
+            deadzone,
+            ..TwistConfig::default()
+        }
+    }
+
+    #[test]
+    fn deadzone_rescales_the_remaining_travel() {
+        let config = config(0.2);
+        assert_eq!(config.apply_deadzone(0.1), 0.0);
+        assert_eq!(config.apply_deadzone(1.0), 1.0);
+        assert!((config.apply_deadzone(-0.6) + 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn whole_range_deadzone_is_zero_not_nan() {
+        for deadzone in [1.0, 2.0, f32::INFINITY, f32::NAN] {
+            let config = config(deadzone);
+            assert_eq!(config.apply_deadzone(1.0), 0.0);
+            assert_eq!(config.apply_deadzone(-1.0), 0.0);
+        }
+    }
+
+    #[test]
+    fn no_twist_without_the_deadman() {
+        let mut controls = Controls::new();
+        controls.left_stick.update(crate::Stick::new(128, 0));
+        assert_eq!(config(0.05).twist(&controls), Twist::default());
+        controls.l1.update(true);
+        assert!(config(0.05).twist(&controls).linear.x > 0.9);
+    }
+}
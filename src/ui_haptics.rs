@@ -0,0 +1,147 @@
+use crate::lightbar::{LightbarLayer, LightbarStack, Rgb};
+use crate::rumble::{MixPolicy, RumbleEffect, RumbleId, RumbleMixer};
+use crate::{Controller, Result};
+use std::time::{Duration, Instant};
+
+/// Something that happened in an app's menus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UiEvent {
+    /// Focus moved to another item.
+    FocusMoved,
+    /// An item was activated.
+    Confirm,
+    /// An action was refused, e.g. a disabled item or an invalid entry.
+    Error,
+}
+
+/// The feedback for one `UiEvent`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cue {
+    pub rumble: Option<RumbleEffect>,
+    /// A color shown on the lightbar's app layer for a while.
+    pub flash: Option<(Rgb, Duration)>,
+}
+
+/// The cue played for each event. The defaults are short enough not to blur
+/// together while scrolling through a list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UiCues {
+    pub focus_moved: Cue,
+    pub confirm: Cue,
+    pub error: Cue,
+}
+
+impl Default for UiCues {
+    fn default() -> Self {
+        UiCues {
+            // A light tick on the weak motor only.
+            focus_moved: Cue {
+                rumble: Some(
+                    RumbleEffect::new(0, 70)
+                        .priority(1)
+                        .lasting(Duration::from_millis(30)),
+                ),
+                flash: None,
+            },
+            confirm: Cue {
+                rumble: Some(
+                    RumbleEffect::new(90, 160)
+                        .priority(2)
+                        .lasting(Duration::from_millis(70)),
+                ),
+                flash: Some((Rgb::new(255, 255, 255), Duration::from_millis(80))),
+            },
+            // A longer thud on the strong motor, so it can't be mistaken for a
+            // confirm.
+            error: Cue {
+                rumble: Some(
+                    RumbleEffect::new(220, 60)
+                        .priority(3)
+                        .lasting(Duration::from_millis(180)),
+                ),
+                flash: Some((Rgb::new(255, 0, 0), Duration::from_millis(250))),
+            },
+        }
+    }
+}
+
+impl UiCues {
+    pub fn cue(&self, event: UiEvent) -> Cue {
+        match event {
+            UiEvent::FocusMoved => self.focus_moved,
+            UiEvent::Confirm => self.confirm,
+            UiEvent::Error => self.error,
+        }
+    }
+}
+
+/// Plays `UiCues` for an app's UI events: call `event` when one happens and
+/// `update` every frame.
+///
+/// The mixer and lightbar stack are public so the app can play its own effects
+/// through them too. Flashes use the app layer, replacing whatever was there,
+/// and clear it when they end. Set `lightbar.base` to the pad's usual color, as
+/// the stack shows it whenever no layer is set.
+pub struct UiHaptics {
+    pub cues: UiCues,
+    pub mixer: RumbleMixer,
+    pub lightbar: LightbarStack,
+    /// The latest focus tick, stopped when focus moves again so fast scrolling
+    /// doesn't pile ticks up into a continuous buzz.
+    focus: Option<RumbleId>,
+    flash_ends: Option<Instant>,
+}
+
+impl Default for UiHaptics {
+    fn default() -> Self {
+        UiHaptics::new(UiCues::default())
+    }
+}
+
+impl UiHaptics {
+    pub fn new(cues: UiCues) -> Self {
+        UiHaptics {
+            cues,
+            mixer: RumbleMixer::new(MixPolicy::Preempt),
+            lightbar: LightbarStack::new(),
+            focus: None,
+            flash_ends: None,
+        }
+    }
+
+    pub fn event(&mut self, event: UiEvent) {
+        self.event_at(event, Instant::now());
+    }
+
+    /// Starts the cue for `event` as of `at`.
+    pub fn event_at(&mut self, event: UiEvent, at: Instant) {
+        let cue = self.cues.cue(event);
+        if let Some(effect) = cue.rumble {
+            if event == UiEvent::FocusMoved {
+                if let Some(id) = self.focus.take() {
+                    self.mixer.stop(id);
+                }
+            }
+            let id = self.mixer.play_at(effect, at);
+            if event == UiEvent::FocusMoved {
+                self.focus = Some(id);
+            }
+        }
+        if let Some((color, duration)) = cue.flash {
+            self.lightbar.set_color(LightbarLayer::App, color);
+            self.flash_ends = Some(at + duration);
+        }
+    }
+
+    /// Ends finished cues and writes any change in rumble or lightbar to the
+    /// controller.
+    pub fn update(&mut self, controller: &mut Controller, at: Instant) -> Result<()> {
+        if self.flash_ends.is_some_and(|ends| at >= ends) {
+            self.lightbar.clear(LightbarLayer::App);
+            self.flash_ends = None;
+        }
+        self.mixer.update(controller, at)?;
+        self.lightbar.update(controller, at)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{Result, Snapshot};
+
+/// A virtual gamepad that the OS presents to other applications.
+pub trait VirtualPad {
+    fn emit(&mut self, snapshot: &Snapshot) -> Result<()>;
+}
+
+#[cfg(all(feature = "uinput", target_os = "linux"))]
+pub use uinput::UinputPad;
+
+#[cfg(all(feature = "uinput", target_os = "linux"))]
+mod uinput {
+    use super::VirtualPad;
+    use crate::{DPad, Error, Result, Snapshot};
+    use std::io;
+    use std::mem;
+
+    const UI_DEV_CREATE: libc::c_ulong = 0x5501;
+    const UI_DEV_DESTROY: libc::c_ulong = 0x5502;
+    const UI_SET_EVBIT: libc::c_ulong = 0x4004_5564;
+    const UI_SET_KEYBIT: libc::c_ulong = 0x4004_5565;
+    const UI_SET_ABSBIT: libc::c_ulong = 0x4004_5567;
+
+    const EV_SYN: u16 = 0x00;
+    const EV_KEY: u16 = 0x01;
+    const EV_ABS: u16 = 0x03;
+    const SYN_REPORT: u16 = 0;
+    const BUS_VIRTUAL: u16 = 0x06;
+
+    const BTN_SOUTH: u16 = 0x130;
+    const BTN_EAST: u16 = 0x131;
+    const BTN_NORTH: u16 = 0x133;
+    const BTN_WEST: u16 = 0x134;
+    const BTN_TL: u16 = 0x136;
+    const BTN_TR: u16 = 0x137;
+    const BTN_TL2: u16 = 0x138;
+    const BTN_TR2: u16 = 0x139;
+    const BTN_SELECT: u16 = 0x13a;
+    const BTN_START: u16 = 0x13b;
+    const BTN_MODE: u16 = 0x13c;
+    const BTN_THUMBL: u16 = 0x13d;
+    const BTN_THUMBR: u16 = 0x13e;
+    const BTN_TOUCH: u16 = 0x14a;
+
+    const ABS_X: u16 = 0x00;
+    const ABS_Y: u16 = 0x01;
+    const ABS_Z: u16 = 0x02;
+    const ABS_RX: u16 = 0x03;
+    const ABS_RY: u16 = 0x04;
+    const ABS_RZ: u16 = 0x05;
+    const ABS_HAT0X: u16 = 0x10;
+    const ABS_HAT0Y: u16 = 0x11;
+
+    const KEYS: [u16; 14] = [
+        BTN_SOUTH, BTN_EAST, BTN_NORTH, BTN_WEST, BTN_TL, BTN_TR, BTN_TL2, BTN_TR2, BTN_SELECT,
+        BTN_START, BTN_MODE, BTN_THUMBL, BTN_THUMBR, BTN_TOUCH,
+    ];
+    const AXES: [u16; 6] = [ABS_X, ABS_Y, ABS_Z, ABS_RX, ABS_RY, ABS_RZ];
+
+    /// A virtual pad created through `/dev/uinput`. Needs write access to it,
+    /// typically via a udev rule or the `input` group.
+    pub struct UinputPad {
+        fd: libc::c_int,
+    }
+
+    impl UinputPad {
+        pub fn create(name: &str) -> Result<Self> {
+            // SAFETY: the path is a valid NUL-terminated string.
+            let fd =
+                unsafe { libc::open(c"/dev/uinput".as_ptr(), libc::O_WRONLY | libc::O_NONBLOCK) };
+            if fd < 0 {
+                return Err(Error::Io(io::Error::last_os_error()));
+            }
+            let pad = UinputPad { fd };
+
+            pad.ioctl(UI_SET_EVBIT, EV_KEY)?;
+            pad.ioctl(UI_SET_EVBIT, EV_ABS)?;
+            for key in KEYS {
+                pad.ioctl(UI_SET_KEYBIT, key)?;
+            }
+
+            // SAFETY: uinput_user_dev is plain old data; all-zeroes is a valid value.
+            let mut dev: libc::uinput_user_dev = unsafe { mem::zeroed() };
+            for (dst, src) in dev.name.iter_mut().zip(name.bytes().take(79)) {
+                *dst = src as libc::c_char;
+            }
+            dev.id.bustype = BUS_VIRTUAL;
+            dev.id.vendor = crate::VENDOR_ID;
+            dev.id.product = crate::PRODUCT_ID;
+            for axis in AXES {
+                pad.ioctl(UI_SET_ABSBIT, axis)?;
+                dev.absmax[axis as usize] = 255;
+            }
+            for hat in [ABS_HAT0X, ABS_HAT0Y] {
+                pad.ioctl(UI_SET_ABSBIT, hat)?;
+                dev.absmin[hat as usize] = -1;
+                dev.absmax[hat as usize] = 1;
+            }
+
+            // SAFETY: `dev` is a live, fully initialized uinput_user_dev.
+            let written = unsafe {
+                libc::write(
+                    pad.fd,
+                    &dev as *const _ as *const libc::c_void,
+                    mem::size_of::<libc::uinput_user_dev>(),
+                )
+            };
+            if written < 0 {
+                return Err(Error::Io(io::Error::last_os_error()));
+            }
+            // SAFETY: UI_DEV_CREATE takes no argument.
+            if unsafe { libc::ioctl(pad.fd, UI_DEV_CREATE) } < 0 {
+                return Err(Error::Io(io::Error::last_os_error()));
+            }
+
+            Ok(pad)
+        }
+
+        fn ioctl(&self, request: libc::c_ulong, value: u16) -> Result<()> {
+            // SAFETY: the UI_SET_*BIT requests take an int by value.
+            if unsafe { libc::ioctl(self.fd, request, value as libc::c_int) } < 0 {
+                return Err(Error::Io(io::Error::last_os_error()));
+            }
+            Ok(())
+        }
+
+        fn write_event(&self, type_: u16, code: u16, value: i32) -> Result<()> {
+            // SAFETY: input_event is plain old data; the kernel fills in the time.
+            let mut event: libc::input_event = unsafe { mem::zeroed() };
+            event.type_ = type_;
+            event.code = code;
+            event.value = value;
+            // SAFETY: `event` is a live input_event.
+            let written = unsafe {
+                libc::write(
+                    self.fd,
+                    &event as *const _ as *const libc::c_void,
+                    mem::size_of::<libc::input_event>(),
+                )
+            };
+            if written < 0 {
+                return Err(Error::Io(io::Error::last_os_error()));
+            }
+            Ok(())
+        }
+    }
+
+    impl VirtualPad for UinputPad {
+        fn emit(&mut self, s: &Snapshot) -> Result<()> {
+            let keys = [
+                (BTN_SOUTH, s.x),
+                (BTN_EAST, s.circle),
+                (BTN_NORTH, s.triangle),
+                (BTN_WEST, s.square),
+                (BTN_TL, s.l1),
+                (BTN_TR, s.r1),
+                (BTN_TL2, s.l2),
+                (BTN_TR2, s.r2),
+                (BTN_SELECT, s.share),
+                (BTN_START, s.options),
+                (BTN_MODE, s.ps),
+                (BTN_THUMBL, s.l3),
+                (BTN_THUMBR, s.r3),
+                (BTN_TOUCH, s.tpad),
+            ];
+            for (code, pressed) in keys {
+                self.write_event(EV_KEY, code, pressed as i32)?;
+            }
+
+            let axes = [
+                (ABS_X, s.left_stick.x),
+                (ABS_Y, s.left_stick.y),
+                (ABS_RX, s.right_stick.x),
+                (ABS_RY, s.right_stick.y),
+                (ABS_Z, s.l2_value),
+                (ABS_RZ, s.r2_value),
+            ];
+            for (code, value) in axes {
+                self.write_event(EV_ABS, code, value as i32)?;
+            }
+
+            let (hat_x, hat_y) = match s.dpad {
+                DPad::Released => (0, 0),
+                DPad::North => (0, -1),
+                DPad::NorthEast => (1, -1),
+                DPad::East => (1, 0),
+                DPad::SouthEast => (1, 1),
+                DPad::South => (0, 1),
+                DPad::SouthWest => (-1, 1),
+                DPad::West => (-1, 0),
+                DPad::NorthWest => (-1, -1),
+            };
+            self.write_event(EV_ABS, ABS_HAT0X, hat_x)?;
+            self.write_event(EV_ABS, ABS_HAT0Y, hat_y)?;
+
+            self.write_event(EV_SYN, SYN_REPORT, 0)
+        }
+    }
+
+    impl Drop for UinputPad {
+        fn drop(&mut self) {
+            // SAFETY: `fd` is owned by this pad and closed exactly once.
+            unsafe {
+                libc::ioctl(self.fd, UI_DEV_DESTROY);
+                libc::close(self.fd);
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::{Duration, Instant};
+
+/// How to keep the button press that woke a Bluetooth pad away from the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WakeGuard {
+    /// Deliver everything.
+    #[default]
+    Off,
+    /// Hold every button, stick and trigger at rest for this long after the
+    /// controller is opened or resumes from sleep.
+    Mute(Duration),
+    /// Hide the PS button after opening or resuming until it has been seen
+    /// released once, so only the waking press is dropped.
+    WakePress,
+}
+
+/// Applies a `WakeGuard` to USB-aligned report data before it's parsed.
+#[derive(Debug, Clone)]
+pub(crate) struct WakeFilter {
+    pub(crate) guard: WakeGuard,
+    /// Set until the first report after (re)connecting, which starts the guard.
+    pending: bool,
+    until: Option<Instant>,
+    ps_blocked: bool,
+}
+
+impl WakeFilter {
+    pub(crate) fn new(guard: WakeGuard) -> Self {
+        WakeFilter {
+            guard,
+            pending: true,
+            until: None,
+            ps_blocked: false,
+        }
+    }
+
+    /// Restarts the guard from the next report, e.g. after `Event::Resumed`.
+    pub(crate) fn arm(&mut self) {
+        self.pending = true;
+    }
+
+    pub(crate) fn apply(&mut self, data: &mut [u8], now: Instant) {
+        if std::mem::take(&mut self.pending) {
+            self.ps_blocked = true;
+            self.until = match self.guard {
+                WakeGuard::Mute(duration) => Some(now + duration),
+                _ => None,
+            };
+        }
+        match self.guard {
+            WakeGuard::Off => {}
+            WakeGuard::Mute(_) => {
+                if self.until.is_some_and(|until| now < until) {
+                    neutralise(data);
+                }
+            }
+            WakeGuard::WakePress => {
+                if let Some(b) = data.get_mut(7).filter(|_| self.ps_blocked) {
+                    if *b & 0x01 != 0 {
+                        *b &= !0x01;
+                    } else {
+                        self.ps_blocked = false;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Centers the sticks and releases every button, the dpad and both triggers,
+/// leaving the report counter alone.
+fn neutralise(data: &mut [u8]) {
+    if data.len() < 10 {
+        return;
+    }
+    data[1..5].fill(0x80);
+    // Dpad released, no face buttons.
+    data[5] = 0x08;
+    data[6] = 0;
+    data[7] &= 0xfc;
+    data[8] = 0;
+    data[9] = 0;
+}
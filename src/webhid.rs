@@ -0,0 +1,168 @@
+use crate::transport::Transport;
+use crate::{Connection, ConnectionInfo, Controller, Error, Result};
+use crate::{PRODUCT_ID, PRODUCT_ID_DONGLE, PRODUCT_ID_V1, VENDOR_ID};
+use js_sys::{DataView, Uint8Array};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{HidDevice, HidDeviceFilter, HidDeviceRequestOptions, HidInputReportEvent};
+
+/// Feature reports fetched when the device is opened, since WebHID can only read
+/// them asynchronously: pairing, and the calibration read that switches a
+/// Bluetooth pad to full reports.
+const PREFETCHED_FEATURES: [u8; 2] = [0x12, 0x05];
+const MAX_QUEUED_REPORTS: usize = 64;
+
+/// A DS4 opened through the browser's WebHID API.
+///
+/// Input reports are queued by the `inputreport` event and handed out by `read`,
+/// which returns 0 when none is pending; call `Controller::update` from a
+/// `requestAnimationFrame` or timer callback. Writes are fire-and-forget.
+pub struct WebHidTransport {
+    device: HidDevice,
+    reports: Rc<RefCell<VecDeque<Vec<u8>>>>,
+    features: Vec<(u8, Vec<u8>)>,
+    _listener: Closure<dyn FnMut(HidInputReportEvent)>,
+}
+
+// SAFETY: without the `atomics` target feature wasm32 has a single thread, so the
+// JS handles inside can never actually be shared or moved across threads.
+#[cfg(not(target_feature = "atomics"))]
+unsafe impl Send for WebHidTransport {}
+
+impl WebHidTransport {
+    /// Shows the browser's device picker filtered to DS4s. Must be called from a
+    /// user gesture such as a click handler.
+    pub async fn request() -> Result<WebHidTransport> {
+        let window = web_sys::window().ok_or(Error::NotFound)?;
+        let filters: Vec<HidDeviceFilter> = [PRODUCT_ID, PRODUCT_ID_V1, PRODUCT_ID_DONGLE]
+            .into_iter()
+            .map(|pid| {
+                let filter = HidDeviceFilter::new();
+                filter.set_vendor_id(VENDOR_ID as u32);
+                filter.set_product_id(pid);
+                filter
+            })
+            .collect();
+        let options = HidDeviceRequestOptions::new(&filters);
+        let devices = JsFuture::from(window.navigator().hid().request_device(&options))
+            .await
+            .map_err(js_error)?;
+        let device = js_sys::Array::from(&devices)
+            .get(0)
+            .dyn_into::<HidDevice>()
+            .map_err(|_| Error::NotFound)?;
+        WebHidTransport::open(device).await
+    }
+
+    pub async fn open(device: HidDevice) -> Result<WebHidTransport> {
+        if !device.opened() {
+            JsFuture::from(device.open()).await.map_err(js_error)?;
+        }
+
+        let reports = Rc::new(RefCell::new(VecDeque::new()));
+        let queue = reports.clone();
+        let listener = Closure::new(move |event: HidInputReportEvent| {
+            let mut report = vec![event.report_id()];
+            report.extend(data_view_bytes(&event.data()));
+            let mut queue = queue.borrow_mut();
+            if queue.len() == MAX_QUEUED_REPORTS {
+                queue.pop_front();
+            }
+            queue.push_back(report);
+        });
+        device.set_oninputreport(Some(listener.as_ref().unchecked_ref()));
+
+        let mut features = Vec::new();
+        for id in PREFETCHED_FEATURES {
+            if let Ok(view) = JsFuture::from(device.receive_feature_report(id)).await {
+                let mut report = vec![id];
+                report.extend(data_view_bytes(&view));
+                features.push((id, report));
+            }
+        }
+
+        Ok(WebHidTransport {
+            device,
+            reports,
+            features,
+            _listener: listener,
+        })
+    }
+
+    /// A controller over this transport, with its saved profile restored.
+    pub fn into_controller(self) -> Result<Controller> {
+        let info = ConnectionInfo {
+            connection: match self.device.product_id() {
+                PRODUCT_ID_DONGLE => Connection::Dongle,
+                // WebHID doesn't expose the bus; `Controller` switches to Bluetooth
+                // on the first 0x11 report.
+                _ => Connection::Usb,
+            },
+            vendor_id: self.device.vendor_id(),
+            product_id: self.device.product_id(),
+            ..ConnectionInfo::default()
+        };
+        let mut controller = Controller::with_transport(self, info);
+        controller.restore_profile()?;
+        Ok(controller)
+    }
+}
+
+impl Transport for WebHidTransport {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let Some(report) = self.reports.borrow_mut().pop_front() else {
+            return Ok(0);
+        };
+        let len = report.len().min(buf.len());
+        buf[..len].copy_from_slice(&report[..len]);
+        Ok(len)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<usize> {
+        let (&id, payload) = data
+            .split_first()
+            .ok_or(Error::MalformedReport("empty output report"))?;
+        let payload = Uint8Array::from(payload);
+        let _ = self
+            .device
+            .send_report_with_u8_array(id, &payload)
+            .map_err(js_error)?;
+        Ok(data.len())
+    }
+
+    /// Answers from the reports fetched at `open`. Asking for the calibration report
+    /// also re-sends the request, redoing the handshake after the pad wakes.
+    fn get_feature_report(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let id = buf.first().copied().unwrap_or(0);
+        if id == 0x05 {
+            let _ = self.device.receive_feature_report(id);
+        }
+        let (_, report) = self
+            .features
+            .iter()
+            .find(|(i, _)| *i == id)
+            .ok_or(Error::MalformedReport("feature report not prefetched"))?;
+        let len = report.len().min(buf.len());
+        buf[..len].copy_from_slice(&report[..len]);
+        Ok(len)
+    }
+}
+
+fn data_view_bytes(view: &DataView) -> Vec<u8> {
+    Uint8Array::new_with_byte_offset_and_length(
+        &view.buffer(),
+        view.byte_offset() as u32,
+        view.byte_length() as u32,
+    )
+    .to_vec()
+}
+
+fn js_error(e: JsValue) -> Error {
+    Error::Io(std::io::Error::other(
+        e.as_string().unwrap_or_else(|| format!("{:?}", e)),
+    ))
+}
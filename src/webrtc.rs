@@ -0,0 +1,648 @@
+use crate::source::{EventQueue, InputEvent, InputSource};
+use crate::{wire, Result, Snapshot};
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{
+    IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket,
+};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use str0m::change::SdpOffer;
+use str0m::channel::ChannelId;
+use str0m::net::{Protocol, Receive};
+use str0m::{Candidate, Event, IceConnectionState, Input, Output, Rtc};
+
+/// The page `SignalingServer` serves: it streams the browser's gamepad to the
+/// host and shows the state the host sends back.
+pub const PAGE: &str = include_str!("webrtc.html");
+
+const MAX_DATAGRAM: usize = 2000;
+/// Browsers cap SDP well below this.
+const MAX_OFFER: usize = 64 * 1024;
+const STUN_TRIES: u32 = 3;
+const STUN_TIMEOUT: Duration = Duration::from_millis(500);
+const STUN_COOKIE: u32 = 0x2112_a442;
+/// A signaling client that hasn't sent its request, or read the response, by
+/// then is dropped.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longer request and header lines are refused.
+const MAX_LINE: u64 = 1024;
+/// Requests with more headers are refused.
+const MAX_HEADERS: usize = 32;
+/// Signaling connections served at once; more are closed straight away.
+const MAX_CLIENTS: usize = 16;
+/// How long `WebRtcSource` waits for a message before reporting no change.
+const SOURCE_WAIT: Duration = Duration::from_millis(4);
+
+fn other(e: impl std::fmt::Display) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+/// Where the native end listens for the browser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerConfig {
+    /// The address offered to the browser as a host candidate; it must be one
+    /// the browser can route to, not `0.0.0.0`.
+    pub host: IpAddr,
+    /// A STUN server to learn this host's public address from, so a browser
+    /// outside the NAT can reach it without a forwarded port.
+    pub stun: Option<SocketAddr>,
+}
+
+impl PeerConfig {
+    pub fn new(host: IpAddr) -> Self {
+        PeerConfig { host, stun: None }
+    }
+
+    pub fn stun(mut self, server: SocketAddr) -> Self {
+        self.stun = Some(server);
+        self
+    }
+}
+
+/// The address of the interface that routes to the internet, a sensible
+/// `PeerConfig::host` for most machines. Sends nothing.
+pub fn local_ip() -> io::Result<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.connect("8.8.8.8:80")?;
+    Ok(socket.local_addr()?.ip())
+}
+
+/// Asks a STUN server for the address `socket` appears as from outside.
+fn reflexive_address(socket: &UdpSocket, server: SocketAddr) -> io::Result<SocketAddr> {
+    // Each `RandomState` is keyed differently, which is random enough to tell
+    // our transaction from stray responses.
+    let mut id = [0u8; 12];
+    for chunk in id.chunks_mut(4) {
+        let bits = RandomState::new().build_hasher().finish();
+        chunk.copy_from_slice(&bits.to_le_bytes()[..4]);
+    }
+    // Binding request, no attributes.
+    let mut request = vec![0x00, 0x01, 0x00, 0x00];
+    request.extend_from_slice(&STUN_COOKIE.to_be_bytes());
+    request.extend_from_slice(&id);
+
+    socket.set_read_timeout(Some(STUN_TIMEOUT))?;
+    let mut buf = [0u8; 512];
+    for _ in 0..STUN_TRIES {
+        socket.send_to(&request, server)?;
+        let n = match socket.recv_from(&mut buf) {
+            Ok((n, from)) if from == server => n,
+            Ok(_) => continue,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+            Err(e) => return Err(e),
+        };
+        // A binding success response for our transaction.
+        if n < 20 || buf[..2] != [0x01, 0x01] || buf[8..20] != id {
+            continue;
+        }
+        if let Some(addr) = mapped_address(&buf[20..n], &id) {
+            return Ok(addr);
+        }
+    }
+    Err(io::Error::new(
+        ErrorKind::TimedOut,
+        "no usable answer from the STUN server",
+    ))
+}
+
+/// The XOR-MAPPED-ADDRESS, or failing that MAPPED-ADDRESS, in a STUN response's
+/// attributes.
+fn mapped_address(mut attrs: &[u8], id: &[u8; 12]) -> Option<SocketAddr> {
+    let mut mapped = None;
+    while attrs.len() >= 4 {
+        let kind = u16::from_be_bytes([attrs[0], attrs[1]]);
+        let len = u16::from_be_bytes([attrs[2], attrs[3]]) as usize;
+        let value = attrs.get(4..4 + len)?;
+        let xor = kind == 0x0020;
+        if (xor || kind == 0x0001) && value.len() >= 8 {
+            let mut port = u16::from_be_bytes([value[2], value[3]]);
+            let mut ip = value[4..].to_vec();
+            if xor {
+                port ^= (STUN_COOKIE >> 16) as u16;
+                let mut key = STUN_COOKIE.to_be_bytes().to_vec();
+                key.extend_from_slice(id);
+                ip.iter_mut().zip(key).for_each(|(b, k)| *b ^= k);
+            }
+            let ip = match (value[1], ip.len()) {
+                (0x01, 4) => IpAddr::from(<[u8; 4]>::try_from(ip).ok()?),
+                (0x02, 16) => IpAddr::from(<[u8; 16]>::try_from(ip).ok()?),
+                _ => return None,
+            };
+            let addr = SocketAddr::new(ip, port);
+            if xor {
+                return Some(addr);
+            }
+            mapped = Some(addr);
+        }
+        // Attributes are padded to 4 bytes.
+        attrs = attrs.get(4 + len.div_ceil(4) * 4..).unwrap_or(&[]);
+    }
+    mapped
+}
+
+/// The native end of a WebRTC data channel opened by a browser.
+///
+/// The browser makes the offer, usually through `SignalingServer`; anything that
+/// can carry the SDP either way, e.g. a chat message or a QR code, works too.
+/// There's no background thread: `poll` drives the connection, so call it
+/// regularly even when only sending.
+pub struct DataChannelPeer {
+    rtc: Rtc,
+    socket: UdpSocket,
+    local: SocketAddr,
+    channel: Option<ChannelId>,
+    received: VecDeque<Vec<u8>>,
+    closed: bool,
+    buf: Vec<u8>,
+}
+
+impl DataChannelPeer {
+    /// Accepts a browser's SDP offer, returning the peer and the SDP answer to
+    /// send back.
+    pub fn answer(offer: &str, config: &PeerConfig) -> io::Result<(DataChannelPeer, String)> {
+        let offer = SdpOffer::from_sdp_string(offer).map_err(other)?;
+        let socket = UdpSocket::bind(SocketAddr::new(config.host, 0))?;
+        let local = socket.local_addr()?;
+        let mut rtc = Rtc::builder()
+            .set_crypto_provider(Arc::new(str0m::crypto::from_feature_flags()))
+            .build(Instant::now());
+        rtc.add_local_candidate(Candidate::host(local, "udp").map_err(other)?);
+        if let Some(server) = config.stun {
+            let public = reflexive_address(&socket, server)?;
+            if public != local {
+                let candidate = Candidate::server_reflexive(public, local, "udp").map_err(other)?;
+                rtc.add_local_candidate(candidate);
+            }
+        }
+        let answer = rtc.sdp_api().accept_offer(offer).map_err(other)?;
+        let peer = DataChannelPeer {
+            rtc,
+            socket,
+            local,
+            channel: None,
+            received: VecDeque::new(),
+            closed: false,
+            buf: vec![0; MAX_DATAGRAM],
+        };
+        Ok((peer, answer.to_sdp_string()))
+    }
+
+    /// Whether the browser's data channel is open, so `send` will deliver.
+    pub fn is_open(&self) -> bool {
+        self.channel.is_some()
+    }
+
+    /// Whether the connection has ended; a closed peer never reopens.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Sends a message, returning `false` if the channel isn't open yet or its
+    /// buffer is full.
+    pub fn send(&mut self, data: &[u8]) -> io::Result<bool> {
+        let Some(mut channel) = self.channel.and_then(|id| self.rtc.channel(id)) else {
+            return Ok(false);
+        };
+        let sent = channel.write(true, data).map_err(other)?;
+        self.transmit()?;
+        Ok(sent)
+    }
+
+    /// Drives the connection for up to `timeout`, returning the next message
+    /// from the browser as soon as one arrives. A zero timeout handles whatever
+    /// has already arrived without blocking.
+    pub fn poll(&mut self, timeout: Duration) -> io::Result<Option<Vec<u8>>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let next = self.transmit()?;
+            if let Some(message) = self.received.pop_front() {
+                return Ok(Some(message));
+            }
+            if self.closed {
+                return Ok(None);
+            }
+            let now = Instant::now();
+            if next <= now {
+                self.rtc.handle_input(Input::Timeout(now)).map_err(other)?;
+                continue;
+            }
+            let wait = next.min(deadline).saturating_duration_since(now);
+            match self.recv(wait)? {
+                Some((n, source)) => {
+                    // Anything that isn't STUN, DTLS or SRTP is stray traffic.
+                    if let Ok(contents) = self.buf[..n].try_into() {
+                        let receive = Receive {
+                            proto: Protocol::Udp,
+                            source,
+                            destination: self.local,
+                            contents,
+                        };
+                        let input = Input::Receive(Instant::now(), receive);
+                        self.rtc.handle_input(input).map_err(other)?;
+                    }
+                }
+                None if Instant::now() >= deadline => return Ok(None),
+                None => {}
+            }
+        }
+    }
+
+    /// Sends the state as a `wire` message.
+    pub fn send_snapshot(&mut self, snapshot: &Snapshot) -> io::Result<bool> {
+        self.send(&wire::encode(snapshot))
+    }
+
+    /// Like `poll`, but returns the next valid `wire` message's state and drops
+    /// anything else.
+    pub fn poll_snapshot(&mut self, timeout: Duration) -> io::Result<Option<Snapshot>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let wait = deadline.saturating_duration_since(Instant::now());
+            match self.poll(wait)? {
+                Some(message) => {
+                    if let Ok(snapshot) = wire::decode(&message) {
+                        return Ok(Some(snapshot));
+                    }
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Ends the connection.
+    pub fn close(&mut self) -> io::Result<()> {
+        self.rtc.disconnect();
+        self.closed = true;
+        self.channel = None;
+        self.transmit()?;
+        Ok(())
+    }
+
+    /// Sends everything str0m has queued and handles its events, returning when
+    /// it next wants to be woken.
+    fn transmit(&mut self) -> io::Result<Instant> {
+        loop {
+            match self.rtc.poll_output().map_err(other)? {
+                Output::Timeout(at) => return Ok(at),
+                Output::Transmit(t) => {
+                    // A lost datagram is for ICE and SCTP to retry.
+                    let _ = self.socket.send_to(&t.contents, t.destination);
+                }
+                Output::Event(Event::ChannelOpen(id, _)) => self.channel = Some(id),
+                Output::Event(Event::ChannelData(data)) => self.received.push_back(data.data),
+                Output::Event(Event::ChannelClose(_))
+                | Output::Event(Event::IceConnectionStateChange(
+                    IceConnectionState::Disconnected,
+                )) => {
+                    self.channel = None;
+                    self.closed = true;
+                }
+                Output::Event(_) => {}
+            }
+            if !self.rtc.is_alive() {
+                self.channel = None;
+                self.closed = true;
+                return Ok(Instant::now());
+            }
+        }
+    }
+
+    /// Waits up to `wait` for a datagram; a zero wait only takes one that has
+    /// already arrived.
+    fn recv(&mut self, wait: Duration) -> io::Result<Option<(usize, SocketAddr)>> {
+        let result = if wait.is_zero() {
+            self.socket.set_nonblocking(true)?;
+            let result = self.socket.recv_from(&mut self.buf);
+            self.socket.set_nonblocking(false)?;
+            result
+        } else {
+            self.socket.set_read_timeout(Some(wait))?;
+            self.socket.recv_from(&mut self.buf)
+        };
+        match result {
+            Ok(received) => Ok(Some(received)),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// A minimal HTTP endpoint for the offer/answer exchange: `GET /` serves `PAGE`
+/// and `POST /offer` takes the browser's SDP offer and answers it.
+///
+/// Browsers allow WebRTC from plain `http://` pages, so this works from a phone
+/// on the same network with no certificates.
+///
+/// **This is for a trusted LAN only.** It speaks plain HTTP, so anyone on the
+/// network path can read the token and take over the session. Both requests
+/// must carry `?token=` with `token()`; `url()` builds the page link with it,
+/// and the page passes it on to `/offer`. Don't forward its port to the
+/// internet.
+pub struct SignalingServer {
+    addr: SocketAddr,
+    token: Arc<str>,
+    peers: Receiver<DataChannelPeer>,
+    stop: Arc<AtomicBool>,
+}
+
+impl SignalingServer {
+    /// Listens on `addr`, serving each client on a thread of its own so a slow
+    /// one can't hold up the rest.
+    pub fn bind(addr: impl ToSocketAddrs, config: PeerConfig) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let token: Arc<str> = new_token().into();
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, peers) = mpsc::channel();
+        let server = Signaling {
+            config,
+            token: token.clone(),
+            clients: AtomicUsize::new(0),
+        };
+        let stopped = stop.clone();
+        thread::Builder::new()
+            .name("ds4-signaling".into())
+            .spawn(move || server.run(listener, sender, &stopped))?;
+        Ok(SignalingServer {
+            addr,
+            token,
+            peers,
+            stop,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.addr)
+    }
+
+    /// The secret every request must carry as `?token=`, fresh for each server.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// The page's address on `host`, token included.
+    pub fn url(&self, host: IpAddr) -> String {
+        format!(
+            "http://{}/?token={}",
+            SocketAddr::new(host, self.addr.port()),
+            self.token
+        )
+    }
+
+    /// Waits until a browser posts an offer that can be answered, and returns
+    /// the new peer. Bad requests get an error response and don't end the wait.
+    pub fn accept(&self) -> io::Result<DataChannelPeer> {
+        self.peers
+            .recv()
+            .map_err(|_| io::Error::new(ErrorKind::BrokenPipe, "signaling server stopped"))
+    }
+}
+
+impl Drop for SignalingServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        // Wake the listener so it sees `stop`.
+        let mut wake = self.addr;
+        if wake.ip().is_unspecified() {
+            wake.set_ip(match wake {
+                SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        let _ = TcpStream::connect_timeout(&wake, CLIENT_TIMEOUT);
+    }
+}
+
+/// A random 128-bit token in hex, from the same source as the STUN
+/// transaction ids.
+fn new_token() -> String {
+    (0..2)
+        .map(|_| format!("{:016x}", RandomState::new().build_hasher().finish()))
+        .collect()
+}
+
+/// Compares without stopping at the first difference, so response times don't
+/// give the token away a byte at a time.
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// What the listener thread and the client threads share.
+struct Signaling {
+    config: PeerConfig,
+    token: Arc<str>,
+    clients: AtomicUsize,
+}
+
+impl Signaling {
+    fn run(self, listener: TcpListener, peers: Sender<DataChannelPeer>, stop: &AtomicBool) {
+        let server = Arc::new(self);
+        for stream in listener.incoming() {
+            if stop.load(Ordering::Acquire) {
+                return;
+            }
+            let Ok(stream) = stream else { continue };
+            if server.clients.fetch_add(1, Ordering::AcqRel) >= MAX_CLIENTS {
+                server.clients.fetch_sub(1, Ordering::AcqRel);
+                continue;
+            }
+            let (client, peers) = (server.clone(), peers.clone());
+            let spawned = thread::Builder::new()
+                .name("ds4-signaling-client".into())
+                .spawn(move || {
+                    if let Ok(Some(peer)) = client.respond(stream) {
+                        let _ = peers.send(peer);
+                    }
+                    client.clients.fetch_sub(1, Ordering::AcqRel);
+                });
+            if spawned.is_err() {
+                server.clients.fetch_sub(1, Ordering::AcqRel);
+            }
+        }
+    }
+
+    fn respond(&self, mut stream: TcpStream) -> io::Result<Option<DataChannelPeer>> {
+        stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+        let mut reader = BufReader::new(&stream);
+        let mut request_line = String::new();
+        let mut complete = read_line(&mut reader, &mut request_line)?;
+        let mut length = 0;
+        let mut headers = 0;
+        while complete {
+            let mut header = String::new();
+            complete = read_line(&mut reader, &mut header)?;
+            if complete && header.trim().is_empty() {
+                break;
+            }
+            headers += 1;
+            if headers > MAX_HEADERS {
+                complete = false;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let authorized = query
+            .split('&')
+            .filter_map(|pair| pair.strip_prefix("token="))
+            .any(|given| same_token(given, &self.token));
+
+        let (status, kind, body, peer) = match (method, path) {
+            _ if !complete => ("400 Bad Request", "text/plain", String::new(), None),
+            ("GET", "/") | ("POST", "/offer") if !authorized => {
+                ("403 Forbidden", "text/plain", String::new(), None)
+            }
+            ("GET", "/") => ("200 OK", "text/html; charset=utf-8", PAGE.to_string(), None),
+            ("POST", "/offer") if length <= MAX_OFFER => {
+                let mut offer = vec![0; length];
+                reader.read_exact(&mut offer)?;
+                let offer = String::from_utf8_lossy(&offer);
+                match DataChannelPeer::answer(&offer, &self.config) {
+                    Ok((peer, answer)) => ("200 OK", "application/sdp", answer, Some(peer)),
+                    Err(e) => ("400 Bad Request", "text/plain", e.to_string(), None),
+                }
+            }
+            _ => ("404 Not Found", "text/plain", String::new(), None),
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            kind,
+            body.len(),
+            body
+        )?;
+        Ok(peer)
+    }
+}
+
+/// Reads one line of at most `MAX_LINE` bytes; false if it didn't end in time.
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> io::Result<bool> {
+    reader.take(MAX_LINE).read_line(line)?;
+    Ok(line.ends_with('\n'))
+}
+
+/// Input from a browser's gamepad, arriving over a data channel.
+pub struct WebRtcSource {
+    pub peer: DataChannelPeer,
+    events: EventQueue,
+}
+
+impl WebRtcSource {
+    pub fn new(peer: DataChannelPeer) -> Self {
+        WebRtcSource {
+            peer,
+            events: EventQueue::new(),
+        }
+    }
+}
+
+impl InputSource for WebRtcSource {
+    /// Fails with `Error::NotFound` once the browser disconnects.
+    fn next_event(&mut self) -> Result<Option<InputEvent>> {
+        if self.events.is_empty() {
+            if self.peer.is_closed() {
+                return Err(crate::Error::NotFound);
+            }
+            if let Some(snapshot) = self.peer.poll_snapshot(SOURCE_WAIT)? {
+                self.events.push_snapshot(snapshot);
+            }
+        }
+        Ok(self.events.pop())
+    }
+
+    fn state(&self) -> Snapshot {
+        self.events.state()
+    }
+    fn pending(&self) -> usize {
+        self.events.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server() -> Option<SignalingServer> {
+        SignalingServer::bind("127.0.0.1:0", PeerConfig::new(Ipv4Addr::LOCALHOST.into())).ok()
+    }
+
+    fn get(server: &SignalingServer, request: &[u8]) -> String {
+        let mut response = String::new();
+        if let Ok(mut stream) = server.local_addr().and_then(TcpStream::connect) {
+            let _ = stream.write_all(request);
+            let _ = stream.read_to_string(&mut response);
+        }
+        response
+    }
+
+    #[test]
+    fn page_needs_the_token() {
+        let Some(server) = server() else { return };
+        let response = get(&server, b"GET / HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 403"), "{}", response);
+        let response = get(&server, b"GET /?token=0 HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 403"), "{}", response);
+        let request = format!("GET /?token={} HTTP/1.1\r\n\r\n", server.token());
+        let response = get(&server, request.as_bytes());
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    }
+
+    #[test]
+    fn offer_needs_the_token() {
+        let Some(server) = server() else { return };
+        let response = get(
+            &server,
+            b"POST /offer HTTP/1.1\r\nContent-Length: 0\r\n\r\n",
+        );
+        assert!(response.starts_with("HTTP/1.1 403"), "{}", response);
+    }
+
+    #[test]
+    fn idle_client_does_not_block_others() {
+        let Some(server) = server() else { return };
+        let _idle = server.local_addr().and_then(TcpStream::connect);
+        let request = format!("GET /?token={} HTTP/1.1\r\n\r\n", server.token());
+        let response = get(&server, request.as_bytes());
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    }
+
+    #[test]
+    fn overlong_line_is_refused() {
+        let Some(server) = server() else { return };
+        let mut request = format!("GET /?token={}", server.token()).into_bytes();
+        // Exactly the cap, so the server reads it all and closes cleanly.
+        request.resize(1024, b'a');
+        let response = get(&server, &request);
+        assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+    }
+
+    #[test]
+    fn too_many_headers_are_refused() {
+        let Some(server) = server() else { return };
+        let mut request = format!("GET /?token={} HTTP/1.1\r\n", server.token());
+        for n in 0..=MAX_HEADERS {
+            request += &format!("X-{}: 1\r\n", n);
+        }
+        // No blank line, so the server reads it all and closes cleanly.
+        let response = get(&server, request.as_bytes());
+        assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+    }
+}